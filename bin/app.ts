@@ -11,11 +11,22 @@ const env = {
   region: process.env.CDK_DEFAULT_REGION,
 };
 
-const paymentStack = new PaymentStack(app, "S-CyberSagePaymentStack", { env });
+// `cdk deploy -c stage=staging` deploys an isolated copy of both stacks with
+// their own tables, secrets and Discord application credentials.
+const stage: string = app.node.tryGetContext("stage") ?? "prod";
+const stackSuffix = stage === "prod" ? "" : `-${stage}`;
 
-new CyberSageCdkStack(app, "S-CyberSageStack", {
+const paymentStack = new PaymentStack(
+  app,
+  `S-CyberSagePaymentStack${stackSuffix}`,
+  { env, stage },
+);
+
+new CyberSageCdkStack(app, `S-CyberSageStack${stackSuffix}`, {
   env,
+  stage,
   guildSubscriptionsTable: paymentStack.guildSubscriptionsTable,
+  stripeSecret: paymentStack.stripeSecret,
 });
 
 app.synth();
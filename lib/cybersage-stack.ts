@@ -7,29 +7,45 @@ import {
 } from "aws-cdk-lib";
 import { Construct } from "constructs";
 import { RetentionDays, LogGroup } from "aws-cdk-lib/aws-logs";
-import { Function, Runtime, Code, Architecture } from "aws-cdk-lib/aws-lambda";
+import {
+  Function,
+  Runtime,
+  Code,
+  Architecture,
+  Tracing,
+} from "aws-cdk-lib/aws-lambda";
 import { HttpApi, HttpMethod, CfnStage } from "aws-cdk-lib/aws-apigatewayv2";
 import { HttpLambdaIntegration } from "aws-cdk-lib/aws-apigatewayv2-integrations";
 import { Table, AttributeType, BillingMode } from "aws-cdk-lib/aws-dynamodb";
+import { PolicyStatement, Role, ServicePrincipal } from "aws-cdk-lib/aws-iam";
 import { Secret } from "aws-cdk-lib/aws-secretsmanager";
+import { Queue } from "aws-cdk-lib/aws-sqs";
+import { SqsEventSource } from "aws-cdk-lib/aws-lambda-event-sources";
+import { Rule, RuleTargetInput, Schedule } from "aws-cdk-lib/aws-events";
+import { LambdaFunction } from "aws-cdk-lib/aws-events-targets";
 import { join } from "path";
 
 interface CyberSageStackProps extends StackProps {
+  stage: string;
   guildSubscriptionsTable: Table;
+  stripeSecret: Secret;
 }
 
 export class CyberSageCdkStack extends Stack {
   constructor(scope: Construct, id: string, props: CyberSageStackProps) {
     super(scope, id, props);
 
-    const { guildSubscriptionsTable } = props;
+    const { stage, guildSubscriptionsTable, stripeSecret } = props;
+    const namePrefix = stage === "prod" ? "" : `${stage}-`;
 
     const roleMappingsTable = new Table(this, "GuildRoleMappingsTable", {
-      tableName: "GuildRoleMappings",
+      tableName: `${namePrefix}GuildRoleMappings`,
       partitionKey: { name: "guild_id", type: AttributeType.STRING },
       sortKey: { name: "mapping_key", type: AttributeType.STRING },
       billingMode: BillingMode.PAY_PER_REQUEST,
       pointInTimeRecovery: true,
+      // Expires dashboard sessions.
+      timeToLiveAttribute: "ttl",
       removalPolicy: RemovalPolicy.DESTROY,
     });
 
@@ -55,12 +71,75 @@ export class CyberSageCdkStack extends Stack {
       },
     });
 
+    const discordOAuthSecret = new Secret(this, "DiscordOAuthSecret", {
+      description: "Discord OAuth2 client secret (Linked Roles)",
+      generateSecretString: {
+        secretStringTemplate: JSON.stringify({}),
+        generateStringKey: "client_secret",
+      },
+    });
+
+    // One field per bot list, named as in BOT_LIST_ENDPOINTS, holding its
+    // API token.
+    const botListSecret = new Secret(this, "BotListSecret", {
+      description: "Bot list API tokens (server count publishing)",
+      secretObjectValue: {},
+    });
+
     const botLogGroup = new LogGroup(this, "DiscordBotLogGroup", {
       retention: RetentionDays.ONE_WEEK,
-      logGroupName: "/aws/lambda/discord-bot-handler",
+      logGroupName: `/aws/lambda/${namePrefix}discord-bot-handler`,
       removalPolicy: RemovalPolicy.DESTROY,
     });
 
+    const taskDlq = new Queue(this, "TaskDLQ", {
+      retentionPeriod: Duration.days(14),
+    });
+
+    const taskQueue = new Queue(this, "TaskQueue", {
+      visibilityTimeout: Duration.seconds(60),
+      deadLetterQueue: { queue: taskDlq, maxReceiveCount: 1 },
+    });
+
+    // Assumed by EventBridge Scheduler to deliver scheduled announcements.
+    const taskSchedulerRole = new Role(this, "TaskSchedulerRole", {
+      assumedBy: new ServicePrincipal("scheduler.amazonaws.com"),
+    });
+    taskQueue.grantSendMessages(taskSchedulerRole);
+
+    // Set these to use tables in another region or a shared data account
+    // instead of the ones above.
+    const dynamoRoleArn = process.env.DYNAMODB_ROLE_ARN ?? "";
+    const dynamoEnvironment = {
+      DYNAMODB_REGION: process.env.DYNAMODB_REGION ?? "",
+      DYNAMODB_ROLE_ARN: dynamoRoleArn,
+      DYNAMODB_EXTERNAL_ID: process.env.DYNAMODB_EXTERNAL_ID ?? "",
+    };
+
+    // Non-secret settings and flag overrides, e.g.
+    // `/cybersage/prod/free_tier_monthly_toggles`.
+    const configParameterPath = `/cybersage/${stage}`;
+    const readConfigParameters = new PolicyStatement({
+      actions: ["ssm:GetParametersByPath"],
+      resources: [
+        `arn:aws:ssm:${this.region}:${this.account}:parameter${configParameterPath}`,
+        `arn:aws:ssm:${this.region}:${this.account}:parameter${configParameterPath}/*`,
+      ],
+    });
+
+    // White-label mode: each other application's token and public key live
+    // in a secret named by this prefix and the application ID, e.g.
+    // `cybersage/prod/tenants/` + `123456789012345678`.
+    const tenantSecretPrefix = process.env.TENANT_SECRET_PREFIX ?? "";
+    // Comma-separated application IDs allowed to use white-label mode.
+    const tenantApplicationIds = process.env.TENANT_APPLICATION_IDS ?? "";
+    const readTenantSecrets = new PolicyStatement({
+      actions: ["secretsmanager:GetSecretValue"],
+      resources: [
+        `arn:aws:secretsmanager:${this.region}:${this.account}:secret:${tenantSecretPrefix}*`,
+      ],
+    });
+
     const lambdaZip = join(__dirname, "../lambda/s-cybersage-rs/bootstrap.zip");
     const discordBotHandler = new Function(this, "DiscordBotHandler", {
       runtime: Runtime.PROVIDED_AL2,
@@ -69,20 +148,190 @@ export class CyberSageCdkStack extends Stack {
       code: Code.fromAsset(lambdaZip),
       memorySize: 256,
       timeout: Duration.seconds(10),
+      tracing: Tracing.ACTIVE,
       environment: {
         ROLE_MAPPINGS_TABLE_NAME: roleMappingsTable.tableName,
         GUILD_SUBSCRIPTIONS_TABLE_NAME: guildSubscriptionsTable.tableName,
         DISCORD_TOKEN_SECRET_ARN: discordTokenSecret.secretArn,
         DISCORD_PUBLIC_KEY_SECRET_ARN: discordPublicKeySecret.secretArn,
+        DISCORD_OAUTH_SECRET_ARN: discordOAuthSecret.secretArn,
+        STRIPE_SECRET_ARN: stripeSecret.secretArn,
+        TASK_QUEUE_URL: taskQueue.queueUrl,
+        TASK_QUEUE_ARN: taskQueue.queueArn,
+        TASK_SCHEDULER_ROLE_ARN: taskSchedulerRole.roleArn,
+        FREE_TIER_MONTHLY_TOGGLES: "100",
+        BOT_OPERATOR_IDS: process.env.BOT_OPERATOR_IDS ?? "",
+        RETENTION_AUDIT_DAYS: process.env.RETENTION_AUDIT_DAYS ?? "",
+        PREMIUM_SKU_ID: process.env.PREMIUM_SKU_ID ?? "",
+        SUBSCRIBE_URL: process.env.SUBSCRIBE_URL ?? "",
+        OPS_WEBHOOK_URL: process.env.OPS_WEBHOOK_URL ?? "",
+        DISCORD_APPLICATION_ID: process.env.DISCORD_CLIENT_ID ?? "",
+        DASHBOARD_REDIRECT_URI: process.env.DASHBOARD_REDIRECT_URI ?? "",
+        DASHBOARD_ORIGIN: process.env.DASHBOARD_ORIGIN ?? "",
+        TENANT_SECRET_PREFIX: tenantSecretPrefix,
+        TENANT_APPLICATION_IDS: tenantApplicationIds,
+        ...dynamoEnvironment,
+        CONFIG_PARAMETER_PATH: configParameterPath,
+        STAGE: stage,
         RUST_LOG: "info",
+        LOG_FORMAT: "json",
       },
       logGroup: botLogGroup,
     });
 
     roleMappingsTable.grantReadWriteData(discordBotHandler);
-    guildSubscriptionsTable.grantReadData(discordBotHandler);
+    roleMappingsTable.grant(discordBotHandler, "dynamodb:DescribeTable");
+    guildSubscriptionsTable.grantReadWriteData(discordBotHandler);
     discordTokenSecret.grantRead(discordBotHandler);
     discordPublicKeySecret.grantRead(discordBotHandler);
+    discordOAuthSecret.grantRead(discordBotHandler);
+    stripeSecret.grantRead(discordBotHandler);
+    // Init fetches its secrets in one call; BatchGetSecretValue can't be
+    // scoped to resources, and each secret still needs GetSecretValue above.
+    discordBotHandler.addToRolePolicy(
+      new PolicyStatement({
+        actions: ["secretsmanager:BatchGetSecretValue"],
+        resources: ["*"],
+      }),
+    );
+    taskQueue.grantSendMessages(discordBotHandler);
+    discordBotHandler.addToRolePolicy(readConfigParameters);
+    if (tenantSecretPrefix) {
+      discordBotHandler.addToRolePolicy(readTenantSecrets);
+    }
+    discordBotHandler.addToRolePolicy(
+      new PolicyStatement({
+        actions: ["scheduler:CreateSchedule"],
+        resources: [
+          `arn:aws:scheduler:${this.region}:${this.account}:schedule/default/announce-*`,
+        ],
+      }),
+    );
+    taskSchedulerRole.grantPassRole(discordBotHandler.grantPrincipal);
+
+    const taskWorker = new Function(this, "TaskWorker", {
+      runtime: Runtime.PROVIDED_AL2,
+      architecture: Architecture.ARM_64,
+      handler: "bootstrap",
+      code: Code.fromAsset(lambdaZip),
+      memorySize: 256,
+      timeout: Duration.seconds(30),
+      environment: {
+        ROLE_MAPPINGS_TABLE_NAME: roleMappingsTable.tableName,
+        DISCORD_TOKEN_SECRET_ARN: discordTokenSecret.secretArn,
+        TASK_QUEUE_URL: taskQueue.queueUrl,
+        GUILD_SUBSCRIPTIONS_TABLE_NAME: guildSubscriptionsTable.tableName,
+        RETENTION_STATS_DAYS: process.env.RETENTION_STATS_DAYS ?? "",
+        RETENTION_USAGE_DAYS: process.env.RETENTION_USAGE_DAYS ?? "",
+        RETENTION_TOMBSTONE_DAYS: process.env.RETENTION_TOMBSTONE_DAYS ?? "",
+        BOT_LIST_ENDPOINTS: process.env.BOT_LIST_ENDPOINTS ?? "",
+        BOT_LIST_SECRET_ARN: botListSecret.secretArn,
+        DISCORD_APPLICATION_ID: process.env.DISCORD_CLIENT_ID ?? "",
+        TENANT_SECRET_PREFIX: tenantSecretPrefix,
+        TENANT_APPLICATION_IDS: tenantApplicationIds,
+        ...dynamoEnvironment,
+        CONFIG_PARAMETER_PATH: configParameterPath,
+        STAGE: stage,
+        RUST_LOG: "info",
+        LOG_FORMAT: "json",
+      },
+    });
+
+    taskWorker.addEventSource(
+      new SqsEventSource(taskQueue, {
+        batchSize: 10,
+        // Lets bursts of log-channel entries arrive in one batch, which the
+        // worker posts as a single message.
+        maxBatchingWindow: Duration.seconds(5),
+        reportBatchItemFailures: true,
+      }),
+    );
+    taskQueue.grantSendMessages(taskWorker);
+    roleMappingsTable.grantReadWriteData(taskWorker);
+    // Read for premium checks, write for the purge job's usage deletes.
+    guildSubscriptionsTable.grantReadWriteData(taskWorker);
+    discordTokenSecret.grantRead(taskWorker);
+    botListSecret.grantRead(taskWorker);
+    taskWorker.addToRolePolicy(readConfigParameters);
+    if (tenantSecretPrefix) {
+      taskWorker.addToRolePolicy(readTenantSecrets);
+    }
+
+    // The handler routes on `detail.job`; the input keeps the fields it uses
+    // to recognise a scheduled event.
+    new Rule(this, "ReconcileRolesSchedule", {
+      schedule: Schedule.cron({ minute: "0", hour: "4" }),
+      targets: [
+        new LambdaFunction(taskWorker, {
+          event: RuleTargetInput.fromObject({
+            "detail-type": "Scheduled Event",
+            source: "aws.events",
+            detail: { job: "reconcile_roles" },
+          }),
+        }),
+      ],
+    });
+
+    new Rule(this, "PurgeExpiredSchedule", {
+      schedule: Schedule.cron({ minute: "0", hour: "5" }),
+      targets: [
+        new LambdaFunction(taskWorker, {
+          event: RuleTargetInput.fromObject({
+            "detail-type": "Scheduled Event",
+            source: "aws.events",
+            detail: { job: "purge_expired" },
+          }),
+        }),
+      ],
+    });
+
+    new Rule(this, "AggregateStatsSchedule", {
+      schedule: Schedule.cron({ minute: "15", hour: "0" }),
+      targets: [
+        new LambdaFunction(taskWorker, {
+          event: RuleTargetInput.fromObject({
+            "detail-type": "Scheduled Event",
+            source: "aws.events",
+            detail: { job: "aggregate_stats" },
+          }),
+        }),
+      ],
+    });
+
+    new Rule(this, "CountRoleMembersSchedule", {
+      schedule: Schedule.rate(Duration.hours(6)),
+      targets: [
+        new LambdaFunction(taskWorker, {
+          event: RuleTargetInput.fromObject({
+            "detail-type": "Scheduled Event",
+            source: "aws.events",
+            detail: { job: "count_role_members" },
+          }),
+        }),
+      ],
+    });
+
+    new Rule(this, "PublishBotStatsSchedule", {
+      schedule: Schedule.rate(Duration.hours(12)),
+      targets: [
+        new LambdaFunction(taskWorker, {
+          event: RuleTargetInput.fromObject({
+            "detail-type": "Scheduled Event",
+            source: "aws.events",
+            detail: { job: "publish_bot_stats" },
+          }),
+        }),
+      ],
+    });
+
+    if (dynamoRoleArn) {
+      const assumeDynamoRole = new PolicyStatement({
+        actions: ["sts:AssumeRole"],
+        resources: [dynamoRoleArn],
+      });
+      discordBotHandler.addToRolePolicy(assumeDynamoRole);
+      taskWorker.addToRolePolicy(assumeDynamoRole);
+    }
 
     const api = new HttpApi(this, "DiscordBotApi", {
       description: "HTTP API for Discord bot interactions",
@@ -110,9 +359,50 @@ export class CyberSageCdkStack extends Stack {
       integration: lambdaIntegration,
     });
 
+    api.addRoutes({
+      path: "/healthz",
+      methods: [HttpMethod.GET],
+      integration: lambdaIntegration,
+    });
+
+    api.addRoutes({
+      path: "/linked-roles",
+      methods: [HttpMethod.GET],
+      integration: lambdaIntegration,
+    });
+
+    api.addRoutes({
+      path: "/linked-roles/callback",
+      methods: [HttpMethod.GET],
+      integration: lambdaIntegration,
+    });
+
+    api.addRoutes({
+      path: "/api/{proxy+}",
+      methods: [
+        HttpMethod.GET,
+        HttpMethod.POST,
+        HttpMethod.PUT,
+        HttpMethod.DELETE,
+        HttpMethod.OPTIONS,
+      ],
+      integration: lambdaIntegration,
+    });
+
+    const apiEndpoint = `https://${api.apiId}.execute-api.${this.region}.amazonaws.com/prod`;
+    discordBotHandler.addEnvironment(
+      "LINKED_ROLES_REDIRECT_URI",
+      `${apiEndpoint}/linked-roles/callback`,
+    );
+
     new CfnOutput(this, "ApiEndpoint", {
-      value: `https://${api.apiId}.execute-api.${this.region}.amazonaws.com/prod/`,
+      value: `${apiEndpoint}/`,
       description: "API Gateway endpoint URL for Discord interactions",
     });
+
+    new CfnOutput(this, "LinkedRolesVerificationUrl", {
+      value: `${apiEndpoint}/linked-roles`,
+      description: "Linked Roles Verification URL for the Discord application",
+    });
   }
 }
@@ -1,20 +1,36 @@
 import { Stack, StackProps, RemovalPolicy } from "aws-cdk-lib";
 import { Construct } from "constructs";
 import { Table, AttributeType, BillingMode } from "aws-cdk-lib/aws-dynamodb";
+import { Secret } from "aws-cdk-lib/aws-secretsmanager";
+
+interface PaymentStackProps extends StackProps {
+  stage: string;
+}
 
 export class PaymentStack extends Stack {
   public readonly guildSubscriptionsTable: Table;
+  public readonly stripeSecret: Secret;
 
-  constructor(scope: Construct, id: string, props?: StackProps) {
+  constructor(scope: Construct, id: string, props: PaymentStackProps) {
     super(scope, id, props);
 
+    const namePrefix = props.stage === "prod" ? "" : `${props.stage}-`;
+
     this.guildSubscriptionsTable = new Table(this, "GuildSubscriptionsTable", {
-      tableName: "GuildSubscriptions",
+      tableName: `${namePrefix}GuildSubscriptions`,
       partitionKey: { name: "guild_id", type: AttributeType.STRING },
       sortKey: { name: "subscription_key", type: AttributeType.STRING },
       billingMode: BillingMode.PAY_PER_REQUEST,
       pointInTimeRecovery: true,
       removalPolicy: RemovalPolicy.DESTROY,
     });
+
+    this.stripeSecret = new Secret(this, "StripeSecret", {
+      description: "Stripe API Secret Key",
+      generateSecretString: {
+        secretStringTemplate: JSON.stringify({}),
+        generateStringKey: "secret_key",
+      },
+    });
   }
 }
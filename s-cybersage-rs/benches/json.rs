@@ -0,0 +1,88 @@
+//! Parsing an autocomplete interaction and writing a full page of choices,
+//! the two JSON steps of the hottest path. Compare the backends with
+//!
+//! ```sh
+//! cargo bench --bench json
+//! cargo bench --bench json --features simd-json
+//! ```
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use s_cybersage_rs::{
+    dal::model::{
+        interaction_request::InteractionRequest,
+        interaction_response::{ApplicationCommandOptionChoice, InteractionResponse},
+    },
+    json,
+};
+
+const AUTOCOMPLETE: &str = r#"{
+    "id": "1300000000000000001",
+    "application_id": "1200000000000000001",
+    "type": 4,
+    "token": "aW50ZXJhY3Rpb246MTMwMDAwMDAwMDAwMDAwMDAwMTpzaW11bGF0ZWQtdG9rZW4tZm9yLWJlbmNobWFya3M",
+    "version": 1,
+    "locale": "en-US",
+    "guild_locale": "en-US",
+    "guild_id": "100000000000000001",
+    "channel_id": "110000000000000001",
+    "app_permissions": "2251799813685247",
+    "entitlements": [],
+    "authorizing_integration_owners": {"0": "100000000000000001"},
+    "context": 0,
+    "member": {
+        "user": {
+            "id": "120000000000000001",
+            "username": "benchmark",
+            "global_name": "Benchmark",
+            "discriminator": "0",
+            "avatar": null,
+            "public_flags": 0
+        },
+        "roles": ["130000000000000001", "130000000000000002", "130000000000000003"],
+        "joined_at": "2024-01-01T00:00:00.000000+00:00",
+        "permissions": "2251799813685247",
+        "nick": null,
+        "deaf": false,
+        "mute": false,
+        "pending": false,
+        "flags": 0
+    },
+    "data": {
+        "id": "1400000000000000001",
+        "name": "role",
+        "type": 1,
+        "options": [{
+            "name": "toggle",
+            "type": 1,
+            "options": [{"name": "role", "type": 3, "value": "mod", "focused": true}]
+        }]
+    }
+}"#;
+
+fn response() -> InteractionResponse {
+    InteractionResponse::autocomplete(
+        (0..25)
+            .map(|i| ApplicationCommandOptionChoice {
+                name: format!("Moderator Team {}", i),
+                value: format!("Moderator Team {}", i),
+            })
+            .collect(),
+    )
+}
+
+fn parse_interaction(c: &mut Criterion) {
+    c.bench_function("parse autocomplete interaction", |b| {
+        b.iter(|| json::from_slice::<InteractionRequest>(AUTOCOMPLETE.as_bytes()).unwrap())
+    });
+}
+
+fn write_response(c: &mut Criterion) {
+    let response = response();
+
+    c.bench_function("write autocomplete response", |b| {
+        b.iter(|| json::to_string(&response).unwrap())
+    });
+}
+
+criterion_group!(benches, parse_interaction, write_response);
+criterion_main!(benches);
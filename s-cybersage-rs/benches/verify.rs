@@ -0,0 +1,66 @@
+//! Checking an interaction's Ed25519 signature, which every signed request
+//! pays before anything else runs. Compare builds (e.g. the arm64 profile
+//! and allocator) on the target hardware with
+//!
+//! ```sh
+//! cargo bench --bench verify
+//! cargo bench --bench verify --profile release-arm64 --features mimalloc
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ed25519_dalek::{Signer, SigningKey};
+use s_cybersage_rs::bal::auth::verify::AuthManager;
+
+const BODY: &[u8] = br#"{"id":"1300000000000000001","application_id":"1200000000000000001","type":2,"token":"aW50ZXJhY3Rpb24","version":1,"guild_id":"100000000000000001","data":{"id":"1400000000000000001","name":"role","type":1,"options":[{"name":"toggle","type":1,"options":[{"name":"role","type":3,"value":"Moderator Team"}]}]}}"#;
+
+/// A current timestamp and its signature over `BODY`.
+fn signed(signing_key: &SigningKey) -> (String, String) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+
+    let mut message = timestamp.as_bytes().to_vec();
+    message.extend_from_slice(BODY);
+
+    (
+        timestamp,
+        hex::encode(signing_key.sign(&message).to_bytes()),
+    )
+}
+
+fn verify(c: &mut Criterion) {
+    let signing_key = SigningKey::from_bytes(&[7; 32]);
+    let public_key = signing_key.verifying_key();
+    let public_key_hex = hex::encode(public_key.to_bytes());
+    let (timestamp, signature) = signed(&signing_key);
+
+    c.bench_function("verify signature, parsing the key", |b| {
+        b.iter(|| {
+            AuthManager::verify_signature(&signature, &timestamp, BODY, &public_key_hex).unwrap()
+        })
+    });
+
+    c.bench_function("verify signature with a parsed key", |b| {
+        b.iter(|| {
+            AuthManager::verify_signature_with_key(&signature, &timestamp, BODY, &public_key)
+                .unwrap()
+        })
+    });
+
+    // During a key rotation the old key is tried first and fails.
+    let old_key = SigningKey::from_bytes(&[8; 32]).verifying_key();
+    let keys = [old_key, public_key];
+
+    c.bench_function("verify signature during a key rotation", |b| {
+        b.iter(|| {
+            AuthManager::verify_signature_with_keys(&signature, &timestamp, BODY, &keys).unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, verify);
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+//! The application's Webhook Events URL, `/events`. Discord signs these
+//! requests like interactions, so they pass the same signature check. Guild
+//! installs and removals update the guild's `GUILD` record; other events are
+//! acknowledged and ignored.
+
+use chrono::Utc;
+use lambda_http::{http::Method, Body, Request, Response};
+use tracing::info;
+
+use crate::{
+    app_state::AppState,
+    dal::{
+        dao::guild_record::GuildRecordDao,
+        model::app_event::{
+            AppEventKind, AppEventRequest, AuthorizationData, APPLICATION_AUTHORIZED,
+            APPLICATION_DEAUTHORIZED, GUILD_INSTALL,
+        },
+    },
+    error::CommandError,
+    http_handler::{self, raw_body, required},
+    json,
+};
+
+const EVENTS_PATH: &str = "/events";
+
+pub fn is_app_event_request(request: &Request) -> bool {
+    request.method() == Method::POST && request.uri().path() == EVENTS_PATH
+}
+
+pub async fn function_handler(request: &Request, state: &AppState) -> Response<Body> {
+    match handle(request, state).await {
+        // Discord expects an empty 204 for pings and events alike.
+        Ok(()) => Response::builder().status(204).body(Body::Empty).unwrap(),
+        Err(err) => http_handler::error_response(err),
+    }
+}
+
+async fn handle(request: &Request, state: &AppState) -> Result<(), CommandError> {
+    let payload: AppEventRequest = json::from_slice(raw_body(request))
+        .map_err(|_| CommandError::BadRequest("invalid JSON"))?;
+
+    let event = match (payload.kind, payload.event) {
+        (AppEventKind::Event, Some(event)) => event,
+        _ => return Ok(()),
+    };
+
+    let (is_install, data) = match event.event_type.as_str() {
+        APPLICATION_AUTHORIZED => (true, event.data),
+        APPLICATION_DEAUTHORIZED => (false, event.data),
+        other => {
+            info!(event_type = other, "Ignoring application event");
+            return Ok(());
+        }
+    };
+
+    let data: AuthorizationData = serde_json::from_value(data).unwrap_or_default();
+
+    // User installs have no guild, and Discord leaves it out of most
+    // deauthorizations, which the reconcile job catches instead.
+    let Some(guild) = data
+        .guild
+        .filter(|_| data.integration_type.unwrap_or(GUILD_INSTALL) == GUILD_INSTALL)
+    else {
+        return Ok(());
+    };
+
+    let role_table = required(&state.config.role_table, "ROLE_MAPPINGS_TABLE_NAME")?;
+    let records = GuildRecordDao::new(state.dynamo_client.clone(), role_table);
+    let now = Utc::now().timestamp();
+
+    if is_install {
+        // Scheduled tasks for the guild run as the application installed.
+        let application_id = payload.application_id.as_deref().filter(|id| {
+            state.tenants.is_some() && state.config.application_id.as_deref() != Some(*id)
+        });
+
+        records
+            .record_install(
+                &guild.id,
+                application_id,
+                guild.name.as_deref(),
+                data.user.as_ref().map(|user| user.id.as_str()),
+                now,
+            )
+            .await?;
+        info!(guild_id = %guild.id, "Recorded guild install");
+    } else {
+        records.tombstone(&guild.id, now).await?;
+        info!(guild_id = %guild.id, "Tombstoned guild");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,319 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use aws_config::{sts::AssumeRoleProvider, Region, SdkConfig};
+use aws_sdk_dynamodb::Client as DynamoClient;
+use aws_sdk_scheduler::Client as SchedulerClient;
+use aws_sdk_sqs::Client as SqsClient;
+
+use crate::{
+    bal::{
+        auth::operator::OperatorAllowlist, bot_lists::bot_list_publisher::BotList,
+        config::retention::RetentionPolicy,
+    },
+    dal::reader::{
+        env_provider::EnvProvider,
+        kms_provider::{CiphertextSource, KmsProvider},
+        parameter_reader::ParameterReader,
+        secret_bundle::SecretBundle,
+        secrets_manager_provider::SecretsManagerProvider,
+        secrets_provider::{SecretsBackend, SecretsProvider},
+        secrets_reader::{SecretCache, SecretSource, SecretsReader},
+        ssm_provider::SsmProvider,
+        tenant_secrets::{TenantCredentials, TenantSecrets},
+    },
+};
+
+pub(crate) static DISCORD_TOKEN_CACHE: SecretCache = SecretCache::new();
+static OAUTH_SECRET_CACHE: SecretCache = SecretCache::new();
+static STRIPE_SECRET_CACHE: SecretCache = SecretCache::new();
+
+/// Environment configuration, read once per process. Each function only gets
+/// the variables its handlers need, so everything is optional here and a
+/// handler treats a missing value it depends on as a misconfiguration.
+#[derive(Debug, Clone, Default)]
+pub struct AppConfig {
+    pub role_table: Option<String>,
+    pub subscription_table: Option<String>,
+    pub discord_token_secret_arn: Option<String>,
+    pub discord_public_key_secret_arn: Option<String>,
+    pub stripe_secret_arn: Option<String>,
+    /// One secret holding every credential above, read once during init.
+    /// When set, the individual secret ARNs are ignored.
+    pub secret_bundle_arn: Option<String>,
+    /// White-label mode: each other application's token and public key are
+    /// in a secret named by this prefix and its application ID. Only the
+    /// applications listed in `tenant_application_ids` are looked up.
+    pub tenant_secret_prefix: Option<String>,
+    pub tenant_application_ids: Vec<String>,
+    pub task_queue_url: Option<String>,
+    /// Scheduled announcements: the queue their schedules send to, and the
+    /// role EventBridge Scheduler assumes to send there.
+    pub task_queue_arn: Option<String>,
+    pub task_scheduler_role_arn: Option<String>,
+    /// Enables the startup check that the registered slash commands cover
+    /// everything the router handles.
+    pub application_id: Option<String>,
+    pub commands_guild_id: Option<String>,
+    /// Linked Roles: the OAuth2 client secret, and the callback URL
+    /// registered for the application.
+    pub discord_oauth_secret_arn: Option<String>,
+    pub linked_roles_redirect_uri: Option<String>,
+    /// Dashboard API: the OAuth2 callback URL the dashboard signs in with,
+    /// and the origin it's served from (for CORS).
+    pub dashboard_redirect_uri: Option<String>,
+    pub dashboard_origin: Option<String>,
+    /// Bot lists the `publish_bot_stats` job posts the server count to, and
+    /// the secret holding their tokens.
+    pub bot_lists: Vec<BotList>,
+    pub bot_list_secret_arn: Option<String>,
+    /// Parameter Store path holding non-secret settings and flag overrides.
+    pub config_parameter_path: Option<String>,
+    /// Where the tables live when that isn't the function's own region or
+    /// account, e.g. a shared data account.
+    pub dynamo_region: Option<String>,
+    pub dynamo_role_arn: Option<String>,
+    pub dynamo_external_id: Option<String>,
+    pub operators: OperatorAllowlist,
+    pub retention: RetentionPolicy,
+    pub secrets_backend: SecretsBackend,
+    /// With a KMS secrets backend, the only key ciphertext may be made with.
+    pub kms_key_id: Option<String>,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        Ok(Self {
+            role_table: var("ROLE_MAPPINGS_TABLE_NAME"),
+            subscription_table: var("GUILD_SUBSCRIPTIONS_TABLE_NAME"),
+            discord_token_secret_arn: var("DISCORD_TOKEN_SECRET_ARN"),
+            discord_public_key_secret_arn: var("DISCORD_PUBLIC_KEY_SECRET_ARN"),
+            stripe_secret_arn: var("STRIPE_SECRET_ARN"),
+            secret_bundle_arn: var("SECRET_BUNDLE_ARN"),
+            tenant_secret_prefix: var("TENANT_SECRET_PREFIX"),
+            tenant_application_ids: var("TENANT_APPLICATION_IDS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect(),
+            task_queue_url: var("TASK_QUEUE_URL"),
+            task_queue_arn: var("TASK_QUEUE_ARN"),
+            task_scheduler_role_arn: var("TASK_SCHEDULER_ROLE_ARN"),
+            application_id: var("DISCORD_APPLICATION_ID"),
+            commands_guild_id: var("COMMANDS_GUILD_ID"),
+            discord_oauth_secret_arn: var("DISCORD_OAUTH_SECRET_ARN"),
+            linked_roles_redirect_uri: var("LINKED_ROLES_REDIRECT_URI"),
+            dashboard_redirect_uri: var("DASHBOARD_REDIRECT_URI"),
+            dashboard_origin: var("DASHBOARD_ORIGIN"),
+            bot_lists: BotList::parse_all(&var("BOT_LIST_ENDPOINTS").unwrap_or_default()),
+            bot_list_secret_arn: var("BOT_LIST_SECRET_ARN"),
+            config_parameter_path: var("CONFIG_PARAMETER_PATH"),
+            dynamo_region: var("DYNAMODB_REGION"),
+            dynamo_role_arn: var("DYNAMODB_ROLE_ARN"),
+            dynamo_external_id: var("DYNAMODB_EXTERNAL_ID"),
+            operators: OperatorAllowlist::parse(&var("BOT_OPERATOR_IDS").unwrap_or_default()),
+            retention: RetentionPolicy::from_env(var),
+            secrets_backend: SecretsBackend::parse(&var("SECRETS_BACKEND").unwrap_or_default())?,
+            kms_key_id: var("KMS_KEY_ID"),
+        })
+    }
+}
+
+/// Long-lived clients and configuration, built once during Lambda init and
+/// cloned into each invocation. The SDK and HTTP clients are cheap handles
+/// onto shared connection pools.
+#[derive(Clone)]
+pub struct AppState {
+    pub dynamo_client: DynamoClient,
+    pub secrets_reader: SecretsReader,
+    /// The validated secret bundle, when `SECRET_BUNDLE_ARN` is set.
+    pub secrets: Option<Arc<SecretBundle>>,
+    pub tenants: Option<TenantSecrets>,
+    pub parameters: ParameterReader,
+    pub sqs_client: SqsClient,
+    pub scheduler_client: SchedulerClient,
+    pub http_client: reqwest::Client,
+    pub config: Arc<AppConfig>,
+}
+
+impl AppState {
+    pub async fn from_env() -> Result<Self> {
+        let mut config = AppConfig::from_env()?;
+        let shared_config = aws_config::load_from_env().await;
+        let dynamo_client = dynamo_client(&shared_config, &config).await;
+        let kms_provider = |source| {
+            Arc::new(KmsProvider::new(
+                aws_sdk_kms::Client::new(&shared_config),
+                source,
+                config.kms_key_id.clone(),
+            ))
+        };
+
+        let secrets_provider: Arc<dyn SecretsProvider> = match config.secrets_backend {
+            SecretsBackend::SecretsManager => Arc::new(SecretsManagerProvider::new(
+                aws_sdk_secretsmanager::Client::new(&shared_config),
+            )),
+            SecretsBackend::Ssm => {
+                Arc::new(SsmProvider::new(aws_sdk_ssm::Client::new(&shared_config)))
+            }
+            SecretsBackend::Env => Arc::new(EnvProvider),
+            SecretsBackend::KmsSsm => kms_provider(CiphertextSource::Ssm(
+                aws_sdk_ssm::Client::new(&shared_config),
+            )),
+            SecretsBackend::KmsDynamoDb => kms_provider(CiphertextSource::DynamoDb {
+                client: dynamo_client.clone(),
+                table_name: config
+                    .role_table
+                    .clone()
+                    .context("ROLE_MAPPINGS_TABLE_NAME is not set")?,
+            }),
+        };
+
+        let secrets = match config.secret_bundle_arn.as_deref() {
+            Some(arn) => {
+                let document = secrets_provider
+                    .fetch_secret(arn)
+                    .await
+                    .context("Failed to read secret bundle")?;
+                let bundle = SecretBundle::from_document(document)?;
+
+                if config.application_id.is_none() {
+                    config.application_id = Some(bundle.application_id.clone());
+                }
+
+                Some(Arc::new(bundle))
+            }
+            None => None,
+        };
+
+        let http_client = reqwest::Client::builder()
+            .user_agent("cybersage-bot")
+            .pool_idle_timeout(std::time::Duration::from_secs(90))
+            .pool_max_idle_per_host(5)
+            .build()?;
+
+        let secrets_reader = SecretsReader::new(secrets_provider);
+        let tenants = config.tenant_secret_prefix.as_ref().map(|prefix| {
+            TenantSecrets::new(
+                secrets_reader.clone(),
+                prefix,
+                config.tenant_application_ids.iter().cloned(),
+            )
+        });
+
+        Ok(Self {
+            dynamo_client,
+            secrets_reader,
+            secrets,
+            tenants,
+            parameters: ParameterReader::new(
+                aws_sdk_ssm::Client::new(&shared_config),
+                config.config_parameter_path.clone(),
+            ),
+            sqs_client: SqsClient::new(&shared_config),
+            scheduler_client: SchedulerClient::new(&shared_config),
+            http_client,
+            config: Arc::new(config),
+        })
+    }
+
+    /// The bot token: the bundle's, or the `token` field of
+    /// `DISCORD_TOKEN_SECRET_ARN`.
+    pub fn discord_token(&self) -> Option<SecretSource> {
+        self.secret_source(
+            |bundle| Some(bundle.discord_token.clone()),
+            &self.config.discord_token_secret_arn,
+            "token",
+            &DISCORD_TOKEN_CACHE,
+        )
+    }
+
+    /// The credentials of a white-label application. `None` when it's the
+    /// bot's own application (`DISCORD_APPLICATION_ID`) or white-label mode
+    /// is off, and the default token and public key apply.
+    pub async fn tenant(&self, application_id: &str) -> Option<Result<TenantCredentials>> {
+        let tenants = self.tenants.as_ref()?;
+
+        if self.config.application_id.as_deref() == Some(application_id) {
+            return None;
+        }
+
+        Some(tenants.get(application_id).await)
+    }
+
+    /// The bot token for an interaction or task of `application_id`.
+    pub async fn discord_token_for(&self, application_id: &str) -> Result<Option<SecretSource>> {
+        match self.tenant(application_id).await {
+            Some(credentials) => Ok(Some(SecretSource::Loaded(credentials?.token))),
+            None => Ok(self.discord_token()),
+        }
+    }
+
+    /// The OAuth2 client secret for Linked Roles and the dashboard.
+    pub fn oauth_client_secret(&self) -> Option<SecretSource> {
+        self.secret_source(
+            |bundle| bundle.oauth_client_secret.clone(),
+            &self.config.discord_oauth_secret_arn,
+            "client_secret",
+            &OAUTH_SECRET_CACHE,
+        )
+    }
+
+    /// The Stripe API key.
+    pub fn stripe_secret(&self) -> Option<SecretSource> {
+        self.secret_source(
+            |bundle| bundle.stripe_secret_key.clone(),
+            &self.config.stripe_secret_arn,
+            "secret_key",
+            &STRIPE_SECRET_CACHE,
+        )
+    }
+
+    fn secret_source(
+        &self,
+        from_bundle: impl FnOnce(&SecretBundle) -> Option<String>,
+        secret_arn: &Option<String>,
+        key: &'static str,
+        cache: &'static SecretCache,
+    ) -> Option<SecretSource> {
+        if let Some(bundle) = &self.secrets {
+            return from_bundle(bundle).map(SecretSource::Loaded);
+        }
+
+        secret_arn.as_ref().map(|arn| SecretSource::Field {
+            reader: self.secrets_reader.clone(),
+            secret_id: arn.clone(),
+            key,
+            cache,
+        })
+    }
+}
+
+/// The DynamoDB client, built from the shared config with the region and
+/// credentials overridden when `config` names another region or a role to
+/// assume. The assumed role's credentials are refreshed by the provider.
+async fn dynamo_client(shared_config: &SdkConfig, config: &AppConfig) -> DynamoClient {
+    let mut builder = aws_sdk_dynamodb::config::Builder::from(shared_config);
+
+    if let Some(region) = &config.dynamo_region {
+        builder = builder.region(Region::new(region.clone()));
+    }
+
+    if let Some(role_arn) = &config.dynamo_role_arn {
+        let mut provider = AssumeRoleProvider::builder(role_arn)
+            .session_name("cybersage-dynamodb")
+            .configure(shared_config);
+
+        if let Some(external_id) = &config.dynamo_external_id {
+            provider = provider.external_id(external_id);
+        }
+
+        builder = builder.credentials_provider(provider.build().await);
+    }
+
+    DynamoClient::from_conf(builder.build())
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use chrono::DateTime;
+use serde_json::{json, Value};
+use tracing::{instrument, warn};
+
+use crate::{
+    bal::discord::channel_client::ChannelClient,
+    dal::{dao::log_channel::LogChannelDao, model::activity_entry::ActivityEntry},
+};
+
+/// Discord's limit on embeds per message.
+const MAX_EMBEDS_PER_MESSAGE: usize = 10;
+const EMBED_COLOR_GREEN: u32 = 0x2E_CC_71;
+const EMBED_COLOR_RED: u32 = 0xE7_4C_3C;
+
+/// Posts queued activity to each guild's log channel, one embed per entry
+/// and up to ten entries per message.
+pub struct ActivityLogPoster {
+    log_channels: LogChannelDao,
+    channels: ChannelClient,
+}
+
+impl ActivityLogPoster {
+    pub fn new(log_channels: LogChannelDao, channels: ChannelClient) -> Self {
+        Self {
+            log_channels,
+            channels,
+        }
+    }
+
+    /// Failures are logged and the entries dropped: a missing permission in
+    /// the log channel shouldn't dead-letter role activity.
+    #[instrument(skip_all, fields(entries = entries.len()))]
+    pub async fn post(&self, entries: Vec<ActivityEntry>) {
+        let mut by_guild: BTreeMap<String, Vec<ActivityEntry>> = BTreeMap::new();
+        for entry in entries {
+            by_guild
+                .entry(entry.guild_id.clone())
+                .or_default()
+                .push(entry);
+        }
+
+        for (guild_id, mut entries) in by_guild {
+            let channel_id = match self.log_channels.get_log_channel(&guild_id).await {
+                Ok(Some(channel_id)) => channel_id,
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!(
+                        guild_id,
+                        error = format!("{:#}", err),
+                        "Failed to look up log channel"
+                    );
+                    continue;
+                }
+            };
+
+            entries.sort_by_key(|entry| entry.occurred_at);
+
+            for chunk in entries.chunks(MAX_EMBEDS_PER_MESSAGE) {
+                let message = json!({
+                    "embeds": chunk.iter().map(embed).collect::<Vec<_>>(),
+                    "allowed_mentions": { "parse": [] },
+                });
+
+                if let Err(err) = self.channels.create_message(&channel_id, &message).await {
+                    warn!(
+                        guild_id,
+                        channel_id,
+                        error = format!("{:#}", err),
+                        "Failed to post to log channel"
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn embed(entry: &ActivityEntry) -> Value {
+    let actor = match entry.actor_id.as_deref() {
+        Some(id) => format!("<@{}>", id),
+        None => "CyberSage".to_string(),
+    };
+
+    let mut embed = json!({
+        "title": entry.activity.title(),
+        "color": if entry.succeeded { EMBED_COLOR_GREEN } else { EMBED_COLOR_RED },
+        "fields": [
+            { "name": "Actor", "value": actor, "inline": true },
+            { "name": "Target", "value": entry.target, "inline": true },
+            { "name": "Result", "value": entry.result, "inline": true },
+        ],
+    });
+
+    if let Some(url) = &entry.thumbnail_url {
+        embed["thumbnail"] = json!({ "url": url });
+    }
+
+    if let Some(at) = DateTime::from_timestamp(entry.occurred_at, 0) {
+        embed["timestamp"] = json!(at.to_rfc3339());
+    }
+
+    embed
+}
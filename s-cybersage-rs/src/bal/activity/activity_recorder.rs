@@ -0,0 +1,57 @@
+use tracing::warn;
+
+use crate::dal::{
+    dao::log_channel::LogChannelDao,
+    model::{activity_entry::ActivityEntry, deferred_task::DeferredTask},
+    queue::task_queue::TaskQueue,
+};
+
+/// Queues role activity for the guild's log channel. The queue worker posts
+/// whatever arrives together in one batch as a single message, so bulk
+/// changes don't flood the channel.
+#[derive(Clone)]
+pub struct ActivityRecorder {
+    log_channels: LogChannelDao,
+    queue: Option<TaskQueue>,
+}
+
+impl ActivityRecorder {
+    pub fn new(log_channels: LogChannelDao, queue: Option<TaskQueue>) -> Self {
+        Self {
+            log_channels,
+            queue,
+        }
+    }
+
+    /// Best-effort, like publishing guild events: entries are dropped if the
+    /// guild has no log channel, no task queue is configured, or queueing
+    /// fails.
+    pub async fn record(&self, entry: ActivityEntry) {
+        let Some(queue) = self.queue.as_ref() else {
+            return;
+        };
+
+        match self.log_channels.get_log_channel(&entry.guild_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return,
+            Err(err) => {
+                warn!(
+                    guild_id = %entry.guild_id,
+                    error = format!("{:#}", err),
+                    "Failed to look up log channel"
+                );
+                return;
+            }
+        }
+
+        let guild_id = entry.guild_id.clone();
+
+        if let Err(err) = queue.enqueue(&DeferredTask::ActivityLog(entry), 0).await {
+            warn!(
+                guild_id,
+                error = format!("{:#}", err),
+                "Failed to queue activity log entry"
+            );
+        }
+    }
+}
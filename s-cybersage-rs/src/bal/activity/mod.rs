@@ -0,0 +1,2 @@
+pub mod activity_log_poster;
+pub mod activity_recorder;
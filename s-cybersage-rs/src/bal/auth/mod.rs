@@ -1 +1,3 @@
+pub mod operator;
+pub mod rate_limiter;
 pub mod verify;
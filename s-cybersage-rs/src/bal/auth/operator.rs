@@ -0,0 +1,36 @@
+use std::collections::HashSet;
+
+use crate::dal::dao::{feature_flag::FeatureFlagDao, global_stats::GlobalStatsDao};
+
+/// Discord user IDs allowed to run bot-operator commands, regardless of
+/// their permissions in the guild the command is issued from.
+#[derive(Debug, Clone, Default)]
+pub struct OperatorAllowlist {
+    user_ids: HashSet<String>,
+}
+
+impl OperatorAllowlist {
+    /// Parses a comma-separated list of user IDs, ignoring blanks.
+    pub fn parse(raw: &str) -> Self {
+        Self {
+            user_ids: raw
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    pub fn contains(&self, user_id: &str) -> bool {
+        !user_id.is_empty() && self.user_ids.contains(user_id)
+    }
+}
+
+/// What `/sysadmin` needs beyond the guild's own stores, grouped so the
+/// command router takes one dependency.
+pub struct OperatorContext {
+    pub allowlist: OperatorAllowlist,
+    pub flag_store: FeatureFlagDao,
+    pub stats_store: GlobalStatsDao,
+}
@@ -0,0 +1,118 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use tracing::warn;
+
+use crate::dal::dao::rate_limit::RateLimitDao;
+
+/// Commands a member can send back to back.
+const BURST: f64 = 5.0;
+/// Tokens regained per second once the burst is spent.
+const REFILL_PER_SECOND: f64 = 0.5;
+/// Instances that may first see a member within one minute. Past this, the
+/// member is spreading a burst across instances.
+const MAX_FIRST_SIGHTINGS_PER_MINUTE: u64 = 5;
+const MAX_BUCKETS: usize = 4096;
+/// A bucket this long untouched is full again, so it can be dropped.
+const BUCKET_IDLE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn full(now: Instant) -> Self {
+        Self {
+            tokens: BURST,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * REFILL_PER_SECOND).min(BURST);
+        self.updated_at = now;
+    }
+}
+
+/// Token buckets keyed by (guild, user), kept per warm instance.
+static BUCKETS: Lazy<Mutex<HashMap<(String, String), Bucket>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Whether a member may run a command or click a component now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateDecision {
+    Allowed,
+    /// Try again after this many seconds.
+    Limited(u64),
+}
+
+/// Turns away command spam before any other work is done. Each warm
+/// instance keeps a token bucket per (guild, user), which costs nothing to
+/// check. Lambda spreads a burst across instances, so the first time an
+/// instance sees a member it also counts the sighting in a shared per-minute
+/// DynamoDB counter; that's the only storage call, and it fails open.
+pub struct RateLimiter {
+    dao: RateLimitDao,
+}
+
+impl RateLimiter {
+    pub fn new(dao: RateLimitDao) -> Self {
+        Self { dao }
+    }
+
+    pub async fn check(&self, guild_id: &str, user_id: &str) -> RateDecision {
+        let (decision, first_sighting) = Self::take_token(guild_id, user_id, Instant::now());
+
+        if decision != RateDecision::Allowed || !first_sighting {
+            return decision;
+        }
+
+        let minute = Utc::now().timestamp() / 60;
+        match self.dao.hit(guild_id, user_id, minute).await {
+            Ok(sightings) if sightings > MAX_FIRST_SIGHTINGS_PER_MINUTE => {
+                RateDecision::Limited(60 - Utc::now().timestamp().rem_euclid(60) as u64)
+            }
+            Ok(_) => RateDecision::Allowed,
+            Err(err) => {
+                warn!(
+                    error = format!("{:#}", err),
+                    "Failed to count rate limit hit; allowing"
+                );
+                RateDecision::Allowed
+            }
+        }
+    }
+
+    /// Takes a token from the member's bucket, creating a full one for a
+    /// member this instance hasn't seen lately. Returns whether it did so.
+    fn take_token(guild_id: &str, user_id: &str, now: Instant) -> (RateDecision, bool) {
+        let Ok(mut buckets) = BUCKETS.lock() else {
+            return (RateDecision::Allowed, false);
+        };
+
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated_at) <= BUCKET_IDLE);
+        }
+
+        let key = (guild_id.to_string(), user_id.to_string());
+        let first_sighting = !buckets.contains_key(&key);
+        let bucket = buckets.entry(key).or_insert_with(|| Bucket::full(now));
+        bucket.refill(now);
+
+        if bucket.tokens < 1.0 {
+            let wait = ((1.0 - bucket.tokens) / REFILL_PER_SECOND).ceil() as u64;
+            return (RateDecision::Limited(wait.max(1)), first_sighting);
+        }
+
+        bucket.tokens -= 1.0;
+        (RateDecision::Allowed, first_sighting)
+    }
+}
@@ -1,80 +1,114 @@
-use anyhow::{bail, Context, Result};
-use ed25519_dalek::{Signature, Verifier, VerifyingKey};
-use std::convert::TryInto;
+#[cfg(feature = "billing")]
+use anyhow::{bail, Result};
+use ed25519_dalek::{Signature, VerifyingKey, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "billing")]
 use crate::dal::dao::subscription::SubscriptionReader;
+use crate::error::AuthError;
 
 const MAX_AGE_SECONDS: i64 = 300;
 const MAX_FUTURE_SKEW: i64 = 30;
 
 #[derive(Clone)]
 pub struct AuthManager {
+    #[cfg(feature = "billing")]
     subscription_reader: SubscriptionReader,
 }
 
 impl AuthManager {
-    pub fn new(subscription_reader: SubscriptionReader) -> Self {
-        Self {
-            subscription_reader,
-        }
-    }
-
     pub fn verify_signature(
-        &self,
         signature_hex: &str,
         timestamp: &str,
         body: &[u8],
         public_key_hex: &str,
-    ) -> Result<()> {
+    ) -> Result<(), AuthError> {
         if signature_hex.is_empty() || timestamp.is_empty() {
-            bail!("Missing required Discord signature headers");
+            return Err(AuthError::MissingHeaders);
         }
 
-        let ts: i64 = timestamp
-            .parse()
-            .context("X-Signature-Timestamp is not a valid integer")?;
+        let public_key = Self::parse_public_key(public_key_hex)?;
 
-        let now: i64 = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        Self::verify_signature_with_key(signature_hex, timestamp, body, &public_key)
+    }
 
-        if ts > now + MAX_FUTURE_SKEW {
-            bail!("Request timestamp is too far in the future");
-        }
+    /// Decodes the hex public key once so callers can keep the parsed key
+    /// instead of decoding and decompressing it on every request.
+    pub fn parse_public_key(public_key_hex: &str) -> Result<VerifyingKey, AuthError> {
+        let mut public_key = [0u8; PUBLIC_KEY_LENGTH];
+        hex::decode_to_slice(public_key_hex, &mut public_key)
+            .map_err(|_| AuthError::InvalidPublicKey)?;
 
-        if now - ts > MAX_AGE_SECONDS {
-            bail!("Request timestamp is too old");
+        VerifyingKey::from_bytes(&public_key).map_err(|_| AuthError::InvalidPublicKey)
+    }
+
+    /// Accepts a signature made with any of `public_keys`, so requests keep
+    /// verifying while the application's key is rotated.
+    pub fn verify_signature_with_keys(
+        signature_hex: &str,
+        timestamp: &str,
+        body: &[u8],
+        public_keys: &[VerifyingKey],
+    ) -> Result<(), AuthError> {
+        let mut result = Err(AuthError::InvalidPublicKey);
+
+        for public_key in public_keys {
+            result = Self::verify_signature_with_key(signature_hex, timestamp, body, public_key);
+
+            if result.is_ok() {
+                break;
+            }
         }
 
-        let public_key_bytes =
-            hex::decode(public_key_hex).context("Failed to decode public key hex")?;
+        result
+    }
 
-        let public_key_array: &[u8; 32] = public_key_bytes
-            .as_slice()
-            .try_into()
-            .context("Public key has invalid length")?;
+    /// Verifies without allocating: the signature is decoded onto the stack
+    /// and the timestamp and body are streamed into the verifier rather than
+    /// concatenated.
+    pub fn verify_signature_with_key(
+        signature_hex: &str,
+        timestamp: &str,
+        body: &[u8],
+        public_key: &VerifyingKey,
+    ) -> Result<(), AuthError> {
+        if signature_hex.is_empty() || timestamp.is_empty() {
+            return Err(AuthError::MissingHeaders);
+        }
 
-        let signature_bytes =
-            hex::decode(signature_hex).context("Failed to decode signature hex")?;
+        let ts: i64 = timestamp.parse().map_err(|_| AuthError::InvalidTimestamp)?;
 
-        let signature_array: &[u8; 64] = signature_bytes
-            .as_slice()
-            .try_into()
-            .context("Signature has invalid length")?;
+        let now: i64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
 
-        let public_key =
-            VerifyingKey::from_bytes(public_key_array).context("Invalid public key bytes")?;
+        if ts > now + MAX_FUTURE_SKEW || now - ts > MAX_AGE_SECONDS {
+            return Err(AuthError::StaleTimestamp);
+        }
 
-        let signature = Signature::from_bytes(signature_array);
+        let mut signature = [0u8; SIGNATURE_LENGTH];
+        hex::decode_to_slice(signature_hex, &mut signature)
+            .map_err(|_| AuthError::InvalidSignature)?;
 
-        let mut message = Vec::with_capacity(timestamp.len() + body.len());
-        message.extend_from_slice(timestamp.as_bytes());
-        message.extend_from_slice(body);
+        let mut verifier = public_key
+            .verify_stream(&Signature::from_bytes(&signature))
+            .map_err(|_| AuthError::InvalidSignature)?;
 
-        public_key
-            .verify(&message, &signature)
-            .context("Signature verification failed")?;
+        verifier.update(timestamp.as_bytes());
+        verifier.update(body);
 
-        Ok(())
+        verifier
+            .finalize_and_verify()
+            .map_err(|_| AuthError::InvalidSignature)
+    }
+}
+
+#[cfg(feature = "billing")]
+impl AuthManager {
+    pub fn new(subscription_reader: SubscriptionReader) -> Self {
+        Self {
+            subscription_reader,
+        }
     }
 
     pub async fn verify_subscription(&self, guild_id: &str) -> Result<()> {
@@ -0,0 +1,13 @@
+use super::{
+    premium_gate::PremiumGate, stripe_client::StripeClient,
+    subscription_manager::SubscriptionManager, usage_meter::UsageMeter,
+};
+
+/// The billing services a request may need, grouped so routers take one
+/// dependency instead of one per billing concern.
+pub struct BillingContext {
+    pub usage_meter: UsageMeter,
+    pub subscription_manager: SubscriptionManager,
+    pub stripe_client: StripeClient,
+    pub premium_gate: PremiumGate,
+}
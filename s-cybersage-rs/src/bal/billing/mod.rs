@@ -0,0 +1,5 @@
+pub mod context;
+pub mod premium_gate;
+pub mod stripe_client;
+pub mod subscription_manager;
+pub mod usage_meter;
@@ -0,0 +1,47 @@
+use crate::dal::model::interaction_response::{Component, InteractionResponse};
+
+/// Single place premium-only paths consult before doing work, so every
+/// blocked action gets the same upsell message and purchase button.
+pub struct PremiumGate {
+    is_premium: bool,
+    sku_id: Option<String>,
+    subscribe_url: Option<String>,
+}
+
+impl PremiumGate {
+    pub fn new(is_premium: bool, sku_id: Option<String>, subscribe_url: Option<String>) -> Self {
+        Self {
+            is_premium,
+            sku_id,
+            subscribe_url,
+        }
+    }
+
+    pub fn is_premium(&self) -> bool {
+        self.is_premium
+    }
+
+    /// Returns `None` when the guild may use `feature`, otherwise the upsell
+    /// response to send instead.
+    pub fn require(&self, feature: &str) -> Option<InteractionResponse> {
+        if self.is_premium {
+            return None;
+        }
+
+        Some(self.upsell(format!("{} is a premium feature.", feature)))
+    }
+
+    pub fn upsell(&self, message: impl Into<String>) -> InteractionResponse {
+        let response = InteractionResponse::ephemeral(message);
+
+        // Discord's native premium button takes precedence; it renders the
+        // SKU's price and handles checkout in-client.
+        let button = match (self.sku_id.as_deref(), self.subscribe_url.as_deref()) {
+            (Some(sku_id), _) => Component::premium_button(sku_id),
+            (None, Some(url)) => Component::link_button("Subscribe", url),
+            (None, None) => return response,
+        };
+
+        response.with_components(vec![Component::action_row(vec![button])])
+    }
+}
@@ -0,0 +1,68 @@
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use tracing::{error, instrument};
+
+use crate::dal::reader::secrets_reader::SecretSource;
+
+#[derive(Debug, Deserialize)]
+struct PortalSession {
+    url: String,
+}
+
+/// Minimal Stripe REST client. The API key is only fetched from the secrets
+/// backend the first time a Stripe call is actually made.
+pub struct StripeClient {
+    client: Client,
+    api_key: Option<SecretSource>,
+}
+
+impl StripeClient {
+    pub fn new(client: Client, api_key: Option<SecretSource>) -> Self {
+        Self { client, api_key }
+    }
+
+    async fn api_key(&self) -> Result<String> {
+        match &self.api_key {
+            Some(api_key) => api_key.get().await,
+            None => bail!("Stripe is not configured"),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn create_portal_session(
+        &self,
+        customer_id: &str,
+        return_url: &str,
+    ) -> Result<String> {
+        let api_key = self.api_key().await?;
+
+        let resp = self
+            .client
+            .post("https://api.stripe.com/v1/billing_portal/sessions")
+            .bearer_auth(api_key)
+            .form(&[("customer", customer_id), ("return_url", return_url)])
+            .send()
+            .await
+            .context("Failed to send create_portal_session request")?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            error!(
+                customer_id,
+                status = %status,
+                body,
+                "Stripe portal session request failed"
+            );
+            bail!("Stripe API error: {}", status);
+        }
+
+        let session: PortalSession = resp
+            .json()
+            .await
+            .context("Failed to deserialize portal session")?;
+
+        Ok(session.url)
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+
+use crate::{
+    commands::sysadmin::MAX_GRANT_DAYS,
+    dal::{
+        dao::{
+            bundle::BundleDao,
+            subscription::{invalidate_cached_status, SubscriptionReader, SubscriptionWriter},
+        },
+        model::guild_event::GuildEvent,
+    },
+};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachOutcome {
+    Attached { used: usize, max: u64 },
+    NoActiveBundle,
+    BundleFull { max: u64 },
+    AlreadyAttached,
+    AttachedElsewhere,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DetachOutcome {
+    Detached,
+    NotAttached,
+}
+
+#[derive(Clone)]
+pub struct SubscriptionManager {
+    reader: SubscriptionReader,
+    writer: SubscriptionWriter,
+    bundles: BundleDao,
+}
+
+impl SubscriptionManager {
+    pub fn new(reader: SubscriptionReader, writer: SubscriptionWriter, bundles: BundleDao) -> Self {
+        Self {
+            reader,
+            writer,
+            bundles,
+        }
+    }
+
+    /// Grants premium to a guild for `days`, extending any active subscription
+    /// rather than cutting it short. Returns the new expiry as a unix timestamp.
+    pub async fn grant_premium(&self, guild_id: &str, days: i64, operator_id: &str) -> Result<i64> {
+        if !(1..=MAX_GRANT_DAYS).contains(&days) {
+            bail!(
+                "Grant duration must be between 1 and {} days",
+                MAX_GRANT_DAYS
+            );
+        }
+
+        let now = Utc::now().timestamp();
+
+        let base = match self.reader.get_active_expiry(guild_id).await? {
+            Some(expires_at) if expires_at > now => expires_at,
+            _ => now,
+        };
+
+        let expires_at = base + days * SECONDS_PER_DAY;
+
+        self.writer
+            .put_active(guild_id, expires_at, "admin_grant", operator_id)
+            .await?;
+
+        Ok(expires_at)
+    }
+
+    /// The guild's current subscription, to publish after it changes.
+    pub async fn changed_event(&self, guild_id: &str) -> Result<GuildEvent> {
+        let (active, expires_at) = self.status(guild_id).await?;
+
+        Ok(GuildEvent::SubscriptionChanged { active, expires_at })
+    }
+
+    /// Whether the guild has premium, and until when if it's time-limited.
+    pub async fn status(&self, guild_id: &str) -> Result<(bool, Option<i64>)> {
+        tokio::try_join!(
+            self.reader.is_active(guild_id),
+            self.reader.get_active_expiry(guild_id),
+        )
+    }
+
+    pub async fn customer_id(&self, guild_id: &str) -> Result<Option<String>> {
+        self.reader.get_customer_id(guild_id).await
+    }
+
+    /// Attaches `guild_id` to the bundle owned by `owner_id`. A guild can be
+    /// covered by at most one bundle at a time.
+    pub async fn attach_bundle(&self, owner_id: &str, guild_id: &str) -> Result<AttachOutcome> {
+        let bundle = match self.bundles.get_bundle(owner_id).await? {
+            Some(b) if b.is_active(Utc::now().timestamp()) => b,
+            _ => return Ok(AttachOutcome::NoActiveBundle),
+        };
+
+        match self.bundles.get_attached_owner(guild_id).await? {
+            Some(existing) if existing == owner_id => return Ok(AttachOutcome::AlreadyAttached),
+            Some(_) => return Ok(AttachOutcome::AttachedElsewhere),
+            None => {}
+        }
+
+        if !bundle.has_capacity() {
+            return Ok(AttachOutcome::BundleFull {
+                max: bundle.max_guilds,
+            });
+        }
+
+        self.bundles.attach(&bundle, guild_id).await?;
+        invalidate_cached_status(guild_id);
+
+        Ok(AttachOutcome::Attached {
+            used: bundle.guild_ids.len() + 1,
+            max: bundle.max_guilds,
+        })
+    }
+
+    pub async fn detach_bundle(&self, owner_id: &str, guild_id: &str) -> Result<DetachOutcome> {
+        match self.bundles.get_attached_owner(guild_id).await? {
+            Some(existing) if existing == owner_id => {}
+            _ => return Ok(DetachOutcome::NotAttached),
+        }
+
+        self.bundles.detach(owner_id, guild_id).await?;
+        invalidate_cached_status(guild_id);
+
+        Ok(DetachOutcome::Detached)
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::dal::dao::usage::UsageDao;
+
+pub const DEFAULT_FREE_TIER_MONTHLY_TOGGLES: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaStatus {
+    Available,
+    Exhausted { limit: u64 },
+}
+
+/// Meters role toggles per guild per calendar month (UTC).
+///
+/// A `monthly_quota` of `None` means the guild is unmetered (premium); usage
+/// is still recorded so it can be reported on.
+pub struct UsageMeter {
+    usage_dao: UsageDao,
+    monthly_quota: Option<u64>,
+}
+
+impl UsageMeter {
+    pub fn new(usage_dao: UsageDao, monthly_quota: Option<u64>) -> Self {
+        Self {
+            usage_dao,
+            monthly_quota,
+        }
+    }
+
+    pub fn current_period() -> String {
+        Utc::now().format("%Y-%m").to_string()
+    }
+
+    pub async fn check_quota(&self, guild_id: &str) -> Result<QuotaStatus> {
+        let limit = match self.monthly_quota {
+            Some(limit) => limit,
+            None => return Ok(QuotaStatus::Available),
+        };
+
+        let used = self
+            .usage_dao
+            .get_toggle_count(guild_id, &Self::current_period())
+            .await?;
+
+        if used >= limit {
+            return Ok(QuotaStatus::Exhausted { limit });
+        }
+
+        Ok(QuotaStatus::Available)
+    }
+
+    /// Toggles recorded this month, metered or not.
+    pub async fn current_usage(&self, guild_id: &str) -> Result<u64> {
+        self.usage_dao
+            .get_toggle_count(guild_id, &Self::current_period())
+            .await
+    }
+
+    pub async fn record_toggle(&self, guild_id: &str) -> Result<u64> {
+        self.usage_dao
+            .increment_toggle_count(guild_id, &Self::current_period())
+            .await
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde_json::json;
+use tracing::{info, instrument, warn};
+
+use crate::dal::reader::secrets_reader::{SecretCache, SecretsReader};
+
+static BOT_LIST_SECRET_CACHE: SecretCache = SecretCache::new();
+
+/// A bot list the server count is posted to. Its API token is the field
+/// named `name` in the bot list secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotList {
+    pub name: String,
+    /// The list's stats endpoint, e.g.
+    /// `https://top.gg/api/bots/<application_id>/stats`.
+    pub url: String,
+}
+
+impl BotList {
+    /// Parses `BOT_LIST_ENDPOINTS`, comma-separated `name=url` pairs.
+    /// Malformed entries are skipped.
+    pub fn parse_all(value: &str) -> Vec<Self> {
+        value
+            .split(',')
+            .filter_map(|entry| {
+                let (name, url) = entry.split_once('=')?;
+                let (name, url) = (name.trim(), url.trim());
+
+                if name.is_empty() || !url.starts_with("https://") {
+                    warn!(entry, "Skipping malformed bot list endpoint");
+                    return None;
+                }
+
+                Some(Self {
+                    name: name.to_string(),
+                    url: url.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Posts the bot's server count to bot lists, top.gg style: a JSON body
+/// with `server_count` and the list's token as the `Authorization` header.
+pub struct BotListPublisher {
+    client: Client,
+    secrets_reader: SecretsReader,
+    secret_arn: String,
+}
+
+impl BotListPublisher {
+    pub fn new(
+        client: Client,
+        secrets_reader: SecretsReader,
+        secret_arn: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            secrets_reader,
+            secret_arn: secret_arn.into(),
+        }
+    }
+
+    /// Posts `server_count` to each list. A list that fails doesn't stop
+    /// the rest; returns how many accepted it.
+    pub async fn publish_all(&self, lists: &[BotList], server_count: usize) -> usize {
+        let mut published = 0;
+
+        for list in lists {
+            match self.publish(list, server_count).await {
+                Ok(()) => published += 1,
+                Err(err) => warn!(
+                    list = %list.name,
+                    error = format!("{:#}", err),
+                    "Failed to publish bot list stats"
+                ),
+            }
+        }
+
+        info!(
+            server_count,
+            published,
+            lists = lists.len(),
+            "Published bot list stats"
+        );
+
+        published
+    }
+
+    #[instrument(skip(self), fields(list = %list.name))]
+    async fn publish(&self, list: &BotList, server_count: usize) -> Result<()> {
+        let token = self
+            .secrets_reader
+            .get_secret_value(&self.secret_arn, &list.name, &BOT_LIST_SECRET_CACHE)
+            .await?;
+
+        let resp = self
+            .client
+            .post(&list.url)
+            .header(reqwest::header::AUTHORIZATION, token)
+            .json(&json!({ "server_count": server_count }))
+            .send()
+            .await
+            .context("Failed to send bot list stats")?;
+
+        if !resp.status().is_success() {
+            bail!("Bot list API error: {}", resp.status());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use tracing::warn;
+
+use crate::dal::{dao::feature_flag::FeatureFlagDao, reader::parameter_reader::ConfigParameters};
+
+/// Runtime switches for risky features, so they can be dark-launched or
+/// turned off without a redeploy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    /// `/role import-all` and `/role export` via the task queue.
+    DeferredRoleTasks,
+    /// Queueing toggles that failed with a retryable Discord error.
+    ToggleRetryQueue,
+    /// Reserving role menus for premium guilds.
+    PremiumRoleMenus,
+    /// Reserving `/role announce` for premium guilds.
+    PremiumAnnouncements,
+    /// Reserving the mass role subcommands for premium guilds.
+    PremiumMassRoles,
+    /// Posting the server count to bot lists on a schedule.
+    BotListStats,
+}
+
+impl Flag {
+    pub const ALL: [Flag; 6] = [
+        Flag::DeferredRoleTasks,
+        Flag::ToggleRetryQueue,
+        Flag::PremiumRoleMenus,
+        Flag::PremiumAnnouncements,
+        Flag::PremiumMassRoles,
+        Flag::BotListStats,
+    ];
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|flag| flag.key() == key)
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Flag::DeferredRoleTasks => "deferred_role_tasks",
+            Flag::ToggleRetryQueue => "toggle_retry_queue",
+            Flag::PremiumRoleMenus => "premium_role_menus",
+            Flag::PremiumAnnouncements => "premium_announcements",
+            Flag::PremiumMassRoles => "premium_mass_roles",
+            Flag::BotListStats => "bot_list_stats",
+        }
+    }
+
+    pub fn default_enabled(self) -> bool {
+        match self {
+            Flag::DeferredRoleTasks => true,
+            Flag::ToggleRetryQueue => true,
+            Flag::PremiumRoleMenus => false,
+            Flag::PremiumAnnouncements => false,
+            Flag::PremiumMassRoles => false,
+            Flag::BotListStats => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FeatureFlags {
+    overrides: HashMap<String, bool>,
+}
+
+impl FeatureFlags {
+    pub fn new(overrides: HashMap<String, bool>) -> Self {
+        Self { overrides }
+    }
+
+    /// Loads the overrides from Parameter Store's `flags/` parameters and
+    /// the stored item, which wins. If the item can't be read, the flags
+    /// fall back to the parameters and then each flag's default, so a flag
+    /// outage never blocks interactions.
+    pub async fn load(dao: &FeatureFlagDao, parameters: &ConfigParameters) -> Self {
+        let mut overrides = parameters.flag_overrides();
+
+        match dao.get_flags().await {
+            Ok(stored) => overrides.extend(stored),
+            Err(err) => {
+                warn!(
+                    error = format!("{:#}", err),
+                    "Failed to load feature flags; using defaults"
+                );
+            }
+        }
+
+        Self::new(overrides)
+    }
+
+    pub fn is_enabled(&self, flag: Flag) -> bool {
+        self.overrides
+            .get(flag.key())
+            .copied()
+            .unwrap_or_else(|| flag.default_enabled())
+    }
+}
@@ -0,0 +1,5 @@
+pub mod feature_flags;
+pub mod premium_features;
+pub mod retention;
+#[cfg(feature = "billing")]
+pub mod settings;
@@ -0,0 +1,37 @@
+use super::feature_flags::{FeatureFlags, Flag};
+
+/// Features that can be reserved for premium guilds. Each is only gated
+/// while its flag is on, so a feature can be moved behind the paywall, or
+/// back out, without a redeploy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PremiumFeature {
+    /// `/rolemenu` and the role menu `/setup` posts.
+    RoleMenus,
+    /// `/role announce`.
+    Announcements,
+    /// `/role mass-assign` and `/role mass-remove`.
+    MassRoles,
+}
+
+impl PremiumFeature {
+    /// How the upsell names the feature.
+    pub fn name(self) -> &'static str {
+        match self {
+            PremiumFeature::RoleMenus => "Role menus",
+            PremiumFeature::Announcements => "Role announcements",
+            PremiumFeature::MassRoles => "Changing roles in bulk",
+        }
+    }
+
+    pub fn flag(self) -> Flag {
+        match self {
+            PremiumFeature::RoleMenus => Flag::PremiumRoleMenus,
+            PremiumFeature::Announcements => Flag::PremiumAnnouncements,
+            PremiumFeature::MassRoles => Flag::PremiumMassRoles,
+        }
+    }
+
+    pub fn is_premium_only(self, flags: &FeatureFlags) -> bool {
+        flags.is_enabled(self.flag())
+    }
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Duration, Utc};
+
+const DEFAULT_STATS_DAYS: i64 = 400;
+const DEFAULT_USAGE_DAYS: i64 = 400;
+const DEFAULT_TOMBSTONE_DAYS: i64 = 30;
+const DEFAULT_AUDIT_DAYS: i64 = 90;
+
+/// How long the `purge_expired` job keeps each kind of record, in days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Daily `STATS#` items, the bot's only historical records.
+    pub stats_days: i64,
+    /// Monthly `USAGE#` counters, counted from the end of their month.
+    pub usage_days: i64,
+    /// Guilds that removed the bot, counted from their tombstone.
+    pub tombstone_days: i64,
+    /// `AUDIT#` command records. These expire through DynamoDB's TTL
+    /// rather than the purge job.
+    pub audit_days: i64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            stats_days: DEFAULT_STATS_DAYS,
+            usage_days: DEFAULT_USAGE_DAYS,
+            tombstone_days: DEFAULT_TOMBSTONE_DAYS,
+            audit_days: DEFAULT_AUDIT_DAYS,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Reads `RETENTION_STATS_DAYS`, `RETENTION_USAGE_DAYS`,
+    /// `RETENTION_TOMBSTONE_DAYS` and `RETENTION_AUDIT_DAYS` through `var`,
+    /// keeping the default for any that's unset or not a positive number.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let days = |name: &str, default: i64| {
+            var(name)
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|days| *days > 0)
+                .unwrap_or(default)
+        };
+
+        Self {
+            stats_days: days("RETENTION_STATS_DAYS", DEFAULT_STATS_DAYS),
+            usage_days: days("RETENTION_USAGE_DAYS", DEFAULT_USAGE_DAYS),
+            tombstone_days: days("RETENTION_TOMBSTONE_DAYS", DEFAULT_TOMBSTONE_DAYS),
+            audit_days: days("RETENTION_AUDIT_DAYS", DEFAULT_AUDIT_DAYS),
+        }
+    }
+
+    /// Stats for days before this `YYYY-MM-DD` are expired.
+    pub fn stats_cutoff(&self, now: DateTime<Utc>) -> String {
+        (now - Duration::days(self.stats_days))
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+
+    /// Usage for months before this `YYYY-MM` ended more than `usage_days`
+    /// ago.
+    pub fn usage_cutoff(&self, now: DateTime<Utc>) -> String {
+        (now - Duration::days(self.usage_days))
+            .format("%Y-%m")
+            .to_string()
+    }
+
+    /// Guilds tombstoned before this (Unix seconds) are expired.
+    pub fn tombstone_cutoff(&self, now: DateTime<Utc>) -> i64 {
+        (now - Duration::days(self.tombstone_days)).timestamp()
+    }
+}
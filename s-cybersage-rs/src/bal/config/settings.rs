@@ -0,0 +1,88 @@
+use once_cell::sync::Lazy;
+use tracing::warn;
+
+use crate::{
+    bal::billing::usage_meter::DEFAULT_FREE_TIER_MONTHLY_TOGGLES,
+    dal::{
+        dao::settings::{SettingsDao, StoredSettings},
+        reader::parameter_reader::ConfigParameters,
+    },
+};
+
+/// The environment never changes within a process, so it's read once.
+static ENV_SETTINGS: Lazy<Settings> = Lazy::new(Settings::read_env);
+
+/// Bot-wide, non-secret tuning values. The environment provides the
+/// deploy-time defaults, Parameter Store overrides them, and the stored
+/// settings item overrides both, so limits and links can be changed without
+/// a redeploy.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub free_tier_monthly_toggles: u64,
+    pub premium_sku_id: Option<String>,
+    pub subscribe_url: Option<String>,
+}
+
+impl Settings {
+    pub fn from_env() -> Self {
+        ENV_SETTINGS.clone()
+    }
+
+    fn read_env() -> Self {
+        Self {
+            free_tier_monthly_toggles: std::env::var("FREE_TIER_MONTHLY_TOGGLES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_FREE_TIER_MONTHLY_TOGGLES),
+            premium_sku_id: std::env::var("PREMIUM_SKU_ID")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            subscribe_url: std::env::var("SUBSCRIBE_URL")
+                .ok()
+                .filter(|v| !v.is_empty()),
+        }
+    }
+
+    /// Environment defaults overlaid with the parameters and then the stored
+    /// settings. If the stored item can't be read, the rest are used as-is.
+    pub async fn load(dao: &SettingsDao, parameters: &ConfigParameters) -> Self {
+        let settings = Self::from_env().with_parameters(parameters);
+
+        match dao.get_settings().await {
+            Ok(stored) => settings.apply(stored),
+            Err(err) => {
+                warn!(
+                    error = format!("{:#}", err),
+                    "Failed to load settings; using environment"
+                );
+                settings
+            }
+        }
+    }
+
+    fn with_parameters(self, parameters: &ConfigParameters) -> Self {
+        Self {
+            free_tier_monthly_toggles: parameters
+                .parse("free_tier_monthly_toggles")
+                .unwrap_or(self.free_tier_monthly_toggles),
+            premium_sku_id: parameters
+                .get("premium_sku_id")
+                .map(str::to_string)
+                .or(self.premium_sku_id),
+            subscribe_url: parameters
+                .get("subscribe_url")
+                .map(str::to_string)
+                .or(self.subscribe_url),
+        }
+    }
+
+    fn apply(self, stored: StoredSettings) -> Self {
+        Self {
+            free_tier_monthly_toggles: stored
+                .free_tier_monthly_toggles
+                .unwrap_or(self.free_tier_monthly_toggles),
+            premium_sku_id: stored.premium_sku_id.or(self.premium_sku_id),
+            subscribe_url: stored.subscribe_url.or(self.subscribe_url),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    bal::discord::oauth_client::{OAuthClient, OAuthGuild, OAuthUser},
+    commands::MANAGE_GUILD,
+    dal::dao::session::{Session, SessionDao},
+};
+
+/// Sessions end after this long, or when the Discord access token behind
+/// them expires if that's sooner.
+const SESSION_LIFETIME_SECONDS: i64 = 12 * 60 * 60;
+
+/// Discord rate-limits `/users/@me/guilds` tightly, and one dashboard page
+/// load makes several guild-scoped calls.
+const GUILDS_CACHE_TTL: Duration = Duration::from_secs(30);
+const GUILDS_CACHE_MAX_ENTRIES: usize = 1024;
+
+type GuildsCache = Lazy<Mutex<HashMap<String, (Vec<OAuthGuild>, Instant)>>>;
+
+/// Managed guilds keyed by user ID.
+static GUILDS_CACHE: GuildsCache = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cached_guilds(user_id: &str) -> Option<Vec<OAuthGuild>> {
+    let cache = GUILDS_CACHE.lock().ok()?;
+    let (guilds, cached_at) = cache.get(user_id)?;
+
+    if cached_at.elapsed() > GUILDS_CACHE_TTL {
+        return None;
+    }
+
+    Some(guilds.clone())
+}
+
+fn cache_guilds(user_id: &str, guilds: &[OAuthGuild]) {
+    if let Ok(mut cache) = GUILDS_CACHE.lock() {
+        if cache.len() >= GUILDS_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (_, cached_at)| cached_at.elapsed() <= GUILDS_CACHE_TTL);
+        }
+
+        cache.insert(user_id.to_string(), (guilds.to_vec(), Instant::now()));
+    }
+}
+
+/// A new session, returned once when the user signs in.
+#[derive(Debug, Serialize)]
+pub struct IssuedSession {
+    pub token: String,
+    pub expires_at: i64,
+    pub user: OAuthUser,
+}
+
+/// Signs dashboard users in with Discord OAuth2 (`identify guilds`) and
+/// decides which guilds they may manage.
+pub struct SessionManager {
+    oauth: OAuthClient,
+    sessions: SessionDao,
+}
+
+impl SessionManager {
+    pub fn new(oauth: OAuthClient, sessions: SessionDao) -> Self {
+        Self { oauth, sessions }
+    }
+
+    /// Exchanges the code from the dashboard's OAuth2 redirect for a
+    /// session token.
+    pub async fn issue(&self, code: &str) -> Result<IssuedSession> {
+        let tokens = self.oauth.exchange_code(code).await?;
+        let user = self.oauth.current_user(&tokens.access_token).await?;
+
+        let token = new_token()?;
+        let expires_at = now() + tokens.expires_in.min(SESSION_LIFETIME_SECONDS);
+
+        let session = Session {
+            user_id: user.id.clone(),
+            access_token: tokens.access_token,
+            expires_at,
+        };
+        self.sessions.save_session(&hash(&token), &session).await?;
+
+        Ok(IssuedSession {
+            token,
+            expires_at,
+            user,
+        })
+    }
+
+    pub async fn authenticate(&self, token: &str) -> Result<Option<Session>> {
+        self.sessions.get_session(&hash(token), now()).await
+    }
+
+    pub async fn revoke(&self, token: &str) -> Result<()> {
+        self.sessions.delete_session(&hash(token)).await
+    }
+
+    /// Guilds the user owns or holds Manage Server in.
+    pub async fn managed_guilds(&self, session: &Session) -> Result<Vec<OAuthGuild>> {
+        if let Some(guilds) = cached_guilds(&session.user_id) {
+            return Ok(guilds);
+        }
+
+        let guilds: Vec<OAuthGuild> = self
+            .oauth
+            .current_user_guilds(&session.access_token)
+            .await?
+            .into_iter()
+            .filter(|guild| guild.grants(MANAGE_GUILD))
+            .collect();
+
+        cache_guilds(&session.user_id, &guilds);
+
+        Ok(guilds)
+    }
+
+    /// The guild, if the user manages it.
+    pub async fn managed_guild(
+        &self,
+        session: &Session,
+        guild_id: &str,
+    ) -> Result<Option<OAuthGuild>> {
+        Ok(self
+            .managed_guilds(session)
+            .await?
+            .into_iter()
+            .find(|guild| guild.id == guild_id))
+    }
+}
+
+/// Sessions are looked up by a hash of their token, so a leaked table
+/// doesn't leak usable tokens.
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn new_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|err| anyhow!("Failed to generate token: {}", err))?;
+
+    Ok(hex::encode(bytes))
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
@@ -0,0 +1,300 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    bal::{
+        activity::activity_recorder::ActivityRecorder,
+        discord::{
+            discord_api::DiscordApi, interaction_client::InteractionClient,
+            member_pager::MemberPager, role_manager::RoleAction,
+        },
+        retry::role_retry_worker::{backoff_seconds, JobOutcome, MAX_ATTEMPTS},
+    },
+    dal::{
+        model::{
+            activity_entry::{role_mention, ActivityEntry, RoleActivity},
+            deferred_task::DeferredTask,
+            mass_role_job::{MassRoleJob, MassRoleProgress},
+        },
+        queue::task_queue::TaskQueue,
+    },
+    error::DiscordApiError,
+    markdown,
+};
+
+/// Role changes made per task before the job re-queues itself. Together
+/// with the pacing below this keeps a task to a few seconds, so it can share
+/// a batch with other tasks without nearing the worker's timeout.
+const CHANGES_PER_TASK: usize = 20;
+
+/// Pause between role changes. Discord's per-guild bucket for member role
+/// changes is small, and a mass change shouldn't starve the guild's own
+/// toggles of it.
+const CHANGE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What stopped a task before it finished the guild.
+enum Interruption {
+    /// The per-task change budget is spent.
+    Budget,
+    /// Discord failed in a way worth retrying, e.g. a rate limit.
+    Retryable(anyhow::Error),
+}
+
+/// Works through `/role mass-assign` and `/role mass-remove` jobs, editing
+/// the command's deferred response with progress after every task. The
+/// interaction token expires after 15 minutes, so very large guilds finish
+/// with the log channel entry as the only report.
+pub struct MassRoleWorker {
+    discord_api: Arc<dyn DiscordApi>,
+    interaction_client: InteractionClient,
+    queue: TaskQueue,
+    activity: ActivityRecorder,
+}
+
+impl MassRoleWorker {
+    pub fn new(
+        discord_api: Arc<dyn DiscordApi>,
+        interaction_client: InteractionClient,
+        queue: TaskQueue,
+        activity: ActivityRecorder,
+    ) -> Self {
+        Self {
+            discord_api,
+            interaction_client,
+            queue,
+            activity,
+        }
+    }
+
+    #[instrument(skip_all, fields(guild_id = %job.origin.guild_id, role_id = %job.role_id, remove = job.remove))]
+    pub async fn process(&self, job: &MassRoleJob) -> Result<JobOutcome> {
+        let mut progress = job.progress;
+        let mut cursor = job.after.clone();
+
+        let interruption = match self.run(job, &mut progress, &mut cursor).await {
+            Ok(None) => return self.finish(job, progress).await,
+            Ok(Some(interruption)) => interruption,
+            Err(err) if DiscordApiError::is_missing_access_error(&err) => {
+                // The role is above the bot's, the bot lost access to the
+                // guild, or it can't list members; no other member would
+                // succeed either.
+                warn!(error = format!("{:#}", err), "Stopping mass role change");
+                self.report(
+                    job,
+                    &format!(
+                        "Stopped after changing {} members: I can't manage '{}' in this \
+                         server. Make sure my role is above it.",
+                        progress.changed,
+                        markdown::escape(&job.role_name)
+                    ),
+                )
+                .await;
+                return Ok(JobOutcome::Applied);
+            }
+            Err(err) => return Err(err),
+        };
+
+        let attempt = match interruption {
+            Interruption::Budget => 0,
+            Interruption::Retryable(err) => {
+                let next_attempt = job.attempt + 1;
+
+                if next_attempt >= MAX_ATTEMPTS {
+                    error!(
+                        attempt = next_attempt,
+                        error = format!("{:#}", err),
+                        "Giving up on mass role change"
+                    );
+                    self.report(
+                        job,
+                        &format!(
+                            "Stopped after changing {} members: Discord kept failing. Run the \
+                             command again to pick up the rest.",
+                            progress.changed
+                        ),
+                    )
+                    .await;
+                    return Ok(JobOutcome::Failed);
+                }
+
+                warn!(
+                    attempt = next_attempt,
+                    error = format!("{:#}", err),
+                    "Pausing mass role change"
+                );
+                next_attempt
+            }
+        };
+
+        let next = MassRoleJob {
+            after: cursor,
+            progress,
+            attempt,
+            ..job.clone()
+        };
+
+        let delay = if attempt == 0 {
+            0
+        } else {
+            backoff_seconds(attempt)
+        };
+
+        self.queue
+            .enqueue(&DeferredTask::MassRole(next), delay)
+            .await?;
+
+        self.report(job, &Self::progress_message(job, progress))
+            .await;
+
+        Ok(JobOutcome::Requeued)
+    }
+
+    /// Changes members after `cursor` until the guild is done (`None`) or the
+    /// task has to stop. `cursor` always names the last member fully handled,
+    /// so a re-queued job neither skips nor repeats anyone.
+    async fn run(
+        &self,
+        job: &MassRoleJob,
+        progress: &mut MassRoleProgress,
+        cursor: &mut Option<String>,
+    ) -> Result<Option<Interruption>> {
+        let guild_id = job.origin.guild_id.as_str();
+        let action = if job.remove {
+            RoleAction::Remove
+        } else {
+            RoleAction::Add
+        };
+
+        let mut pager = MemberPager::new(self.discord_api.as_ref(), guild_id, cursor.clone());
+        let mut changes = 0;
+
+        loop {
+            let page = match pager.next_page().await {
+                Ok(Some(page)) => page,
+                Ok(None) => return Ok(None),
+                Err(err) if DiscordApiError::is_retryable_error(&err) => {
+                    return Ok(Some(Interruption::Retryable(err)))
+                }
+                Err(err) => return Err(err),
+            };
+
+            for member in page {
+                let has_role = member.roles.contains(&job.role_id);
+                let in_filter = job
+                    .filter_role_id
+                    .as_ref()
+                    .is_none_or(|filter| member.roles.contains(filter));
+
+                if in_filter && has_role == job.remove {
+                    if changes == CHANGES_PER_TASK {
+                        return Ok(Some(Interruption::Budget));
+                    }
+
+                    if changes > 0 {
+                        tokio::time::sleep(CHANGE_INTERVAL).await;
+                    }
+                    changes += 1;
+
+                    match self
+                        .discord_api
+                        .modify_user_role(guild_id, &member.user.id, &job.role_id, action)
+                        .await
+                    {
+                        Ok(()) => progress.changed += 1,
+                        // The member left since the page was fetched.
+                        Err(err)
+                            if matches!(
+                                err.downcast_ref::<DiscordApiError>(),
+                                Some(DiscordApiError::NotFound)
+                            ) =>
+                        {
+                            progress.failed += 1
+                        }
+                        Err(err) if DiscordApiError::is_retryable_error(&err) => {
+                            return Ok(Some(Interruption::Retryable(err)))
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+
+                progress.checked += 1;
+                *cursor = Some(member.user.id);
+            }
+        }
+    }
+
+    async fn finish(&self, job: &MassRoleJob, progress: MassRoleProgress) -> Result<JobOutcome> {
+        info!(
+            checked = progress.checked,
+            changed = progress.changed,
+            failed = progress.failed,
+            "Finished mass role change"
+        );
+
+        let verb = if job.remove { "Removed" } else { "Gave" };
+        let mut message = format!(
+            "{} '{}' {} {} members ({} checked).",
+            verb,
+            markdown::escape(&job.role_name),
+            if job.remove { "from" } else { "to" },
+            progress.changed,
+            progress.checked
+        );
+        if progress.failed > 0 {
+            message.push_str(&format!(
+                " {} members left before they could be changed.",
+                progress.failed
+            ));
+        }
+
+        self.report(job, &message).await;
+
+        let activity = if job.remove {
+            RoleActivity::MassRemoved
+        } else {
+            RoleActivity::MassAssigned
+        };
+
+        self.activity
+            .record(
+                ActivityEntry::new(
+                    &job.origin.guild_id,
+                    activity,
+                    job.origin.user_id.as_deref(),
+                    role_mention(&job.role_id),
+                )
+                .succeeded(message),
+            )
+            .await;
+
+        Ok(JobOutcome::Applied)
+    }
+
+    fn progress_message(job: &MassRoleJob, progress: MassRoleProgress) -> String {
+        format!(
+            "{} '{}'… {} members changed so far ({} checked).",
+            if job.remove { "Removing" } else { "Assigning" },
+            markdown::escape(&job.role_name),
+            progress.changed,
+            progress.checked
+        )
+    }
+
+    async fn report(&self, job: &MassRoleJob, message: &str) {
+        let origin = &job.origin;
+
+        if let Err(err) = self
+            .interaction_client
+            .edit_original_response(&origin.application_id, &origin.interaction_token, message)
+            .await
+        {
+            warn!(
+                guild_id = %origin.guild_id,
+                error = format!("{:#}", err),
+                "Failed to edit original response"
+            );
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use tracing::{error, info, instrument, warn};
+
+#[cfg(feature = "billing")]
+use crate::dal::dao::subscription::SubscriptionReader;
+use crate::{
+    bal::{
+        discord::{discord_api::DiscordApi, member_pager::MemberPager},
+        retry::role_retry_worker::{backoff_seconds, JobOutcome, MAX_ATTEMPTS},
+    },
+    dal::{
+        dao::role_store::RoleStore,
+        model::{deferred_task::DeferredTask, member_count::MemberCountJob},
+        queue::task_queue::TaskQueue,
+    },
+    error::DiscordApiError,
+};
+
+/// Member pages fetched per task before the job re-queues itself with its
+/// cursor, keeping each invocation well inside the worker's timeout.
+const PAGES_PER_TASK: usize = 10;
+
+/// Counts how many members hold each registered role, for the suffix shown
+/// in autocomplete and the dashboard. Premium only: other guilds' counts are
+/// cleared instead.
+pub struct MemberCounter {
+    role_store: Arc<dyn RoleStore>,
+    discord_api: Arc<dyn DiscordApi>,
+    queue: TaskQueue,
+    #[cfg(feature = "billing")]
+    subscriptions: Option<SubscriptionReader>,
+}
+
+impl MemberCounter {
+    pub fn new(
+        role_store: Arc<dyn RoleStore>,
+        discord_api: Arc<dyn DiscordApi>,
+        queue: TaskQueue,
+        #[cfg(feature = "billing")] subscriptions: Option<SubscriptionReader>,
+    ) -> Self {
+        Self {
+            role_store,
+            discord_api,
+            queue,
+            #[cfg(feature = "billing")]
+            subscriptions,
+        }
+    }
+
+    #[instrument(skip_all, fields(guild_id = %job.guild_id, after = ?job.after))]
+    pub async fn count(&self, job: &MemberCountJob) -> Result<JobOutcome> {
+        let guild_id = job.guild_id.as_str();
+
+        // Checked once per count rather than per page, so a count that has
+        // started finishes even if the subscription lapses meanwhile.
+        if job.after.is_none() && !self.is_premium(guild_id).await? {
+            self.clear(guild_id).await?;
+            return Ok(JobOutcome::Applied);
+        }
+
+        let registered: HashSet<String> = self
+            .role_store
+            .list_roles(guild_id)
+            .await?
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect();
+
+        if registered.is_empty() {
+            self.clear(guild_id).await?;
+            return Ok(JobOutcome::Applied);
+        }
+
+        let mut counts = job.counts.clone();
+        let mut pager = MemberPager::new(self.discord_api.as_ref(), guild_id, job.after.clone());
+
+        for _ in 0..PAGES_PER_TASK {
+            let page = match pager.next_page().await {
+                Ok(Some(page)) => page,
+                Ok(None) => break,
+                Err(err) => return self.retry(job, pager.cursor(), counts, err).await,
+            };
+
+            for role_id in page.into_iter().flat_map(|member| member.roles) {
+                if registered.contains(&role_id) {
+                    *counts.entry(role_id).or_default() += 1;
+                }
+            }
+        }
+
+        if !pager.is_exhausted() {
+            let next = MemberCountJob {
+                guild_id: guild_id.to_string(),
+                after: pager.cursor().map(str::to_string),
+                counts,
+                attempt: 0,
+            };
+
+            self.queue
+                .enqueue(&DeferredTask::CountRoleMembers(next), 0)
+                .await?;
+
+            return Ok(JobOutcome::Requeued);
+        }
+
+        // Roles nobody holds are counted too, so they show "(0 members)"
+        // rather than no count at all.
+        for role_id in registered {
+            counts.entry(role_id).or_insert(0);
+        }
+
+        self.role_store.set_member_counts(guild_id, &counts).await?;
+
+        info!(roles = counts.len(), "Counted role members");
+
+        Ok(JobOutcome::Applied)
+    }
+
+    /// Re-queues the job from the page that failed, keeping the totals so
+    /// far. A guild the bot can't list members of, because it was removed
+    /// or lacks the Server Members intent, has its counts cleared instead.
+    async fn retry(
+        &self,
+        job: &MemberCountJob,
+        cursor: Option<&str>,
+        counts: HashMap<String, u64>,
+        err: anyhow::Error,
+    ) -> Result<JobOutcome> {
+        if DiscordApiError::is_missing_access_error(&err) {
+            warn!(
+                error = format!("{:#}", err),
+                "Cannot list guild members; clearing counts"
+            );
+            self.clear(&job.guild_id).await?;
+            return Ok(JobOutcome::Applied);
+        }
+
+        if !DiscordApiError::is_retryable_error(&err) {
+            return Err(err);
+        }
+
+        let next_attempt = job.attempt + 1;
+
+        if next_attempt >= MAX_ATTEMPTS {
+            error!(
+                attempt = next_attempt,
+                error = format!("{:#}", err),
+                "Giving up on member count"
+            );
+            return Ok(JobOutcome::Failed);
+        }
+
+        warn!(
+            attempt = next_attempt,
+            error = format!("{:#}", err),
+            "Requeueing member count"
+        );
+
+        let retry = MemberCountJob {
+            guild_id: job.guild_id.clone(),
+            after: cursor.map(str::to_string),
+            counts,
+            attempt: next_attempt,
+        };
+
+        self.queue
+            .enqueue(
+                &DeferredTask::CountRoleMembers(retry),
+                backoff_seconds(next_attempt),
+            )
+            .await?;
+
+        Ok(JobOutcome::Requeued)
+    }
+
+    async fn clear(&self, guild_id: &str) -> Result<()> {
+        if self
+            .role_store
+            .get_member_counts(guild_id)
+            .await?
+            .is_empty()
+        {
+            return Ok(());
+        }
+
+        self.role_store
+            .set_member_counts(guild_id, &HashMap::new())
+            .await
+    }
+
+    #[cfg(feature = "billing")]
+    async fn is_premium(&self, guild_id: &str) -> Result<bool> {
+        match self.subscriptions.as_ref() {
+            Some(reader) => reader.is_active(guild_id).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Without billing there are no tiers, so every guild gets counts.
+    #[cfg(not(feature = "billing"))]
+    async fn is_premium(&self, _guild_id: &str) -> Result<bool> {
+        Ok(true)
+    }
+}
@@ -0,0 +1,6 @@
+pub mod mass_role_worker;
+pub mod member_counter;
+pub mod retention_purger;
+pub mod stats_aggregator;
+pub mod task_credentials;
+pub mod task_executor;
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use tracing::{info, instrument};
+
+#[cfg(feature = "billing")]
+use anyhow::Context;
+
+#[cfg(feature = "billing")]
+use crate::dal::dao::usage::USAGE_KEY_PREFIX;
+use crate::{
+    bal::config::retention::RetentionPolicy,
+    dal::{
+        dao::{global_stats::STATS_KEY_PREFIX, retention::RetentionDao},
+        model::{deferred_task::DeferredTask, purge_job::PurgeJob},
+        queue::task_queue::TaskQueue,
+    },
+};
+
+/// Items deleted per task before the job re-queues itself, keeping each
+/// invocation well inside the worker's timeout.
+const MAX_DELETES_PER_TASK: usize = 250;
+const DELETES_PER_BATCH: usize = 25;
+/// Pause between batches, so a purge stays at about 25 deletes a second and
+/// leaves the tables' throughput to interactions.
+const BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Deletes records past their retention for the `purge_expired` job.
+pub struct RetentionPurger {
+    roles: RetentionDao,
+    #[cfg(feature = "billing")]
+    subscriptions: Option<RetentionDao>,
+    queue: TaskQueue,
+    policy: RetentionPolicy,
+}
+
+impl RetentionPurger {
+    pub fn new(
+        roles: RetentionDao,
+        #[cfg(feature = "billing")] subscriptions: Option<RetentionDao>,
+        queue: TaskQueue,
+        policy: RetentionPolicy,
+    ) -> Self {
+        Self {
+            roles,
+            #[cfg(feature = "billing")]
+            subscriptions,
+            queue,
+            policy,
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn purge(&self, job: &PurgeJob) -> Result<()> {
+        let now = Utc::now();
+
+        let (dao, keys, more) = match job {
+            PurgeJob::Stats => {
+                let keys = self
+                    .roles
+                    .global_keys_before(
+                        STATS_KEY_PREFIX,
+                        &self.policy.stats_cutoff(now),
+                        MAX_DELETES_PER_TASK,
+                    )
+                    .await?;
+                let more = keys.len() == MAX_DELETES_PER_TASK;
+                (&self.roles, keys, more)
+            }
+
+            #[cfg(feature = "billing")]
+            PurgeJob::Usage => {
+                let dao = self
+                    .subscriptions
+                    .as_ref()
+                    .context("GUILD_SUBSCRIPTIONS_TABLE_NAME is not set")?;
+                let keys = dao
+                    .keys_before(
+                        USAGE_KEY_PREFIX,
+                        &self.policy.usage_cutoff(now),
+                        MAX_DELETES_PER_TASK,
+                    )
+                    .await?;
+                let more = keys.len() == MAX_DELETES_PER_TASK;
+                (dao, keys, more)
+            }
+
+            PurgeJob::Guild { guild_id } => {
+                let cutoff = self.policy.tombstone_cutoff(now);
+
+                // Checked again by every task, since a guild that re-adds
+                // the bot mid-purge clears its tombstone.
+                if !self.roles.is_tombstoned_before(guild_id, cutoff).await? {
+                    info!("Guild is no longer expired; stopping purge");
+                    return Ok(());
+                }
+
+                let keys = self
+                    .roles
+                    .guild_keys(guild_id, MAX_DELETES_PER_TASK)
+                    .await?;
+
+                // The tombstone goes last, so an interrupted purge is found
+                // again by the next run, and only if it's still expired.
+                let rest: Vec<_> = keys
+                    .into_iter()
+                    .filter(|key| !self.roles.is_guild_record(key))
+                    .collect();
+
+                if rest.is_empty() {
+                    let deleted = self.roles.delete_guild_record(guild_id, cutoff).await?;
+                    info!(deleted, "Purged guild record");
+                    return Ok(());
+                }
+
+                (&self.roles, rest, true)
+            }
+        };
+
+        let deleted = keys.len();
+        let mut batches = keys.chunks(DELETES_PER_BATCH).peekable();
+        while let Some(batch) = batches.next() {
+            dao.delete_batch(batch.to_vec()).await?;
+
+            if batches.peek().is_some() {
+                tokio::time::sleep(BATCH_INTERVAL).await;
+            }
+        }
+
+        info!(deleted, more, "Purged expired items");
+
+        if more {
+            self.queue
+                .enqueue(&DeferredTask::PurgeExpired(job.clone()), 0)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
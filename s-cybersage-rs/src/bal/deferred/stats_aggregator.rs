@@ -0,0 +1,65 @@
+use anyhow::Result;
+use tracing::{info, instrument};
+
+#[cfg(feature = "billing")]
+use crate::dal::dao::subscription::SubscriptionReader;
+use crate::{
+    dal::{dao::global_stats::GlobalStatsDao, model::daily_stats::GuildDayStats},
+    metrics,
+};
+
+/// Adds one guild's day to the global daily stats, for the `aggregate_stats`
+/// job. Toggles are the growth of the guild's summed per-role toggle counts
+/// since its last aggregation.
+pub struct StatsAggregator {
+    stats: GlobalStatsDao,
+    #[cfg(feature = "billing")]
+    subscriptions: Option<SubscriptionReader>,
+}
+
+impl StatsAggregator {
+    pub fn new(
+        stats: GlobalStatsDao,
+        #[cfg(feature = "billing")] subscriptions: Option<SubscriptionReader>,
+    ) -> Self {
+        Self {
+            stats,
+            #[cfg(feature = "billing")]
+            subscriptions,
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn aggregate(&self, guild_id: &str, date: &str) -> Result<()> {
+        let (roles, total) = self.stats.guild_totals(guild_id).await?;
+
+        let Some(previous) = self.stats.advance_baseline(guild_id, date, total).await? else {
+            info!("Guild already aggregated for this day");
+            return Ok(());
+        };
+
+        let stats = GuildDayStats {
+            roles,
+            toggles: total.saturating_sub(previous),
+            premium: self.is_premium(guild_id).await?,
+        };
+
+        self.stats.add_guild(date, &stats).await?;
+        metrics::emit_guild_day(&stats);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "billing")]
+    async fn is_premium(&self, guild_id: &str) -> Result<bool> {
+        match &self.subscriptions {
+            Some(subscriptions) => subscriptions.is_active(guild_id).await,
+            None => Ok(false),
+        }
+    }
+
+    #[cfg(not(feature = "billing"))]
+    async fn is_premium(&self, _guild_id: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use tracing::instrument;
+
+use crate::dal::{
+    dao::guild_record::GuildRecordDao, model::deferred_task::DeferredTask,
+    reader::tenant_secrets::TenantSecrets,
+};
+
+/// Picks the bot a deferred task calls Discord as. Tasks queued by an
+/// interaction run as its application; guild tasks run as the application
+/// on the guild's `GUILD` record, looked up once per guild per batch.
+pub struct TaskCredentials {
+    tenants: Option<TenantSecrets>,
+    default_application_id: Option<String>,
+    guild_records: GuildRecordDao,
+    guild_applications: HashMap<String, Option<String>>,
+}
+
+impl TaskCredentials {
+    pub fn new(
+        tenants: Option<TenantSecrets>,
+        default_application_id: Option<String>,
+        guild_records: GuildRecordDao,
+    ) -> Self {
+        Self {
+            tenants,
+            default_application_id,
+            guild_records,
+            guild_applications: HashMap::new(),
+        }
+    }
+
+    /// The white-label application the task runs as and its bot token, or
+    /// `None` for the default bot.
+    #[instrument(skip_all, fields(kind = task.kind()))]
+    pub async fn tenant_for(&mut self, task: &DeferredTask) -> Result<Option<(String, String)>> {
+        if self.tenants.is_none() {
+            return Ok(None);
+        }
+
+        let application_id = match (task.application_id(), task.bot_guild_id()) {
+            (Some(application_id), _) => Some(application_id.to_string()),
+            (None, Some(guild_id)) => self.guild_application(guild_id).await?,
+            (None, None) => None,
+        };
+
+        let Some(application_id) =
+            application_id.filter(|id| self.default_application_id.as_ref() != Some(id))
+        else {
+            return Ok(None);
+        };
+
+        let Some(tenants) = &self.tenants else {
+            return Ok(None);
+        };
+        let credentials = tenants.get(&application_id).await?;
+
+        Ok(Some((application_id, credentials.token)))
+    }
+
+    async fn guild_application(&mut self, guild_id: &str) -> Result<Option<String>> {
+        if let Some(application_id) = self.guild_applications.get(guild_id) {
+            return Ok(application_id.clone());
+        }
+
+        let application_id = self.guild_records.application_id(guild_id).await?;
+        self.guild_applications
+            .insert(guild_id.to_string(), application_id.clone());
+
+        Ok(application_id)
+    }
+}
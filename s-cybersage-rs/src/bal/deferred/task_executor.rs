@@ -0,0 +1,360 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use chrono::Utc;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    bal::{
+        activity::activity_recorder::ActivityRecorder,
+        discord::{
+            discord_api::DiscordApi, interaction_client::InteractionClient,
+            role_hierarchy::RoleHierarchy,
+        },
+    },
+    dal::{
+        dao::{guild_record::GuildRecordDao, role_store::RoleStore},
+        model::{
+            activity_entry::{ActivityEntry, RoleActivity},
+            batch_report::BatchReport,
+            deferred_task::TaskOrigin,
+            role_announcement::RoleAnnouncement,
+            role_name,
+        },
+    },
+    error::{CommandError, DiscordApiError},
+    markdown,
+};
+
+const MAX_MESSAGE_CHARS: usize = 1_900;
+/// Embed field values are capped at 1024 characters.
+const MAX_FIELD_CHARS: usize = 1_000;
+
+/// Runs heavy guild-wide tasks on the queue worker and reports the result by
+/// replacing the interaction's deferred placeholder.
+pub struct TaskExecutor {
+    role_store: Arc<dyn RoleStore>,
+    discord_api: Arc<dyn DiscordApi>,
+    interaction_client: InteractionClient,
+    activity: ActivityRecorder,
+    guild_records: GuildRecordDao,
+}
+
+impl TaskExecutor {
+    pub fn new(
+        role_store: Arc<dyn RoleStore>,
+        discord_api: Arc<dyn DiscordApi>,
+        interaction_client: InteractionClient,
+        activity: ActivityRecorder,
+        guild_records: GuildRecordDao,
+    ) -> Self {
+        Self {
+            role_store,
+            discord_api,
+            interaction_client,
+            activity,
+            guild_records,
+        }
+    }
+
+    /// Registers every non-managed guild role (except @everyone) as
+    /// self-assignable, apart from roles with moderation permissions and
+    /// roles the bot or the member who asked couldn't assign.
+    pub async fn import_roles(&self, origin: &TaskOrigin) -> Result<()> {
+        let result = self.try_import_roles(origin).await;
+
+        let message = match &result {
+            Ok(report) => report.summary("roles imported"),
+            Err(_) => "Failed to import roles.".to_string(),
+        };
+
+        self.report(origin, &message).await;
+
+        // One entry for the whole import rather than one per saved role.
+        let entry = ActivityEntry::new(
+            &origin.guild_id,
+            RoleActivity::Imported,
+            origin.user_id.as_deref(),
+            "All server roles",
+        );
+        let entry = match &result {
+            Ok(report) if !report.has_failures() => entry.succeeded(message),
+            _ => entry.failed(message),
+        };
+        self.activity.record(entry).await;
+
+        result.map(|_| ())
+    }
+
+    /// Saves each role on its own, so one failed save is reported alongside
+    /// the roles that were imported rather than stopping the import.
+    async fn try_import_roles(&self, origin: &TaskOrigin) -> Result<BatchReport> {
+        let guild_id = origin.guild_id.as_str();
+
+        let hierarchy =
+            RoleHierarchy::load(self.discord_api.as_ref(), guild_id, &origin.application_id)
+                .await?;
+
+        let mut report = BatchReport::default();
+
+        // The @everyone role shares the guild's ID; managed roles belong to
+        // integrations and can't be assigned manually.
+        for role in hierarchy
+            .roles()
+            .iter()
+            .filter(|r| !r.managed && r.id != guild_id)
+        {
+            // Held to what `/role save` allows the member who asked, or
+            // they could make roles above their own self-assignable.
+            let refusal = hierarchy.role_refusal(role).or_else(|| {
+                hierarchy.member_refusal(role.position, &origin.member_roles, origin.administrator)
+            });
+            if let Some(reason) = refusal {
+                report.record_failure(&role.name, reason);
+                continue;
+            }
+
+            let name = match role_name::normalize(&role.name) {
+                Ok(name) => name,
+                Err(invalid) => {
+                    report.record_failure(&role.name, invalid.reason());
+                    continue;
+                }
+            };
+
+            // Another role already holds the name, or this role was saved
+            // under an alias because of such a clash; overwriting either
+            // would make toggling by name ambiguous.
+            match self
+                .role_store
+                .name_conflict(guild_id, &role.id, name)
+                .await
+            {
+                Ok(None) => {}
+                Ok(Some(_)) => {
+                    report.record_failure(name, "another role is registered under this name");
+                    continue;
+                }
+                Err(err) => {
+                    report.record_failure(name, CommandError::from(err).short_reason());
+                    continue;
+                }
+            }
+
+            match self
+                .role_store
+                .save_role_with_icon(guild_id, &role.id, name, &role.icon)
+                .await
+            {
+                Ok(()) => report.record_success(),
+                Err(err) => {
+                    warn!(
+                        role_id = %role.id,
+                        error = format!("{:#}", err),
+                        "Failed to import role"
+                    );
+                    report.record_failure(&role.name, CommandError::from(err).short_reason());
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Removes mappings for roles that were deleted in Discord, so
+    /// autocomplete stops offering them, and logs what was removed. Guilds
+    /// Discord reports unknown or inaccessible are left alone, but
+    /// tombstoned so the retention job can purge them; any other 403 or 404
+    /// fails the task so it's retried.
+    #[instrument(skip(self))]
+    pub async fn reconcile_roles(&self, guild_id: &str) -> Result<()> {
+        let live: HashSet<String> = match self.discord_api.fetch_guild_roles(guild_id).await {
+            Ok(roles) => roles.into_iter().map(|role| role.id).collect(),
+            Err(err) if DiscordApiError::is_guild_unavailable_error(&err) => {
+                warn!(
+                    error = format!("{:#}", err),
+                    "Skipping reconciliation for inaccessible guild"
+                );
+                return self
+                    .guild_records
+                    .tombstone(guild_id, Utc::now().timestamp())
+                    .await;
+            }
+            Err(err) => return Err(err),
+        };
+
+        self.guild_records.clear_tombstone(guild_id).await?;
+
+        let mut pruned = Vec::new();
+        let mut report = BatchReport::default();
+
+        for (name, id) in self.role_store.list_roles(guild_id).await? {
+            if live.contains(&id) {
+                continue;
+            }
+
+            match self.role_store.delete_role(guild_id, &id).await {
+                Ok(()) => {
+                    report.record_success();
+                    pruned.push(name);
+                }
+                Err(err) => {
+                    warn!(
+                        role_id = %id,
+                        error = format!("{:#}", err),
+                        "Failed to remove mapping for deleted role"
+                    );
+                    report.record_failure(name, CommandError::from(err).short_reason());
+                }
+            }
+        }
+
+        if pruned.is_empty() && !report.has_failures() {
+            return Ok(());
+        }
+
+        info!(
+            pruned = pruned.len(),
+            failed = report.failures.len(),
+            "Removed mappings for deleted roles"
+        );
+
+        pruned.sort_by_key(|name| name.to_lowercase());
+
+        let entry = ActivityEntry::new(
+            guild_id,
+            RoleActivity::Pruned,
+            None,
+            format!("{} deleted role(s)", pruned.len() + report.failures.len()),
+        );
+        let entry = if report.has_failures() {
+            entry.failed(report.summary("mappings removed"))
+        } else {
+            entry.succeeded(Self::format_pruned(&pruned))
+        };
+        self.activity.record(entry).await;
+
+        Ok(())
+    }
+
+    fn format_pruned(names: &[String]) -> String {
+        let mut body = String::new();
+        let mut omitted = 0;
+
+        for name in names {
+            let line = format!("'{}'\n", markdown::escape(name));
+
+            if body.len() + line.len() > MAX_FIELD_CHARS {
+                omitted += 1;
+                continue;
+            }
+
+            body.push_str(&line);
+        }
+
+        if omitted > 0 {
+            body.push_str(&format!("…and {} more.", omitted));
+        }
+
+        body.trim_end().to_string()
+    }
+
+    /// Posts a scheduled `/role announce`, unless the role stopped being
+    /// self-assignable meanwhile. A channel the bot can no longer post in is
+    /// logged and skipped rather than retried.
+    #[instrument(skip_all, fields(guild_id = %announcement.guild_id, role_id = %announcement.role_id))]
+    pub async fn post_announcement(&self, announcement: &RoleAnnouncement) -> Result<()> {
+        if self
+            .role_store
+            .get_role_by_id(&announcement.guild_id, &announcement.role_id)
+            .await?
+            .is_none()
+        {
+            info!("Skipping announcement for unregistered role");
+            return Ok(());
+        }
+
+        match self
+            .discord_api
+            .create_message(&announcement.channel_id, &announcement.message())
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) if DiscordApiError::is_missing_access_error(&err) => {
+                warn!(
+                    channel_id = %announcement.channel_id,
+                    error = format!("{:#}", err),
+                    "Skipping announcement for inaccessible channel"
+                );
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn export_roles(&self, origin: &TaskOrigin) -> Result<()> {
+        let result = self.role_store.list_roles(&origin.guild_id).await;
+
+        let message = match &result {
+            Ok(roles) if roles.is_empty() => "No self-assignable roles are registered.".to_string(),
+            Ok(roles) => Self::format_export(roles),
+            Err(_) => "Failed to export roles.".to_string(),
+        };
+
+        self.report(origin, &message).await;
+        result.map(|_| ())
+    }
+
+    /// The export message: `(name, id)` pairs as CSV in a code block, cut
+    /// off with a count of the rest once it reaches Discord's message limit.
+    pub fn format_export(roles: &[(String, String)]) -> String {
+        let mut body = String::new();
+        let mut omitted = 0;
+
+        for (name, id) in roles {
+            let line = format!("{},{}\n", csv_field(id), csv_field(name));
+
+            if body.len() + line.len() > MAX_MESSAGE_CHARS {
+                omitted += 1;
+                continue;
+            }
+
+            body.push_str(&line);
+        }
+
+        let mut message = format!("```csv\nrole_id,role_name\n{}```", body);
+
+        if omitted > 0 {
+            message.push_str(&format!("\n…and {} more.", omitted));
+        }
+
+        message
+    }
+
+    async fn report(&self, origin: &TaskOrigin, message: &str) {
+        if let Err(err) = self
+            .interaction_client
+            .edit_original_response(&origin.application_id, &origin.interaction_token, message)
+            .await
+        {
+            warn!(
+                guild_id = %origin.guild_id,
+                error = format!("{:#}", err),
+                "Failed to edit original response"
+            );
+        }
+    }
+}
+
+/// Quotes `value` per RFC 4180 when it holds a comma, quote or line break,
+/// doubling its quotes. Backticks are followed by a zero-width space, so a
+/// name can't close the code block the export is sent in.
+fn csv_field(value: &str) -> String {
+    let value = value.replace('`', "`\u{200B}");
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::instrument;
+
+use crate::error::DiscordApiError;
+
+#[derive(Debug, Deserialize)]
+struct CreatedMessage {
+    id: String,
+}
+
+/// Posts and edits bot messages in guild channels.
+#[derive(Clone)]
+pub struct ChannelClient {
+    client: Client,
+    bot_token: String,
+}
+
+impl ChannelClient {
+    pub fn new(client: Client, bot_token: impl Into<String>) -> Self {
+        Self {
+            client,
+            bot_token: bot_token.into(),
+        }
+    }
+
+    /// `message` is a Discord message object, e.g. `{"embeds": [...]}`.
+    /// Returns the new message's ID.
+    #[instrument(skip(self, message))]
+    pub async fn create_message(&self, channel_id: &str, message: &Value) -> Result<String> {
+        let url = format!(
+            "https://discord.com/api/v10/channels/{}/messages",
+            channel_id
+        );
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .json(message)
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send create_message request")?;
+
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error while creating message");
+        }
+
+        let created: CreatedMessage = resp
+            .json()
+            .await
+            .context("Failed to deserialize created message")?;
+
+        Ok(created.id)
+    }
+
+    /// Replaces the fields present in `message`. Only the bot's own messages
+    /// can be edited.
+    #[instrument(skip(self, message))]
+    pub async fn edit_message(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        message: &Value,
+    ) -> Result<()> {
+        let url = format!(
+            "https://discord.com/api/v10/channels/{}/messages/{}",
+            channel_id, message_id
+        );
+
+        let resp = self
+            .client
+            .patch(&url)
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .json(message)
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send edit_message request")?;
+
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error while editing message");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::role_manager::{GuildMember, GuildRole, RoleAction};
+use crate::dal::model::application_command::ApplicationCommand;
+
+/// The Discord REST operations the bot performs with its bot token.
+/// Implemented by `RoleManager` against discord.com (wrapped in
+/// `LazyDiscordApi` on the interaction path) and by `RecordingDiscordApi` for
+/// tests.
+#[async_trait]
+pub trait DiscordApi: Send + Sync {
+    async fn fetch_member_roles(&self, guild_id: &str, user_id: &str) -> Result<Vec<String>>;
+
+    async fn fetch_guild_roles(&self, guild_id: &str) -> Result<Vec<GuildRole>>;
+
+    /// One page of the guild's members, ordered by user ID and starting
+    /// after `after`. Needs the Server Members intent; see `MemberPager`.
+    async fn list_guild_members(
+        &self,
+        guild_id: &str,
+        after: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GuildMember>>;
+
+    /// Fails with a `DiscordApiError`; see `DiscordApiError::is_retryable`.
+    async fn modify_user_role(
+        &self,
+        guild_id: &str,
+        user_id: &str,
+        role_id: &str,
+        action: RoleAction,
+    ) -> Result<()>;
+
+    /// Posts `message` to a channel, returning the new message's ID.
+    async fn create_message(&self, channel_id: &str, message: &Value) -> Result<String>;
+
+    async fn edit_message(&self, channel_id: &str, message_id: &str, message: &Value)
+        -> Result<()>;
+
+    /// Commands registered for the application, globally or in one guild.
+    async fn fetch_application_commands(
+        &self,
+        application_id: &str,
+        guild_id: Option<&str>,
+    ) -> Result<Vec<ApplicationCommand>>;
+}
@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::json;
+use tracing::instrument;
+
+use crate::{dal::model::interaction_response::MessageFlags, stage};
+
+/// Client for the interaction webhook endpoints, which are authorized by the
+/// interaction token rather than the bot token (valid for 15 minutes).
+#[derive(Clone)]
+pub struct InteractionClient {
+    client: Client,
+}
+
+impl InteractionClient {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    #[instrument(skip(self, interaction_token, content))]
+    pub async fn edit_original_response(
+        &self,
+        application_id: &str,
+        interaction_token: &str,
+        content: &str,
+    ) -> Result<()> {
+        let url = format!(
+            "https://discord.com/api/v10/webhooks/{}/{}/messages/@original",
+            application_id, interaction_token
+        );
+
+        self.client
+            .patch(&url)
+            .json(&json!({ "content": stage::mark(content) }))
+            .send()
+            .await
+            .context("Failed to send edit_original_response request")?
+            .error_for_status()
+            .context("Discord returned error while editing original response")?;
+
+        Ok(())
+    }
+
+    /// Sends an ephemeral followup message. The first followup to a deferred
+    /// response replaces its "thinking…" placeholder.
+    #[instrument(skip(self, interaction_token, content))]
+    pub async fn create_followup(
+        &self,
+        application_id: &str,
+        interaction_token: &str,
+        content: &str,
+    ) -> Result<()> {
+        let url = format!(
+            "https://discord.com/api/v10/webhooks/{}/{}",
+            application_id, interaction_token
+        );
+
+        self.client
+            .post(&url)
+            .json(&json!({
+                "content": stage::mark(content),
+                "flags": MessageFlags::EPHEMERAL.bits(),
+            }))
+            .send()
+            .await
+            .context("Failed to send create_followup request")?
+            .error_for_status()
+            .context("Discord returned error while creating followup")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::OnceCell;
+
+use super::{
+    discord_api::DiscordApi,
+    role_manager::{GuildMember, GuildRole, RoleAction, RoleManager},
+};
+use crate::dal::{
+    model::application_command::ApplicationCommand, reader::secrets_reader::SecretSource,
+};
+
+/// A `RoleManager` that fetches the bot token on its first Discord call, so
+/// commands that never call Discord (saving a role, or work deferred to the
+/// task worker) don't wait on the secrets backend.
+pub struct LazyDiscordApi {
+    client: Client,
+    token: SecretSource,
+    role_manager: OnceCell<RoleManager>,
+}
+
+impl LazyDiscordApi {
+    pub fn new(client: Client, token: SecretSource) -> Self {
+        Self {
+            client,
+            token,
+            role_manager: OnceCell::new(),
+        }
+    }
+
+    async fn role_manager(&self) -> Result<&RoleManager> {
+        self.role_manager
+            .get_or_try_init(|| async {
+                let token = self.token.get().await?;
+
+                Ok(RoleManager::new(self.client.clone(), token))
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl DiscordApi for LazyDiscordApi {
+    async fn fetch_member_roles(&self, guild_id: &str, user_id: &str) -> Result<Vec<String>> {
+        self.role_manager()
+            .await?
+            .fetch_member_roles(guild_id, user_id)
+            .await
+    }
+
+    async fn fetch_guild_roles(&self, guild_id: &str) -> Result<Vec<GuildRole>> {
+        self.role_manager().await?.fetch_guild_roles(guild_id).await
+    }
+
+    async fn list_guild_members(
+        &self,
+        guild_id: &str,
+        after: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GuildMember>> {
+        self.role_manager()
+            .await?
+            .list_guild_members(guild_id, after, limit)
+            .await
+    }
+
+    async fn create_message(&self, channel_id: &str, message: &Value) -> Result<String> {
+        self.role_manager()
+            .await?
+            .create_message(channel_id, message)
+            .await
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        message: &Value,
+    ) -> Result<()> {
+        self.role_manager()
+            .await?
+            .edit_message(channel_id, message_id, message)
+            .await
+    }
+
+    async fn modify_user_role(
+        &self,
+        guild_id: &str,
+        user_id: &str,
+        role_id: &str,
+        action: RoleAction,
+    ) -> Result<()> {
+        self.role_manager()
+            .await?
+            .modify_user_role(guild_id, user_id, role_id, action)
+            .await
+    }
+
+    async fn fetch_application_commands(
+        &self,
+        application_id: &str,
+        guild_id: Option<&str>,
+    ) -> Result<Vec<ApplicationCommand>> {
+        self.role_manager()
+            .await?
+            .fetch_application_commands(application_id, guild_id)
+            .await
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+
+use super::{discord_api::DiscordApi, role_manager::GuildMember};
+
+/// Discord's maximum page size for listing guild members.
+pub const MEMBER_PAGE_SIZE: u32 = 1000;
+
+/// Walks a guild's member list a page at a time. The cursor is the last user
+/// ID seen, so a job that runs out of time can be resumed from `cursor()` in
+/// a later invocation.
+pub struct MemberPager<'a> {
+    discord_api: &'a dyn DiscordApi,
+    guild_id: String,
+    after: Option<String>,
+    exhausted: bool,
+}
+
+impl<'a> MemberPager<'a> {
+    pub fn new(
+        discord_api: &'a dyn DiscordApi,
+        guild_id: impl Into<String>,
+        after: Option<String>,
+    ) -> Self {
+        Self {
+            discord_api,
+            guild_id: guild_id.into(),
+            after,
+            exhausted: false,
+        }
+    }
+
+    /// The next page, or `None` once every member has been returned. A
+    /// failed request leaves the cursor where it was.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<GuildMember>>> {
+        if self.exhausted {
+            return Ok(None);
+        }
+
+        let members = self
+            .discord_api
+            .list_guild_members(&self.guild_id, self.after.as_deref(), MEMBER_PAGE_SIZE)
+            .await?;
+
+        self.exhausted = members.len() < MEMBER_PAGE_SIZE as usize;
+
+        match members.last() {
+            Some(last) => self.after = Some(last.user.id.clone()),
+            None => return Ok(None),
+        }
+
+        Ok(Some(members))
+    }
+
+    pub fn cursor(&self) -> Option<&str> {
+        self.after.as_deref()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
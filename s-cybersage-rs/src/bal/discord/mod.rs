@@ -1 +1,10 @@
+pub mod channel_client;
+pub mod discord_api;
+pub mod interaction_client;
+pub mod lazy_discord_api;
+pub mod member_pager;
+pub mod oauth_client;
+#[cfg(any(test, feature = "test-util"))]
+pub mod recording_discord_api;
+pub mod role_hierarchy;
 pub mod role_manager;
@@ -0,0 +1,238 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::{
+    commands::ADMINISTRATOR, dal::model::role_connection::RoleConnection, error::DiscordApiError,
+};
+
+const AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+const TOKEN_URL: &str = "https://discord.com/api/v10/oauth2/token";
+
+/// Tokens from Discord's OAuth2 token endpoint.
+#[derive(Debug, Deserialize)]
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Seconds until `access_token` expires.
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthUser {
+    pub id: String,
+    pub username: String,
+}
+
+/// A guild the user is in, from `/users/@me/guilds`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthGuild {
+    pub id: String,
+    pub name: String,
+
+    #[serde(default)]
+    pub icon: Option<String>,
+
+    #[serde(default)]
+    pub owner: bool,
+
+    /// The user's permission bitfield in the guild, as a decimal string.
+    #[serde(default)]
+    pub permissions: String,
+}
+
+impl OAuthGuild {
+    pub fn has_permission(&self, permission: u64) -> bool {
+        self.permissions
+            .parse::<u64>()
+            .is_ok_and(|permissions| permissions & permission == permission)
+    }
+
+    /// Whether the user owns the guild or holds `permission` or
+    /// Administrator in it.
+    pub fn grants(&self, permission: u64) -> bool {
+        self.owner || self.has_permission(ADMINISTRATOR) || self.has_permission(permission)
+    }
+}
+
+/// Discord's OAuth2 endpoints, acting as the application rather than the bot:
+/// the Linked Roles flow and dashboard sign-in.
+pub struct OAuthClient {
+    client: Client,
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+}
+
+impl OAuthClient {
+    pub fn new(
+        client: Client,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        redirect_uri: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            redirect_uri: redirect_uri.into(),
+        }
+    }
+
+    /// Where to send the user to grant access. `state` comes back on the
+    /// callback unchanged. Needs no secret, so it isn't a method.
+    pub fn authorize_url(
+        client_id: &str,
+        redirect_uri: &str,
+        scopes: &str,
+        state: &str,
+    ) -> Result<Url> {
+        Url::parse_with_params(
+            AUTHORIZE_URL,
+            [
+                ("client_id", client_id),
+                ("redirect_uri", redirect_uri),
+                ("response_type", "code"),
+                ("scope", scopes),
+                ("state", state),
+                ("prompt", "consent"),
+            ],
+        )
+        .context("Failed to build authorize URL")
+    }
+
+    /// Where to send a server admin to add the bot with `permissions`.
+    /// With `guild_id`, that server is preselected, so an admin can re-grant
+    /// permissions the bot's role lost.
+    pub fn bot_invite_url(
+        client_id: &str,
+        guild_id: Option<&str>,
+        permissions: u64,
+    ) -> Result<Url> {
+        let permissions = permissions.to_string();
+        let mut params = vec![
+            ("client_id", client_id),
+            ("scope", "bot applications.commands"),
+            ("permissions", permissions.as_str()),
+        ];
+        if let Some(guild_id) = guild_id {
+            params.extend([("guild_id", guild_id), ("disable_guild_select", "true")]);
+        }
+
+        Url::parse_with_params(AUTHORIZE_URL, params).context("Failed to build invite URL")
+    }
+
+    #[instrument(skip_all)]
+    pub async fn exchange_code(&self, code: &str) -> Result<OAuthTokens> {
+        self.request_tokens(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_uri),
+        ])
+        .await
+        .context("Failed to exchange authorization code")
+    }
+
+    #[instrument(skip_all)]
+    pub async fn refresh(&self, refresh_token: &str) -> Result<OAuthTokens> {
+        self.request_tokens(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ])
+        .await
+        .context("Failed to refresh access token")
+    }
+
+    async fn request_tokens(&self, params: &[(&str, &str)]) -> Result<OAuthTokens> {
+        let resp = self
+            .client
+            .post(TOKEN_URL)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(params)
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send token request")?;
+
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error from the token endpoint");
+        }
+
+        resp.json().await.context("Failed to deserialize tokens")
+    }
+
+    #[instrument(skip_all)]
+    pub async fn current_user(&self, access_token: &str) -> Result<OAuthUser> {
+        let resp = self
+            .client
+            .get("https://discord.com/api/v10/users/@me")
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send current user request")?;
+
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error while fetching current user");
+        }
+
+        resp.json()
+            .await
+            .context("Failed to deserialize current user")
+    }
+
+    /// Needs the `guilds` scope.
+    #[instrument(skip_all)]
+    pub async fn current_user_guilds(&self, access_token: &str) -> Result<Vec<OAuthGuild>> {
+        let resp = self
+            .client
+            .get("https://discord.com/api/v10/users/@me/guilds")
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send current user guilds request")?;
+
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error while fetching current user guilds");
+        }
+
+        resp.json()
+            .await
+            .context("Failed to deserialize current user guilds")
+    }
+
+    /// Replaces the user's metadata for this application.
+    #[instrument(skip_all)]
+    pub async fn put_role_connection(
+        &self,
+        access_token: &str,
+        connection: &RoleConnection,
+    ) -> Result<()> {
+        let url = format!(
+            "https://discord.com/api/v10/users/@me/applications/{}/role-connection",
+            self.client_id
+        );
+
+        let resp = self
+            .client
+            .put(&url)
+            .bearer_auth(access_token)
+            .json(connection)
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send role connection update")?;
+
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error while updating role connection");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use super::{
+    discord_api::DiscordApi,
+    role_manager::{GuildMember, GuildRole, MemberUser, RoleAction},
+};
+use crate::{dal::model::application_command::ApplicationCommand, error::DiscordApiError};
+
+/// A call made through `RecordingDiscordApi`, in the order it was made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscordCall {
+    FetchMemberRoles {
+        guild_id: String,
+        user_id: String,
+    },
+    FetchGuildRoles {
+        guild_id: String,
+    },
+    ListGuildMembers {
+        guild_id: String,
+        after: Option<String>,
+    },
+    FetchApplicationCommands {
+        application_id: String,
+        guild_id: Option<String>,
+    },
+    CreateMessage {
+        channel_id: String,
+        message: Value,
+    },
+    EditMessage {
+        channel_id: String,
+        message_id: String,
+        message: Value,
+    },
+    ModifyUserRole {
+        guild_id: String,
+        user_id: String,
+        role_id: String,
+        action: RoleAction,
+    },
+}
+
+/// Failures `RecordingDiscordApi` can be scripted to return, mirroring the
+/// errors `RoleManager` produces for the corresponding Discord responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptedFailure {
+    /// 403, e.g. the role is above the bot's highest role.
+    Forbidden,
+    NotFound,
+    /// Unknown Guild or Missing Access on a guild-level call.
+    GuildUnavailable,
+    RateLimited,
+    ServerError,
+}
+
+impl ScriptedFailure {
+    fn into_error(self) -> anyhow::Error {
+        match self {
+            ScriptedFailure::Forbidden => DiscordApiError::Forbidden,
+            ScriptedFailure::NotFound => DiscordApiError::NotFound,
+            ScriptedFailure::GuildUnavailable => DiscordApiError::GuildUnavailable,
+            ScriptedFailure::RateLimited => DiscordApiError::RateLimited,
+            ScriptedFailure::ServerError => {
+                DiscordApiError::ServerError(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+        .into()
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    calls: Vec<DiscordCall>,
+    member_roles: HashMap<(String, String), Vec<String>>,
+    guild_roles: HashMap<String, Vec<GuildRole>>,
+    application_commands: Vec<ApplicationCommand>,
+    modify_failures: VecDeque<ScriptedFailure>,
+    fetch_roles_failures: VecDeque<ScriptedFailure>,
+}
+
+/// `DiscordApi` test double that records every call and serves scripted
+/// member and guild roles. Successful modifications update the member's
+/// roles, so repeated toggles behave like the real API.
+#[derive(Debug, Default)]
+pub struct RecordingDiscordApi {
+    state: Mutex<State>,
+}
+
+impl RecordingDiscordApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_member_roles(self, guild_id: &str, user_id: &str, roles: &[&str]) -> Self {
+        self.lock().member_roles.insert(
+            (guild_id.to_string(), user_id.to_string()),
+            roles.iter().map(|r| r.to_string()).collect(),
+        );
+        self
+    }
+
+    pub fn with_guild_roles(self, guild_id: &str, roles: Vec<GuildRole>) -> Self {
+        self.lock().guild_roles.insert(guild_id.to_string(), roles);
+        self
+    }
+
+    pub fn with_application_commands(self, commands: Vec<ApplicationCommand>) -> Self {
+        self.lock().application_commands = commands;
+        self
+    }
+
+    /// Makes the next `modify_user_role` call fail; queued failures are
+    /// consumed in order.
+    pub fn fail_next_modify(self, failure: ScriptedFailure) -> Self {
+        self.lock().modify_failures.push_back(failure);
+        self
+    }
+
+    /// Makes the next `fetch_guild_roles` call fail; queued failures are
+    /// consumed in order.
+    pub fn fail_next_fetch_roles(self, failure: ScriptedFailure) -> Self {
+        self.lock().fetch_roles_failures.push_back(failure);
+        self
+    }
+
+    pub fn calls(&self) -> Vec<DiscordCall> {
+        self.lock().calls.clone()
+    }
+
+    pub fn member_roles(&self, guild_id: &str, user_id: &str) -> Vec<String> {
+        self.lock()
+            .member_roles
+            .get(&(guild_id.to_string(), user_id.to_string()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // A panicking test shouldn't hide the recorded calls from others.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl DiscordApi for RecordingDiscordApi {
+    async fn fetch_member_roles(&self, guild_id: &str, user_id: &str) -> Result<Vec<String>> {
+        let mut state = self.lock();
+
+        state.calls.push(DiscordCall::FetchMemberRoles {
+            guild_id: guild_id.to_string(),
+            user_id: user_id.to_string(),
+        });
+
+        match state
+            .member_roles
+            .get(&(guild_id.to_string(), user_id.to_string()))
+        {
+            Some(roles) => Ok(roles.clone()),
+            None => bail!("Member not found in guild"),
+        }
+    }
+
+    async fn fetch_guild_roles(&self, guild_id: &str) -> Result<Vec<GuildRole>> {
+        let mut state = self.lock();
+
+        state.calls.push(DiscordCall::FetchGuildRoles {
+            guild_id: guild_id.to_string(),
+        });
+
+        if let Some(failure) = state.fetch_roles_failures.pop_front() {
+            return Err(failure.into_error());
+        }
+
+        Ok(state.guild_roles.get(guild_id).cloned().unwrap_or_default())
+    }
+
+    /// Pages through the scripted members in snowflake order.
+    async fn list_guild_members(
+        &self,
+        guild_id: &str,
+        after: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GuildMember>> {
+        let mut state = self.lock();
+
+        state.calls.push(DiscordCall::ListGuildMembers {
+            guild_id: guild_id.to_string(),
+            after: after.map(str::to_string),
+        });
+
+        let snowflake = |id: &str| id.parse::<u64>().unwrap_or_default();
+        let after = after.map(snowflake).unwrap_or_default();
+
+        let mut members: Vec<GuildMember> = state
+            .member_roles
+            .iter()
+            .filter(|((guild, user_id), _)| guild == guild_id && snowflake(user_id) > after)
+            .map(|((_, user_id), roles)| GuildMember {
+                user: MemberUser {
+                    id: user_id.clone(),
+                },
+                roles: roles.clone(),
+            })
+            .collect();
+
+        members.sort_by_key(|member| snowflake(&member.user.id));
+        members.truncate(limit as usize);
+
+        Ok(members)
+    }
+
+    /// Messages get sequential IDs, starting from 1.
+    async fn create_message(&self, channel_id: &str, message: &Value) -> Result<String> {
+        let mut state = self.lock();
+
+        state.calls.push(DiscordCall::CreateMessage {
+            channel_id: channel_id.to_string(),
+            message: message.clone(),
+        });
+
+        let created = state
+            .calls
+            .iter()
+            .filter(|call| matches!(call, DiscordCall::CreateMessage { .. }))
+            .count();
+
+        Ok(created.to_string())
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        message: &Value,
+    ) -> Result<()> {
+        self.lock().calls.push(DiscordCall::EditMessage {
+            channel_id: channel_id.to_string(),
+            message_id: message_id.to_string(),
+            message: message.clone(),
+        });
+
+        Ok(())
+    }
+
+    async fn fetch_application_commands(
+        &self,
+        application_id: &str,
+        guild_id: Option<&str>,
+    ) -> Result<Vec<ApplicationCommand>> {
+        let mut state = self.lock();
+
+        state.calls.push(DiscordCall::FetchApplicationCommands {
+            application_id: application_id.to_string(),
+            guild_id: guild_id.map(str::to_string),
+        });
+
+        Ok(state.application_commands.clone())
+    }
+
+    async fn modify_user_role(
+        &self,
+        guild_id: &str,
+        user_id: &str,
+        role_id: &str,
+        action: RoleAction,
+    ) -> Result<()> {
+        let mut state = self.lock();
+
+        state.calls.push(DiscordCall::ModifyUserRole {
+            guild_id: guild_id.to_string(),
+            user_id: user_id.to_string(),
+            role_id: role_id.to_string(),
+            action,
+        });
+
+        if let Some(failure) = state.modify_failures.pop_front() {
+            return Err(failure.into_error());
+        }
+
+        let roles = state
+            .member_roles
+            .entry((guild_id.to_string(), user_id.to_string()))
+            .or_default();
+
+        match action {
+            RoleAction::Add if !roles.iter().any(|r| r == role_id) => {
+                roles.push(role_id.to_string())
+            }
+            RoleAction::Add => {}
+            RoleAction::Remove => roles.retain(|r| r != role_id),
+        }
+
+        Ok(())
+    }
+}
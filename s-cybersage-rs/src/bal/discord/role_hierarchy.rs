@@ -0,0 +1,91 @@
+use anyhow::Result;
+
+use super::{discord_api::DiscordApi, role_manager::GuildRole};
+use crate::commands::ELEVATED_PERMISSIONS;
+
+/// A guild's roles and where the bot sits among them, for deciding which
+/// roles members may hand themselves or others through the bot.
+pub struct RoleHierarchy {
+    roles: Vec<GuildRole>,
+    bot_position: i64,
+}
+
+impl RoleHierarchy {
+    /// Fetches the guild's roles and the bot's. The bot's user shares the
+    /// application's ID.
+    pub async fn load(
+        discord_api: &dyn DiscordApi,
+        guild_id: &str,
+        application_id: &str,
+    ) -> Result<Self> {
+        let (roles, bot_roles) = tokio::try_join!(
+            discord_api.fetch_guild_roles(guild_id),
+            discord_api.fetch_member_roles(guild_id, application_id),
+        )?;
+
+        Ok(Self::new(roles, &bot_roles))
+    }
+
+    pub fn new(roles: Vec<GuildRole>, bot_roles: &[String]) -> Self {
+        let mut hierarchy = Self {
+            roles,
+            bot_position: 0,
+        };
+        hierarchy.bot_position = hierarchy.highest_position(bot_roles);
+
+        hierarchy
+    }
+
+    pub fn roles(&self) -> &[GuildRole] {
+        &self.roles
+    }
+
+    pub fn role(&self, role_id: &str) -> Option<&GuildRole> {
+        self.roles.iter().find(|role| role.id == role_id)
+    }
+
+    /// The position of the highest of `role_ids`; 0, @everyone's, if none
+    /// of them are in the guild.
+    pub fn highest_position(&self, role_ids: &[String]) -> i64 {
+        self.roles
+            .iter()
+            .filter(|role| role_ids.contains(&role.id))
+            .map(|role| role.position)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Why a role with `permissions` (a decimal bitfield) at `position`
+    /// can't be handed out through the bot, if it can't.
+    pub fn refusal(&self, permissions: &str, position: i64) -> Option<&'static str> {
+        let elevated = permissions
+            .parse::<u64>()
+            .is_ok_and(|granted| granted & ELEVATED_PERMISSIONS != 0);
+
+        if elevated {
+            Some("grants moderation permissions")
+        } else if position >= self.bot_position {
+            Some("not below the bot's highest role")
+        } else {
+            None
+        }
+    }
+
+    /// `refusal` for one of the guild's roles.
+    pub fn role_refusal(&self, role: &GuildRole) -> Option<&'static str> {
+        self.refusal(&role.permissions, role.position)
+    }
+
+    /// Why a member holding `member_roles` can't hand out a role at
+    /// `position`, if they can't: it's at or above their own highest role.
+    /// Administrators, which Discord makes the owner too, may hand out any.
+    pub fn member_refusal(
+        &self,
+        position: i64,
+        member_roles: &[String],
+        administrator: bool,
+    ) -> Option<&'static str> {
+        (!administrator && position >= self.highest_position(member_roles))
+            .then_some("not below your highest role")
+    }
+}
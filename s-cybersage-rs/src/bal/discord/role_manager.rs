@@ -1,17 +1,69 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
 use serde::Deserialize;
-use tracing::{error, info, warn};
+use serde_json::Value;
+use tracing::{error, info, instrument, warn};
 
-#[derive(Debug, Clone, Copy)]
+use crate::{
+    bal::discord::{channel_client::ChannelClient, discord_api::DiscordApi},
+    dal::model::{application_command::ApplicationCommand, role_icon::RoleIcon},
+    error::DiscordApiError,
+    ops_alert,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RoleAction {
     Add,
     Remove,
 }
 
-#[derive(Debug, Deserialize)]
-struct GuildMember {
-    roles: Vec<String>,
+#[derive(Debug, Clone, Deserialize)]
+pub struct GuildMember {
+    pub user: MemberUser,
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemberUser {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GuildRole {
+    pub id: String,
+    pub name: String,
+
+    #[serde(default)]
+    pub managed: bool,
+
+    /// Decimal bitfield, as Discord sends it.
+    #[serde(default)]
+    pub permissions: String,
+
+    /// Higher roles sit above lower ones; a member can only be given roles
+    /// below their highest.
+    #[serde(default)]
+    pub position: i64,
+
+    #[serde(flatten)]
+    pub icon: RoleIcon,
+}
+
+impl GuildRole {
+    /// Whether the role grants any of `permissions`.
+    pub fn grants_any(&self, permissions: u64) -> bool {
+        self.permissions
+            .parse::<u64>()
+            .is_ok_and(|granted| granted & permissions != 0)
+    }
+}
+
+/// The JSON body of a Discord error response.
+#[derive(Deserialize)]
+struct DiscordErrorBody {
+    #[serde(default)]
+    code: Option<u64>,
 }
 
 pub struct RoleManager {
@@ -26,8 +78,12 @@ impl RoleManager {
             bot_token: bot_token.into(),
         }
     }
+}
 
-    pub async fn fetch_member_roles(&self, guild_id: &str, user_id: &str) -> Result<Vec<String>> {
+#[async_trait]
+impl DiscordApi for RoleManager {
+    #[instrument(skip(self))]
+    async fn fetch_member_roles(&self, guild_id: &str, user_id: &str) -> Result<Vec<String>> {
         let url = format!(
             "https://discord.com/api/v10/guilds/{}/members/{}",
             guild_id, user_id
@@ -39,20 +95,14 @@ impl RoleManager {
             .header("Authorization", format!("Bot {}", self.bot_token))
             .send()
             .await
+            .map_err(DiscordApiError::Transport)
             .context("Failed to send fetch_member_roles request")?;
 
-        if resp.status() == StatusCode::NOT_FOUND {
-            bail!("Member not found in guild");
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error while fetching member");
         }
 
-        if resp.status() == StatusCode::FORBIDDEN {
-            bail!("Bot lacks permission to fetch member");
-        }
-
-        let resp = resp
-            .error_for_status()
-            .context("Discord returned error while fetching member")?;
-
         let member: GuildMember = resp
             .json()
             .await
@@ -61,7 +111,123 @@ impl RoleManager {
         Ok(member.roles)
     }
 
-    pub async fn modify_user_role(
+    #[instrument(skip(self))]
+    async fn fetch_guild_roles(&self, guild_id: &str) -> Result<Vec<GuildRole>> {
+        let url = format!("https://discord.com/api/v10/guilds/{}/roles", guild_id);
+
+        let resp = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send fetch_guild_roles request")?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let code = resp
+                .json::<DiscordErrorBody>()
+                .await
+                .ok()
+                .and_then(|body| body.code);
+            return Err(DiscordApiError::from_guild_status(status, code))
+                .context("Discord returned error while fetching guild roles");
+        }
+
+        resp.json()
+            .await
+            .context("Failed to deserialize guild roles")
+    }
+
+    #[instrument(skip(self))]
+    async fn list_guild_members(
+        &self,
+        guild_id: &str,
+        after: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<GuildMember>> {
+        let url = format!(
+            "https://discord.com/api/v10/guilds/{}/members?limit={}&after={}",
+            guild_id,
+            limit,
+            after.unwrap_or("0")
+        );
+
+        let resp = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send list_guild_members request")?;
+
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error while listing guild members");
+        }
+
+        resp.json()
+            .await
+            .context("Failed to deserialize guild members")
+    }
+
+    async fn create_message(&self, channel_id: &str, message: &Value) -> Result<String> {
+        ChannelClient::new(self.client.clone(), self.bot_token.clone())
+            .create_message(channel_id, message)
+            .await
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: &str,
+        message_id: &str,
+        message: &Value,
+    ) -> Result<()> {
+        ChannelClient::new(self.client.clone(), self.bot_token.clone())
+            .edit_message(channel_id, message_id, message)
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn fetch_application_commands(
+        &self,
+        application_id: &str,
+        guild_id: Option<&str>,
+    ) -> Result<Vec<ApplicationCommand>> {
+        let url = match guild_id {
+            Some(guild_id) => format!(
+                "https://discord.com/api/v10/applications/{}/guilds/{}/commands",
+                application_id, guild_id
+            ),
+            None => format!(
+                "https://discord.com/api/v10/applications/{}/commands",
+                application_id
+            ),
+        };
+
+        let resp = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .send()
+            .await
+            .map_err(DiscordApiError::Transport)
+            .context("Failed to send fetch_application_commands request")?;
+
+        if !resp.status().is_success() {
+            return Err(DiscordApiError::from_status(resp.status()))
+                .context("Discord returned error while fetching application commands");
+        }
+
+        resp.json()
+            .await
+            .context("Failed to deserialize application commands")
+    }
+
+    #[instrument(skip(self))]
+    async fn modify_user_role(
         &self,
         guild_id: &str,
         user_id: &str,
@@ -78,53 +244,72 @@ impl RoleManager {
             RoleAction::Remove => self.client.delete(&url),
         };
 
-        let resp = request_builder
+        let resp = match request_builder
             .header("Authorization", format!("Bot {}", self.bot_token))
             .send()
             .await
-            .context("Failed to send modify_user_role request")?;
+        {
+            Ok(resp) => resp,
+            Err(err) => {
+                return Err(DiscordApiError::Transport(err))
+                    .context("Failed to send modify_user_role request")
+            }
+        };
 
         match resp.status() {
             status if status.is_success() => {
-                info!(
-                    "Successfully {:?} role {} for user {}",
-                    action, role_id, user_id
-                );
+                info!(?action, role_id, user_id, "Modified member role");
                 Ok(())
             }
 
             StatusCode::FORBIDDEN => {
                 error!(
-                    "Permission error while {:?} role {} for user {}",
-                    action, role_id, user_id
+                    ?action,
+                    role_id, user_id, "Permission error while modifying member role"
                 );
-                bail!("Bot lacks permission to modify role (check role hierarchy)")
+                Err(DiscordApiError::Forbidden.into())
             }
 
             StatusCode::NOT_FOUND => {
                 error!(
-                    "Role or user not found while {:?} role {} for user {}",
-                    action, role_id, user_id
+                    ?action,
+                    role_id, user_id, "Role or user not found while modifying member role"
                 );
-                bail!("Role or user not found")
+                Err(DiscordApiError::NotFound.into())
             }
 
             StatusCode::TOO_MANY_REQUESTS => {
                 let body = resp.text().await.unwrap_or_default();
                 warn!(
-                    "Rate limited while {:?} role {} for user {}: {}",
-                    action, role_id, user_id, body
+                    ?action,
+                    role_id, user_id, body, "Rate limited while modifying member role"
                 );
-                bail!("Rate limited by Discord API")
+                Err(DiscordApiError::RateLimited.into())
             }
 
             other => {
                 let body = resp.text().await.unwrap_or_default();
                 error!(
-                    "Failed to {:?} role {} for user {}: status {}, body: {}",
-                    action, role_id, user_id, other, body
+                    ?action,
+                    role_id,
+                    user_id,
+                    status = %other,
+                    body,
+                    "Failed to modify member role"
                 );
-                bail!("Discord API error: {}", other);
+
+                if other.is_server_error() {
+                    ops_alert::report_in_background(
+                        &self.client,
+                        "Discord API server error",
+                        &format!(
+                            "{:?} role {}: status {}, body: {}",
+                            action, role_id, other, body
+                        ),
+                    );
+                }
+
+                Err(DiscordApiError::from_status(other).into())
             }
         }
     }
@@ -0,0 +1,65 @@
+use chrono::Utc;
+use tracing::warn;
+
+use crate::dal::{
+    dao::webhook::WebhookDao,
+    model::{
+        deferred_task::DeferredTask,
+        guild_event::{GuildEvent, GuildEventDelivery},
+    },
+    queue::task_queue::TaskQueue,
+};
+
+/// Queues guild events for delivery to the guild's webhook by the queue
+/// worker, so a slow endpoint never holds up an interaction.
+#[derive(Clone)]
+pub struct EventPublisher {
+    webhooks: WebhookDao,
+    queue: Option<TaskQueue>,
+}
+
+impl EventPublisher {
+    pub fn new(webhooks: WebhookDao, queue: Option<TaskQueue>) -> Self {
+        Self { webhooks, queue }
+    }
+
+    /// Best-effort: events are dropped, with a warning, if the guild has no
+    /// webhook, no task queue is configured, or queueing fails. The change
+    /// itself has already happened by the time it's published.
+    pub async fn publish(&self, guild_id: &str, event: GuildEvent) {
+        let Some(queue) = self.queue.as_ref() else {
+            return;
+        };
+
+        match self.webhooks.is_configured(guild_id).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                warn!(
+                    guild_id,
+                    event = event.name(),
+                    error = format!("{:#}", err),
+                    "Failed to look up guild webhook"
+                );
+                return;
+            }
+        }
+
+        let name = event.name();
+        let task = DeferredTask::GuildEvent(GuildEventDelivery {
+            guild_id: guild_id.to_string(),
+            occurred_at: Utc::now().timestamp(),
+            event,
+            attempt: 0,
+        });
+
+        if let Err(err) = queue.enqueue(&task, 0).await {
+            warn!(
+                guild_id,
+                event = name,
+                error = format!("{:#}", err),
+                "Failed to queue guild event"
+            );
+        }
+    }
+}
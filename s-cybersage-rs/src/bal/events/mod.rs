@@ -0,0 +1,2 @@
+pub mod event_publisher;
+pub mod webhook_sender;
@@ -0,0 +1,296 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect::Policy,
+    Client, Url,
+};
+use serde::Serialize;
+use sha2::Sha256;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    bal::retry::role_retry_worker::{backoff_seconds, JobOutcome, MAX_ATTEMPTS},
+    dal::{
+        dao::webhook::WebhookDao,
+        model::{
+            deferred_task::DeferredTask,
+            guild_event::{GuildEvent, GuildEventDelivery},
+        },
+        queue::task_queue::TaskQueue,
+    },
+};
+
+/// Short, since the worker delivers a batch of events one after another.
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const SIGNATURE_HEADER: &str = "x-cybersage-signature";
+pub const TIMESTAMP_HEADER: &str = "x-cybersage-timestamp";
+pub const EVENT_HEADER: &str = "x-cybersage-event";
+
+/// Host suffixes that only resolve inside a network, never to a public
+/// receiver.
+const INTERNAL_SUFFIXES: &[&str] = &[".localhost", ".local", ".internal", ".localdomain"];
+
+/// The endpoint an admin registered, if it's an absolute `https` URL on a
+/// public host name. IP literals, `localhost` and single-label or internal
+/// names are refused, so deliveries can't be pointed at the function's own
+/// network (e.g. the Lambda runtime API). Public names that resolve to such
+/// addresses are caught by `PublicResolver` when the delivery connects.
+pub fn parse_endpoint(url: &str) -> Option<Url> {
+    Url::parse(url.trim())
+        .ok()
+        .filter(|url| url.scheme() == "https" && is_public_host(url))
+}
+
+fn is_public_host(url: &Url) -> bool {
+    // `domain` is `None` for IPv4 and IPv6 literals.
+    let Some(host) = url.domain() else {
+        return false;
+    };
+
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+    host.contains('.')
+        && host != "localhost"
+        && !INTERNAL_SUFFIXES
+            .iter()
+            .any(|suffix| host.ends_with(suffix))
+}
+
+/// Whether `ip` is reachable on the public internet: not private, loopback,
+/// link-local (which includes the instance metadata service), shared,
+/// reserved or otherwise special-purpose.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(mapped),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8, "this network".
+        || a == 0
+        // 100.64.0.0/10, carrier-grade NAT.
+        || (a == 100 && (64..128).contains(&b))
+        // 192.0.0.0/24, protocol assignments.
+        || (a == 192 && b == 0 && ip.octets()[2] == 0)
+        // 198.18.0.0/15, benchmarking.
+        || (a == 198 && (18..20).contains(&b))
+        // 240.0.0.0/4, reserved.
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // fc00::/7, unique local.
+        || (first & 0xfe00) == 0xfc00
+        // fe80::/10, link-local.
+        || (first & 0xffc0) == 0xfe80
+        // 2001:db8::/32, documentation.
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8)
+        // 64:ff9b::/96 and friends translate to IPv4 addresses that may be
+        // private.
+        || first == 0x0064)
+}
+
+/// Resolves webhook hosts like the system resolver, but only to public
+/// addresses, so a public name pointing into a private network (or
+/// rebinding to one after it was registered) is never connected to.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<_> = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|addr| is_public_address(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(anyhow!("{} doesn't resolve to a public address", host).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// A fresh signing secret, shown to the admin once when they register the
+/// webhook.
+pub fn new_secret() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|err| anyhow!("Failed to generate secret: {}", err))?;
+
+    Ok(hex::encode(bytes))
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>` keyed with the
+/// webhook's secret. Receivers recompute it to authenticate a delivery and
+/// reject stale timestamps to stop replays.
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    // HMAC takes keys of any length.
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// The webhook request body: the delivery without its retry bookkeeping.
+#[derive(Serialize)]
+struct WebhookBody<'a> {
+    guild_id: &'a str,
+    occurred_at: i64,
+    event: &'a GuildEvent,
+}
+
+/// Posts queued guild events to the guild's webhook, re-enqueueing
+/// retryable failures with the same backoff as role modifications. It has
+/// its own HTTP client that never follows redirects, connects directly
+/// rather than through a proxy, and only connects to public addresses,
+/// since the endpoint is chosen by guild admins.
+pub struct WebhookSender {
+    client: Client,
+    webhooks: WebhookDao,
+    queue: TaskQueue,
+}
+
+impl WebhookSender {
+    pub fn new(webhooks: WebhookDao, queue: TaskQueue) -> Result<Self> {
+        let client = Client::builder()
+            .user_agent("cybersage-bot")
+            .redirect(Policy::none())
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+            .context("Failed to build the webhook HTTP client")?;
+
+        Ok(Self {
+            client,
+            webhooks,
+            queue,
+        })
+    }
+
+    #[instrument(skip_all, fields(guild_id = %delivery.guild_id, event = delivery.event.name()))]
+    pub async fn deliver(&self, delivery: &GuildEventDelivery) -> Result<JobOutcome> {
+        let err = match self.send(delivery).await {
+            Ok(()) => return Ok(JobOutcome::Applied),
+            Err(err) => err,
+        };
+
+        let next_attempt = delivery.attempt + 1;
+
+        if next_attempt < MAX_ATTEMPTS {
+            warn!(
+                attempt = next_attempt,
+                error = format!("{:#}", err),
+                "Requeueing guild event"
+            );
+
+            let retry = DeferredTask::GuildEvent(GuildEventDelivery {
+                attempt: next_attempt,
+                ..delivery.clone()
+            });
+
+            self.queue
+                .enqueue(&retry, backoff_seconds(next_attempt))
+                .await?;
+
+            return Ok(JobOutcome::Requeued);
+        }
+
+        error!(
+            attempt = next_attempt,
+            error = format!("{:#}", err),
+            "Giving up on guild event"
+        );
+
+        Ok(JobOutcome::Failed)
+    }
+
+    /// Errors on failures worth retrying: network errors, timeouts, 5xx,
+    /// 408 and 429. Other 4xx responses mean the endpoint rejected the event,
+    /// so it's dropped.
+    async fn send(&self, delivery: &GuildEventDelivery) -> Result<()> {
+        // Looked up at delivery time, so removing the webhook stops queued
+        // events too.
+        let Some(webhook) = self.webhooks.get_webhook(&delivery.guild_id).await? else {
+            return Ok(());
+        };
+
+        // Webhooks registered before the host checks aren't trusted either.
+        let Some(url) = parse_endpoint(&webhook.url) else {
+            warn!("Dropping guild event for a webhook on a non-public host");
+            return Ok(());
+        };
+
+        let body = serde_json::to_vec(&WebhookBody {
+            guild_id: &delivery.guild_id,
+            occurred_at: delivery.occurred_at,
+            event: &delivery.event,
+        })
+        .context("Failed to serialize guild event")?;
+        let timestamp = Utc::now().timestamp();
+
+        let response = self
+            .client
+            .post(url)
+            .timeout(SEND_TIMEOUT)
+            .header("content-type", "application/json")
+            .header(EVENT_HEADER, delivery.event.name())
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(
+                SIGNATURE_HEADER,
+                signature(&webhook.secret, timestamp, &body),
+            )
+            .body(body)
+            .send()
+            .await
+            .context("Failed to send guild event")?;
+
+        let status = response.status();
+
+        if status.is_success() {
+            info!(status = status.as_u16(), "Delivered guild event");
+            return Ok(());
+        }
+
+        if status.is_server_error() || matches!(status.as_u16(), 408 | 429) {
+            bail!("Guild webhook responded with {}", status);
+        }
+
+        warn!(
+            status = status.as_u16(),
+            "Guild webhook rejected event; dropping"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use crate::dal::model::role_connection::{RoleConnectionMetadata, RoleConnectionMetadataType};
+
+/// Shown on the user's profile next to the linked account.
+pub const PLATFORM_NAME: &str = "CyberSage";
+
+/// Set for every user who completed the link.
+pub const VERIFIED: &str = "verified";
+
+/// Set while the user owns an active subscription bundle.
+#[cfg(feature = "billing")]
+pub const SUBSCRIBER: &str = "subscriber";
+
+/// The criteria guilds can pick from when configuring a linked role.
+/// `register-commands` registers these alongside the slash commands.
+#[cfg_attr(not(feature = "billing"), allow(unused_mut))]
+pub fn definitions() -> Vec<RoleConnectionMetadata> {
+    let mut definitions = vec![RoleConnectionMetadata::new(
+        RoleConnectionMetadataType::BooleanEqual,
+        VERIFIED,
+        "Verified member",
+        "Linked their Discord account with CyberSage",
+    )];
+
+    #[cfg(feature = "billing")]
+    definitions.push(RoleConnectionMetadata::new(
+        RoleConnectionMetadataType::BooleanEqual,
+        SUBSCRIBER,
+        "Subscriber",
+        "Owns an active CyberSage subscription",
+    ));
+
+    definitions
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+#[cfg(feature = "billing")]
+use super::metadata::SUBSCRIBER;
+use super::metadata::{PLATFORM_NAME, VERIFIED};
+#[cfg(feature = "billing")]
+use crate::dal::dao::bundle::BundleDao;
+use crate::{
+    bal::discord::oauth_client::{OAuthClient, OAuthTokens, OAuthUser},
+    dal::{
+        dao::role_connection::{RoleConnectionDao, RoleConnectionGrant},
+        model::role_connection::RoleConnection,
+    },
+};
+
+/// Access tokens this close to expiry are refreshed before use.
+const REFRESH_MARGIN_SECONDS: i64 = 60;
+
+/// Links Discord accounts for Linked Roles and keeps their metadata current.
+pub struct RoleConnectionManager {
+    oauth: OAuthClient,
+    grants: RoleConnectionDao,
+    #[cfg(feature = "billing")]
+    bundles: BundleDao,
+}
+
+impl RoleConnectionManager {
+    pub fn new(
+        oauth: OAuthClient,
+        grants: RoleConnectionDao,
+        #[cfg(feature = "billing")] bundles: BundleDao,
+    ) -> Self {
+        Self {
+            oauth,
+            grants,
+            #[cfg(feature = "billing")]
+            bundles,
+        }
+    }
+
+    /// Completes the OAuth2 callback: pushes the user's metadata and keeps
+    /// the grant for later updates.
+    pub async fn link(&self, code: &str) -> Result<OAuthUser> {
+        let tokens = self.oauth.exchange_code(code).await?;
+        let user = self.oauth.current_user(&tokens.access_token).await?;
+
+        let connection = self.connection_for(&user.id).await?;
+        self.oauth
+            .put_role_connection(&tokens.access_token, &connection)
+            .await?;
+
+        self.grants
+            .save_grant(&grant_from(&user.id, tokens))
+            .await?;
+
+        Ok(user)
+    }
+
+    /// Pushes the user's current metadata with their stored grant,
+    /// refreshing it first if needed. Returns `false` if they never linked.
+    pub async fn sync(&self, user_id: &str) -> Result<bool> {
+        let Some(mut grant) = self.grants.get_grant(user_id).await? else {
+            return Ok(false);
+        };
+
+        if grant.expires_at <= now() + REFRESH_MARGIN_SECONDS {
+            let tokens = self.oauth.refresh(&grant.refresh_token).await?;
+            grant = grant_from(user_id, tokens);
+            self.grants.save_grant(&grant).await?;
+        }
+
+        let connection = self.connection_for(user_id).await?;
+        self.oauth
+            .put_role_connection(&grant.access_token, &connection)
+            .await?;
+
+        Ok(true)
+    }
+
+    #[cfg_attr(not(feature = "billing"), allow(unused_mut))]
+    async fn connection_for(&self, user_id: &str) -> Result<RoleConnection> {
+        let mut metadata = BTreeMap::from([(VERIFIED.to_string(), 1)]);
+
+        #[cfg(feature = "billing")]
+        {
+            let subscriber = self
+                .bundles
+                .get_bundle(user_id)
+                .await?
+                .is_some_and(|bundle| bundle.is_active(now()));
+
+            metadata.insert(SUBSCRIBER.to_string(), i64::from(subscriber));
+        }
+
+        #[cfg(not(feature = "billing"))]
+        let _ = user_id;
+
+        Ok(RoleConnection {
+            platform_name: PLATFORM_NAME.to_string(),
+            metadata,
+        })
+    }
+}
+
+fn grant_from(user_id: &str, tokens: OAuthTokens) -> RoleConnectionGrant {
+    RoleConnectionGrant {
+        user_id: user_id.to_string(),
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_at: now() + tokens.expires_in,
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
@@ -1,3 +1,13 @@
+pub mod activity;
 pub mod auth;
+#[cfg(feature = "billing")]
+pub mod billing;
+pub mod bot_lists;
+pub mod config;
+pub mod dashboard;
+pub mod deferred;
 pub mod discord;
-pub mod route;
\ No newline at end of file
+pub mod events;
+pub mod linked_roles;
+pub mod retry;
+pub mod route;
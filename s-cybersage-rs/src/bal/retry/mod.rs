@@ -0,0 +1 @@
+pub mod role_retry_worker;
@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tracing::{error, warn};
+
+use crate::{
+    bal::discord::{
+        discord_api::DiscordApi, interaction_client::InteractionClient, role_manager::RoleAction,
+    },
+    bal::{activity::activity_recorder::ActivityRecorder, events::event_publisher::EventPublisher},
+    dal::{
+        model::{
+            activity_entry::{role_mention, ActivityEntry, RoleActivity},
+            deferred_task::DeferredTask,
+            guild_event::GuildEvent,
+            role_job::RoleModificationJob,
+        },
+        queue::task_queue::TaskQueue,
+    },
+    error::DiscordApiError,
+    markdown,
+};
+
+pub const INITIAL_DELAY_SECONDS: i32 = 5;
+pub const MAX_ATTEMPTS: u32 = 5;
+const MAX_DELAY_SECONDS: i32 = 900;
+
+/// Delay before retry `attempt` of a queued task, doubling up to a cap.
+pub fn backoff_seconds(attempt: u32) -> i32 {
+    INITIAL_DELAY_SECONDS
+        .saturating_mul(1 << attempt.min(8))
+        .min(MAX_DELAY_SECONDS)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    Applied,
+    Requeued,
+    Failed,
+}
+
+/// Applies queued role modifications, re-enqueueing retryable failures with
+/// exponential backoff and reporting the final result with a followup to the
+/// deferred interaction response.
+pub struct RoleRetryWorker {
+    discord_api: Arc<dyn DiscordApi>,
+    interaction_client: InteractionClient,
+    queue: TaskQueue,
+    events: EventPublisher,
+    activity: ActivityRecorder,
+}
+
+impl RoleRetryWorker {
+    pub fn new(
+        discord_api: Arc<dyn DiscordApi>,
+        interaction_client: InteractionClient,
+        queue: TaskQueue,
+        events: EventPublisher,
+        activity: ActivityRecorder,
+    ) -> Self {
+        Self {
+            discord_api,
+            interaction_client,
+            queue,
+            events,
+            activity,
+        }
+    }
+
+    pub async fn process(&self, job: &RoleModificationJob) -> Result<JobOutcome> {
+        let action = if job.remove {
+            RoleAction::Remove
+        } else {
+            RoleAction::Add
+        };
+
+        let err = match self
+            .discord_api
+            .modify_user_role(&job.guild_id, &job.user_id, &job.role_id, action)
+            .await
+        {
+            Ok(()) => {
+                let message = if job.remove {
+                    format!("Removed '{}'.", markdown::escape(&job.role_name))
+                } else {
+                    format!("Added '{}'.", markdown::escape(&job.role_name))
+                };
+                self.report(job, &message).await;
+                self.record(job, true, message).await;
+                self.events
+                    .publish(
+                        &job.guild_id,
+                        GuildEvent::RoleToggled {
+                            user_id: job.user_id.clone(),
+                            role_id: job.role_id.clone(),
+                            role_name: job.role_name.clone(),
+                            added: !job.remove,
+                        },
+                    )
+                    .await;
+                return Ok(JobOutcome::Applied);
+            }
+            Err(err) => err,
+        };
+
+        let next_attempt = job.attempt + 1;
+
+        if DiscordApiError::is_retryable_error(&err) && next_attempt < MAX_ATTEMPTS {
+            warn!(
+                guild_id = %job.guild_id,
+                role_id = %job.role_id,
+                attempt = next_attempt,
+                error = format!("{:#}", err),
+                "Requeueing role modification"
+            );
+
+            let retry = DeferredTask::RoleModification(RoleModificationJob {
+                attempt: next_attempt,
+                ..job.clone()
+            });
+
+            self.queue
+                .enqueue(&retry, backoff_seconds(next_attempt))
+                .await?;
+
+            return Ok(JobOutcome::Requeued);
+        }
+
+        error!(
+            guild_id = %job.guild_id,
+            role_id = %job.role_id,
+            attempt = next_attempt,
+            error = format!("{:#}", err),
+            "Giving up on role modification"
+        );
+
+        let message = format!(
+            "Failed to modify '{}'. Please try again later.",
+            markdown::escape(&job.role_name)
+        );
+        self.report(job, &message).await;
+        self.record(job, false, message).await;
+
+        Ok(JobOutcome::Failed)
+    }
+
+    async fn record(&self, job: &RoleModificationJob, succeeded: bool, message: String) {
+        let entry = ActivityEntry::new(
+            &job.guild_id,
+            RoleActivity::Toggled,
+            Some(&job.user_id),
+            role_mention(&job.role_id),
+        );
+
+        let entry = if succeeded {
+            entry.succeeded(message)
+        } else {
+            entry.failed(message)
+        };
+
+        self.activity.record(entry).await;
+    }
+
+    async fn report(&self, job: &RoleModificationJob, message: &str) {
+        if let Err(err) = self
+            .interaction_client
+            .create_followup(&job.application_id, &job.interaction_token, message)
+            .await
+        {
+            // The token is only valid for 15 minutes; late retries can't report back.
+            warn!(
+                guild_id = %job.guild_id,
+                error = format!("{:#}", err),
+                "Failed to send followup"
+            );
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+use crate::{
+    bal::config::premium_features::PremiumFeature,
+    dal::model::{
+        deferred_task::DeferredTask,
+        interaction_request::{ApplicationCommandData, CommandOption},
+        interaction_response::InteractionResponse,
+    },
+};
+
+use super::request_context::RequestContext;
+
+/// Cooldowns tracked per warm instance before expired ones are swept.
+const MAX_COOLDOWNS: usize = 4096;
+/// The longest cooldown a subcommand can declare.
+const MAX_COOLDOWN: Duration = Duration::from_secs(3_600);
+
+pub type HandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<InteractionResponse>> + Send + 'a>>;
+
+/// Runs one subcommand against the router `R`.
+pub type Handler<R> = for<'a> fn(&'a R, Invocation<'a>) -> HandlerFuture<'a>;
+
+pub type SlowHandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<Deferral>> + Send + 'a>>;
+
+/// Validates a slow subcommand and says what the task worker should do.
+pub type SlowHandler<R> = for<'a> fn(&'a R, Invocation<'a>) -> SlowHandlerFuture<'a>;
+
+/// What a slow subcommand's handler decides before the work is queued.
+pub enum Deferral {
+    /// Run this on the task worker. The router answers with a deferred
+    /// ephemeral reply, which the worker edits when it's done.
+    Queue(DeferredTask),
+    /// Answer right away instead, e.g. because an option is invalid.
+    Reply(InteractionResponse),
+}
+
+/// How the router runs a route once its middleware has passed.
+pub enum Execution<R> {
+    /// Within the interaction's three seconds.
+    Inline(Handler<R>),
+    /// On the task worker, for work that could outlast Discord's deadline.
+    Slow(SlowHandler<R>),
+}
+
+/// A check the router runs before a subcommand's handler, after the
+/// member's access from `commands::access`. The first one that refuses
+/// answers the interaction instead of the handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Middleware {
+    /// Answers with the upsell instead, for guilds without premium, while
+    /// the feature is reserved for premium.
+    Premium(PremiumFeature),
+    /// Lets each member run the subcommand once per this many seconds,
+    /// counted from when its task was queued, so a run the handler refused
+    /// can be retried straight away. Only slow subcommands queue tasks.
+    /// Tracked per warm instance, so it only blunts repeated clicks.
+    Cooldown(u64),
+}
+
+/// One invocation of a registered subcommand, as its handler sees it.
+#[derive(Clone, Copy)]
+pub struct Invocation<'a> {
+    pub ctx: &'a RequestContext,
+    pub guild_id: &'a str,
+    pub data: &'a ApplicationCommandData,
+    /// Absent for commands without subcommands, like `/setup`.
+    pub subcommand: Option<&'a CommandOption>,
+}
+
+impl<'a> Invocation<'a> {
+    pub fn subcommand_name(&self) -> &'a str {
+        self.subcommand.map(|sub| sub.name.as_str()).unwrap_or("")
+    }
+
+    /// The value of the subcommand's option called `name`.
+    pub fn option(&self, name: &str) -> Option<&'a serde_json::Value> {
+        self.subcommand?
+            .options
+            .iter()
+            .find(|opt| opt.name == name)
+            .and_then(|opt| opt.value.as_ref())
+    }
+
+    pub fn str_option(&self, name: &str) -> Option<&'a str> {
+        self.option(name).and_then(|val| val.as_str())
+    }
+}
+
+pub struct Route<R> {
+    pub middleware: &'static [Middleware],
+    pub execution: Execution<R>,
+}
+
+impl<R> Route<R> {
+    /// How long a member waits between runs, if the route has a cooldown.
+    pub fn cooldown(&self) -> Option<Duration> {
+        self.middleware
+            .iter()
+            .find_map(|middleware| match middleware {
+                Middleware::Cooldown(seconds) => Some(Duration::from_secs(*seconds)),
+                _ => None,
+            })
+    }
+}
+
+/// Subcommand handlers keyed by command and subcommand name. Commands
+/// without subcommands register under an empty subcommand name.
+pub struct CommandRegistry<R> {
+    commands: HashMap<&'static str, HashMap<&'static str, Route<R>>>,
+}
+
+impl<R> Default for CommandRegistry<R> {
+    fn default() -> Self {
+        Self {
+            commands: HashMap::new(),
+        }
+    }
+}
+
+impl<R> CommandRegistry<R> {
+    pub fn register(
+        self,
+        command: &'static str,
+        subcommand: &'static str,
+        middleware: &'static [Middleware],
+        handler: Handler<R>,
+    ) -> Self {
+        self.insert(command, subcommand, middleware, Execution::Inline(handler))
+    }
+
+    /// Registers a subcommand whose work always runs on the task worker.
+    pub fn register_slow(
+        self,
+        command: &'static str,
+        subcommand: &'static str,
+        middleware: &'static [Middleware],
+        handler: SlowHandler<R>,
+    ) -> Self {
+        self.insert(command, subcommand, middleware, Execution::Slow(handler))
+    }
+
+    fn insert(
+        mut self,
+        command: &'static str,
+        subcommand: &'static str,
+        middleware: &'static [Middleware],
+        execution: Execution<R>,
+    ) -> Self {
+        self.commands.entry(command).or_default().insert(
+            subcommand,
+            Route {
+                middleware,
+                execution,
+            },
+        );
+        self
+    }
+
+    pub fn has_command(&self, command: &str) -> bool {
+        self.commands.contains_key(command)
+    }
+
+    pub fn route(&self, command: &str, subcommand: &str) -> Option<&Route<R>> {
+        self.commands.get(command)?.get(subcommand)
+    }
+}
+
+/// Guild, user and `"command subcommand"`.
+type CooldownKey = (String, String, String);
+
+/// When each member's cooldown for each subcommand ends, kept per warm
+/// instance.
+static COOLDOWNS: Lazy<Mutex<HashMap<CooldownKey, Instant>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cooldown_key(invocation: &Invocation, user_id: &str) -> CooldownKey {
+    (
+        invocation.guild_id.to_string(),
+        user_id.to_string(),
+        format!("{} {}", invocation.data.name, invocation.subcommand_name()),
+    )
+}
+
+/// How long is left of the member's cooldown for the invocation, if one is
+/// running.
+pub fn cooldown_remaining(invocation: &Invocation, user_id: &str) -> Option<Duration> {
+    let key = cooldown_key(invocation, user_id);
+    let now = Instant::now();
+
+    COOLDOWNS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&key)
+        .map(|expires_at| expires_at.saturating_duration_since(now))
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// Starts the member's cooldown for the invocation, replacing any that's
+/// running.
+pub fn start_cooldown(invocation: &Invocation, user_id: &str, period: Duration) {
+    let key = cooldown_key(invocation, user_id);
+    let period = period.min(MAX_COOLDOWN);
+    let now = Instant::now();
+
+    let mut cooldowns = COOLDOWNS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if cooldowns.len() >= MAX_COOLDOWNS {
+        cooldowns.retain(|_, expires_at| *expires_at > now);
+    }
+
+    // Still full of running cooldowns: drop the one closest to ending.
+    if cooldowns.len() >= MAX_COOLDOWNS {
+        let soonest = cooldowns
+            .iter()
+            .min_by_key(|(_, expires_at)| **expires_at)
+            .map(|(key, _)| key.clone());
+        if let Some(soonest) = soonest {
+            cooldowns.remove(&soonest);
+        }
+    }
+
+    cooldowns.insert(key, now + period);
+}
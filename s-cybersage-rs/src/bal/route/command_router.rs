@@ -1,34 +1,184 @@
+use std::{sync::Arc, time::Instant};
+
 use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use tracing::{error, warn};
 
+#[cfg(feature = "billing")]
+use crate::{
+    bal::billing::{
+        context::BillingContext,
+        subscription_manager::{AttachOutcome, DetachOutcome},
+        usage_meter::QuotaStatus,
+    },
+    commands::subscription,
+};
 use crate::{
-    bal::discord::role_manager::{RoleAction, RoleManager},
+    bal::{
+        activity::activity_recorder::ActivityRecorder,
+        auth::operator::OperatorContext,
+        config::{
+            feature_flags::{FeatureFlags, Flag},
+            premium_features::PremiumFeature,
+        },
+        discord::{
+            discord_api::DiscordApi, oauth_client::OAuthClient, role_hierarchy::RoleHierarchy,
+            role_manager::RoleAction,
+        },
+        events::{
+            event_publisher::EventPublisher,
+            webhook_sender::{self, parse_endpoint},
+        },
+        retry::role_retry_worker::INITIAL_DELAY_SECONDS,
+    },
+    commands::{
+        self, config, invite, role, rolemenu, setup, sysadmin, Access, ADMINISTRATOR,
+        BOT_PERMISSIONS, MANAGE_ROLES, SEND_MESSAGES, VIEW_CHANNEL,
+    },
+    correlation,
     dal::{
-        dao::guild::GuildDao,
+        dao::{
+            favorite::FavoriteDao, guild_stores::GuildStores, role_store::RoleStore,
+            webhook::GuildWebhook,
+        },
         model::{
-            interaction_request::{ApplicationCommandData, InteractionRequest},
-            interaction_response::{ApplicationCommandOptionChoice, InteractionResponse},
+            activity_entry::{role_mention, ActivityEntry, RoleActivity},
+            age_requirement::AgeRequirement,
+            audit_entry::{AuditEntry, AuditOutcome},
+            daily_stats::DailyStats,
+            deferred_task::{DeferredTask, TaskOrigin},
+            guild_event::GuildEvent,
+            interaction_request::{ApplicationCommandData, InteractionRequest, ResolvedRole},
+            interaction_response::{
+                ApplicationCommandOptionChoice, ButtonStyle, Component, InteractionResponse,
+            },
+            mass_role_job::MassRoleJob,
+            role_announcement::RoleAnnouncement,
+            role_job::RoleModificationJob,
+            role_menu::{EmojiBinding, PartialEmoji, RoleMenu, MAX_MENU_BUTTONS},
+            role_name,
         },
+        queue::{task_queue::TaskQueue, task_scheduler::TaskScheduler},
     },
+    error::{CommandError, DiscordApiError},
+    markdown,
+    metrics::{self, CommandMetric, Outcome},
+    timestamp,
+};
+
+use super::{
+    command_registry::{self, CommandRegistry, Deferral, Execution, Invocation, Middleware},
+    component_router::REMOVE_BUTTON_ID_PREFIX,
+    request_context::RequestContext,
+    setup_wizard::{self, SetupAction, SetupStep},
 };
 
+/// Discord's limit on an autocomplete choice's name.
+const MAX_CHOICE_NAME_CHARS: usize = 100;
+/// Discord's limit on a message's content.
+const MAX_MESSAGE_CHARS: usize = 2_000;
+/// How far ahead an announcement can be scheduled.
+const MAX_ANNOUNCE_DAYS: i64 = 30;
+/// Audit entries `/role history` shows.
+const HISTORY_ENTRIES: usize = 15;
+/// Days of global stats `/sysadmin stats` shows.
+const STATS_DAYS: i32 = 7;
+/// The message of the role menu `/setup` posts.
+const SETUP_MENU_TEXT: &str = "Pick your roles below.";
+
+/// Discord's limit on a button's label.
+const MAX_BUTTON_LABEL_CHARS: usize = 80;
+/// Discord allows five rows of five buttons on a message.
+const MAX_BUTTONS: usize = 25;
+const BUTTONS_PER_ROW: usize = 5;
+
+/// Seconds between a member's runs of the subcommands that start
+/// guild-wide work.
+const GUILD_TASK_COOLDOWN_SECONDS: u64 = 30;
+
+const GUILD_TASK: &[Middleware] = &[Middleware::Cooldown(GUILD_TASK_COOLDOWN_SECONDS)];
+const ROLE_MENUS: &[Middleware] = &[Middleware::Premium(PremiumFeature::RoleMenus)];
+const MASS_ROLES: &[Middleware] = &[
+    Middleware::Premium(PremiumFeature::MassRoles),
+    Middleware::Cooldown(GUILD_TASK_COOLDOWN_SECONDS),
+];
+const ANNOUNCEMENTS: &[Middleware] = &[
+    Middleware::Premium(PremiumFeature::Announcements),
+    Middleware::Cooldown(GUILD_TASK_COOLDOWN_SECONDS),
+];
+
+/// Every subcommand the router handles, built once per warm instance.
+static ROUTES: Lazy<CommandRegistry<CommandRouter>> = Lazy::new(CommandRouter::routes);
+
+/// Which ways `change_role` may change the member's roles.
+#[derive(Debug, Clone, Copy)]
+enum RoleChange {
+    Toggle,
+    AddOnly,
+    RemoveOnly,
+}
+
 pub struct CommandRouter {
-    guild_dao: GuildDao,
-    role_manager: RoleManager,
+    role_store: Arc<dyn RoleStore>,
+    discord_api: Arc<dyn DiscordApi>,
+    #[cfg(feature = "billing")]
+    billing: BillingContext,
+    operators: OperatorContext,
+    task_queue: Option<TaskQueue>,
+    task_scheduler: Option<TaskScheduler>,
+    stores: GuildStores,
+    events: EventPublisher,
+    activity: ActivityRecorder,
 }
 
 impl CommandRouter {
-    pub fn new(guild_dao: GuildDao, role_manager: RoleManager) -> Self {
+    pub fn new(
+        role_store: Arc<dyn RoleStore>,
+        discord_api: Arc<dyn DiscordApi>,
+        #[cfg(feature = "billing")] billing: BillingContext,
+        operators: OperatorContext,
+        task_queue: Option<TaskQueue>,
+        task_scheduler: Option<TaskScheduler>,
+        stores: GuildStores,
+    ) -> Self {
+        let events = EventPublisher::new(stores.webhooks.clone(), task_queue.clone());
+        let activity = ActivityRecorder::new(stores.log_channels.clone(), task_queue.clone());
+
         Self {
-            guild_dao,
-            role_manager,
+            role_store,
+            discord_api,
+            #[cfg(feature = "billing")]
+            billing,
+            operators,
+            task_queue,
+            task_scheduler,
+            stores,
+            events,
+            activity,
         }
     }
 
-    pub async fn handle_autocomplete(
-        &self,
+    pub async fn handle_autocomplete(&self, ctx: &RequestContext) -> Result<InteractionResponse> {
+        Ok(Self::autocomplete(
+            self.role_store.as_ref(),
+            &self.stores.favorites,
+            &ctx.interaction,
+        )
+        .await)
+    }
+
+    /// Role-name suggestions for the focused option, with the user's
+    /// favorites first. Only needs the role and favorite stores, so the HTTP
+    /// handler can answer autocomplete without building a full router.
+    pub async fn autocomplete(
+        role_store: &dyn RoleStore,
+        favorites: &FavoriteDao,
         interaction: &InteractionRequest,
-    ) -> Result<InteractionResponse> {
+    ) -> InteractionResponse {
         let guild_id = interaction.guild_id.as_deref().unwrap_or("");
+        let user_id = Self::user_id(interaction);
 
         let prefix = interaction
             .data
@@ -39,138 +189,2257 @@ impl CommandRouter {
             .and_then(|val| val.as_str())
             .unwrap_or("");
 
-        let roles = self
-            .guild_dao
-            .query_roles_by_prefix(guild_id, prefix)
+        let min_length = role_store
+            .get_autocomplete_min_length(guild_id)
             .await
-            .unwrap_or_default();
+            .unwrap_or_default()
+            .unwrap_or(config::MIN_LENGTH);
+
+        let (favorites, member_counts) = tokio::join!(
+            favorites.get_favorites(guild_id, user_id),
+            role_store.get_member_counts(guild_id),
+        );
+        let favorites = favorites.unwrap_or_default();
+        let member_counts = member_counts.unwrap_or_default();
+
+        let typed = prefix.trim();
+        let roles = if typed.is_empty() && !favorites.is_empty() {
+            // Nothing typed yet; the favorites alone are worth offering.
+            Self::favorite_roles(role_store, guild_id, &favorites).await
+        } else if typed.chars().count() < min_length as usize {
+            return InteractionResponse::autocomplete(Self::keep_typing(typed, min_length));
+        } else {
+            role_store
+                .query_roles_by_prefix(guild_id, prefix, &favorites)
+                .await
+        };
 
         let choices: Vec<ApplicationCommandOptionChoice> = roles
+            .unwrap_or_default()
             .into_iter()
-            .map(|(role_name, _)| ApplicationCommandOptionChoice {
-                name: role_name.clone(),
+            .map(|(role_name, role_id)| ApplicationCommandOptionChoice {
+                name: match member_counts.get(&role_id) {
+                    Some(&count) => Self::with_member_count(&role_name, count),
+                    None => role_name.clone(),
+                },
                 value: role_name,
             })
             .collect();
 
-        Ok(InteractionResponse::autocomplete(choices))
+        InteractionResponse::autocomplete(choices)
     }
 
-    pub async fn handle_command(
-        &self,
-        interaction: &InteractionRequest,
-    ) -> Result<InteractionResponse> {
-        let guild_id = interaction.guild_id.as_deref().unwrap_or("");
+    /// The favorites that are still registered, as `(name, id)` pairs.
+    async fn favorite_roles(
+        role_store: &dyn RoleStore,
+        guild_id: &str,
+        favorites: &[String],
+    ) -> Result<Vec<(String, String)>> {
+        let mut roles = Vec::new();
+
+        for role_id in favorites {
+            if let Some(role) = role_store.get_role_by_id(guild_id, role_id).await? {
+                roles.push(role);
+            }
+        }
+
+        roles.sort_by_key(|(name, _)| name.to_lowercase());
+
+        Ok(roles)
+    }
+
+    /// `name (123 members)`, shortening the name so the choice stays within
+    /// Discord's 100-character limit.
+    fn with_member_count(role_name: &str, count: u64) -> String {
+        let suffix = match count {
+            1 => " (1 member)".to_string(),
+            n => format!(" ({} members)", n),
+        };
+
+        let max_name_chars = MAX_CHOICE_NAME_CHARS - suffix.chars().count();
+
+        Self::truncate(role_name, max_name_chars) + &suffix
+    }
+
+    /// Shown instead of suggestions until the guild's minimum length is
+    /// typed. Picking it submits what was typed, so a short role name can
+    /// still be toggled by its exact name.
+    fn keep_typing(typed: &str, min_length: u32) -> Vec<ApplicationCommandOptionChoice> {
+        if typed.is_empty() {
+            return vec![];
+        }
+
+        vec![ApplicationCommandOptionChoice {
+            name: format!("Keep typing… ({} characters to search)", min_length),
+            value: typed.to_string(),
+        }]
+    }
+
+    pub async fn handle_command(&self, ctx: &RequestContext) -> Result<InteractionResponse> {
+        let interaction = &ctx.interaction;
+        let guild_id = ctx.guild_id.as_str();
 
         let cmd_data: &ApplicationCommandData = match interaction.data.as_ref() {
             Some(d) => d,
             None => return Ok(InteractionResponse::ephemeral("Invalid command data.")),
         };
 
-        match cmd_data.name.as_str() {
-            "role" => {
-                self.handle_role_command(guild_id, cmd_data, interaction)
-                    .await
-            }
-            _ => Ok(InteractionResponse::ephemeral("Unknown command.")),
+        let started = Instant::now();
+
+        let (audit_outcome, result) = self.dispatch(guild_id, cmd_data, ctx).await;
+        let latency = started.elapsed();
+
+        metrics::emit_command(&CommandMetric {
+            command: &cmd_data.name,
+            subcommand: cmd_data
+                .options
+                .first()
+                .map(|sub| sub.name.as_str())
+                .unwrap_or(""),
+            outcome: if result.is_ok() {
+                Outcome::Success
+            } else {
+                Outcome::Error
+            },
+            latency,
+            guild_tier: self.guild_tier(),
+        });
+
+        self.audit(ctx, cmd_data, audit_outcome, latency).await;
+
+        result
+    }
+
+    /// Records the invocation for `/role history`. Wraps every command, so
+    /// handlers never record it themselves; a failed write is only logged.
+    async fn audit(
+        &self,
+        ctx: &RequestContext,
+        cmd_data: &ApplicationCommandData,
+        outcome: AuditOutcome,
+        latency: std::time::Duration,
+    ) {
+        let subcommand = cmd_data.options.first();
+        let entry = AuditEntry {
+            interaction_id: ctx.interaction.id.clone(),
+            user_id: Self::user_id(&ctx.interaction).to_string(),
+            command: match subcommand {
+                Some(sub) => format!("{} {}", cmd_data.name, sub.name),
+                None => cmd_data.name.clone(),
+            },
+            options_hash: AuditEntry::hash_options(
+                subcommand
+                    .map(|sub| sub.options.as_slice())
+                    .unwrap_or_default(),
+            ),
+            outcome,
+            duration_ms: latency.as_millis() as u64,
+            occurred_at: Utc::now().timestamp_millis(),
+        };
+
+        if let Err(err) = self.stores.audit.record(&ctx.guild_id, &entry).await {
+            warn!(
+                command = %entry.command,
+                error = format!("{:#}", err),
+                "Failed to record command audit entry"
+            );
+        }
+    }
+
+    /// Registers each subcommand's handler and the middleware it runs
+    /// behind. A new subcommand is one more line here.
+    fn routes() -> CommandRegistry<Self> {
+        #[cfg_attr(not(feature = "billing"), allow(unused_mut))]
+        let mut routes = CommandRegistry::<Self>::default()
+            .register(role::NAME, role::SAVE, &[], |r, inv| {
+                Box::pin(r.role_save(inv))
+            })
+            .register(role::NAME, role::TOGGLE, &[], |r, inv| {
+                Box::pin(r.role_toggle(inv))
+            })
+            .register_slow(role::NAME, role::IMPORT_ALL, GUILD_TASK, |r, inv| {
+                Box::pin(r.role_import_all(inv))
+            })
+            .register_slow(role::NAME, role::EXPORT, GUILD_TASK, |r, inv| {
+                Box::pin(r.role_export(inv))
+            })
+            .register_slow(role::NAME, role::MASS_ASSIGN, MASS_ROLES, |r, inv| {
+                Box::pin(r.role_mass(inv))
+            })
+            .register_slow(role::NAME, role::MASS_REMOVE, MASS_ROLES, |r, inv| {
+                Box::pin(r.role_mass(inv))
+            })
+            .register(role::NAME, role::ANNOUNCE, ANNOUNCEMENTS, |r, inv| {
+                Box::pin(r.role_announce(inv))
+            })
+            .register(role::NAME, role::FAVORITE, &[], |r, inv| {
+                Box::pin(r.role_favorite(inv))
+            })
+            .register(role::NAME, role::HISTORY, &[], |r, inv| {
+                Box::pin(r.role_history(inv))
+            })
+            .register(role::NAME, role::MINE, &[], |r, inv| {
+                Box::pin(r.role_mine(inv))
+            })
+            .register(
+                config::NAME,
+                config::AUTOCOMPLETE_MIN_LENGTH,
+                &[],
+                |r, inv| Box::pin(r.config_autocomplete_min_length(inv)),
+            )
+            .register(config::NAME, config::ROLE_LIMIT, &[], |r, inv| {
+                Box::pin(r.config_role_limit(inv))
+            })
+            .register(config::NAME, config::MIN_AGE, &[], |r, inv| {
+                Box::pin(r.config_min_age(inv))
+            })
+            .register(config::NAME, config::WEBHOOK, &[], |r, inv| {
+                Box::pin(r.config_webhook(inv))
+            })
+            .register(config::NAME, config::WEBHOOK_REMOVE, &[], |r, inv| {
+                Box::pin(r.config_webhook_remove(inv))
+            })
+            .register(config::NAME, config::LOG_CHANNEL, &[], |r, inv| {
+                Box::pin(r.config_log_channel(inv))
+            })
+            .register(config::NAME, config::PREREQUISITE, &[], |r, inv| {
+                Box::pin(r.config_prerequisite(inv))
+            })
+            .register(config::NAME, config::PREREQUISITE_CLEAR, &[], |r, inv| {
+                Box::pin(r.config_prerequisite(inv))
+            })
+            .register(config::NAME, config::CHANNELS, &[], |r, inv| {
+                Box::pin(r.config_channels(inv))
+            })
+            .register(config::NAME, config::CHANNELS_CLEAR, &[], |r, inv| {
+                Box::pin(r.config_channels(inv))
+            })
+            .register(rolemenu::NAME, rolemenu::POST, ROLE_MENUS, |r, inv| {
+                Box::pin(r.rolemenu_post(inv))
+            })
+            .register(
+                rolemenu::NAME,
+                rolemenu::BIND_EMOJI,
+                ROLE_MENUS,
+                |r, inv| Box::pin(r.rolemenu_edit(inv)),
+            )
+            .register(rolemenu::NAME, rolemenu::UNBIND_EMOJI, &[], |r, inv| {
+                Box::pin(r.rolemenu_edit(inv))
+            })
+            .register(setup::NAME, "", &[], |r, inv| Box::pin(r.setup_start(inv)))
+            .register(invite::NAME, "", &[], |r, inv| Box::pin(r.invite(inv)))
+            .register(sysadmin::NAME, sysadmin::STATS, &[], |r, inv| {
+                Box::pin(r.sysadmin_stats(inv))
+            })
+            .register(sysadmin::NAME, sysadmin::FLAG, &[], |r, inv| {
+                Box::pin(r.sysadmin_flag(inv))
+            })
+            .register(sysadmin::NAME, sysadmin::GUILD, &[], |r, inv| {
+                Box::pin(r.sysadmin_guild(inv))
+            });
+
+        #[cfg(feature = "billing")]
+        {
+            routes = routes
+                .register(sysadmin::NAME, sysadmin::GRANT_PREMIUM, &[], |r, inv| {
+                    Box::pin(r.sysadmin_grant_premium(inv))
+                })
+                .register(subscription::NAME, subscription::MANAGE, &[], |r, inv| {
+                    Box::pin(r.subscription_manage(inv))
+                })
+                .register(subscription::NAME, subscription::ATTACH, &[], |r, inv| {
+                    Box::pin(r.subscription_attach(inv))
+                })
+                .register(subscription::NAME, subscription::DETACH, &[], |r, inv| {
+                    Box::pin(r.subscription_detach(inv))
+                });
         }
+
+        routes
     }
 
-    async fn handle_role_command(
+    /// Looks up the subcommand's route, checks the member may run it, and
+    /// runs its middleware in order, then its handler unless one of them
+    /// answered instead. Returns how it went for the audit alongside the
+    /// reply.
+    async fn dispatch(
         &self,
         guild_id: &str,
         cmd_data: &ApplicationCommandData,
-        interaction: &InteractionRequest,
-    ) -> Result<InteractionResponse> {
-        let subcommand = match cmd_data.options.first() {
-            Some(s) => s,
-            None => return Ok(InteractionResponse::ephemeral("Missing subcommand.")),
-        };
-
-        match subcommand.name.as_str() {
-            "save" => {
-                let role_id = subcommand
-                    .options
-                    .first()
-                    .and_then(|opt| opt.value.as_ref())
-                    .and_then(|val| val.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                if role_id.is_empty() {
-                    return Ok(InteractionResponse::ephemeral("Role is required."));
-                }
+        ctx: &RequestContext,
+    ) -> (AuditOutcome, Result<InteractionResponse>) {
+        let refused = |response| (AuditOutcome::Refused, Ok(response));
 
-                let role_name = match cmd_data
-                    .resolved
-                    .as_ref()
-                    .and_then(|r| r.roles.get(&role_id))
-                    .map(|r| r.name.clone())
-                {
-                    Some(n) => n,
-                    None => return Ok(InteractionResponse::ephemeral("Resolved role missing.")),
-                };
+        if !ROUTES.has_command(&cmd_data.name) {
+            return refused(InteractionResponse::ephemeral("Unknown command."));
+        }
 
-                self.guild_dao
-                    .save_role(guild_id, &role_id, &role_name)
-                    .await?;
+        let subcommand = cmd_data.options.first();
+        let invocation = Invocation {
+            ctx,
+            guild_id,
+            data: cmd_data,
+            subcommand,
+        };
 
-                Ok(InteractionResponse::ephemeral(
-                    "Role registered successfully.",
-                ))
+        let Some(route) = ROUTES.route(&cmd_data.name, invocation.subcommand_name()) else {
+            return refused(InteractionResponse::ephemeral(match subcommand {
+                Some(_) => "Unknown subcommand.",
+                None => "Missing subcommand.",
+            }));
+        };
+
+        if let Some(refusal) = self.check_access(
+            commands::access(&cmd_data.name, invocation.subcommand_name()),
+            &ctx.interaction,
+        ) {
+            return refused(refusal);
+        }
+
+        for middleware in route.middleware {
+            if let Some(refusal) = self.run_middleware(*middleware, &invocation) {
+                return refused(refusal);
             }
+        }
 
-            "toggle" => {
-                let role_name_input = subcommand
-                    .options
-                    .first()
-                    .and_then(|opt| opt.value.as_ref())
-                    .and_then(|val| val.as_str())
-                    .unwrap_or("")
-                    .to_string();
+        let result = match route.execution {
+            Execution::Inline(handler) => handler(self, invocation).await,
+            Execution::Slow(handler) => match handler(self, invocation).await {
+                Ok(Deferral::Queue(task)) => self.defer(&invocation, route.cooldown(), task).await,
+                Ok(Deferral::Reply(response)) => Ok(response),
+                Err(err) => Err(err),
+            },
+        };
+        let outcome = match result {
+            Ok(_) => AuditOutcome::Success,
+            Err(_) => AuditOutcome::Error,
+        };
 
-                let (role_name, role_id) = match self
-                    .guild_dao
-                    .get_role_by_name(guild_id, &role_name_input)
-                    .await?
-                {
-                    Some(role) => role,
-                    None => return Ok(InteractionResponse::ephemeral("Role not self-assignable.")),
-                };
+        (outcome, result)
+    }
 
-                let user_id = interaction
-                    .member
-                    .as_ref()
-                    .map(|m| m.user.id.as_str())
-                    .unwrap_or("");
+    /// The refusal to send if the member doesn't have `access`. Members are
+    /// checked even when Discord hid the command from them, since server
+    /// admins can widen a command's permissions in their settings.
+    fn check_access(
+        &self,
+        access: Access,
+        interaction: &InteractionRequest,
+    ) -> Option<InteractionResponse> {
+        match access {
+            Access::Everyone => None,
+            Access::Permissions(permissions) => {
+                let allowed = interaction.member.as_ref().is_some_and(|member| {
+                    member.has_permission(ADMINISTRATOR) || member.has_permission(permissions)
+                });
 
-                let member_roles = self
-                    .role_manager
-                    .fetch_member_roles(guild_id, user_id)
-                    .await?;
+                (!allowed).then(|| {
+                    InteractionResponse::ephemeral(format!(
+                        "You need the {} permission to use this command.",
+                        Access::permission_name(permissions)
+                    ))
+                })
+            }
+            Access::Operator => (!self
+                .operators
+                .allowlist
+                .contains(Self::user_id(interaction)))
+            .then(|| {
+                InteractionResponse::ephemeral("This command is restricted to bot operators.")
+            }),
+        }
+    }
 
-                let has_role = member_roles.iter().any(|r| r == &role_id);
+    /// The response to send instead of running the handler, if `middleware`
+    /// refuses the invocation.
+    fn run_middleware(
+        &self,
+        middleware: Middleware,
+        invocation: &Invocation,
+    ) -> Option<InteractionResponse> {
+        let user_id = Self::user_id(&invocation.ctx.interaction);
 
-                let action = if has_role {
-                    RoleAction::Remove
-                } else {
-                    RoleAction::Add
-                };
+        match middleware {
+            Middleware::Premium(feature) => self.premium_upsell(feature, &invocation.ctx.flags),
+            Middleware::Cooldown(_) => command_registry::cooldown_remaining(invocation, user_id)
+                .map(|remaining| {
+                    InteractionResponse::ephemeral(format!(
+                        "You just ran this. Try again {}.",
+                        timestamp::relative_in(remaining.as_secs().max(1))
+                    ))
+                }),
+        }
+    }
 
-                self.role_manager
-                    .modify_user_role(guild_id, user_id, &role_id, action)
-                    .await?;
+    /// The upsell to reply with instead, when `feature` is reserved for
+    /// premium and the guild doesn't have it.
+    #[cfg(feature = "billing")]
+    fn premium_upsell(
+        &self,
+        feature: PremiumFeature,
+        flags: &FeatureFlags,
+    ) -> Option<InteractionResponse> {
+        if !feature.is_premium_only(flags) {
+            return None;
+        }
 
-                let message = if has_role {
-                    format!("Removed '{}'.", role_name)
-                } else {
-                    format!("Added '{}'.", role_name)
-                };
+        self.billing.premium_gate.require(feature.name())
+    }
+
+    #[cfg(not(feature = "billing"))]
+    fn premium_upsell(
+        &self,
+        _feature: PremiumFeature,
+        _flags: &FeatureFlags,
+    ) -> Option<InteractionResponse> {
+        None
+    }
+
+    #[cfg(feature = "billing")]
+    fn guild_tier(&self) -> &'static str {
+        if self.billing.premium_gate.is_premium() {
+            "premium"
+        } else {
+            "free"
+        }
+    }
+
+    #[cfg(not(feature = "billing"))]
+    fn guild_tier(&self) -> &'static str {
+        "free"
+    }
+
+    /// The upsell to reply with instead of toggling, when the guild has used
+    /// up its free-tier toggles.
+    #[cfg(feature = "billing")]
+    async fn quota_upsell(&self, guild_id: &str) -> Result<Option<InteractionResponse>> {
+        match self.billing.usage_meter.check_quota(guild_id).await? {
+            QuotaStatus::Exhausted { limit } => {
+                Ok(Some(self.billing.premium_gate.upsell(format!(
+                    "This server has reached its free-tier limit of {} role toggles this month. \
+                     Upgrade to premium for unlimited toggles.",
+                    limit
+                ))))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    #[cfg(not(feature = "billing"))]
+    async fn quota_upsell(&self, _guild_id: &str) -> Result<Option<InteractionResponse>> {
+        Ok(None)
+    }
+
+    /// Meters a toggle that already happened, so a failure is logged rather
+    /// than reported to the member as if the toggle had failed.
+    #[cfg(feature = "billing")]
+    async fn record_toggle(&self, guild_id: &str) {
+        if let Err(err) = self.billing.usage_meter.record_toggle(guild_id).await {
+            warn!(
+                guild_id,
+                error = format!("{:#}", err),
+                "Failed to meter role toggle"
+            );
+        }
+    }
 
-                Ok(InteractionResponse::ephemeral(message))
+    #[cfg(not(feature = "billing"))]
+    async fn record_toggle(&self, _guild_id: &str) {}
+
+    async fn role_save(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let guild_id = inv.guild_id;
+        let role_id = inv.str_option(role::ROLE_OPTION).unwrap_or("").to_string();
+
+        if role_id.is_empty() {
+            return Ok(InteractionResponse::ephemeral("Role is required."));
+        }
+
+        let resolved_role = match inv
+            .data
+            .resolved
+            .as_ref()
+            .and_then(|r| r.roles.get(&role_id))
+        {
+            Some(r) => r,
+            None => return Ok(InteractionResponse::ephemeral("Resolved role missing.")),
+        };
+
+        if let Some(reason) = self.save_refusal(&inv, resolved_role).await? {
+            return Ok(InteractionResponse::ephemeral(format!(
+                "That role can't be self-assignable: {}.",
+                reason
+            )));
+        }
+
+        let alias = inv.str_option(role::ALIAS_OPTION);
+        let role_name = match role_name::normalize(alias.unwrap_or(&resolved_role.name)) {
+            Ok(name) => name.to_string(),
+            Err(invalid) if alias.is_some() => {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "That alias can't be used: {}.",
+                    invalid.reason()
+                )))
             }
+            Err(invalid) => {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "That role can't be saved: {}. Rename it in Server Settings or save it \
+                     with `alias`.",
+                    invalid.reason()
+                )))
+            }
+        };
+
+        if let Some((_, other_id)) = self
+            .role_store
+            .name_conflict(guild_id, &role_id, &role_name)
+            .await?
+        {
+            return Ok(InteractionResponse::ephemeral(format!(
+                "{} is already registered as '{}'. Save this role under another name with \
+                 `/role save role:{} alias:<name>`.",
+                role_mention(&other_id),
+                markdown::escape(&role_name),
+                role_mention(&role_id)
+            )));
+        }
+
+        if let Some(duplicate) = self.claim_interaction(inv.ctx).await? {
+            return Ok(duplicate);
+        }
+
+        if let Err(err) = self
+            .role_store
+            .save_role_with_icon(guild_id, &role_id, &role_name, &resolved_role.icon)
+            .await
+        {
+            self.release_interaction(inv.ctx).await;
+            return Err(err);
+        }
+
+        self.activity
+            .record(
+                ActivityEntry::new(
+                    guild_id,
+                    RoleActivity::Saved,
+                    Some(Self::user_id(&inv.ctx.interaction)),
+                    role_mention(&role_id),
+                )
+                .succeeded(format!(
+                    "'{}' is self-assignable",
+                    markdown::escape(&role_name)
+                ))
+                .with_thumbnail(resolved_role.icon.url(&role_id)),
+            )
+            .await;
+
+        self.events
+            .publish(guild_id, GuildEvent::RoleRegistered { role_id, role_name })
+            .await;
+
+        Ok(InteractionResponse::ephemeral(
+            "Role registered successfully.",
+        ))
+    }
+
+    /// Why `role` can't be saved, if it can't: the roles `/role import-all`
+    /// skips, and roles at or above the member's own highest, which they
+    /// couldn't hand out themselves.
+    async fn save_refusal(
+        &self,
+        inv: &Invocation<'_>,
+        role: &ResolvedRole,
+    ) -> Result<Option<&'static str>> {
+        let interaction = &inv.ctx.interaction;
+
+        if role.id == inv.guild_id {
+            return Ok(Some("everyone already has @everyone"));
+        }
+
+        if role.managed {
+            return Ok(Some("it's managed by an integration"));
+        }
+
+        let hierarchy = RoleHierarchy::load(
+            self.discord_api.as_ref(),
+            inv.guild_id,
+            &interaction.application_id,
+        )
+        .await?;
+
+        if let Some(reason) = hierarchy.refusal(&role.permissions, role.position) {
+            return Ok(Some(reason));
+        }
+
+        Ok(Self::member_refusal(&hierarchy, interaction, role.position))
+    }
+
+    /// `RoleHierarchy::member_refusal` for the member who sent
+    /// `interaction`.
+    fn member_refusal(
+        hierarchy: &RoleHierarchy,
+        interaction: &InteractionRequest,
+        position: i64,
+    ) -> Option<&'static str> {
+        interaction.member.as_ref().and_then(|member| {
+            hierarchy.member_refusal(
+                position,
+                &member.roles,
+                member.has_permission(ADMINISTRATOR),
+            )
+        })
+    }
+
+    async fn role_toggle(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let role_name_input = inv.str_option(role::ROLE_OPTION).unwrap_or("").trim();
+
+        let (role_name, role_id) = match self
+            .role_store
+            .get_role_by_name(inv.guild_id, role_name_input)
+            .await?
+        {
+            Some(role) => role,
+            None => return Ok(InteractionResponse::ephemeral("Role not self-assignable.")),
+        };
+
+        self.toggle_role(inv.ctx, &role_id, &role_name).await
+    }
+
+    async fn role_import_all(&self, inv: Invocation<'_>) -> Result<Deferral> {
+        Ok(Deferral::Queue(Self::import_task(
+            inv.guild_id,
+            &inv.ctx.interaction,
+        )))
+    }
+
+    /// The import `/role import-all` and the setup wizard queue. The origin
+    /// carries the roles of who asked, which the import holds each role to.
+    fn import_task(guild_id: &str, interaction: &InteractionRequest) -> DeferredTask {
+        DeferredTask::ImportRoles(Self::task_origin(guild_id, interaction))
+    }
+
+    async fn role_export(&self, inv: Invocation<'_>) -> Result<Deferral> {
+        Ok(Deferral::Queue(DeferredTask::ExportRoles(
+            Self::task_origin(inv.guild_id, &inv.ctx.interaction),
+        )))
+    }
+
+    async fn role_favorite(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let guild_id = inv.guild_id;
+        let role_name_input = inv.str_option(role::ROLE_OPTION).unwrap_or("").trim();
+
+        let Some((role_name, role_id)) = self
+            .role_store
+            .get_role_by_name(guild_id, role_name_input)
+            .await?
+        else {
+            return Ok(InteractionResponse::ephemeral("Role not self-assignable."));
+        };
+
+        let user_id = Self::user_id(&inv.ctx.interaction);
+        let favorites = self
+            .stores
+            .favorites
+            .get_favorites(guild_id, user_id)
+            .await?;
+
+        if favorites.contains(&role_id) {
+            self.stores
+                .favorites
+                .remove_favorite(guild_id, user_id, &role_id)
+                .await?;
+
+            return Ok(InteractionResponse::ephemeral(format!(
+                "Removed '{}' from your favorites.",
+                markdown::escape(&role_name)
+            )));
+        }
+
+        if favorites.len() >= role::MAX_FAVORITES {
+            return Ok(InteractionResponse::ephemeral(format!(
+                "You can have at most {} favorites. Remove one with `/role favorite` first.",
+                role::MAX_FAVORITES
+            )));
+        }
+
+        self.stores
+            .favorites
+            .add_favorite(guild_id, user_id, &role_id)
+            .await?;
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "Added '{}' to your favorites. It'll be suggested first when you pick a role.",
+            markdown::escape(&role_name)
+        )))
+    }
+
+    /// The member's self-assignable roles, each with a Remove button, and
+    /// their favorites. Uses the roles Discord sent with the interaction, so
+    /// it costs no Discord call.
+    async fn role_mine(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let (guild_id, interaction) = (inv.guild_id, &inv.ctx.interaction);
+        let member_roles = interaction
+            .member
+            .as_ref()
+            .map(|member| member.roles.as_slice())
+            .unwrap_or_default();
+
+        let (registered, favorites) = tokio::try_join!(
+            self.role_store.list_roles(guild_id),
+            self.stores
+                .favorites
+                .get_favorites(guild_id, Self::user_id(interaction)),
+        )?;
+
+        let mut held: Vec<(String, String)> = registered
+            .iter()
+            .filter(|(_, id)| member_roles.contains(id))
+            .cloned()
+            .collect();
+        held.sort_by_key(|(name, _)| name.to_lowercase());
+
+        let favorites: Vec<String> = registered
+            .iter()
+            .filter(|(_, id)| favorites.contains(id))
+            .map(|(_, id)| role_mention(id))
+            .collect();
+
+        if held.is_empty() && favorites.is_empty() {
+            return Ok(InteractionResponse::ephemeral(
+                "You don't have any self-assignable roles. Take one with `/role toggle`.",
+            ));
+        }
+
+        let description = if held.is_empty() {
+            "You don't have any self-assignable roles.".to_string()
+        } else {
+            held.iter()
+                .map(|(_, id)| role_mention(id))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let mut embed = json!({
+            "title": "Your roles",
+            "description": description,
+        });
+        if !favorites.is_empty() {
+            embed["fields"] = json!([{ "name": "Favorites", "value": favorites.join(" ") }]);
+        }
+        if held.len() > MAX_BUTTONS {
+            embed["footer"] = json!({
+                "text": format!(
+                    "Only {} buttons fit; remove the rest with /role toggle.",
+                    MAX_BUTTONS
+                ),
+            });
+        }
+
+        let buttons: Vec<Component> = held
+            .iter()
+            .take(MAX_BUTTONS)
+            .map(|(name, id)| {
+                Component::button(
+                    ButtonStyle::Danger,
+                    Self::truncate(&format!("Remove {}", name), MAX_BUTTON_LABEL_CHARS),
+                    format!("{}{}", REMOVE_BUTTON_ID_PREFIX, id),
+                )
+            })
+            .collect();
+
+        let mut rows = Vec::new();
+        let mut buttons = buttons.into_iter().peekable();
+        while buttons.peek().is_some() {
+            rows.push(Component::action_row(
+                buttons.by_ref().take(BUTTONS_PER_ROW).collect(),
+            ));
+        }
+
+        Ok(InteractionResponse::ephemeral_embed(embed).with_components(rows))
+    }
+
+    /// The guild's latest commands, or one member's, from the audit.
+    async fn role_history(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let member = inv.str_option(role::MEMBER_OPTION);
+        let entries = self
+            .stores
+            .audit
+            .recent(inv.guild_id, member, HISTORY_ENTRIES)
+            .await?;
+
+        if entries.is_empty() {
+            return Ok(InteractionResponse::ephemeral("No commands recorded yet."));
+        }
+
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} <@{}> `/{}` {}, {} ms",
+                    timestamp::relative(entry.occurred_at / 1000),
+                    entry.user_id,
+                    entry.command,
+                    entry.outcome.as_str(),
+                    entry.duration_ms
+                )
+            })
+            .collect();
+
+        Ok(InteractionResponse::ephemeral(lines.join("\n")))
+    }
+
+    /// Shortens `text` to `max_chars`, ending it with an ellipsis if cut.
+    fn truncate(text: &str, max_chars: usize) -> String {
+        if text.chars().count() <= max_chars {
+            return text.to_string();
+        }
+
+        let mut truncated: String = text.chars().take(max_chars - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+
+    /// Claims the interaction's ID before a write, so a duplicate delivery
+    /// of it is answered instead of applying the change again.
+    async fn claim_interaction(&self, ctx: &RequestContext) -> Result<Option<InteractionResponse>> {
+        let claimed = self
+            .stores
+            .idempotency
+            .claim(&ctx.guild_id, &ctx.interaction.id)
+            .await?;
+
+        Ok((!claimed).then(|| InteractionResponse::ephemeral("That request was already handled.")))
+    }
+
+    /// Gives up the claim after its write failed. A leftover claim only
+    /// turns a redelivery into "already handled", so failing to release it
+    /// is logged rather than returned.
+    async fn release_interaction(&self, ctx: &RequestContext) {
+        if let Err(err) = self
+            .stores
+            .idempotency
+            .release(&ctx.guild_id, &ctx.interaction.id)
+            .await
+        {
+            warn!(
+                error = format!("{:#}", err),
+                "Failed to release interaction claim"
+            );
+        }
+    }
+
+    /// Adds or removes a role for the member who asked, from `/role toggle`
+    /// or a role menu button.
+    pub async fn toggle_role(
+        &self,
+        ctx: &RequestContext,
+        role_id: &str,
+        role_name: &str,
+    ) -> Result<InteractionResponse> {
+        self.change_role(ctx, role_id, role_name, RoleChange::Toggle)
+            .await
+    }
+
+    /// Adds a role for the member who asked, from an announcement's Join
+    /// button. Members who already have it are told so rather than having
+    /// it removed.
+    pub async fn join_role(
+        &self,
+        ctx: &RequestContext,
+        role_id: &str,
+        role_name: &str,
+    ) -> Result<InteractionResponse> {
+        self.change_role(ctx, role_id, role_name, RoleChange::AddOnly)
+            .await
+    }
 
-            _ => Ok(InteractionResponse::ephemeral("Unknown subcommand.")),
+    /// Removes a role from the member who asked, from a `/role mine` Remove
+    /// button.
+    pub async fn remove_role(
+        &self,
+        ctx: &RequestContext,
+        role_id: &str,
+        role_name: &str,
+    ) -> Result<InteractionResponse> {
+        self.change_role(ctx, role_id, role_name, RoleChange::RemoveOnly)
+            .await
+    }
+
+    async fn change_role(
+        &self,
+        ctx: &RequestContext,
+        role_id: &str,
+        role_name: &str,
+        change: RoleChange,
+    ) -> Result<InteractionResponse> {
+        let interaction = &ctx.interaction;
+        let guild_id = ctx.guild_id.as_str();
+
+        if let Some(setup) = Self::missing_manage_roles(interaction) {
+            return Ok(setup);
+        }
+
+        if let Some(upsell) = self.quota_upsell(guild_id).await? {
+            return Ok(upsell);
+        }
+
+        let user_id = Self::user_id(interaction);
+
+        let (member_roles, prerequisites, role_limit, age_requirement, allowed_channels) = tokio::try_join!(
+            self.discord_api.fetch_member_roles(guild_id, user_id),
+            self.role_store.get_prerequisites(guild_id, role_id),
+            self.role_store.get_role_limit(guild_id),
+            self.role_store.get_age_requirement(guild_id),
+            self.role_store.get_allowed_channels(guild_id, role_id),
+        )?;
+
+        let in_allowed_channel = allowed_channels.is_empty()
+            || interaction
+                .channel_id
+                .as_ref()
+                .is_some_and(|channel_id| allowed_channels.contains(channel_id));
+
+        if !in_allowed_channel {
+            return Ok(InteractionResponse::ephemeral(format!(
+                "'{}' can only be toggled from {}.",
+                markdown::escape(role_name),
+                Self::channel_mentions(&allowed_channels)
+            )));
+        }
+
+        let has_role = member_roles.iter().any(|r| r == role_id);
+
+        match change {
+            RoleChange::AddOnly if has_role => {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "You already have '{}'.",
+                    markdown::escape(role_name)
+                )))
+            }
+            RoleChange::RemoveOnly if !has_role => {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "You don't have '{}'.",
+                    markdown::escape(role_name)
+                )))
+            }
+            _ => {}
+        }
+
+        // Only taking a role is gated; members can always drop one.
+        let eligible_at = age_requirement
+            .zip(interaction.member.as_ref())
+            .and_then(|(requirement, member)| requirement.eligible_at(member));
+
+        if let Some(eligible_at) = eligible_at.filter(|at| !has_role && *at > Utc::now()) {
+            return Ok(InteractionResponse::ephemeral(format!(
+                "This server lets members take roles a little while after they arrive. You can \
+                 take '{}' {}.",
+                markdown::escape(role_name),
+                timestamp::relative(eligible_at.timestamp())
+            )));
+        }
+
+        let missing: Vec<String> = prerequisites
+            .iter()
+            .filter(|required| !member_roles.contains(required))
+            .map(|required| role_mention(required))
+            .collect();
+
+        if !has_role && !missing.is_empty() {
+            return Ok(InteractionResponse::ephemeral(format!(
+                "You need {} to take '{}'.",
+                missing.join(", "),
+                markdown::escape(role_name)
+            )));
+        }
+
+        if let Some(limit) = role_limit.filter(|_| !has_role) {
+            let held = self
+                .role_store
+                .list_roles(guild_id)
+                .await?
+                .iter()
+                .filter(|(_, id)| member_roles.contains(id))
+                .count();
+
+            if held >= limit as usize {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "You can have at most {} self-assignable roles. Remove one with `/role mine` \
+                     to take '{}'.",
+                    limit,
+                    markdown::escape(role_name)
+                )));
+            }
+        }
+
+        let action = if has_role {
+            RoleAction::Remove
+        } else {
+            RoleAction::Add
+        };
+
+        // Claimed only now, so a delivery turned away by the checks above
+        // doesn't leave a claim behind.
+        if let Some(duplicate) = self.claim_interaction(ctx).await? {
+            return Ok(duplicate);
+        }
+
+        if let Err(err) = self
+            .discord_api
+            .modify_user_role(guild_id, user_id, role_id, action)
+            .await
+        {
+            if DiscordApiError::is_retryable_error(&err) {
+                if let Some(queue) = self
+                    .task_queue
+                    .as_ref()
+                    .filter(|_| ctx.flags.is_enabled(Flag::ToggleRetryQueue))
+                {
+                    let task = DeferredTask::RoleModification(RoleModificationJob {
+                        guild_id: guild_id.to_string(),
+                        user_id: user_id.to_string(),
+                        role_id: role_id.to_string(),
+                        role_name: role_name.to_string(),
+                        remove: has_role,
+                        application_id: interaction.application_id.clone(),
+                        interaction_token: interaction.token.clone(),
+                        attempt: 0,
+                    });
+
+                    // The retry worker reports the result with a followup,
+                    // which replaces the deferred response's placeholder.
+                    if queue.enqueue(&task, INITIAL_DELAY_SECONDS).await.is_ok() {
+                        self.record_toggle(guild_id).await;
+
+                        return Ok(InteractionResponse::deferred_ephemeral());
+                    }
+                }
+            }
+
+            error!(
+                ?action,
+                role_id,
+                user_id,
+                reference = %correlation::reference(),
+                error = format!("{:#}", err),
+                "Failed to modify member role"
+            );
+
+            self.release_interaction(ctx).await;
+
+            let message = CommandError::from(err).user_message();
+
+            self.activity
+                .record(
+                    ActivityEntry::new(
+                        guild_id,
+                        RoleActivity::Toggled,
+                        Some(user_id),
+                        role_mention(role_id),
+                    )
+                    .failed(message.clone()),
+                )
+                .await;
+
+            return Ok(InteractionResponse::ephemeral(message));
+        }
+
+        self.record_toggle(guild_id).await;
+
+        // Only ranks autocomplete, so a failure isn't worth failing the
+        // toggle over.
+        if let Err(err) = self
+            .role_store
+            .increment_toggle_count(guild_id, role_id)
+            .await
+        {
+            warn!(
+                role_id,
+                error = format!("{:#}", err),
+                "Failed to count role toggle"
+            );
+        }
+
+        let message = if has_role {
+            format!("Removed '{}'.", markdown::escape(role_name))
+        } else {
+            format!("Added '{}'.", markdown::escape(role_name))
+        };
+
+        self.activity
+            .record(
+                ActivityEntry::new(
+                    guild_id,
+                    RoleActivity::Toggled,
+                    Some(user_id),
+                    role_mention(role_id),
+                )
+                .succeeded(message.clone()),
+            )
+            .await;
+
+        self.events
+            .publish(
+                guild_id,
+                GuildEvent::RoleToggled {
+                    user_id: user_id.to_string(),
+                    role_id: role_id.to_string(),
+                    role_name: role_name.to_string(),
+                    added: !has_role,
+                },
+            )
+            .await;
+
+        Ok(InteractionResponse::ephemeral(message))
+    }
+
+    /// Queues a change of one role across the guild's members.
+    async fn role_mass(&self, inv: Invocation<'_>) -> Result<Deferral> {
+        let (guild_id, interaction) = (inv.guild_id, &inv.ctx.interaction);
+        let option = |name: &str| inv.str_option(name).map(str::to_string);
+
+        let Some(role_id) = option(role::ROLE_OPTION) else {
+            return Ok(Deferral::Reply(InteractionResponse::ephemeral(
+                "Role is required.",
+            )));
+        };
+
+        let Some(role) = inv
+            .data
+            .resolved
+            .as_ref()
+            .and_then(|r| r.roles.get(&role_id))
+        else {
+            return Ok(Deferral::Reply(InteractionResponse::ephemeral(
+                "Resolved role missing.",
+            )));
+        };
+
+        if role_id == guild_id {
+            return Ok(Deferral::Reply(InteractionResponse::ephemeral(
+                "Everyone already has @everyone.",
+            )));
+        }
+
+        if let Some(setup) = Self::missing_manage_roles(interaction) {
+            return Ok(Deferral::Reply(setup));
+        }
+
+        let hierarchy = RoleHierarchy::load(
+            self.discord_api.as_ref(),
+            guild_id,
+            &interaction.application_id,
+        )
+        .await?;
+        // Checked against the member too, or they could hand themselves a
+        // role above their own along with everyone else.
+        let refusal = match hierarchy.role(&role_id) {
+            Some(role) if role.managed => Some("it's managed by an integration"),
+            Some(role) => hierarchy
+                .role_refusal(role)
+                .or_else(|| Self::member_refusal(&hierarchy, interaction, role.position)),
+            None => Some("it no longer exists"),
+        };
+        if let Some(reason) = refusal {
+            return Ok(Deferral::Reply(InteractionResponse::ephemeral(format!(
+                "'{}' can't be handed out in bulk: {}.",
+                markdown::escape(&role.name),
+                reason
+            ))));
+        }
+
+        let job = MassRoleJob {
+            origin: Self::task_origin(guild_id, interaction),
+            role_id,
+            role_name: role.name.clone(),
+            remove: inv.subcommand_name() == role::MASS_REMOVE,
+            filter_role_id: option(role::FILTER_OPTION),
+            after: None,
+            progress: Default::default(),
+            attempt: 0,
+        };
+
+        Ok(Deferral::Queue(DeferredTask::MassRole(job)))
+    }
+
+    /// Posts a message advertising a registered role, now or at the time
+    /// given.
+    async fn role_announce(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let (guild_id, interaction) = (inv.guild_id, &inv.ctx.interaction);
+        let option = |name: &str| {
+            inv.str_option(name)
+                .map(str::trim)
+                .filter(|val| !val.is_empty())
+        };
+
+        let role_id = option(role::ROLE_OPTION).unwrap_or("");
+        let Some((role_name, role_id)) = self.role_store.get_role_by_id(guild_id, role_id).await?
+        else {
+            return Ok(InteractionResponse::ephemeral(
+                "That role isn't self-assignable. Register it with `/role save` first.",
+            ));
+        };
+
+        let text = option(role::TEXT_OPTION);
+        if text.is_some_and(|text| text.chars().count() > MAX_MESSAGE_CHARS) {
+            return Ok(InteractionResponse::ephemeral(format!(
+                "The announcement can be at most {} characters.",
+                MAX_MESSAGE_CHARS
+            )));
+        }
+
+        let channel_id = match Self::postable_channel(interaction) {
+            Ok(channel_id) => channel_id,
+            Err(refusal) => return Ok(refusal),
+        };
+
+        let announcement = RoleAnnouncement {
+            guild_id: guild_id.to_string(),
+            channel_id: channel_id.to_string(),
+            role_id,
+            role_name,
+            text: text.map(str::to_string),
+        };
+
+        let Some(at) = option(role::AT_OPTION) else {
+            self.discord_api
+                .create_message(&announcement.channel_id, &announcement.message())
+                .await?;
+
+            return Ok(InteractionResponse::ephemeral(format!(
+                "Announced '{}'.",
+                markdown::escape(&announcement.role_name)
+            )));
+        };
+
+        let now = Utc::now();
+        let at = match Self::parse_time(at) {
+            Some(at) if at > now && at <= now + Duration::days(MAX_ANNOUNCE_DAYS) => at,
+            _ => {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "Give a time within the next {} days, as a Unix timestamp or like \
+                     `2030-01-31T18:00:00Z`.",
+                    MAX_ANNOUNCE_DAYS
+                )))
+            }
+        };
+
+        let Some(scheduler) = self.task_scheduler.as_ref() else {
+            return Ok(InteractionResponse::ephemeral(
+                "Scheduled announcements are not configured.",
+            ));
+        };
+
+        scheduler
+            .schedule(
+                &format!("announce-{}", interaction.id),
+                at,
+                &DeferredTask::RoleAnnouncement(announcement.clone()),
+            )
+            .await?;
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "'{}' will be announced here {}.",
+            markdown::escape(&announcement.role_name),
+            timestamp::relative(at.timestamp())
+        )))
+    }
+
+    /// An RFC 3339 time or Unix seconds, as Discord's `<t:…>` tags use.
+    fn parse_time(input: &str) -> Option<DateTime<Utc>> {
+        if let Ok(seconds) = input.parse::<i64>() {
+            return DateTime::from_timestamp(seconds, 0);
+        }
+
+        DateTime::parse_from_rfc3339(input)
+            .ok()
+            .map(|at| at.with_timezone(&Utc))
+    }
+
+    /// How to fix the bot's permissions, if the interaction says it lacks
+    /// Manage Roles, so role changes aren't attempted only to fail with a
+    /// 403. Offers a re-invite link for this server with the permissions the
+    /// bot needs.
+    fn missing_manage_roles(interaction: &InteractionRequest) -> Option<InteractionResponse> {
+        if !interaction.app_lacks_permission(MANAGE_ROLES) {
+            return None;
+        }
+
+        let response = InteractionResponse::ephemeral(
+            "I need the Manage Roles permission to change roles. Ask an admin to give it to my \
+             role in Server Settings, or to re-invite me with the button below. My role also has \
+             to be above the roles I manage.",
+        );
+
+        match OAuthClient::bot_invite_url(
+            &interaction.application_id,
+            interaction.guild_id.as_deref(),
+            BOT_PERMISSIONS,
+        ) {
+            Ok(url) => Some(response.with_components(vec![Component::action_row(vec![
+                Component::link_button("Re-invite with permissions", url.as_str()),
+            ])])),
+            Err(_) => Some(response),
+        }
+    }
+
+    /// The channel the interaction came from, if the bot can post there;
+    /// otherwise the reply to send instead of a post Discord would refuse.
+    fn postable_channel(interaction: &InteractionRequest) -> Result<&str, InteractionResponse> {
+        let Some(channel_id) = interaction.channel_id.as_deref() else {
+            return Err(InteractionResponse::ephemeral("Channel ID missing."));
+        };
+
+        if interaction.app_lacks_permission(VIEW_CHANNEL | SEND_MESSAGES) {
+            return Err(InteractionResponse::ephemeral(
+                "I can't post in this channel. Give me View Channel and Send Messages here, or \
+                 run this in another channel.",
+            ));
+        }
+
+        Ok(channel_id)
+    }
+
+    fn task_origin(guild_id: &str, interaction: &InteractionRequest) -> TaskOrigin {
+        let member = interaction.member.as_ref();
+
+        TaskOrigin {
+            guild_id: guild_id.to_string(),
+            application_id: interaction.application_id.clone(),
+            interaction_token: interaction.token.clone(),
+            user_id: Some(Self::user_id(interaction).to_string()),
+            member_roles: member.map(|m| m.roles.clone()).unwrap_or_default(),
+            administrator: member.is_some_and(|m| m.has_permission(ADMINISTRATOR)),
+        }
+    }
+
+    fn user_id(interaction: &InteractionRequest) -> &str {
+        interaction
+            .member
+            .as_ref()
+            .map(|m| m.user.id.as_str())
+            .unwrap_or("")
+    }
+
+    /// Hands a task to the queue worker and acknowledges the interaction with
+    /// a deferred response the worker edits once the task completes. Starts
+    /// the member's `cooldown` once the task is queued.
+    async fn defer(
+        &self,
+        invocation: &Invocation<'_>,
+        cooldown: Option<std::time::Duration>,
+        task: DeferredTask,
+    ) -> Result<InteractionResponse> {
+        if !invocation.ctx.flags.is_enabled(Flag::DeferredRoleTasks) {
+            return Ok(InteractionResponse::ephemeral(
+                "This feature is temporarily unavailable.",
+            ));
+        }
+
+        let queue = match self.task_queue.as_ref() {
+            Some(q) => q,
+            None => {
+                return Ok(InteractionResponse::ephemeral(
+                    "Background tasks are not configured.",
+                ))
+            }
+        };
+
+        queue.enqueue(&task, 0).await?;
+
+        if let Some(cooldown) = cooldown {
+            let user_id = Self::user_id(&invocation.ctx.interaction);
+            command_registry::start_cooldown(invocation, user_id, cooldown);
+        }
+
+        Ok(InteractionResponse::deferred_ephemeral())
+    }
+
+    /// Opens the setup wizard on its first step.
+    async fn setup_start(&self, _inv: Invocation<'_>) -> Result<InteractionResponse> {
+        Ok(
+            InteractionResponse::ephemeral(SetupStep::first().content(""))
+                .with_components(SetupStep::first().components()),
+        )
+    }
+
+    /// The link for adding the bot to another server, asking for the
+    /// permissions it needs there.
+    async fn invite(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let url = OAuthClient::bot_invite_url(
+            &inv.ctx.interaction.application_id,
+            None,
+            BOT_PERMISSIONS,
+        )?;
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "Add me to another server you manage with the button below, or share this link: <{url}>"
+        ))
+        .with_components(vec![Component::action_row(vec![Component::link_button(
+            "Add to server",
+            url.as_str(),
+        )])]))
+    }
+
+    /// Completes or skips a `/setup` step and replaces the wizard's message
+    /// with the next one.
+    pub async fn handle_setup(
+        &self,
+        ctx: &RequestContext,
+        action: SetupAction,
+    ) -> Result<InteractionResponse> {
+        // The wizard's components need what `/setup` itself does.
+        if let Some(refusal) =
+            self.check_access(commands::access(setup::NAME, ""), &ctx.interaction)
+        {
+            return Ok(refusal);
+        }
+
+        let guild_id = ctx.guild_id.as_str();
+        let value = ctx
+            .interaction
+            .data
+            .as_ref()
+            .and_then(|data| data.values.first())
+            .map(String::as_str);
+
+        let (step, note) = match action {
+            SetupAction::Skip(step) => (step, "Skipped.".to_string()),
+
+            SetupAction::Apply(step @ SetupStep::LogChannel) => {
+                let Some(channel_id) = value else {
+                    return Ok(InteractionResponse::ephemeral("Pick a channel."));
+                };
+
+                self.stores
+                    .log_channels
+                    .set_log_channel(guild_id, channel_id)
+                    .await?;
+
+                (
+                    step,
+                    format!("Role activity will be logged to <#{}>.", channel_id),
+                )
+            }
+
+            SetupAction::Apply(step @ SetupStep::RoleLimit) => {
+                let limit = value
+                    .and_then(|val| val.parse::<u32>().ok())
+                    .filter(|val| (1..=config::MAX_ROLE_LIMIT).contains(val));
+
+                self.role_store.set_role_limit(guild_id, limit).await?;
+
+                (step, Self::role_limit_message(limit))
+            }
+
+            SetupAction::Apply(step @ SetupStep::RoleMenu) => {
+                if let Some(upsell) = self.premium_upsell(PremiumFeature::RoleMenus, &ctx.flags) {
+                    return Ok(upsell);
+                }
+
+                let channel_id = match Self::postable_channel(&ctx.interaction) {
+                    Ok(channel_id) => channel_id,
+                    Err(refusal) => return Ok(refusal),
+                };
+
+                let message =
+                    json!({ "content": SETUP_MENU_TEXT, "allowed_mentions": { "parse": [] } });
+                let message_id = self
+                    .discord_api
+                    .create_message(channel_id, &message)
+                    .await?;
+
+                self.stores
+                    .role_menus
+                    .create_menu(guild_id, &message_id, channel_id)
+                    .await?;
+
+                (
+                    step,
+                    format!(
+                        "Role menu posted. Add buttons with `/rolemenu bind-emoji message:{}`.",
+                        message_id
+                    ),
+                )
+            }
+
+            // The wizard only needs Manage Server, but importing makes roles
+            // self-assignable, which `/role import-all` reserves for Manage
+            // Roles. Either way the import itself skips roles above the
+            // member's own.
+            SetupAction::Apply(step @ SetupStep::ImportRoles)
+                if self
+                    .check_access(
+                        commands::access(role::NAME, role::IMPORT_ALL),
+                        &ctx.interaction,
+                    )
+                    .is_some() =>
+            {
+                (
+                    step,
+                    "Skipped importing roles: it needs the Manage Roles permission. Someone who \
+                     has it can run `/role import-all` later."
+                        .to_string(),
+                )
+            }
+
+            SetupAction::Apply(SetupStep::ImportRoles) => {
+                if !ctx.flags.is_enabled(Flag::DeferredRoleTasks) {
+                    return Ok(InteractionResponse::ephemeral(
+                        "This feature is temporarily unavailable.",
+                    ));
+                }
+                let Some(queue) = self.task_queue.as_ref() else {
+                    return Ok(InteractionResponse::ephemeral(
+                        "Background tasks are not configured.",
+                    ));
+                };
+
+                // The import reports by editing the original response, which
+                // for a component interaction is the wizard's message.
+                queue
+                    .enqueue(&Self::import_task(guild_id, &ctx.interaction), 0)
+                    .await?;
+
+                return Ok(InteractionResponse::update_message(
+                    "Importing roles… this message will update when it's done. Setup is complete.",
+                    vec![],
+                ));
+            }
+        };
+
+        Ok(match step.next() {
+            Some(next) => {
+                InteractionResponse::update_message(next.content(&note), next.components())
+            }
+            None => InteractionResponse::update_message(setup_wizard::finished(&note), vec![]),
+        })
+    }
+
+    async fn rolemenu_post(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let text = inv.str_option(rolemenu::TEXT_OPTION).unwrap_or("").trim();
+        if text.is_empty() || text.chars().count() > MAX_MESSAGE_CHARS {
+            return Ok(InteractionResponse::ephemeral(format!(
+                "The menu's message must be 1 to {} characters.",
+                MAX_MESSAGE_CHARS
+            )));
+        }
+
+        let channel_id = match Self::postable_channel(&inv.ctx.interaction) {
+            Ok(channel_id) => channel_id,
+            Err(refusal) => return Ok(refusal),
+        };
+
+        let message = json!({ "content": text, "allowed_mentions": { "parse": [] } });
+        let message_id = self
+            .discord_api
+            .create_message(channel_id, &message)
+            .await?;
+
+        self.stores
+            .role_menus
+            .create_menu(inv.guild_id, &message_id, channel_id)
+            .await?;
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "Role menu posted. Add buttons with `/rolemenu bind-emoji message:{}`.",
+            message_id
+        )))
+    }
+
+    /// Adds or removes one of a role menu's buttons.
+    async fn rolemenu_edit(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let guild_id = inv.guild_id;
+        let option = |name: &str| inv.str_option(name).unwrap_or("");
+
+        let message_id = Self::message_id(option(rolemenu::MESSAGE_OPTION));
+        let Some(mut menu) = self
+            .stores
+            .role_menus
+            .get_menu(guild_id, message_id)
+            .await?
+        else {
+            return Ok(InteractionResponse::ephemeral(
+                "That message isn't a role menu. Post one with `/rolemenu post`.",
+            ));
+        };
+
+        let emoji = option(rolemenu::EMOJI_OPTION).trim();
+        if PartialEmoji::parse(emoji).is_none() {
+            return Ok(InteractionResponse::ephemeral(
+                "That isn't an emoji. Use a unicode emoji or one from this server.",
+            ));
+        }
+
+        let reply = match inv.subcommand_name() {
+            rolemenu::BIND_EMOJI => {
+                let role_id = option(rolemenu::ROLE_OPTION);
+                let Some(role) = inv
+                    .data
+                    .resolved
+                    .as_ref()
+                    .and_then(|r| r.roles.get(role_id))
+                else {
+                    return Ok(InteractionResponse::ephemeral("Resolved role missing."));
+                };
+
+                if role_id == guild_id {
+                    return Ok(InteractionResponse::ephemeral(
+                        "Everyone already has @everyone.",
+                    ));
+                }
+
+                // Anyone can click the button, so it's held to what
+                // `/role save` allows.
+                if self
+                    .role_store
+                    .get_role_by_id(guild_id, role_id)
+                    .await?
+                    .is_none()
+                {
+                    return Ok(InteractionResponse::ephemeral(format!(
+                        "Only self-assignable roles can go on a role menu. Register it with \
+                         `/role save role:{}` first.",
+                        role_mention(role_id)
+                    )));
+                }
+
+                if let Some(bound) = menu
+                    .binding_for_role(role_id)
+                    .filter(|bound| bound.emoji != emoji)
+                {
+                    return Ok(InteractionResponse::ephemeral(format!(
+                        "'{}' is already on this menu as {}.",
+                        markdown::escape(&role.name),
+                        bound.emoji
+                    )));
+                }
+
+                menu.bindings.retain(|binding| binding.emoji != emoji);
+                if menu.bindings.len() >= MAX_MENU_BUTTONS {
+                    return Ok(InteractionResponse::ephemeral(format!(
+                        "A role menu can have at most {} buttons.",
+                        MAX_MENU_BUTTONS
+                    )));
+                }
+
+                let binding = EmojiBinding {
+                    emoji: emoji.to_string(),
+                    role_id: role_id.to_string(),
+                    role_name: role.name.clone(),
+                    bound_at: Utc::now().timestamp(),
+                };
+                menu.bindings.push(binding.clone());
+
+                // The message is edited first, so an emoji Discord rejects
+                // is never stored.
+                self.render_menu(&menu).await?;
+                self.stores
+                    .role_menus
+                    .bind_emoji(guild_id, message_id, &binding)
+                    .await?;
+
+                format!("{} now toggles '{}'.", emoji, markdown::escape(&role.name))
+            }
+
+            rolemenu::UNBIND_EMOJI => {
+                if menu.binding_for_emoji(emoji).is_none() {
+                    return Ok(InteractionResponse::ephemeral(
+                        "That emoji isn't on this menu.",
+                    ));
+                }
+
+                menu.bindings.retain(|binding| binding.emoji != emoji);
+
+                self.render_menu(&menu).await?;
+                self.stores
+                    .role_menus
+                    .unbind_emoji(guild_id, message_id, emoji)
+                    .await?;
+
+                format!("Removed {} from the menu.", emoji)
+            }
+
+            _ => "Unknown subcommand.".to_string(),
+        };
+
+        Ok(InteractionResponse::ephemeral(reply))
+    }
+
+    async fn render_menu(&self, menu: &RoleMenu) -> Result<()> {
+        self.discord_api
+            .edit_message(
+                &menu.channel_id,
+                &menu.message_id,
+                &json!({ "components": menu.components() }),
+            )
+            .await
+    }
+
+    fn role_limit_message(limit: Option<u32>) -> String {
+        match limit {
+            Some(limit) => format!(
+                "Members can now hold at most {} self-assignable roles.",
+                limit
+            ),
+            None => "Members can hold any number of self-assignable roles.".to_string(),
+        }
+    }
+
+    /// Accepts a message ID or a message link, whose last segment is the ID.
+    fn message_id(input: &str) -> &str {
+        input.trim().rsplit('/').next().unwrap_or_default()
+    }
+
+    async fn config_autocomplete_min_length(
+        &self,
+        inv: Invocation<'_>,
+    ) -> Result<InteractionResponse> {
+        let length = match inv
+            .option(config::LENGTH_OPTION)
+            .and_then(|val| val.as_u64())
+            .and_then(|val| u32::try_from(val).ok())
+            .filter(|val| (config::MIN_LENGTH..=config::MAX_LENGTH).contains(val))
+        {
+            Some(l) => l,
+            None => {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "Length must be between {} and {}.",
+                    config::MIN_LENGTH,
+                    config::MAX_LENGTH
+                )))
+            }
+        };
+
+        self.role_store
+            .set_autocomplete_min_length(inv.guild_id, length)
+            .await?;
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "Role suggestions now appear after {} typed character(s).",
+            length
+        )))
+    }
+
+    async fn config_role_limit(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let limit = inv
+            .option(config::LIMIT_OPTION)
+            .and_then(|val| val.as_u64())
+            .and_then(|val| u32::try_from(val).ok())
+            .filter(|val| (1..=config::MAX_ROLE_LIMIT).contains(val));
+
+        self.role_store.set_role_limit(inv.guild_id, limit).await?;
+
+        Ok(InteractionResponse::ephemeral(Self::role_limit_message(
+            limit,
+        )))
+    }
+
+    async fn config_min_age(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let requirement = inv
+            .option(config::HOURS_OPTION)
+            .and_then(|val| val.as_u64())
+            .and_then(|val| u32::try_from(val).ok())
+            .filter(|val| (1..=config::MAX_MIN_AGE_HOURS).contains(val))
+            .map(|hours| AgeRequirement {
+                hours,
+                account: inv
+                    .option(config::ACCOUNT_OPTION)
+                    .and_then(|val| val.as_bool())
+                    .unwrap_or(false),
+            });
+
+        self.role_store
+            .set_age_requirement(inv.guild_id, requirement)
+            .await?;
+
+        Ok(InteractionResponse::ephemeral(match requirement {
+            Some(requirement) => format!(
+                "Only {} can now take self-assignable roles.",
+                requirement.describe()
+            ),
+            None => "Members can take self-assignable roles as soon as they join.".to_string(),
+        }))
+    }
+
+    async fn config_webhook(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let url = match inv.str_option(config::URL_OPTION).and_then(parse_endpoint) {
+            Some(u) => u,
+            None => {
+                return Ok(InteractionResponse::ephemeral(
+                    "The webhook URL must be a full https:// URL.",
+                ))
+            }
+        };
+
+        let webhook = GuildWebhook {
+            url: url.to_string(),
+            secret: webhook_sender::new_secret()?,
+        };
+
+        self.stores
+            .webhooks
+            .set_webhook(inv.guild_id, &webhook)
+            .await?;
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "Events will be sent to {}. Each request is signed with this secret, which \
+             won't be shown again:\n`{}`\nVerify the `{}` header against an \
+             HMAC-SHA256 of `<{}>.<body>`.",
+            webhook.url,
+            webhook.secret,
+            webhook_sender::SIGNATURE_HEADER,
+            webhook_sender::TIMESTAMP_HEADER,
+        )))
+    }
+
+    async fn config_log_channel(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let guild_id = inv.guild_id;
+        let channel_id = inv.str_option(config::CHANNEL_OPTION);
+
+        match channel_id {
+            Some(channel_id) => {
+                self.stores
+                    .log_channels
+                    .set_log_channel(guild_id, channel_id)
+                    .await?;
+
+                Ok(InteractionResponse::ephemeral(format!(
+                    "Role activity will be logged to <#{}>. The bot needs Send Messages \
+                     and Embed Links there.",
+                    channel_id
+                )))
+            }
+            None => {
+                self.stores.log_channels.clear_log_channel(guild_id).await?;
+
+                Ok(InteractionResponse::ephemeral(
+                    "Role activity will no longer be logged.",
+                ))
+            }
+        }
+    }
+
+    async fn config_webhook_remove(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        self.stores.webhooks.clear_webhook(inv.guild_id).await?;
+
+        Ok(InteractionResponse::ephemeral(
+            "Events will no longer be sent to the webhook.",
+        ))
+    }
+
+    async fn config_prerequisite(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let guild_id = inv.guild_id;
+        let option = |name: &str| inv.str_option(name).unwrap_or("");
+
+        let role_id = option(config::ROLE_OPTION);
+        let Some((role_name, _)) = self.role_store.get_role_by_id(guild_id, role_id).await? else {
+            return Ok(InteractionResponse::ephemeral(
+                "That role isn't self-assignable. Register it with `/role save` first.",
+            ));
+        };
+
+        if inv.subcommand_name() == config::PREREQUISITE_CLEAR {
+            self.role_store
+                .set_prerequisites(guild_id, role_id, &[])
+                .await?;
+
+            return Ok(InteractionResponse::ephemeral(format!(
+                "Anyone can now take '{}'.",
+                markdown::escape(&role_name)
+            )));
+        }
+
+        let required_id = option(config::REQUIRED_OPTION);
+        if required_id == role_id || required_id == guild_id {
+            return Ok(InteractionResponse::ephemeral(
+                "A role can't require itself or @everyone.",
+            ));
+        }
+
+        let mut required = self.role_store.get_prerequisites(guild_id, role_id).await?;
+        if !required.iter().any(|id| id == required_id) {
+            if required.len() >= config::MAX_PREREQUISITES {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "A role can have at most {} prerequisites.",
+                    config::MAX_PREREQUISITES
+                )));
+            }
+
+            required.push(required_id.to_string());
+            self.role_store
+                .set_prerequisites(guild_id, role_id, &required)
+                .await?;
+        }
+
+        let mentions: Vec<String> = required.iter().map(|id| role_mention(id)).collect();
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "Members now need {} to take '{}'.",
+            mentions.join(", "),
+            markdown::escape(&role_name)
+        )))
+    }
+
+    /// Adds a channel a role may be toggled from, or clears the list.
+    async fn config_channels(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let guild_id = inv.guild_id;
+        let option = |name: &str| inv.str_option(name).unwrap_or("");
+
+        let role_id = option(config::ROLE_OPTION);
+        let Some((role_name, _)) = self.role_store.get_role_by_id(guild_id, role_id).await? else {
+            return Ok(InteractionResponse::ephemeral(
+                "That role isn't self-assignable. Register it with `/role save` first.",
+            ));
+        };
+
+        if inv.subcommand_name() == config::CHANNELS_CLEAR {
+            self.role_store
+                .set_allowed_channels(guild_id, role_id, &[])
+                .await?;
+
+            return Ok(InteractionResponse::ephemeral(format!(
+                "'{}' can now be toggled from any channel.",
+                markdown::escape(&role_name)
+            )));
+        }
+
+        let channel_id = option(config::CHANNEL_OPTION);
+        let mut channels = self
+            .role_store
+            .get_allowed_channels(guild_id, role_id)
+            .await?;
+        if !channels.iter().any(|id| id == channel_id) {
+            if channels.len() >= config::MAX_ROLE_CHANNELS {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "A role can be limited to at most {} channels.",
+                    config::MAX_ROLE_CHANNELS
+                )));
+            }
+
+            channels.push(channel_id.to_string());
+            self.role_store
+                .set_allowed_channels(guild_id, role_id, &channels)
+                .await?;
+        }
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "'{}' can now only be toggled from {}.",
+            markdown::escape(&role_name),
+            Self::channel_mentions(&channels)
+        )))
+    }
+
+    fn channel_mentions(channel_ids: &[String]) -> String {
+        channel_ids
+            .iter()
+            .map(|id| format!("<#{}>", id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    async fn sysadmin_stats(&self, _inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let days = self.operators.stats_store.recent(STATS_DAYS).await?;
+
+        Ok(InteractionResponse::ephemeral(Self::format_stats(&days)))
+    }
+
+    async fn sysadmin_flag(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let name = inv.str_option(sysadmin::FLAG_OPTION).unwrap_or("").trim();
+        let Some(flag) = Flag::from_key(name) else {
+            let names: Vec<&str> = Flag::ALL.iter().map(|flag| flag.key()).collect();
+            return Ok(InteractionResponse::ephemeral(format!(
+                "Unknown flag. Flags: {}.",
+                names.join(", ")
+            )));
+        };
+        let Some(enabled) = inv
+            .option(sysadmin::ENABLED_OPTION)
+            .and_then(|val| val.as_bool())
+        else {
+            return Ok(InteractionResponse::ephemeral("Enabled is required."));
+        };
+
+        self.operators
+            .flag_store
+            .set_flag(flag.key(), enabled)
+            .await?;
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "`{}` is now {}. Warm functions pick it up within a minute.",
+            flag.key(),
+            if enabled { "on" } else { "off" }
+        )))
+    }
+
+    async fn sysadmin_guild(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let target_guild_id = Self::target_guild_id(inv);
+        if target_guild_id.is_empty() {
+            return Ok(InteractionResponse::ephemeral("Guild ID is required."));
+        }
+
+        Ok(InteractionResponse::ephemeral(
+            self.describe_guild(target_guild_id).await?,
+        ))
+    }
+
+    #[cfg(feature = "billing")]
+    async fn sysadmin_grant_premium(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let target_guild_id = Self::target_guild_id(inv);
+        if target_guild_id.is_empty() {
+            return Ok(InteractionResponse::ephemeral("Guild ID is required."));
+        }
+
+        let days = match inv
+            .option(sysadmin::DURATION_OPTION)
+            .and_then(|val| val.as_i64())
+        {
+            Some(d) if (1..=sysadmin::MAX_GRANT_DAYS).contains(&d) => d,
+            Some(_) => {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "Duration must be between 1 and {} days.",
+                    sysadmin::MAX_GRANT_DAYS
+                )))
+            }
+            None => return Ok(InteractionResponse::ephemeral("Duration is required.")),
+        };
+
+        let expires_at = match self
+            .billing
+            .subscription_manager
+            .grant_premium(target_guild_id, days, Self::user_id(&inv.ctx.interaction))
+            .await
+        {
+            Ok(e) => e,
+            Err(err) => {
+                return Ok(InteractionResponse::ephemeral(format!(
+                    "Failed to grant premium: {}",
+                    err
+                )))
+            }
+        };
+
+        self.events
+            .publish(
+                target_guild_id,
+                GuildEvent::SubscriptionChanged {
+                    active: true,
+                    expires_at: Some(expires_at),
+                },
+            )
+            .await;
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "Premium granted to guild {} until {}.",
+            target_guild_id,
+            timestamp::expiry(expires_at)
+        )))
+    }
+
+    /// The `guild_id` option of the `/sysadmin` subcommands that target a
+    /// guild.
+    fn target_guild_id<'a>(inv: Invocation<'a>) -> &'a str {
+        inv.str_option(sysadmin::GUILD_ID_OPTION)
+            .unwrap_or("")
+            .trim()
+    }
+
+    fn format_stats(days: &[DailyStats]) -> String {
+        if days.is_empty() {
+            return "No stats yet. They're aggregated daily, shortly after midnight UTC."
+                .to_string();
+        }
+
+        let mut lines = vec!["**Daily stats (UTC)**".to_string()];
+        for day in days {
+            #[cfg_attr(not(feature = "billing"), allow(unused_mut))]
+            let mut line = format!(
+                "`{}` {} guilds, {} active, {} roles, {} toggles",
+                day.date, day.guilds, day.active_guilds, day.roles, day.toggles
+            );
+
+            #[cfg(feature = "billing")]
+            line.push_str(&format!(
+                ", {} premium ({:.1}%)",
+                day.premium_guilds,
+                day.premium_conversion() * 100.0
+            ));
+
+            lines.push(line);
+        }
+
+        lines.join("\n")
+    }
+
+    /// What's stored for a guild, for `/sysadmin guild`.
+    async fn describe_guild(&self, guild_id: &str) -> Result<String> {
+        let (roles, min_length, role_limit, age_requirement, log_channel, has_webhook) = tokio::try_join!(
+            self.role_store.list_roles(guild_id),
+            self.role_store.get_autocomplete_min_length(guild_id),
+            self.role_store.get_role_limit(guild_id),
+            self.role_store.get_age_requirement(guild_id),
+            self.stores.log_channels.get_log_channel(guild_id),
+            self.stores.webhooks.is_configured(guild_id),
+        )?;
+
+        #[cfg_attr(not(feature = "billing"), allow(unused_mut))]
+        let mut lines = Vec::from([
+            format!("**Guild {}**", guild_id),
+            format!("Registered roles: {}", roles.len()),
+            format!(
+                "Autocomplete min length: {}",
+                min_length.unwrap_or(config::MIN_LENGTH)
+            ),
+            format!(
+                "Role limit: {}",
+                role_limit.map_or("none".to_string(), |limit| limit.to_string())
+            ),
+            format!(
+                "Minimum age: {}",
+                age_requirement.map_or("none".to_string(), |requirement| requirement.describe())
+            ),
+            format!(
+                "Log channel: {}",
+                log_channel.map_or("none".to_string(), |id| format!("<#{}>", id))
+            ),
+            format!(
+                "Webhook: {}",
+                if has_webhook { "configured" } else { "none" }
+            ),
+        ]);
+
+        #[cfg(feature = "billing")]
+        {
+            let ((active, expires_at), toggles) = tokio::try_join!(
+                self.billing.subscription_manager.status(guild_id),
+                self.billing.usage_meter.current_usage(guild_id),
+            )?;
+
+            lines.push(format!(
+                "Premium: {}",
+                match (active, expires_at) {
+                    (true, Some(expires_at)) => {
+                        format!("active until {}", timestamp::expiry(expires_at))
+                    }
+                    (true, None) => "active".to_string(),
+                    (false, _) => "inactive".to_string(),
+                }
+            ));
+            lines.push(format!("Toggles this month: {}", toggles));
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    #[cfg(feature = "billing")]
+    async fn subscription_manage(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let guild_id = inv.guild_id;
+        let customer_id = match self
+            .billing
+            .subscription_manager
+            .customer_id(guild_id)
+            .await?
+        {
+            Some(id) => id,
+            None => {
+                return Ok(InteractionResponse::ephemeral(
+                    "This server has no billing account to manage.",
+                ))
+            }
+        };
+
+        let return_url = format!("https://discord.com/channels/{}", guild_id);
+
+        let url = match self
+            .billing
+            .stripe_client
+            .create_portal_session(&customer_id, &return_url)
+            .await
+        {
+            Ok(u) => u,
+            Err(_) => {
+                return Ok(InteractionResponse::ephemeral(
+                    "Failed to open the billing portal. Please try again later.",
+                ))
+            }
+        };
+
+        Ok(InteractionResponse::ephemeral(format!(
+            "Manage your subscription here (link expires shortly): {}",
+            url
+        )))
+    }
+
+    #[cfg(feature = "billing")]
+    async fn subscription_attach(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let (guild_id, user_id) = (inv.guild_id, Self::user_id(&inv.ctx.interaction));
+        let outcome = self
+            .billing
+            .subscription_manager
+            .attach_bundle(user_id, guild_id)
+            .await?;
+
+        if matches!(outcome, AttachOutcome::Attached { .. }) {
+            self.publish_subscription_change(guild_id).await;
+        }
+
+        let message = match outcome {
+            AttachOutcome::Attached { used, max } => format!(
+                "This server is now covered by your bundle ({}/{} servers used).",
+                used, max
+            ),
+            AttachOutcome::NoActiveBundle => {
+                "You don't own an active bundle subscription.".to_string()
+            }
+            AttachOutcome::BundleFull { max } => format!(
+                "Your bundle already covers {} servers. Detach one first.",
+                max
+            ),
+            AttachOutcome::AlreadyAttached => {
+                "This server is already covered by your bundle.".to_string()
+            }
+            AttachOutcome::AttachedElsewhere => {
+                "This server is already covered by another member's bundle.".to_string()
+            }
+        };
+
+        Ok(InteractionResponse::ephemeral(message))
+    }
+
+    #[cfg(feature = "billing")]
+    async fn subscription_detach(&self, inv: Invocation<'_>) -> Result<InteractionResponse> {
+        let (guild_id, user_id) = (inv.guild_id, Self::user_id(&inv.ctx.interaction));
+        let outcome = self
+            .billing
+            .subscription_manager
+            .detach_bundle(user_id, guild_id)
+            .await?;
+
+        if outcome == DetachOutcome::Detached {
+            self.publish_subscription_change(guild_id).await;
+        }
+
+        let message = match outcome {
+            DetachOutcome::Detached => "This server was removed from your bundle.",
+            DetachOutcome::NotAttached => "This server is not covered by your bundle.",
+        };
+
+        Ok(InteractionResponse::ephemeral(message))
+    }
+
+    #[cfg(feature = "billing")]
+    async fn publish_subscription_change(&self, guild_id: &str) {
+        match self
+            .billing
+            .subscription_manager
+            .changed_event(guild_id)
+            .await
+        {
+            Ok(event) => self.events.publish(guild_id, event).await,
+            Err(err) => error!(
+                guild_id,
+                error = format!("{:#}", err),
+                "Failed to read subscription for guild event"
+            ),
         }
     }
 }
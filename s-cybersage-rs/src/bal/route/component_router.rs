@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::dal::{
+    dao::{role_menu::RoleMenuDao, role_store::RoleStore},
+    model::{
+        interaction_response::InteractionResponse, role_announcement::JOIN_BUTTON_ID_PREFIX,
+        role_menu::BUTTON_ID_PREFIX,
+    },
+};
+
+use super::{
+    request_context::RequestContext,
+    setup_wizard::{SetupAction, SETUP_ID_PREFIX},
+};
+
+/// Prefix of the `custom_id` of the Remove buttons in `/role mine`; the rest
+/// is the role ID.
+pub const REMOVE_BUTTON_ID_PREFIX: &str = "roleremove:";
+
+/// What a clicked component asks for, once checked against stored state.
+pub enum ComponentAction {
+    ToggleRole {
+        role_id: String,
+        role_name: String,
+    },
+    /// Like `ToggleRole`, but never removes the role.
+    JoinRole {
+        role_id: String,
+        role_name: String,
+    },
+    /// Like `ToggleRole`, but never adds the role.
+    RemoveRole {
+        role_id: String,
+        role_name: String,
+    },
+    Setup(SetupAction),
+    Reply(InteractionResponse),
+}
+
+/// Routes message component interactions by their `custom_id`. Actions that
+/// other routers already implement, such as toggling a role, are returned
+/// rather than performed, so both paths share one implementation.
+pub struct ComponentRouter {
+    role_menus: RoleMenuDao,
+    role_store: Arc<dyn RoleStore>,
+}
+
+impl ComponentRouter {
+    pub fn new(role_menus: RoleMenuDao, role_store: Arc<dyn RoleStore>) -> Self {
+        Self {
+            role_menus,
+            role_store,
+        }
+    }
+
+    pub async fn resolve(&self, ctx: &RequestContext) -> Result<ComponentAction> {
+        let custom_id = ctx
+            .interaction
+            .data
+            .as_ref()
+            .and_then(|data| data.custom_id.as_deref())
+            .unwrap_or("");
+
+        if let Some(role_id) = custom_id.strip_prefix(BUTTON_ID_PREFIX) {
+            return self.role_menu_button(ctx, role_id).await;
+        }
+
+        if let Some(role_id) = custom_id.strip_prefix(JOIN_BUTTON_ID_PREFIX) {
+            return Ok(match self.registered_role(ctx, role_id).await? {
+                Some((role_name, role_id)) => ComponentAction::JoinRole { role_id, role_name },
+                None => Self::no_longer_registered(),
+            });
+        }
+
+        if let Some(role_id) = custom_id.strip_prefix(REMOVE_BUTTON_ID_PREFIX) {
+            return Ok(match self.registered_role(ctx, role_id).await? {
+                Some((role_name, role_id)) => ComponentAction::RemoveRole { role_id, role_name },
+                None => Self::no_longer_registered(),
+            });
+        }
+
+        if let Some(action) = custom_id
+            .strip_prefix(SETUP_ID_PREFIX)
+            .and_then(SetupAction::parse)
+        {
+            return Ok(ComponentAction::Setup(action));
+        }
+
+        Ok(ComponentAction::Reply(InteractionResponse::ephemeral(
+            "Unknown component.",
+        )))
+    }
+
+    /// Only toggles roles still bound on the clicked message and still
+    /// self-assignable, so buttons left on a menu after an unbind, or after
+    /// the role's mapping was removed, do nothing.
+    async fn role_menu_button(
+        &self,
+        ctx: &RequestContext,
+        role_id: &str,
+    ) -> Result<ComponentAction> {
+        let Some(message) = ctx.interaction.message.as_ref() else {
+            return Ok(ComponentAction::Reply(InteractionResponse::ephemeral(
+                "Unknown component.",
+            )));
+        };
+
+        let bound = self
+            .role_menus
+            .get_menu(&ctx.guild_id, &message.id)
+            .await?
+            .is_some_and(|menu| menu.binding_for_role(role_id).is_some());
+
+        if !bound {
+            return Ok(ComponentAction::Reply(InteractionResponse::ephemeral(
+                "That button is no longer bound to a role.",
+            )));
+        }
+
+        Ok(match self.registered_role(ctx, role_id).await? {
+            Some((role_name, role_id)) => ComponentAction::ToggleRole { role_id, role_name },
+            None => Self::no_longer_registered(),
+        })
+    }
+
+    /// Buttons stay on their message, so the role is checked against the guild's current mappings on every click.
+    async fn registered_role(
+        &self,
+        ctx: &RequestContext,
+        role_id: &str,
+    ) -> Result<Option<(String, String)>> {
+        self.role_store.get_role_by_id(&ctx.guild_id, role_id).await
+    }
+
+    fn no_longer_registered() -> ComponentAction {
+        ComponentAction::Reply(InteractionResponse::ephemeral(
+            "That role is no longer self-assignable.",
+        ))
+    }
+}
@@ -1,32 +1,59 @@
 use anyhow::Result;
 
 use crate::dal::model::{
-    interaction_request::{InteractionRequest, InteractionType},
-    interaction_response::InteractionResponse,
+    interaction_request::InteractionType, interaction_response::InteractionResponse,
 };
 
-use super::command_router::CommandRouter;
+use super::{
+    command_router::CommandRouter,
+    component_router::{ComponentAction, ComponentRouter},
+    request_context::RequestContext,
+};
 
 pub struct InteractionRouter {
     command_router: CommandRouter,
+    component_router: ComponentRouter,
 }
 
 impl InteractionRouter {
-    pub fn new(command_router: CommandRouter) -> Self {
-        Self { command_router }
+    pub fn new(command_router: CommandRouter, component_router: ComponentRouter) -> Self {
+        Self {
+            command_router,
+            component_router,
+        }
     }
 
-    pub async fn route(&self, interaction: &InteractionRequest) -> Result<InteractionResponse> {
-        match interaction.interaction_type {
+    pub async fn route(&self, ctx: &RequestContext) -> Result<InteractionResponse> {
+        match ctx.interaction.interaction_type {
             InteractionType::Ping => Ok(InteractionResponse::pong()),
 
             InteractionType::ApplicationCommandAutocomplete => {
-                self.command_router.handle_autocomplete(interaction).await
+                self.command_router.handle_autocomplete(ctx).await
             }
 
-            InteractionType::ApplicationCommand => {
-                self.command_router.handle_command(interaction).await
-            }
+            InteractionType::ApplicationCommand => self.command_router.handle_command(ctx).await,
+
+            InteractionType::MessageComponent => match self.component_router.resolve(ctx).await? {
+                ComponentAction::ToggleRole { role_id, role_name } => {
+                    self.command_router
+                        .toggle_role(ctx, &role_id, &role_name)
+                        .await
+                }
+                ComponentAction::JoinRole { role_id, role_name } => {
+                    self.command_router
+                        .join_role(ctx, &role_id, &role_name)
+                        .await
+                }
+                ComponentAction::RemoveRole { role_id, role_name } => {
+                    self.command_router
+                        .remove_role(ctx, &role_id, &role_name)
+                        .await
+                }
+                ComponentAction::Setup(action) => {
+                    self.command_router.handle_setup(ctx, action).await
+                }
+                ComponentAction::Reply(response) => Ok(response),
+            },
 
             InteractionType::Unknown => Ok(InteractionResponse::ephemeral(
                 "Unsupported interaction type.",
@@ -1,2 +1,6 @@
+pub mod command_registry;
+pub mod command_router;
+pub mod component_router;
 pub mod interaction_router;
-pub mod command_router;
\ No newline at end of file
+pub mod request_context;
+pub mod setup_wizard;
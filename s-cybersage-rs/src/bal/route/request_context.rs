@@ -0,0 +1,34 @@
+#[cfg(feature = "billing")]
+use crate::bal::config::settings::Settings;
+use crate::{
+    bal::config::feature_flags::FeatureFlags, dal::model::interaction_request::InteractionRequest,
+};
+
+const DEFAULT_LOCALE: &str = "en-US";
+
+/// Everything about an interaction that's resolved once per invocation: the
+/// parsed request, its guild, and that guild's configuration. The HTTP
+/// handler builds it and the routers read from it, so nothing below the
+/// handler repeats a settings, flag or subscription lookup.
+pub struct RequestContext {
+    pub interaction: InteractionRequest,
+    pub guild_id: String,
+    /// The invoking user's locale, falling back to the guild's.
+    pub locale: String,
+    pub flags: FeatureFlags,
+    #[cfg(feature = "billing")]
+    pub settings: Settings,
+    #[cfg(feature = "billing")]
+    pub is_premium: bool,
+}
+
+impl RequestContext {
+    pub fn resolve_locale(interaction: &InteractionRequest) -> String {
+        interaction
+            .locale
+            .as_deref()
+            .or(interaction.guild_locale.as_deref())
+            .unwrap_or(DEFAULT_LOCALE)
+            .to_string()
+    }
+}
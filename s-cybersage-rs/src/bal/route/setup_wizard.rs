@@ -0,0 +1,176 @@
+use crate::dal::model::interaction_response::{ButtonStyle, Component, SelectOption};
+
+/// Prefix of the `custom_id` of every `/setup` component.
+pub const SETUP_ID_PREFIX: &str = "setup:";
+
+/// Discord channel types a log channel can be: text and announcement.
+const LOG_CHANNEL_TYPES: [u8; 2] = [0, 5];
+
+/// Offered by the role limit step; 0 means no limit.
+const ROLE_LIMIT_CHOICES: [u32; 6] = [0, 1, 2, 3, 5, 10];
+
+/// The steps of `/setup`, in order. Importing runs in the background and
+/// reports by editing the wizard's message, so it comes last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupStep {
+    LogChannel,
+    RoleLimit,
+    RoleMenu,
+    ImportRoles,
+}
+
+const STEPS: [SetupStep; 4] = [
+    SetupStep::LogChannel,
+    SetupStep::RoleLimit,
+    SetupStep::RoleMenu,
+    SetupStep::ImportRoles,
+];
+
+/// What a clicked `/setup` component asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupAction {
+    /// Complete the step with what was picked or clicked.
+    Apply(SetupStep),
+    Skip(SetupStep),
+}
+
+impl SetupAction {
+    /// Parses a `custom_id` without its `SETUP_ID_PREFIX`.
+    pub fn parse(id: &str) -> Option<Self> {
+        match id.strip_prefix("skip:") {
+            Some(step) => SetupStep::from_id(step).map(SetupAction::Skip),
+            None => SetupStep::from_id(id).map(SetupAction::Apply),
+        }
+    }
+}
+
+impl SetupStep {
+    pub fn first() -> Self {
+        STEPS[0]
+    }
+
+    pub fn next(self) -> Option<Self> {
+        STEPS.get(self.index() + 1).copied()
+    }
+
+    fn index(self) -> usize {
+        STEPS
+            .iter()
+            .position(|step| *step == self)
+            .unwrap_or_default()
+    }
+
+    fn id(self) -> &'static str {
+        match self {
+            SetupStep::LogChannel => "log-channel",
+            SetupStep::RoleLimit => "role-limit",
+            SetupStep::RoleMenu => "role-menu",
+            SetupStep::ImportRoles => "import",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        STEPS.into_iter().find(|step| step.id() == id)
+    }
+
+    /// The step's message, after `note` on what the previous step did.
+    pub fn content(self, note: &str) -> String {
+        let (title, description) = match self {
+            SetupStep::LogChannel => (
+                "Log channel",
+                "Pick a channel to log role changes to. I need Send Messages and Embed Links there.",
+            ),
+            SetupStep::RoleLimit => (
+                "Role limit",
+                "Pick how many self-assignable roles a member can hold at once.",
+            ),
+            SetupStep::RoleMenu => (
+                "Role menu",
+                "Post a role menu in this channel. Members click its buttons to toggle roles; \
+                 add buttons afterwards with `/rolemenu bind-emoji`.",
+            ),
+            SetupStep::ImportRoles => (
+                "Import roles",
+                "Make every existing server role self-assignable, except managed roles and \
+                 @everyone. Register roles one at a time with `/role save` instead by skipping.",
+            ),
+        };
+
+        let mut content = String::new();
+        if !note.is_empty() {
+            content.push_str(note);
+            content.push_str("\n\n");
+        }
+        content.push_str(&format!(
+            "**Step {} of {}: {}**\n{}",
+            self.index() + 1,
+            STEPS.len(),
+            title,
+            description
+        ));
+        content
+    }
+
+    pub fn components(self) -> Vec<Component> {
+        let custom_id = format!("{}{}", SETUP_ID_PREFIX, self.id());
+
+        let input = match self {
+            SetupStep::LogChannel => Some(Component::channel_select(
+                custom_id,
+                "Log channel",
+                LOG_CHANNEL_TYPES.to_vec(),
+            )),
+            SetupStep::RoleLimit => Some(Component::string_select(
+                custom_id,
+                "Roles per member",
+                ROLE_LIMIT_CHOICES
+                    .into_iter()
+                    .map(|limit| SelectOption {
+                        label: match limit {
+                            0 => "No limit".to_string(),
+                            1 => "1 role".to_string(),
+                            n => format!("{} roles", n),
+                        },
+                        value: limit.to_string(),
+                    })
+                    .collect(),
+            )),
+            SetupStep::RoleMenu | SetupStep::ImportRoles => None,
+        };
+
+        let mut buttons = Vec::new();
+        match self {
+            SetupStep::RoleMenu => buttons.push(Component::button(
+                ButtonStyle::Primary,
+                "Post menu",
+                format!("{}{}", SETUP_ID_PREFIX, self.id()),
+            )),
+            SetupStep::ImportRoles => buttons.push(Component::button(
+                ButtonStyle::Primary,
+                "Import roles",
+                format!("{}{}", SETUP_ID_PREFIX, self.id()),
+            )),
+            SetupStep::LogChannel | SetupStep::RoleLimit => {}
+        }
+        buttons.push(Component::button(
+            ButtonStyle::Secondary,
+            "Skip",
+            format!("{}skip:{}", SETUP_ID_PREFIX, self.id()),
+        ));
+
+        input
+            .map(|input| Component::action_row(vec![input]))
+            .into_iter()
+            .chain([Component::action_row(buttons)])
+            .collect()
+    }
+}
+
+/// Shown after the last step when it was skipped.
+pub fn finished(note: &str) -> String {
+    format!(
+        "{}\n\nSetup is complete. Change any of this later with `/config`, `/rolemenu` and \
+         `/role import-all`.",
+        note
+    )
+}
@@ -0,0 +1,292 @@
+//! Checks a deployment's configuration before go-live and prints a readiness
+//! report.
+//!
+//! ```sh
+//! doctor
+//! ```
+//!
+//! Reads the functions' environment variables (`ROLE_MAPPINGS_TABLE_NAME`,
+//! the `*_SECRET_ARN`s, `DISCORD_APPLICATION_ID`, ...) from the environment
+//! or from `.env` (`.env.<STAGE>` when `STAGE` is set), and checks with the
+//! caller's AWS credentials that:
+//!
+//! - each table is active, and it and its indexes can be read;
+//! - each configured secret can be read and has the field the bot reads;
+//! - the bot token is accepted by Discord (`GET /users/@me`);
+//! - the registered slash commands cover everything the router handles.
+//!
+//! Checks whose variables aren't set are skipped. Exits non-zero if any
+//! check failed.
+
+use std::fmt::Display;
+
+use anyhow::{bail, Context, Result};
+use s_cybersage_rs::{
+    app_state::AppState,
+    bal::{
+        auth::verify::AuthManager,
+        discord::{discord_api::DiscordApi, role_manager::RoleManager},
+    },
+    commands,
+};
+use serde::Deserialize;
+
+#[derive(Default)]
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn record(&mut self, check: &str, result: Result<String>) {
+        match result {
+            Ok(detail) => println!("ok    {}: {}", check, detail),
+            Err(err) => {
+                println!("FAIL  {}: {:#}", check, err);
+                self.failures += 1;
+            }
+        }
+    }
+
+    fn skip(&self, check: &str, reason: impl Display) {
+        println!("skip  {}: {}", check, reason);
+    }
+}
+
+#[derive(Deserialize)]
+struct CurrentUser {
+    id: String,
+    username: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let env_file = match std::env::var("STAGE") {
+        Ok(stage) if !stage.is_empty() => format!(".env.{}", stage),
+        _ => ".env".to_string(),
+    };
+    dotenvy::from_filename(&env_file).ok();
+
+    let state = AppState::from_env().await?;
+    let config = state.config.clone();
+    let mut report = Report::default();
+
+    for (check, table) in [
+        ("role table", &config.role_table),
+        ("subscriptions table", &config.subscription_table),
+    ] {
+        match table {
+            Some(table) => report.record(check, check_table(&state, table).await),
+            None => report.skip(check, "its table name isn't set"),
+        }
+    }
+
+    let mut token = None;
+    let mut secrets = Vec::new();
+
+    match &state.secrets {
+        Some(bundle) => {
+            report.record(
+                "secret bundle",
+                Ok(format!(
+                    "valid, with {} public key(s)",
+                    bundle.discord_public_keys.len()
+                )),
+            );
+            token = Some(bundle.discord_token.clone());
+        }
+        None => secrets.extend([
+            (
+                "Discord token secret",
+                config.discord_token_secret_arn.clone(),
+                "token".to_string(),
+            ),
+            (
+                "Discord public key secret",
+                config.discord_public_key_secret_arn.clone(),
+                "key".to_string(),
+            ),
+            (
+                "Discord OAuth2 secret",
+                config.discord_oauth_secret_arn.clone(),
+                "client_secret".to_string(),
+            ),
+            (
+                "Stripe secret",
+                config.stripe_secret_arn.clone(),
+                "secret_key".to_string(),
+            ),
+        ]),
+    }
+
+    secrets.extend(config.bot_lists.iter().map(|list| {
+        (
+            "bot list secret",
+            config.bot_list_secret_arn.clone(),
+            list.name.clone(),
+        )
+    }));
+
+    for (check, arn, key) in secrets {
+        let Some(arn) = arn else {
+            report.skip(check, "its ARN isn't set");
+            continue;
+        };
+
+        let result = read_secret(&state, &arn, &key).await.and_then(|value| {
+            match key.as_str() {
+                "key" => {
+                    AuthManager::parse_public_key(&value)
+                        .context("'key' isn't a hex Ed25519 public key")?;
+                }
+                "token" => token = Some(value),
+                _ => {}
+            }
+
+            Ok(format!("'{}' is set", key))
+        });
+        report.record(check, result);
+    }
+
+    match &token {
+        Some(token) => report.record("Discord token", check_token(&state, token).await),
+        None => report.skip("Discord token", "the token secret couldn't be read"),
+    }
+
+    match (&token, &config.application_id) {
+        (Some(token), Some(application_id)) => report.record(
+            "registered commands",
+            check_commands(
+                &state,
+                token,
+                application_id,
+                config.commands_guild_id.as_deref(),
+            )
+            .await,
+        ),
+        (None, _) => report.skip("registered commands", "the bot token isn't available"),
+        (_, None) => report.skip("registered commands", "DISCORD_APPLICATION_ID isn't set"),
+    }
+
+    if report.failures > 0 {
+        bail!("{} checks failed", report.failures);
+    }
+
+    println!("Ready.");
+
+    Ok(())
+}
+
+/// Confirms the table is active and that it and each global secondary index
+/// can be read, which is what the functions' IAM policies have to allow.
+async fn check_table(state: &AppState, table: &str) -> Result<String> {
+    let description = state
+        .dynamo_client
+        .describe_table()
+        .table_name(table)
+        .send()
+        .await
+        .context("DescribeTable failed")?
+        .table
+        .context("DescribeTable returned no table")?;
+
+    let status = description
+        .table_status
+        .as_ref()
+        .map(|status| status.as_str().to_string())
+        .unwrap_or_default();
+    if status != "ACTIVE" {
+        bail!("the table is {}", status);
+    }
+
+    state
+        .dynamo_client
+        .scan()
+        .table_name(table)
+        .limit(1)
+        .send()
+        .await
+        .context("reading the table failed")?;
+
+    let indexes: Vec<String> = description
+        .global_secondary_indexes
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|index| index.index_name)
+        .collect();
+
+    for index in &indexes {
+        state
+            .dynamo_client
+            .scan()
+            .table_name(table)
+            .index_name(index)
+            .limit(1)
+            .send()
+            .await
+            .with_context(|| format!("reading index {} failed", index))?;
+    }
+
+    if indexes.is_empty() {
+        return Ok(format!("{} is active and readable", table));
+    }
+
+    Ok(format!(
+        "{} and {} are active and readable",
+        table,
+        indexes.join(", ")
+    ))
+}
+
+/// Reads one field of a secret the way the functions do.
+async fn read_secret(state: &AppState, arn: &str, key: &str) -> Result<String> {
+    state.secrets_reader.fetch_secret_value(arn, key).await
+}
+
+async fn check_token(state: &AppState, token: &str) -> Result<String> {
+    let resp = state
+        .http_client
+        .get("https://discord.com/api/v10/users/@me")
+        .header("Authorization", format!("Bot {}", token))
+        .send()
+        .await
+        .context("Failed to reach Discord")?;
+
+    if !resp.status().is_success() {
+        bail!("Discord rejected the token ({})", resp.status());
+    }
+
+    let user: CurrentUser = resp
+        .json()
+        .await
+        .context("Failed to deserialize the current user")?;
+
+    Ok(format!("signed in as {} ({})", user.username, user.id))
+}
+
+async fn check_commands(
+    state: &AppState,
+    token: &str,
+    application_id: &str,
+    guild_id: Option<&str>,
+) -> Result<String> {
+    let registered = RoleManager::new(state.http_client.clone(), token)
+        .fetch_application_commands(application_id, guild_id)
+        .await?;
+
+    let missing = commands::drift(&registered);
+    if !missing.is_empty() {
+        bail!(
+            "not registered: {}; run register-commands",
+            missing.join(", ")
+        );
+    }
+
+    Ok(format!(
+        "{} commands registered {}",
+        registered.len(),
+        match guild_id {
+            Some(guild_id) => format!("in guild {}", guild_id),
+            None => "globally".to_string(),
+        }
+    ))
+}
@@ -0,0 +1,143 @@
+//! Registers the bot's slash commands with Discord, replacing whatever is
+//! currently registered in the chosen scope.
+//!
+//! ```sh
+//! register-commands [--guild <guild_id>] [--dry-run]
+//! ```
+//!
+//! Reads `DISCORD_TOKEN` and `DISCORD_CLIENT_ID` from the environment or from
+//! `.env` (`.env.<STAGE>` when `STAGE` is set). Without `--guild` the
+//! commands are registered globally, along with the Linked Roles metadata
+//! (which is always application-wide).
+
+use anyhow::{bail, Context, Result};
+use s_cybersage_rs::{bal::linked_roles::metadata, commands};
+
+struct Args {
+    guild_id: Option<String>,
+    dry_run: bool,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args {
+        guild_id: None,
+        dry_run: false,
+    };
+
+    let mut raw = std::env::args().skip(1);
+
+    while let Some(arg) = raw.next() {
+        match arg.as_str() {
+            "--guild" => args.guild_id = Some(raw.next().context("--guild needs a guild ID")?),
+            "--dry-run" => args.dry_run = true,
+            other => bail!("Unknown argument '{}'", other),
+        }
+    }
+
+    Ok(args)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let env_file = match std::env::var("STAGE") {
+        Ok(stage) if !stage.is_empty() => format!(".env.{}", stage),
+        _ => ".env".to_string(),
+    };
+    dotenvy::from_filename(&env_file).ok();
+
+    let args = parse_args()?;
+    let definitions = commands::definitions();
+
+    if args.dry_run {
+        println!("{}", serde_json::to_string_pretty(&definitions)?);
+
+        if args.guild_id.is_none() {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&metadata::definitions())?
+            );
+        }
+
+        return Ok(());
+    }
+
+    let token = std::env::var("DISCORD_TOKEN").context("DISCORD_TOKEN is not set")?;
+    let application_id =
+        std::env::var("DISCORD_CLIENT_ID").context("DISCORD_CLIENT_ID is not set")?;
+
+    let url = match args.guild_id.as_deref() {
+        Some(guild_id) => format!(
+            "https://discord.com/api/v10/applications/{}/guilds/{}/commands",
+            application_id, guild_id
+        ),
+        None => format!(
+            "https://discord.com/api/v10/applications/{}/commands",
+            application_id
+        ),
+    };
+
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .put(&url)
+        .header("Authorization", format!("Bot {}", token))
+        .json(&definitions)
+        .send()
+        .await
+        .context("Failed to send command registration request")?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        bail!("Discord rejected the commands ({}): {}", status, body);
+    }
+
+    match &args.guild_id {
+        Some(guild_id) => println!(
+            "Registered {} commands in guild {}.",
+            definitions.len(),
+            guild_id
+        ),
+        None => println!("Registered {} commands globally.", definitions.len()),
+    }
+
+    if args.guild_id.is_none() {
+        register_metadata(&client, &token, &application_id).await?;
+    }
+
+    Ok(())
+}
+
+async fn register_metadata(
+    client: &reqwest::Client,
+    token: &str,
+    application_id: &str,
+) -> Result<()> {
+    let records = metadata::definitions();
+    let url = format!(
+        "https://discord.com/api/v10/applications/{}/role-connections/metadata",
+        application_id
+    );
+
+    let resp = client
+        .put(&url)
+        .header("Authorization", format!("Bot {}", token))
+        .json(&records)
+        .send()
+        .await
+        .context("Failed to send role connection metadata request")?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        bail!(
+            "Discord rejected the role connection metadata ({}): {}",
+            status,
+            body
+        );
+    }
+
+    println!("Registered {} linked role criteria.", records.len());
+
+    Ok(())
+}
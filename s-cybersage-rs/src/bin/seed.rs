@@ -0,0 +1,173 @@
+//! Fills a role table with synthetic guilds and role mappings for load tests
+//! and local development.
+//!
+//! ```sh
+//! seed --table <name> [--guilds <n>] [--roles <m>] [--realistic]
+//! ```
+//!
+//! Writes `n` guilds × `m` roles through `GuildDao`, so the items match what
+//! `/role save` produces. Guild and role IDs are deterministic, making
+//! re-runs overwrite rather than duplicate. `--realistic` names roles like a
+//! real server ("Moderator", "Blue Team Captain", ...) instead of
+//! "Role 0001", which gives autocomplete prefixes something to match.
+//! Credentials and region come from the usual AWS environment; point
+//! `AWS_ENDPOINT_URL` at DynamoDB Local to seed a local table.
+
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use s_cybersage_rs::dal::dao::{guild::GuildDao, role_store::RoleStore};
+use tokio::task::JoinSet;
+
+/// Puts in flight at once; keeps well under on-demand throttling limits.
+const CONCURRENCY: usize = 25;
+
+const GUILD_ID_BASE: u64 = 900_000_000_000_000_000;
+const ROLE_ID_BASE: u64 = 910_000_000_000_000_000;
+
+const ROLE_NOUNS: &[&str] = &[
+    "Moderator",
+    "Admin",
+    "Member",
+    "Gamer",
+    "Artist",
+    "Streamer",
+    "Developer",
+    "Musician",
+    "Captain",
+    "Helper",
+    "Event Host",
+    "Announcements",
+];
+
+const ROLE_QUALIFIERS: &[&str] = &[
+    "",
+    "Red Team",
+    "Blue Team",
+    "Senior",
+    "Junior",
+    "EU",
+    "NA",
+    "APAC",
+    "Verified",
+    "Night Shift",
+];
+
+struct Args {
+    table: String,
+    guilds: u64,
+    roles: u64,
+    realistic: bool,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut table = None;
+    let mut args = Args {
+        table: String::new(),
+        guilds: 10,
+        roles: 50,
+        realistic: false,
+    };
+
+    let mut raw = std::env::args().skip(1);
+
+    while let Some(arg) = raw.next() {
+        match arg.as_str() {
+            "--table" => table = Some(raw.next().context("--table needs a table name")?),
+            "--guilds" => {
+                args.guilds = raw
+                    .next()
+                    .context("--guilds needs a count")?
+                    .parse()
+                    .context("--guilds must be a number")?
+            }
+            "--roles" => {
+                args.roles = raw
+                    .next()
+                    .context("--roles needs a count")?
+                    .parse()
+                    .context("--roles must be a number")?
+            }
+            "--realistic" => args.realistic = true,
+            other => bail!("Unknown argument '{}'", other),
+        }
+    }
+
+    args.table = table
+        .or_else(|| std::env::var("ROLE_MAPPINGS_TABLE_NAME").ok())
+        .context("--table or ROLE_MAPPINGS_TABLE_NAME is required")?;
+
+    Ok(args)
+}
+
+fn role_name(index: u64, realistic: bool) -> String {
+    if !realistic {
+        return format!("Role {:04}", index + 1);
+    }
+
+    let noun = ROLE_NOUNS[index as usize % ROLE_NOUNS.len()];
+    let combination = index as usize / ROLE_NOUNS.len();
+    let qualifier = ROLE_QUALIFIERS[combination % ROLE_QUALIFIERS.len()];
+    let round = combination / ROLE_QUALIFIERS.len();
+
+    let name = match qualifier {
+        "" => noun.to_string(),
+        q => format!("{} {}", q, noun),
+    };
+
+    match round {
+        0 => name,
+        n => format!("{} {}", name, n + 1),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let env_file = match std::env::var("STAGE") {
+        Ok(stage) if !stage.is_empty() => format!(".env.{}", stage),
+        _ => ".env".to_string(),
+    };
+    dotenvy::from_filename(&env_file).ok();
+
+    let args = parse_args()?;
+    let shared_config = aws_config::load_from_env().await;
+    let dao = Arc::new(GuildDao::new(
+        aws_sdk_dynamodb::Client::new(&shared_config),
+        &args.table,
+    ));
+
+    let mut in_flight = JoinSet::new();
+    let mut written: u64 = 0;
+
+    for guild in 0..args.guilds {
+        let guild_id = (GUILD_ID_BASE + guild).to_string();
+
+        for role in 0..args.roles {
+            if in_flight.len() >= CONCURRENCY {
+                if let Some(result) = in_flight.join_next().await {
+                    result.context("Seed task panicked")??;
+                    written += 1;
+                }
+            }
+
+            let dao = Arc::clone(&dao);
+            let guild_id = guild_id.clone();
+            let role_id = (ROLE_ID_BASE + guild * args.roles + role).to_string();
+            let name = role_name(role, args.realistic);
+
+            in_flight.spawn(async move { dao.save_role(&guild_id, &role_id, &name).await });
+        }
+    }
+
+    while let Some(result) = in_flight.join_next().await {
+        result.context("Seed task panicked")??;
+        written += 1;
+    }
+
+    println!(
+        "Seeded {} role mappings across {} guilds in {}.",
+        written, args.guilds, args.table
+    );
+
+    Ok(())
+}
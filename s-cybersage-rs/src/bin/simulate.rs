@@ -0,0 +1,184 @@
+//! Sends signed interaction payloads to a deployed handler, so a staging
+//! stage can be smoke-tested without going through Discord.
+//!
+//! ```sh
+//! simulate keygen
+//! simulate <ping|toggle|autocomplete> --url <url> [--guild <id>] [--user <id>] [--role <name>]
+//! ```
+//!
+//! Payloads are signed with the hex-encoded Ed25519 seed in
+//! `SIMULATE_SIGNING_KEY`, read from the environment or from `.env`
+//! (`.env.<STAGE>` when `STAGE` is set). `keygen` prints a fresh seed and the
+//! public key to store in the target stage's Discord public key secret; a
+//! stage configured that way no longer accepts requests from Discord itself.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use ed25519_dalek::{Signer, SigningKey};
+use s_cybersage_rs::commands::role;
+use serde_json::{json, Value};
+
+enum Kind {
+    Ping,
+    Toggle,
+    Autocomplete,
+}
+
+struct Args {
+    kind: Kind,
+    url: Option<String>,
+    guild_id: String,
+    user_id: String,
+    role: String,
+}
+
+fn parse_args(mut raw: impl Iterator<Item = String>) -> Result<Args> {
+    let kind = match raw.next().as_deref() {
+        Some("ping") => Kind::Ping,
+        Some("toggle") => Kind::Toggle,
+        Some("autocomplete") => Kind::Autocomplete,
+        Some(other) => bail!("Unknown interaction '{}'", other),
+        None => bail!("Usage: simulate <keygen|ping|toggle|autocomplete> --url <url>"),
+    };
+
+    let mut args = Args {
+        kind,
+        url: std::env::var("SIMULATE_URL").ok(),
+        guild_id: "0".to_string(),
+        user_id: "0".to_string(),
+        role: String::new(),
+    };
+
+    while let Some(arg) = raw.next() {
+        match arg.as_str() {
+            "--url" => args.url = Some(raw.next().context("--url needs a URL")?),
+            "--guild" => args.guild_id = raw.next().context("--guild needs a guild ID")?,
+            "--user" => args.user_id = raw.next().context("--user needs a user ID")?,
+            "--role" => args.role = raw.next().context("--role needs a role name")?,
+            other => bail!("Unknown argument '{}'", other),
+        }
+    }
+
+    Ok(args)
+}
+
+fn keygen() -> Result<()> {
+    let mut seed = [0u8; 32];
+    openssl::rand::rand_bytes(&mut seed).context("Failed to generate a signing key")?;
+
+    let signing_key = SigningKey::from_bytes(&seed);
+
+    println!("SIMULATE_SIGNING_KEY={}", hex::encode(seed));
+    println!(
+        "public key: {}",
+        hex::encode(signing_key.verifying_key().to_bytes())
+    );
+
+    Ok(())
+}
+
+fn signing_key() -> Result<SigningKey> {
+    let seed_hex =
+        std::env::var("SIMULATE_SIGNING_KEY").context("SIMULATE_SIGNING_KEY is not set")?;
+
+    let seed: [u8; 32] = hex::decode(seed_hex.trim())
+        .context("SIMULATE_SIGNING_KEY is not valid hex")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("SIMULATE_SIGNING_KEY must be 32 bytes"))?;
+
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+fn payload(args: &Args, interaction_id: &str) -> Value {
+    let application_id = std::env::var("DISCORD_CLIENT_ID").unwrap_or_else(|_| "0".to_string());
+
+    let mut payload = json!({
+        "id": interaction_id,
+        "application_id": application_id,
+        "token": "simulated",
+        "guild_id": args.guild_id,
+        "member": { "user": { "id": args.user_id }, "roles": [] },
+    });
+
+    let (kind, focused) = match args.kind {
+        Kind::Ping => {
+            payload["type"] = json!(1);
+            return payload;
+        }
+        Kind::Toggle => (2, false),
+        Kind::Autocomplete => (4, true),
+    };
+
+    let mut role_option = json!({ "name": role::ROLE_OPTION, "type": 3, "value": args.role });
+    if focused {
+        role_option["focused"] = json!(true);
+    }
+
+    payload["type"] = json!(kind);
+    payload["data"] = json!({
+        "id": "0",
+        "name": role::NAME,
+        "type": 1,
+        "options": [{ "name": role::TOGGLE, "type": 1, "options": [role_option] }],
+    });
+
+    payload
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let env_file = match std::env::var("STAGE") {
+        Ok(stage) if !stage.is_empty() => format!(".env.{}", stage),
+        _ => ".env".to_string(),
+    };
+    dotenvy::from_filename(&env_file).ok();
+
+    let mut raw = std::env::args().skip(1).peekable();
+
+    if raw.peek().map(String::as_str) == Some("keygen") {
+        return keygen();
+    }
+
+    let args = parse_args(raw)?;
+    let url = args
+        .url
+        .clone()
+        .context("--url or SIMULATE_URL is required")?;
+    let signing_key = signing_key()?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let timestamp = now.as_secs().to_string();
+    let body = serde_json::to_vec(&payload(&args, &now.as_millis().to_string()))?;
+
+    let mut message = Vec::with_capacity(timestamp.len() + body.len());
+    message.extend_from_slice(timestamp.as_bytes());
+    message.extend_from_slice(&body);
+
+    let signature = signing_key.sign(&message);
+
+    let resp = reqwest::Client::new()
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .header("X-Signature-Ed25519", hex::encode(signature.to_bytes()))
+        .header("X-Signature-Timestamp", &timestamp)
+        .body(body)
+        .send()
+        .await
+        .context("Failed to send simulated interaction")?;
+
+    let status = resp.status();
+    let text = resp.text().await.unwrap_or_default();
+
+    println!("{}", status);
+    match serde_json::from_str::<Value>(&text) {
+        Ok(json) => println!("{}", serde_json::to_string_pretty(&json)?),
+        Err(_) => println!("{}", text),
+    }
+
+    if !status.is_success() {
+        bail!("Handler responded with {}", status);
+    }
+
+    Ok(())
+}
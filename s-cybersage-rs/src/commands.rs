@@ -0,0 +1,720 @@
+//! Slash command schemas, shared by the `register-commands` binary and the
+//! command router so the names the router matches on can't drift from what
+//! gets registered.
+
+use tracing::{info, warn};
+
+use crate::{
+    bal::discord::discord_api::DiscordApi,
+    dal::model::application_command::{
+        ApplicationCommand, CommandOptionDefinition, CommandOptionType,
+    },
+};
+
+pub const KICK_MEMBERS: u64 = 1 << 1;
+pub const BAN_MEMBERS: u64 = 1 << 2;
+pub const ADMINISTRATOR: u64 = 1 << 3;
+pub const MANAGE_CHANNELS: u64 = 1 << 4;
+pub const MANAGE_GUILD: u64 = 1 << 5;
+pub const VIEW_CHANNEL: u64 = 1 << 10;
+pub const SEND_MESSAGES: u64 = 1 << 11;
+pub const MANAGE_ROLES: u64 = 1 << 28;
+pub const MANAGE_WEBHOOKS: u64 = 1 << 29;
+pub const MODERATE_MEMBERS: u64 = 1 << 40;
+
+/// Moderation and server management permissions. Roles granting any of them
+/// are never imported as self-assignable.
+pub const ELEVATED_PERMISSIONS: u64 = ADMINISTRATOR
+    | MANAGE_GUILD
+    | MANAGE_ROLES
+    | MANAGE_CHANNELS
+    | MANAGE_WEBHOOKS
+    | KICK_MEMBERS
+    | BAN_MEMBERS
+    | MODERATE_MEMBERS;
+
+/// What the bot's invite link asks for.
+pub const BOT_PERMISSIONS: u64 = VIEW_CHANNEL | SEND_MESSAGES | MANAGE_ROLES;
+
+/// Who may run a command or subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Everyone,
+    /// Members holding all of these permission bits, or Administrator.
+    Permissions(u64),
+    /// Only the user IDs in `BOT_OPERATOR_IDS`. Discord shows the command to
+    /// Administrators, who are refused unless they're also operators.
+    Operator,
+}
+
+impl Access {
+    /// The `default_member_permissions` to register a command with, so
+    /// Discord hides it from members the router would refuse anyway.
+    pub fn default_member_permissions(self) -> Option<u64> {
+        match self {
+            Access::Everyone => None,
+            Access::Permissions(permissions) => Some(permissions),
+            Access::Operator => Some(ADMINISTRATOR),
+        }
+    }
+
+    /// The permission named in the refusal, for the ones commands require.
+    pub fn permission_name(permissions: u64) -> &'static str {
+        match permissions {
+            MANAGE_GUILD => "Manage Server",
+            MANAGE_ROLES => "Manage Roles",
+            _ => "Administrator",
+        }
+    }
+}
+
+/// Who may run `command`'s `subcommand`; an empty subcommand asks about the
+/// command itself. Registration and the command router both read this, so
+/// what Discord offers and what the router enforces can't drift. Discord only
+/// takes `default_member_permissions` per command, so subcommands stricter
+/// than their command are enforced by the router alone.
+pub fn access(command: &str, subcommand: &str) -> Access {
+    match (command, subcommand) {
+        (role::NAME, role::ANNOUNCE | role::HISTORY) => Access::Permissions(MANAGE_GUILD),
+        // Saving a role makes it self-assignable, and mass roles hand it to
+        // everyone, so only members who could hand it out themselves may.
+        (
+            role::NAME,
+            role::SAVE | role::IMPORT_ALL | role::EXPORT | role::MASS_ASSIGN | role::MASS_REMOVE,
+        ) => Access::Permissions(MANAGE_ROLES),
+        (config::NAME, _) | (rolemenu::NAME, _) | (setup::NAME, _) => {
+            Access::Permissions(MANAGE_GUILD)
+        }
+        #[cfg(feature = "billing")]
+        (subscription::NAME, _) => Access::Permissions(ADMINISTRATOR),
+        (sysadmin::NAME, _) => Access::Operator,
+        _ => Access::Everyone,
+    }
+}
+
+pub mod role {
+    pub const NAME: &str = "role";
+    pub const TOGGLE: &str = "toggle";
+    pub const SAVE: &str = "save";
+    pub const IMPORT_ALL: &str = "import-all";
+    pub const EXPORT: &str = "export";
+    pub const MASS_ASSIGN: &str = "mass-assign";
+    pub const MASS_REMOVE: &str = "mass-remove";
+    pub const ANNOUNCE: &str = "announce";
+    pub const FAVORITE: &str = "favorite";
+    pub const MINE: &str = "mine";
+    pub const HISTORY: &str = "history";
+
+    pub const MAX_FAVORITES: usize = 10;
+
+    /// The role option of `toggle`, `save` and the mass subcommands.
+    pub const ROLE_OPTION: &str = "role";
+    /// Limits `mass-assign` and `mass-remove` to members holding this role.
+    pub const FILTER_OPTION: &str = "filter";
+    /// Replaces `announce`'s default message.
+    pub const TEXT_OPTION: &str = "text";
+    /// When to post `announce`; it posts right away without one.
+    pub const AT_OPTION: &str = "at";
+    /// Limits `history` to one member's commands.
+    pub const MEMBER_OPTION: &str = "member";
+    /// Registers `save`'s role under this name instead of its own.
+    pub const ALIAS_OPTION: &str = "alias";
+}
+
+pub mod config {
+    pub const NAME: &str = "config";
+    pub const AUTOCOMPLETE_MIN_LENGTH: &str = "autocomplete-min-length";
+    pub const LENGTH_OPTION: &str = "length";
+    pub const WEBHOOK: &str = "webhook";
+    pub const WEBHOOK_REMOVE: &str = "webhook-remove";
+    pub const URL_OPTION: &str = "url";
+    pub const LOG_CHANNEL: &str = "log-channel";
+    pub const CHANNEL_OPTION: &str = "channel";
+    pub const PREREQUISITE: &str = "prerequisite";
+    pub const PREREQUISITE_CLEAR: &str = "prerequisite-clear";
+    pub const ROLE_OPTION: &str = "role";
+    /// The role `prerequisite` makes members hold first.
+    pub const REQUIRED_OPTION: &str = "required";
+
+    pub const MAX_PREREQUISITES: usize = 10;
+
+    pub const CHANNELS: &str = "channels";
+    pub const CHANNELS_CLEAR: &str = "channels-clear";
+    pub const MAX_ROLE_CHANNELS: usize = 10;
+
+    /// Caps the self-assignable roles a member can hold; no limit removes
+    /// the cap.
+    pub const ROLE_LIMIT: &str = "role-limit";
+    pub const LIMIT_OPTION: &str = "limit";
+    pub const MAX_ROLE_LIMIT: u32 = 100;
+    pub const MIN_AGE: &str = "min-age";
+    pub const HOURS_OPTION: &str = "hours";
+    /// Makes `min-age` count from account creation instead of joining.
+    pub const ACCOUNT_OPTION: &str = "account";
+    /// Thirty days.
+    pub const MAX_MIN_AGE_HOURS: u32 = 720;
+
+    /// Bounds for `autocomplete-min-length`; one character is the behaviour
+    /// for guilds that never set it.
+    pub const MIN_LENGTH: u32 = 1;
+    pub const MAX_LENGTH: u32 = 5;
+}
+
+pub mod rolemenu {
+    pub const NAME: &str = "rolemenu";
+    pub const POST: &str = "post";
+    pub const BIND_EMOJI: &str = "bind-emoji";
+    pub const UNBIND_EMOJI: &str = "unbind-emoji";
+    pub const TEXT_OPTION: &str = "text";
+    /// The menu message's ID, or a link to it.
+    pub const MESSAGE_OPTION: &str = "message";
+    pub const EMOJI_OPTION: &str = "emoji";
+    pub const ROLE_OPTION: &str = "role";
+}
+
+pub mod setup {
+    pub const NAME: &str = "setup";
+}
+
+pub mod invite {
+    pub const NAME: &str = "invite";
+}
+
+#[cfg(feature = "billing")]
+pub mod subscription {
+    pub const NAME: &str = "subscription";
+    pub const MANAGE: &str = "manage";
+    pub const ATTACH: &str = "attach";
+    pub const DETACH: &str = "detach";
+}
+
+/// Bot-operator commands, usable only by IDs in `BOT_OPERATOR_IDS`.
+pub mod sysadmin {
+    pub const NAME: &str = "sysadmin";
+    pub const STATS: &str = "stats";
+    #[cfg(feature = "billing")]
+    pub const GRANT_PREMIUM: &str = "grant-premium";
+    pub const FLAG: &str = "flag";
+    pub const GUILD: &str = "guild";
+    pub const GUILD_ID_OPTION: &str = "guild_id";
+    #[cfg(feature = "billing")]
+    pub const DURATION_OPTION: &str = "duration";
+    /// Ten years.
+    #[cfg(feature = "billing")]
+    pub const MAX_GRANT_DAYS: i64 = 3_650;
+    pub const FLAG_OPTION: &str = "flag";
+    pub const ENABLED_OPTION: &str = "enabled";
+}
+
+pub fn definitions() -> Vec<ApplicationCommand> {
+    #[cfg_attr(not(feature = "billing"), allow(unused_mut))]
+    let mut definitions = vec![
+        role_command(),
+        config_command(),
+        rolemenu_command(),
+        setup_command(),
+        invite_command(),
+        sysadmin_command(),
+    ];
+
+    #[cfg(feature = "billing")]
+    definitions.push(subscription_command());
+
+    definitions
+        .into_iter()
+        .map(
+            |command| match access(&command.name, "").default_member_permissions() {
+                Some(permissions) => command.default_member_permissions(permissions),
+                None => command,
+            },
+        )
+        .collect()
+}
+
+fn role_command() -> ApplicationCommand {
+    ApplicationCommand::new(role::NAME, "Manage self-assignable roles")
+        .option(
+            CommandOptionDefinition::subcommand(role::TOGGLE, "Assign or remove a role").option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::String,
+                    role::ROLE_OPTION,
+                    "The role you want",
+                )
+                .autocomplete()
+                .required(),
+            ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                role::FAVORITE,
+                "Add a role to your favorites, or remove it",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::String,
+                    role::ROLE_OPTION,
+                    "The role to add or remove",
+                )
+                .autocomplete()
+                .required(),
+            ),
+        )
+        .option(CommandOptionDefinition::subcommand(
+            role::MINE,
+            "List your self-assignable roles and favorites",
+        ))
+        .option(
+            CommandOptionDefinition::subcommand(
+                role::HISTORY,
+                "Show recent commands in this server",
+            )
+            .option(CommandOptionDefinition::new(
+                CommandOptionType::User,
+                role::MEMBER_OPTION,
+                "Only this member's commands",
+            )),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(role::SAVE, "Register a role as self-assignable")
+                .option(
+                    CommandOptionDefinition::new(
+                        CommandOptionType::Role,
+                        role::ROLE_OPTION,
+                        "The role to register",
+                    )
+                    .required(),
+                )
+                .option(CommandOptionDefinition::new(
+                    CommandOptionType::String,
+                    role::ALIAS_OPTION,
+                    "The name to register it under, if another role has its name",
+                )),
+        )
+        .option(CommandOptionDefinition::subcommand(
+            role::IMPORT_ALL,
+            "Register every assignable server role",
+        ))
+        .option(CommandOptionDefinition::subcommand(
+            role::EXPORT,
+            "Export the registered roles as CSV",
+        ))
+        .option(mass_role_subcommand(
+            role::MASS_ASSIGN,
+            "Give a role to every member (admins only)",
+            "The role to give",
+        ))
+        .option(mass_role_subcommand(
+            role::MASS_REMOVE,
+            "Take a role from every member (admins only)",
+            "The role to take",
+        ))
+        .option(
+            CommandOptionDefinition::subcommand(
+                role::ANNOUNCE,
+                "Post a message with a Join button for a role (admins only)",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Role,
+                    role::ROLE_OPTION,
+                    "The self-assignable role to announce",
+                )
+                .required(),
+            )
+            .option(CommandOptionDefinition::new(
+                CommandOptionType::String,
+                role::TEXT_OPTION,
+                "The message to post instead of the default",
+            ))
+            .option(CommandOptionDefinition::new(
+                CommandOptionType::String,
+                role::AT_OPTION,
+                "When to post, e.g. 2030-01-31T18:00:00Z or a Unix timestamp",
+            )),
+        )
+}
+
+fn mass_role_subcommand(
+    name: &str,
+    description: &str,
+    role_description: &str,
+) -> CommandOptionDefinition {
+    CommandOptionDefinition::subcommand(name, description)
+        .option(
+            CommandOptionDefinition::new(
+                CommandOptionType::Role,
+                role::ROLE_OPTION,
+                role_description,
+            )
+            .required(),
+        )
+        .option(CommandOptionDefinition::new(
+            CommandOptionType::Role,
+            role::FILTER_OPTION,
+            "Only members who have this role",
+        ))
+}
+
+fn config_command() -> ApplicationCommand {
+    ApplicationCommand::new(config::NAME, "Configure the bot for this server")
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::AUTOCOMPLETE_MIN_LENGTH,
+                "Characters to type before role suggestions appear",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Integer,
+                    config::LENGTH_OPTION,
+                    "Number of characters",
+                )
+                .range(config::MIN_LENGTH.into(), config::MAX_LENGTH.into())
+                .required(),
+            ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::ROLE_LIMIT,
+                "Cap how many self-assignable roles a member can hold",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Integer,
+                    config::LIMIT_OPTION,
+                    "Most roles per member; leave out to remove the cap",
+                )
+                .range(1, config::MAX_ROLE_LIMIT.into()),
+            ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::MIN_AGE,
+                "Require members to have been around a while before taking roles",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Integer,
+                    config::HOURS_OPTION,
+                    "Hours since joining; leave out to remove the requirement",
+                )
+                .range(1, config::MAX_MIN_AGE_HOURS.into()),
+            )
+            .option(CommandOptionDefinition::new(
+                CommandOptionType::Boolean,
+                config::ACCOUNT_OPTION,
+                "Count from account creation instead of joining the server",
+            )),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::WEBHOOK,
+                "Send role and subscription events to an external endpoint",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::String,
+                    config::URL_OPTION,
+                    "An https:// URL",
+                )
+                .required(),
+            ),
+        )
+        .option(CommandOptionDefinition::subcommand(
+            config::WEBHOOK_REMOVE,
+            "Stop sending events to the external endpoint",
+        ))
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::LOG_CHANNEL,
+                "Log role activity to a channel, or stop logging if none is given",
+            )
+            .option(CommandOptionDefinition::new(
+                CommandOptionType::Channel,
+                config::CHANNEL_OPTION,
+                "The channel to log to",
+            )),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::PREREQUISITE,
+                "Require members to hold a role before taking another",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Role,
+                    config::ROLE_OPTION,
+                    "The self-assignable role",
+                )
+                .required(),
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Role,
+                    config::REQUIRED_OPTION,
+                    "The role members must already have",
+                )
+                .required(),
+            ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::PREREQUISITE_CLEAR,
+                "Let anyone take a role again",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Role,
+                    config::ROLE_OPTION,
+                    "The self-assignable role",
+                )
+                .required(),
+            ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::CHANNELS,
+                "Only allow toggling a role from certain channels",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Role,
+                    config::ROLE_OPTION,
+                    "The self-assignable role",
+                )
+                .required(),
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Channel,
+                    config::CHANNEL_OPTION,
+                    "A channel the role can be toggled from",
+                )
+                .required(),
+            ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                config::CHANNELS_CLEAR,
+                "Let a role be toggled from any channel again",
+            )
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Role,
+                    config::ROLE_OPTION,
+                    "The self-assignable role",
+                )
+                .required(),
+            ),
+        )
+}
+
+fn rolemenu_command() -> ApplicationCommand {
+    let message_option = || {
+        CommandOptionDefinition::new(
+            CommandOptionType::String,
+            rolemenu::MESSAGE_OPTION,
+            "The menu's message ID or link",
+        )
+        .required()
+    };
+    let emoji_option = || {
+        CommandOptionDefinition::new(
+            CommandOptionType::String,
+            rolemenu::EMOJI_OPTION,
+            "The emoji shown on the button",
+        )
+        .required()
+    };
+
+    ApplicationCommand::new(rolemenu::NAME, "Post role menus with emoji buttons")
+        .option(
+            CommandOptionDefinition::subcommand(rolemenu::POST, "Post a role menu in this channel")
+                .option(
+                    CommandOptionDefinition::new(
+                        CommandOptionType::String,
+                        rolemenu::TEXT_OPTION,
+                        "The menu's message",
+                    )
+                    .required(),
+                ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(
+                rolemenu::BIND_EMOJI,
+                "Add a button that toggles a role",
+            )
+            .option(message_option())
+            .option(emoji_option())
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Role,
+                    rolemenu::ROLE_OPTION,
+                    "The role the button toggles",
+                )
+                .required(),
+            ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(rolemenu::UNBIND_EMOJI, "Remove a button")
+                .option(message_option())
+                .option(emoji_option()),
+        )
+}
+
+fn setup_command() -> ApplicationCommand {
+    ApplicationCommand::new(setup::NAME, "Walk through setting up the bot")
+}
+
+fn invite_command() -> ApplicationCommand {
+    ApplicationCommand::new(invite::NAME, "Get a link to add the bot to another server")
+}
+
+#[cfg(feature = "billing")]
+fn subscription_command() -> ApplicationCommand {
+    ApplicationCommand::new(subscription::NAME, "Manage this guild's subscription")
+        .option(CommandOptionDefinition::subcommand(
+            subscription::MANAGE,
+            "Open the billing portal to update payment or cancel",
+        ))
+        .option(CommandOptionDefinition::subcommand(
+            subscription::ATTACH,
+            "Cover this server with your bundle subscription",
+        ))
+        .option(CommandOptionDefinition::subcommand(
+            subscription::DETACH,
+            "Remove this server from your bundle subscription",
+        ))
+}
+
+fn sysadmin_command() -> ApplicationCommand {
+    let guild_id_option = |description: &str| {
+        CommandOptionDefinition::new(
+            CommandOptionType::String,
+            sysadmin::GUILD_ID_OPTION,
+            description,
+        )
+        .required()
+    };
+
+    let command = ApplicationCommand::new(sysadmin::NAME, "Bot operator tools")
+        .option(CommandOptionDefinition::subcommand(
+            sysadmin::STATS,
+            "Show the last week of bot-wide usage stats",
+        ))
+        .option(
+            CommandOptionDefinition::subcommand(sysadmin::FLAG, "Turn a feature flag on or off")
+                .option(
+                    CommandOptionDefinition::new(
+                        CommandOptionType::String,
+                        sysadmin::FLAG_OPTION,
+                        "The flag's name, e.g. deferred_role_tasks",
+                    )
+                    .required(),
+                )
+                .option(
+                    CommandOptionDefinition::new(
+                        CommandOptionType::Boolean,
+                        sysadmin::ENABLED_OPTION,
+                        "Whether the feature is on",
+                    )
+                    .required(),
+                ),
+        )
+        .option(
+            CommandOptionDefinition::subcommand(sysadmin::GUILD, "Show a guild's stored data")
+                .option(guild_id_option("The guild to inspect")),
+        );
+
+    #[cfg(feature = "billing")]
+    let command = command.option(
+        CommandOptionDefinition::subcommand(sysadmin::GRANT_PREMIUM, "Grant premium to a guild")
+            .option(guild_id_option("The guild to grant premium to"))
+            .option(
+                CommandOptionDefinition::new(
+                    CommandOptionType::Integer,
+                    sysadmin::DURATION_OPTION,
+                    "Duration in days",
+                )
+                .range(1, sysadmin::MAX_GRANT_DAYS)
+                .required(),
+            ),
+    );
+
+    command
+}
+
+/// Paths such as `role toggle role` that the definitions declare but
+/// `registered` lacks or registers with a different option type.
+pub fn drift(registered: &[ApplicationCommand]) -> Vec<String> {
+    let mut missing = Vec::new();
+
+    for expected in definitions() {
+        match registered.iter().find(|c| c.name == expected.name) {
+            Some(command) => option_drift(
+                &expected.name,
+                &expected.options,
+                &command.options,
+                &mut missing,
+            ),
+            None => missing.push(expected.name),
+        }
+    }
+
+    missing
+}
+
+fn option_drift(
+    path: &str,
+    expected: &[CommandOptionDefinition],
+    registered: &[CommandOptionDefinition],
+    missing: &mut Vec<String>,
+) {
+    for option in expected {
+        let option_path = format!("{} {}", path, option.name);
+
+        match registered
+            .iter()
+            .find(|o| o.name == option.name && o.kind == option.kind)
+        {
+            Some(found) => option_drift(&option_path, &option.options, &found.options, missing),
+            None => missing.push(option_path),
+        }
+    }
+}
+
+/// Logs every command path the router handles that isn't registered with
+/// Discord, e.g. after deploying a new subcommand without re-registering.
+/// Failures to fetch the registered commands are logged and ignored.
+pub async fn log_registration_drift(
+    discord_api: &dyn DiscordApi,
+    application_id: &str,
+    guild_id: Option<&str>,
+) {
+    let registered = match discord_api
+        .fetch_application_commands(application_id, guild_id)
+        .await
+    {
+        Ok(registered) => registered,
+        Err(err) => {
+            warn!(
+                error = format!("{:#}", err),
+                "Failed to fetch registered commands"
+            );
+            return;
+        }
+    };
+
+    let missing = drift(&registered);
+
+    if missing.is_empty() {
+        info!("Registered commands match the handler");
+        return;
+    }
+
+    for path in missing {
+        warn!(
+            path,
+            "Handler expects a command option that isn't registered"
+        );
+    }
+}
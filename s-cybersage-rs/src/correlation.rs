@@ -0,0 +1,37 @@
+//! Per-invocation correlation reference, derived from the Lambda request ID,
+//! so a "ref" code quoted by a user can be matched to the invocation's logs.
+
+use std::future::Future;
+
+const REFERENCE_LEN: usize = 6;
+
+tokio::task_local! {
+    static REFERENCE: String;
+}
+
+/// Short form of a Lambda request ID: its first few alphanumeric characters.
+pub fn short_reference(request_id: &str) -> String {
+    request_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(REFERENCE_LEN)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Runs `future` with `request_id` available to `reference` and `tag`.
+pub async fn scope<F: Future>(request_id: &str, future: F) -> F::Output {
+    REFERENCE.scope(short_reference(request_id), future).await
+}
+
+pub fn reference() -> String {
+    REFERENCE
+        .try_with(|r| r.clone())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Appends the current reference to a user-facing failure message, e.g.
+/// `"Failed to modify role (ref: ab12cd)."`.
+pub fn tag(message: &str) -> String {
+    format!("{} (ref: {}).", message, reference())
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use chrono::Duration;
+use tracing::instrument;
+
+use crate::{
+    dal::model::audit_entry::{AuditEntry, AuditOutcome},
+    error::StorageError,
+};
+
+const AUDIT_KEY_PREFIX: &str = "AUDIT#";
+/// Items read per page when looking for one member's entries.
+const PAGE_SIZE: i32 = 100;
+/// Pages read before giving up on finding more of one member's entries.
+const MAX_PAGES: usize = 5;
+
+/// Command invocations, stored in the role table as
+/// `AUDIT#<unix_ms>#<interaction_id>` under the guild so they sort by time.
+/// Each expires via `ttl` after the retention period.
+#[derive(Clone)]
+pub struct AuditDao {
+    client: Client,
+    table_name: String,
+    retention_days: i64,
+}
+
+impl AuditDao {
+    pub fn new(client: Client, table_name: impl Into<String>, retention_days: i64) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+            retention_days,
+        }
+    }
+
+    #[instrument(skip(self, entry), fields(table = %self.table_name, command = %entry.command))]
+    pub async fn record(&self, guild_id: &str, entry: &AuditEntry) -> Result<()> {
+        let expires_at =
+            entry.occurred_at / 1000 + Duration::days(self.retention_days).num_seconds();
+
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("guild_id", AttributeValue::S(guild_id.to_string()))
+            .item(
+                "mapping_key",
+                AttributeValue::S(format!(
+                    "{}{:013}#{}",
+                    AUDIT_KEY_PREFIX, entry.occurred_at, entry.interaction_id
+                )),
+            )
+            .item("user_id", AttributeValue::S(entry.user_id.clone()))
+            .item("command", AttributeValue::S(entry.command.clone()))
+            .item(
+                "options_hash",
+                AttributeValue::S(entry.options_hash.clone()),
+            )
+            .item(
+                "outcome",
+                AttributeValue::S(entry.outcome.as_str().to_string()),
+            )
+            .item(
+                "duration_ms",
+                AttributeValue::N(entry.duration_ms.to_string()),
+            )
+            .item("ttl", AttributeValue::N(expires_at.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("put audit entry", err))?;
+
+        Ok(())
+    }
+
+    /// Up to `limit` of the guild's entries, newest first, optionally only
+    /// those of one member.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn recent(
+        &self,
+        guild_id: &str,
+        user_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<AuditEntry>> {
+        let mut entries = Vec::new();
+        let mut start_key = None;
+
+        for _ in 0..MAX_PAGES {
+            let mut query = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .key_condition_expression(
+                    "guild_id = :guild_id AND begins_with(mapping_key, :prefix)",
+                )
+                .expression_attribute_values(":guild_id", AttributeValue::S(guild_id.to_string()))
+                .expression_attribute_values(
+                    ":prefix",
+                    AttributeValue::S(AUDIT_KEY_PREFIX.to_string()),
+                )
+                .scan_index_forward(false)
+                .limit(PAGE_SIZE)
+                .set_exclusive_start_key(start_key);
+
+            if let Some(user_id) = user_id {
+                query = query
+                    .filter_expression("user_id = :user_id")
+                    .expression_attribute_values(
+                        ":user_id",
+                        AttributeValue::S(user_id.to_string()),
+                    );
+            }
+
+            let response = query
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("list audit entries", err))?;
+
+            entries.extend(
+                response
+                    .items
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(Self::entry),
+            );
+
+            start_key = response.last_evaluated_key;
+            if entries.len() >= limit || start_key.is_none() {
+                break;
+            }
+        }
+
+        entries.truncate(limit);
+
+        Ok(entries)
+    }
+
+    fn entry(item: &HashMap<String, AttributeValue>) -> Option<AuditEntry> {
+        let string = |name: &str| item.get(name)?.as_s().ok().cloned();
+
+        let key = string("mapping_key")?;
+        let (occurred_at, interaction_id) = key.strip_prefix(AUDIT_KEY_PREFIX)?.split_once('#')?;
+
+        Some(AuditEntry {
+            interaction_id: interaction_id.to_string(),
+            user_id: string("user_id")?,
+            command: string("command")?,
+            options_hash: string("options_hash").unwrap_or_default(),
+            outcome: AuditOutcome::from_key(&string("outcome")?)?,
+            duration_ms: item
+                .get("duration_ms")
+                .and_then(|v| v.as_n().ok())
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(0),
+            occurred_at: occurred_at.parse().ok()?,
+        })
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, Delete, Put, TransactWriteItem, Update},
+    Client,
+};
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+const BUNDLE_OWNER_PREFIX: &str = "USER#";
+const BUNDLE_KEY: &str = "BUNDLE";
+const BUNDLE_ATTACHMENT_PREFIX: &str = "BUNDLE#";
+const DEFAULT_MAX_GUILDS: u64 = 3;
+
+/// A user-owned subscription that can cover several guilds.
+///
+/// Stored in the subscriptions table under `USER#<owner_id>` / `BUNDLE`, with
+/// a `BUNDLE#<owner_id>` marker item in each attached guild's partition.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub owner_id: String,
+    pub status: String,
+    pub expires_at: i64,
+    pub max_guilds: u64,
+    pub guild_ids: Vec<String>,
+}
+
+impl Bundle {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.status == "active" && now <= self.expires_at
+    }
+
+    pub fn has_capacity(&self) -> bool {
+        (self.guild_ids.len() as u64) < self.max_guilds
+    }
+}
+
+#[derive(Clone)]
+pub struct BundleDao {
+    client: Client,
+    table_name: String,
+}
+
+impl BundleDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    fn owner_key(owner_id: &str) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            (
+                "guild_id".to_string(),
+                AttributeValue::S(format!("{}{}", BUNDLE_OWNER_PREFIX, owner_id)),
+            ),
+            (
+                "subscription_key".to_string(),
+                AttributeValue::S(BUNDLE_KEY.to_string()),
+            ),
+        ])
+    }
+
+    fn attachment_key(owner_id: &str, guild_id: &str) -> HashMap<String, AttributeValue> {
+        HashMap::from([
+            (
+                "guild_id".to_string(),
+                AttributeValue::S(guild_id.to_string()),
+            ),
+            (
+                "subscription_key".to_string(),
+                AttributeValue::S(format!("{}{}", BUNDLE_ATTACHMENT_PREFIX, owner_id)),
+            ),
+        ])
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_bundle(&self, owner_id: &str) -> Result<Option<Bundle>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .set_key(Some(Self::owner_key(owner_id)))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get bundle", err))?;
+
+        let item = match response.item {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        let status = item
+            .get("status")
+            .and_then(|v| v.as_s().ok())
+            .cloned()
+            .unwrap_or_else(|| "inactive".to_string());
+
+        let expires_at = item
+            .get("expires_at")
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse::<i64>().ok())
+            .unwrap_or(0);
+
+        let max_guilds = item
+            .get("max_guilds")
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_MAX_GUILDS);
+
+        let guild_ids = item
+            .get("guild_ids")
+            .and_then(|v| v.as_ss().ok())
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(Some(Bundle {
+            owner_id: owner_id.to_string(),
+            status,
+            expires_at,
+            max_guilds,
+            guild_ids,
+        }))
+    }
+
+    /// Returns the owner of the bundle covering `guild_id`, if any.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_attached_owner(&self, guild_id: &str) -> Result<Option<String>> {
+        let response = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .key_condition_expression(
+                "guild_id = :guild_id AND begins_with(subscription_key, :prefix)",
+            )
+            .expression_attribute_values(":guild_id", AttributeValue::S(guild_id.to_string()))
+            .expression_attribute_values(
+                ":prefix",
+                AttributeValue::S(BUNDLE_ATTACHMENT_PREFIX.to_string()),
+            )
+            .limit(1)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("query bundle attachment", err))?;
+
+        Ok(response
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .and_then(|item| {
+                item.get("subscription_key")?
+                    .as_s()
+                    .ok()?
+                    .strip_prefix(BUNDLE_ATTACHMENT_PREFIX)
+                    .map(|s| s.to_string())
+            }))
+    }
+
+    /// Atomically adds `guild_id` to the bundle and writes the guild's
+    /// attachment marker. Fails if the bundle filled up concurrently.
+    #[instrument(skip(self, bundle), fields(table = %self.table_name, owner_id = %bundle.owner_id))]
+    pub async fn attach(&self, bundle: &Bundle, guild_id: &str) -> Result<()> {
+        let update = Update::builder()
+            .table_name(&self.table_name)
+            .set_key(Some(Self::owner_key(&bundle.owner_id)))
+            .update_expression("ADD guild_ids :guild")
+            .condition_expression(
+                "attribute_not_exists(guild_ids) OR (size(guild_ids) < :max AND NOT contains(guild_ids, :guild_id))",
+            )
+            .expression_attribute_values(":guild", AttributeValue::Ss(vec![guild_id.to_string()]))
+            .expression_attribute_values(":guild_id", AttributeValue::S(guild_id.to_string()))
+            .expression_attribute_values(":max", AttributeValue::N(bundle.max_guilds.to_string()))
+            .build()
+            .context("Failed to build bundle update")?;
+
+        let put = Put::builder()
+            .table_name(&self.table_name)
+            .set_item(Some(Self::attachment_key(&bundle.owner_id, guild_id)))
+            .condition_expression("attribute_not_exists(guild_id)")
+            .build()
+            .context("Failed to build bundle attachment")?;
+
+        self.client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().update(update).build())
+            .transact_items(TransactWriteItem::builder().put(put).build())
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("attach guild to bundle", err))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn detach(&self, owner_id: &str, guild_id: &str) -> Result<()> {
+        let update = Update::builder()
+            .table_name(&self.table_name)
+            .set_key(Some(Self::owner_key(owner_id)))
+            .update_expression("DELETE guild_ids :guild")
+            .expression_attribute_values(":guild", AttributeValue::Ss(vec![guild_id.to_string()]))
+            .build()
+            .context("Failed to build bundle update")?;
+
+        let delete = Delete::builder()
+            .table_name(&self.table_name)
+            .set_key(Some(Self::attachment_key(owner_id, guild_id)))
+            .build()
+            .context("Failed to build bundle detachment")?;
+
+        self.client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().update(update).build())
+            .transact_items(TransactWriteItem::builder().delete(delete).build())
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("detach guild from bundle", err))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use once_cell::sync::Lazy;
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+const FAVORITE_CACHE_TTL: Duration = Duration::from_secs(60);
+const FAVORITE_CACHE_MAX_ENTRIES: usize = 1024;
+
+type FavoriteKey = (String, String, String);
+type FavoriteCache = Lazy<Mutex<HashMap<FavoriteKey, (Vec<String>, Instant)>>>;
+
+/// Favorites keyed by (table, guild, user). Read on every autocomplete
+/// keystroke and only changed by the user's own `/role favorite`, which
+/// refreshes this instance's entry.
+static FAVORITE_CACHE: FavoriteCache = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cache_key(table_name: &str, guild_id: &str, user_id: &str) -> FavoriteKey {
+    (
+        table_name.to_string(),
+        guild_id.to_string(),
+        user_id.to_string(),
+    )
+}
+
+fn cached_favorites(table_name: &str, guild_id: &str, user_id: &str) -> Option<Vec<String>> {
+    let cache = FAVORITE_CACHE.lock().ok()?;
+    let (role_ids, cached_at) = cache.get(&cache_key(table_name, guild_id, user_id))?;
+
+    if cached_at.elapsed() > FAVORITE_CACHE_TTL {
+        return None;
+    }
+
+    Some(role_ids.clone())
+}
+
+fn cache_favorites(table_name: &str, guild_id: &str, user_id: &str, role_ids: Vec<String>) {
+    if let Ok(mut cache) = FAVORITE_CACHE.lock() {
+        if cache.len() >= FAVORITE_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (_, cached_at)| cached_at.elapsed() <= FAVORITE_CACHE_TTL);
+        }
+
+        cache.insert(
+            cache_key(table_name, guild_id, user_id),
+            (role_ids, Instant::now()),
+        );
+    }
+}
+
+fn forget_favorites(table_name: &str, guild_id: &str, user_id: &str) {
+    if let Ok(mut cache) = FAVORITE_CACHE.lock() {
+        cache.remove(&cache_key(table_name, guild_id, user_id));
+    }
+}
+
+fn favorites_key(user_id: &str) -> AttributeValue {
+    AttributeValue::S(format!("USERFAV#{}", user_id))
+}
+
+/// Roles a member has marked with `/role favorite`, stored in the role table
+/// as `USERFAV#<user_id>` items with a `role_ids` string set.
+#[derive(Clone)]
+pub struct FavoriteDao {
+    client: Client,
+    table_name: String,
+}
+
+impl FavoriteDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    /// The user's favorite role IDs, sorted.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_favorites(&self, guild_id: &str, user_id: &str) -> Result<Vec<String>> {
+        if let Some(role_ids) = cached_favorites(&self.table_name, guild_id, user_id) {
+            return Ok(role_ids);
+        }
+
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key("mapping_key", favorites_key(user_id))
+            .projection_expression("role_ids")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get favorite roles", err))?;
+
+        let mut role_ids = response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("role_ids"))
+            .and_then(|v| v.as_ss().ok())
+            .cloned()
+            .unwrap_or_default();
+        role_ids.sort();
+
+        cache_favorites(&self.table_name, guild_id, user_id, role_ids.clone());
+
+        Ok(role_ids)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn add_favorite(&self, guild_id: &str, user_id: &str, role_id: &str) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key("mapping_key", favorites_key(user_id))
+            .update_expression("ADD role_ids :role")
+            .expression_attribute_values(":role", AttributeValue::Ss(vec![role_id.to_string()]))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("add favorite role", err))?;
+
+        forget_favorites(&self.table_name, guild_id, user_id);
+
+        Ok(())
+    }
+
+    /// Removing a role that isn't a favorite is not an error.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn remove_favorite(
+        &self,
+        guild_id: &str,
+        user_id: &str,
+        role_id: &str,
+    ) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key("mapping_key", favorites_key(user_id))
+            .update_expression("DELETE role_ids :role")
+            .expression_attribute_values(":role", AttributeValue::Ss(vec![role_id.to_string()]))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("remove favorite role", err))?;
+
+        forget_favorites(&self.table_name, guild_id, user_id);
+
+        Ok(())
+    }
+}
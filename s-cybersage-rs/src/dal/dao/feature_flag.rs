@@ -0,0 +1,139 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use once_cell::sync::Lazy;
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+/// Partition for bot-wide items; guild IDs are numeric snowflakes, so this
+/// can't collide with a real guild.
+pub const GLOBAL_PARTITION: &str = "GLOBAL";
+const FEATURE_FLAGS_KEY: &str = "FEATURE_FLAGS";
+const FLAGS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+type FlagOverrides = HashMap<String, bool>;
+
+/// Flag overrides shared across invocations on a warm Lambda, so a toggle
+/// takes effect within the TTL without a redeploy.
+static FLAGS_CACHE: Lazy<Mutex<Option<(FlagOverrides, Instant)>>> = Lazy::new(|| Mutex::new(None));
+
+fn cached_flags() -> Option<FlagOverrides> {
+    let cache = FLAGS_CACHE.lock().ok()?;
+    let (flags, cached_at) = cache.as_ref()?;
+
+    if cached_at.elapsed() > FLAGS_CACHE_TTL {
+        return None;
+    }
+
+    Some(flags.clone())
+}
+
+fn forget_flags() {
+    if let Ok(mut cache) = FLAGS_CACHE.lock() {
+        *cache = None;
+    }
+}
+
+fn cache_flags(flags: &FlagOverrides) {
+    if let Ok(mut cache) = FLAGS_CACHE.lock() {
+        *cache = Some((flags.clone(), Instant::now()));
+    }
+}
+
+/// Reads and writes the `GLOBAL`/`FEATURE_FLAGS` item of the role mappings table, whose
+/// `flags` map attribute holds boolean overrides keyed by flag name.
+pub struct FeatureFlagDao {
+    client: Client,
+    table_name: String,
+}
+
+impl FeatureFlagDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    pub async fn get_flags(&self) -> Result<FlagOverrides> {
+        if let Some(flags) = cached_flags() {
+            return Ok(flags);
+        }
+
+        let flags = self.fetch_flags().await?;
+        cache_flags(&flags);
+
+        Ok(flags)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn fetch_flags(&self) -> Result<FlagOverrides> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(GLOBAL_PARTITION.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(FEATURE_FLAGS_KEY.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get feature flags", err))?;
+
+        let flags = response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("flags"))
+            .and_then(|v| v.as_m().ok())
+            .map(|flags| {
+                flags
+                    .iter()
+                    .filter_map(|(name, value)| Some((name.clone(), *value.as_bool().ok()?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(flags)
+    }
+
+    /// Overrides one flag, keeping the others. Other warm functions pick it
+    /// up once their cache expires.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn set_flag(&self, name: &str, enabled: bool) -> Result<()> {
+        let mut flags = self.fetch_flags().await?;
+        flags.insert(name.to_string(), enabled);
+
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(GLOBAL_PARTITION.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(FEATURE_FLAGS_KEY.to_string()),
+            )
+            .update_expression("SET flags = :flags")
+            .expression_attribute_values(
+                ":flags",
+                AttributeValue::M(
+                    flags
+                        .into_iter()
+                        .map(|(name, value)| (name, AttributeValue::Bool(value)))
+                        .collect(),
+                ),
+            )
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("set feature flag", err))?;
+
+        forget_flags();
+
+        Ok(())
+    }
+}
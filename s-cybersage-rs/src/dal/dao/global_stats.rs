@@ -0,0 +1,193 @@
+use anyhow::Result;
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, ReturnValue},
+    Client,
+};
+use tracing::instrument;
+
+use crate::{
+    dal::model::daily_stats::{DailyStats, GuildDayStats},
+    error::StorageError,
+};
+
+use super::{feature_flag::GLOBAL_PARTITION, guild::GUILD_CONFIG_KEY};
+
+pub(crate) const STATS_KEY_PREFIX: &str = "STATS#";
+
+/// Reads and writes the daily `GLOBAL`/`STATS#<date>` items of the role
+/// mappings table, and the per-guild toggle baseline they're computed from.
+pub struct GlobalStatsDao {
+    client: Client,
+    table_name: String,
+}
+
+impl GlobalStatsDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    /// The guild's registered roles and their summed toggle counts.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn guild_totals(&self, guild_id: &str) -> Result<(u64, u64)> {
+        let mut roles = 0;
+        let mut toggles = 0;
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .key_condition_expression(
+                    "guild_id = :guild_id AND begins_with(mapping_key, :prefix)",
+                )
+                .projection_expression("toggle_count")
+                .expression_attribute_values(":guild_id", AttributeValue::S(guild_id.to_string()))
+                .expression_attribute_values(":prefix", AttributeValue::S("ROLE#".to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("sum role toggles", err))?;
+
+            for item in response.items.unwrap_or_default() {
+                roles += 1;
+                toggles += number(item.get("toggle_count"));
+            }
+
+            start_key = response.last_evaluated_key;
+
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok((roles, toggles))
+    }
+
+    /// Records `toggles` as the guild's total as of `date` and returns the
+    /// total recorded before, so the difference is the day's toggles. A guild
+    /// aggregated for the first time starts from `toggles`. Returns `None` if
+    /// the guild was already aggregated for `date`, so a redelivered task
+    /// isn't counted twice.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn advance_baseline(
+        &self,
+        guild_id: &str,
+        date: &str,
+        toggles: u64,
+    ) -> Result<Option<u64>> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .update_expression("SET aggregated_toggles = :toggles, aggregated_date = :date")
+            .condition_expression(
+                "attribute_not_exists(aggregated_date) OR aggregated_date < :date",
+            )
+            .expression_attribute_values(":toggles", AttributeValue::N(toggles.to_string()))
+            .expression_attribute_values(":date", AttributeValue::S(date.to_string()))
+            .return_values(ReturnValue::UpdatedOld)
+            .send()
+            .await;
+
+        let response =
+            match result.map_err(|err| StorageError::from_sdk("advance stats baseline", err)) {
+                Ok(response) => response,
+                Err(StorageError::ConditionFailed { .. }) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+
+        let previous = response
+            .attributes
+            .as_ref()
+            .and_then(|attrs| attrs.get("aggregated_toggles"))
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse::<u64>().ok())
+            .unwrap_or(toggles);
+
+        Ok(Some(previous))
+    }
+
+    /// Adds one guild's figures to the day's totals.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn add_guild(&self, date: &str, stats: &GuildDayStats) -> Result<()> {
+        let count = |n: u64| AttributeValue::N(n.to_string());
+
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(GLOBAL_PARTITION.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("{}{}", STATS_KEY_PREFIX, date)),
+            )
+            .update_expression(
+                "ADD guilds :one, active_guilds :active, #roles :roles, toggles :toggles, \
+                 premium_guilds :premium",
+            )
+            .expression_attribute_names("#roles", "roles")
+            .expression_attribute_values(":one", count(1))
+            .expression_attribute_values(":active", count(u64::from(stats.toggles > 0)))
+            .expression_attribute_values(":roles", count(stats.roles))
+            .expression_attribute_values(":toggles", count(stats.toggles))
+            .expression_attribute_values(":premium", count(u64::from(stats.premium)))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("add guild stats", err))?;
+
+        Ok(())
+    }
+
+    /// The most recent `days` days of stats, newest first.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn recent(&self, days: i32) -> Result<Vec<DailyStats>> {
+        let response = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .key_condition_expression("guild_id = :global AND begins_with(mapping_key, :prefix)")
+            .expression_attribute_values(":global", AttributeValue::S(GLOBAL_PARTITION.to_string()))
+            .expression_attribute_values(":prefix", AttributeValue::S(STATS_KEY_PREFIX.to_string()))
+            .scan_index_forward(false)
+            .limit(days)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("list daily stats", err))?;
+
+        Ok(response
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| {
+                Some(DailyStats {
+                    date: item
+                        .get("mapping_key")?
+                        .as_s()
+                        .ok()?
+                        .strip_prefix(STATS_KEY_PREFIX)?
+                        .to_string(),
+                    guilds: number(item.get("guilds")),
+                    active_guilds: number(item.get("active_guilds")),
+                    roles: number(item.get("roles")),
+                    toggles: number(item.get("toggles")),
+                    premium_guilds: number(item.get("premium_guilds")),
+                })
+            })
+            .collect())
+    }
+}
+
+fn number(value: Option<&AttributeValue>) -> u64 {
+    value
+        .and_then(|v| v.as_n().ok())
+        .and_then(|n| n.parse::<u64>().ok())
+        .unwrap_or(0)
+}
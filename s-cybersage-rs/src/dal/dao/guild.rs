@@ -1,5 +1,180 @@
-use anyhow::{Context, Result};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
 use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use once_cell::sync::Lazy;
+use tracing::instrument;
+
+use crate::{
+    dal::model::{age_requirement::AgeRequirement, role_icon::RoleIcon},
+    error::StorageError,
+};
+
+use super::role_store::RoleStore;
+
+/// Autocomplete returns at most this many choices.
+const MAX_PREFIX_RESULTS: usize = 25;
+/// Roles matching a prefix that are read and ranked by toggle count before
+/// the top `MAX_PREFIX_RESULTS` are kept. A shorter result is every role
+/// matching the prefix.
+const MAX_RANKED_CANDIDATES: usize = 100;
+const PREFIX_CACHE_TTL: Duration = Duration::from_secs(5);
+const PREFIX_CACHE_MAX_ENTRIES: usize = 1024;
+
+type Roles = Vec<(String, String)>;
+/// `(name, id, toggle count)`, in name order.
+type Candidates = Vec<(String, String, u64)>;
+type PrefixKey = (String, String, String);
+type TtlCache<K, V> = Lazy<Mutex<HashMap<K, (V, Instant)>>>;
+
+/// Prefix query results keyed by (table, guild, normalized prefix). Someone typing
+/// a role name sends an autocomplete interaction per keystroke, so the same
+/// and overlapping prefixes arrive within a few seconds of each other.
+static PREFIX_CACHE: TtlCache<PrefixKey, Candidates> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The cached candidates for `prefix`, or for a shorter prefix whose result
+/// was complete, filtered down to the ones matching `prefix`.
+fn cached_prefix(table_name: &str, guild_id: &str, prefix: &str) -> Option<Candidates> {
+    let cache = PREFIX_CACHE.lock().ok()?;
+
+    let fresh = |prefix: &str| {
+        cache
+            .get(&(
+                table_name.to_string(),
+                guild_id.to_string(),
+                prefix.to_string(),
+            ))
+            .filter(|(_, cached_at)| cached_at.elapsed() <= PREFIX_CACHE_TTL)
+            .map(|(roles, _)| roles)
+    };
+
+    if let Some(roles) = fresh(prefix) {
+        return Some(roles.clone());
+    }
+
+    prefix.char_indices().rev().skip(1).find_map(|(end, c)| {
+        let roles = fresh(&prefix[..end + c.len_utf8()])?;
+
+        (roles.len() < MAX_RANKED_CANDIDATES).then(|| {
+            roles
+                .iter()
+                .filter(|(name, _, _)| name.to_lowercase().starts_with(prefix))
+                .cloned()
+                .collect()
+        })
+    })
+}
+
+fn cache_prefix(table_name: &str, guild_id: &str, prefix: &str, roles: &Candidates) {
+    if let Ok(mut cache) = PREFIX_CACHE.lock() {
+        if cache.len() >= PREFIX_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (_, cached_at)| cached_at.elapsed() <= PREFIX_CACHE_TTL);
+        }
+
+        if cache.len() < PREFIX_CACHE_MAX_ENTRIES {
+            cache.insert(
+                (
+                    table_name.to_string(),
+                    guild_id.to_string(),
+                    prefix.to_string(),
+                ),
+                (roles.clone(), Instant::now()),
+            );
+        }
+    }
+}
+
+/// `favorites` first, then the most-toggled, keeping name order between
+/// roles toggled equally often.
+fn rank(mut candidates: Candidates, favorites: &[String]) -> Roles {
+    candidates.sort_by_key(|(_, id, toggles)| (!favorites.contains(id), Reverse(*toggles)));
+
+    candidates
+        .into_iter()
+        .take(MAX_PREFIX_RESULTS)
+        .map(|(name, id, _)| (name, id))
+        .collect()
+}
+
+/// Drops a guild's cached prefixes after its roles change, so this instance
+/// doesn't suggest stale names for the rest of the TTL.
+fn forget_guild(table_name: &str, guild_id: &str) {
+    if let Ok(mut cache) = PREFIX_CACHE.lock() {
+        cache.retain(|(table, guild, _), _| table != table_name || guild != guild_id);
+    }
+}
+
+/// Sort key of the per-guild configuration item in the role mappings table.
+pub(crate) const GUILD_CONFIG_KEY: &str = "CONFIG";
+const GUILD_CONFIG_CACHE_TTL: Duration = Duration::from_secs(60);
+
+type GuildKey = (String, String);
+
+/// Per-guild autocomplete minimum lengths keyed by (table, guild). Read on
+/// every autocomplete keystroke but rarely changed, so a stale value for up
+/// to the TTL on other instances is acceptable.
+static MIN_LENGTH_CACHE: TtlCache<GuildKey, Option<u32>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cached_min_length(table_name: &str, guild_id: &str) -> Option<Option<u32>> {
+    let cache = MIN_LENGTH_CACHE.lock().ok()?;
+    let (length, cached_at) = cache.get(&(table_name.to_string(), guild_id.to_string()))?;
+
+    if cached_at.elapsed() > GUILD_CONFIG_CACHE_TTL {
+        return None;
+    }
+
+    Some(*length)
+}
+
+fn cache_min_length(table_name: &str, guild_id: &str, length: Option<u32>) {
+    if let Ok(mut cache) = MIN_LENGTH_CACHE.lock() {
+        if cache.len() >= PREFIX_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (_, cached_at)| cached_at.elapsed() <= GUILD_CONFIG_CACHE_TTL);
+        }
+
+        cache.insert(
+            (table_name.to_string(), guild_id.to_string()),
+            (length, Instant::now()),
+        );
+    }
+}
+
+type MemberCounts = HashMap<String, u64>;
+
+/// Per-guild member counts keyed by (table, guild). Shown on every
+/// autocomplete keystroke and only rewritten by the scheduled count.
+static MEMBER_COUNT_CACHE: TtlCache<GuildKey, MemberCounts> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cached_member_counts(table_name: &str, guild_id: &str) -> Option<MemberCounts> {
+    let cache = MEMBER_COUNT_CACHE.lock().ok()?;
+    let (counts, cached_at) = cache.get(&(table_name.to_string(), guild_id.to_string()))?;
+
+    if cached_at.elapsed() > GUILD_CONFIG_CACHE_TTL {
+        return None;
+    }
+
+    Some(counts.clone())
+}
+
+fn cache_member_counts(table_name: &str, guild_id: &str, counts: MemberCounts) {
+    if let Ok(mut cache) = MEMBER_COUNT_CACHE.lock() {
+        if cache.len() >= PREFIX_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (_, cached_at)| cached_at.elapsed() <= GUILD_CONFIG_CACHE_TTL);
+        }
+
+        cache.insert(
+            (table_name.to_string(), guild_id.to_string()),
+            (counts, Instant::now()),
+        );
+    }
+}
 
 pub struct GuildDao {
     client: Client,
@@ -14,7 +189,81 @@ impl GuildDao {
         }
     }
 
-    pub async fn get_role_by_id(
+    async fn put_role(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        role_name: &str,
+        icon: &RoleIcon,
+    ) -> Result<()> {
+        let mut values = HashMap::from([
+            (
+                ":role_id".to_string(),
+                AttributeValue::S(role_id.to_string()),
+            ),
+            (
+                ":role_name".to_string(),
+                AttributeValue::S(role_name.to_string()),
+            ),
+            (
+                ":role_name_normalized".to_string(),
+                AttributeValue::S(role_name.to_lowercase()),
+            ),
+        ]);
+
+        let mut set = vec![
+            "role_id = :role_id",
+            "role_name = :role_name",
+            "role_name_normalized = :role_name_normalized",
+        ];
+        let mut remove = vec![];
+
+        // Updated rather than replaced, so settings such as prerequisites
+        // survive a re-save, while an icon removed in Discord is dropped.
+        match &icon.icon {
+            Some(hash) => {
+                set.push("role_icon = :role_icon");
+                values.insert(":role_icon".to_string(), AttributeValue::S(hash.clone()));
+            }
+            None => remove.push("role_icon"),
+        }
+        match &icon.unicode_emoji {
+            Some(emoji) => {
+                set.push("role_emoji = :role_emoji");
+                values.insert(":role_emoji".to_string(), AttributeValue::S(emoji.clone()));
+            }
+            None => remove.push("role_emoji"),
+        }
+
+        let mut update_expression = format!("SET {}", set.join(", "));
+        if !remove.is_empty() {
+            update_expression.push_str(&format!(" REMOVE {}", remove.join(", ")));
+        }
+
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("ROLE#{}", role_id)),
+            )
+            .update_expression(update_expression)
+            .set_expression_attribute_values(Some(values))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("save role", err))?;
+
+        forget_guild(&self.table_name, guild_id);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RoleStore for GuildDao {
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn get_role_by_id(
         &self,
         guild_id: &str,
         role_id: &str,
@@ -30,7 +279,7 @@ impl GuildDao {
             )
             .send()
             .await
-            .context("Failed to get role by ID")?;
+            .map_err(|err| StorageError::from_sdk("get role by ID", err))?;
 
         if let Some(item) = response.item {
             let role_name = item
@@ -51,10 +300,12 @@ impl GuildDao {
         Ok(None)
     }
 
-    pub async fn query_roles_by_prefix(
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn query_roles_by_prefix(
         &self,
         guild_id: &str,
         prefix: &str,
+        favorites: &[String],
     ) -> Result<Vec<(String, String)>> {
         if prefix.trim().is_empty() {
             return Ok(vec![]);
@@ -62,6 +313,10 @@ impl GuildDao {
 
         let normalized_prefix = prefix.to_lowercase();
 
+        if let Some(candidates) = cached_prefix(&self.table_name, guild_id, &normalized_prefix) {
+            return Ok(rank(candidates, favorites));
+        }
+
         let response = self
             .client
             .query()
@@ -71,48 +326,71 @@ impl GuildDao {
                 "guild_id = :guild_id AND begins_with(role_name_normalized, :prefix)",
             )
             .expression_attribute_values(":guild_id", AttributeValue::S(guild_id.to_string()))
-            .expression_attribute_values(":prefix", AttributeValue::S(normalized_prefix))
-            .limit(25)
+            .expression_attribute_values(":prefix", AttributeValue::S(normalized_prefix.clone()))
+            .limit(MAX_RANKED_CANDIDATES as i32)
             .send()
             .await
-            .context("Failed to query roles by prefix")?;
+            .map_err(|err| StorageError::from_sdk("query roles by prefix", err))?;
 
-        let roles = response
+        let candidates: Candidates = response
             .items
             .unwrap_or_default()
             .into_iter()
             .filter_map(|item| {
                 let role_name = item.get("role_name")?.as_s().ok()?.to_string();
                 let role_id = item.get("role_id")?.as_s().ok()?.to_string();
-                Some((role_name, role_id))
+                let toggles = item
+                    .get("toggle_count")
+                    .and_then(|v| v.as_n().ok())
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or_default();
+                Some((role_name, role_id, toggles))
             })
             .collect();
 
-        Ok(roles)
+        cache_prefix(&self.table_name, guild_id, &normalized_prefix, &candidates);
+
+        Ok(rank(candidates, favorites))
     }
 
-    pub async fn save_role(&self, guild_id: &str, role_id: &str, role_name: &str) -> Result<()> {
-        let normalized_name = role_name.to_lowercase();
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn save_role(&self, guild_id: &str, role_id: &str, role_name: &str) -> Result<()> {
+        self.put_role(guild_id, role_id, role_name, &RoleIcon::default())
+            .await
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn save_role_with_icon(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        role_name: &str,
+        icon: &RoleIcon,
+    ) -> Result<()> {
+        self.put_role(guild_id, role_id, role_name, icon).await
+    }
 
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn delete_role(&self, guild_id: &str, role_id: &str) -> Result<()> {
         self.client
-            .put_item()
+            .delete_item()
             .table_name(&self.table_name)
-            .item("guild_id", AttributeValue::S(guild_id.to_string()))
-            .item(
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
                 "mapping_key",
                 AttributeValue::S(format!("ROLE#{}", role_id)),
             )
-            .item("role_id", AttributeValue::S(role_id.to_string()))
-            .item("role_name", AttributeValue::S(role_name.to_string()))
-            .item("role_name_normalized", AttributeValue::S(normalized_name))
             .send()
             .await
-            .context("Failed to save role")?;
+            .map_err(|err| StorageError::from_sdk("delete role", err))?;
+
+        forget_guild(&self.table_name, guild_id);
 
         Ok(())
     }
 
-    pub async fn get_role_by_name(
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn get_role_by_name(
         &self,
         guild_id: &str,
         role_name: &str,
@@ -130,7 +408,7 @@ impl GuildDao {
             .limit(1)
             .send()
             .await
-            .context("Failed to query role by name")?;
+            .map_err(|err| StorageError::from_sdk("query role by name", err))?;
 
         if let Some(mut items) = response.items {
             if let Some(item) = items.pop() {
@@ -152,4 +430,528 @@ impl GuildDao {
 
         Ok(None)
     }
+
+    /// Returns every role mapping for the guild as `(name, id)` pairs,
+    /// following pagination.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn list_roles(&self, guild_id: &str) -> Result<Vec<(String, String)>> {
+        let mut roles = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .key_condition_expression(
+                    "guild_id = :guild_id AND begins_with(mapping_key, :prefix)",
+                )
+                .expression_attribute_values(":guild_id", AttributeValue::S(guild_id.to_string()))
+                .expression_attribute_values(":prefix", AttributeValue::S("ROLE#".to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("list roles", err))?;
+
+            roles.extend(
+                response
+                    .items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|item| {
+                        let role_name = item.get("role_name")?.as_s().ok()?.to_string();
+                        let role_id = item.get("role_id")?.as_s().ok()?.to_string();
+                        Some((role_name, role_id))
+                    }),
+            );
+
+            start_key = response.last_evaluated_key;
+
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(roles)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn get_prerequisites(&self, guild_id: &str, role_id: &str) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("ROLE#{}", role_id)),
+            )
+            .projection_expression("required_role_ids")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get role prerequisites", err))?;
+
+        let mut required = response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("required_role_ids"))
+            .and_then(|v| v.as_ss().ok())
+            .cloned()
+            .unwrap_or_default();
+        required.sort();
+
+        Ok(required)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn set_prerequisites(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        required_role_ids: &[String],
+    ) -> Result<()> {
+        let request = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("ROLE#{}", role_id)),
+            )
+            .condition_expression("attribute_exists(mapping_key)");
+
+        // DynamoDB has no empty sets, so clearing removes the attribute.
+        let request = if required_role_ids.is_empty() {
+            request.update_expression("REMOVE required_role_ids")
+        } else {
+            request
+                .update_expression("SET required_role_ids = :required")
+                .expression_attribute_values(
+                    ":required",
+                    AttributeValue::Ss(required_role_ids.to_vec()),
+                )
+        };
+
+        request
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("set role prerequisites", err))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn get_allowed_channels(&self, guild_id: &str, role_id: &str) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("ROLE#{}", role_id)),
+            )
+            .projection_expression("allowed_channel_ids")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get role channels", err))?;
+
+        let mut channels = response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("allowed_channel_ids"))
+            .and_then(|v| v.as_ss().ok())
+            .cloned()
+            .unwrap_or_default();
+        channels.sort();
+
+        Ok(channels)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn set_allowed_channels(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        channel_ids: &[String],
+    ) -> Result<()> {
+        let request = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("ROLE#{}", role_id)),
+            )
+            .condition_expression("attribute_exists(mapping_key)");
+
+        let request = if channel_ids.is_empty() {
+            request.update_expression("REMOVE allowed_channel_ids")
+        } else {
+            request
+                .update_expression("SET allowed_channel_ids = :channels")
+                .expression_attribute_values(":channels", AttributeValue::Ss(channel_ids.to_vec()))
+        };
+
+        request
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("set role channels", err))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn increment_toggle_count(&self, guild_id: &str, role_id: &str) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("ROLE#{}", role_id)),
+            )
+            .update_expression("ADD toggle_count :one")
+            .condition_expression("attribute_exists(mapping_key)")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("record role toggle", err))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn list_role_icons(&self, guild_id: &str) -> Result<HashMap<String, RoleIcon>> {
+        let mut icons = HashMap::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .key_condition_expression(
+                    "guild_id = :guild_id AND begins_with(mapping_key, :prefix)",
+                )
+                .filter_expression("attribute_exists(role_icon) OR attribute_exists(role_emoji)")
+                .projection_expression("role_id, role_icon, role_emoji")
+                .expression_attribute_values(":guild_id", AttributeValue::S(guild_id.to_string()))
+                .expression_attribute_values(":prefix", AttributeValue::S("ROLE#".to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("list role icons", err))?;
+
+            icons.extend(
+                response
+                    .items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|item| {
+                        let string = |name: &str| item.get(name)?.as_s().ok().cloned();
+                        let icon = RoleIcon {
+                            icon: string("role_icon"),
+                            unicode_emoji: string("role_emoji"),
+                        };
+
+                        Some((string("role_id")?, icon))
+                    }),
+            );
+
+            start_key = response.last_evaluated_key;
+
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(icons)
+    }
+
+    /// Scans the whole table, so it's only for scheduled jobs.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn list_guilds(&self) -> Result<Vec<String>> {
+        let mut guilds = BTreeSet::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("begins_with(mapping_key, :prefix)")
+                .projection_expression("guild_id")
+                .expression_attribute_values(":prefix", AttributeValue::S("ROLE#".to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("list guilds", err))?;
+
+            guilds.extend(
+                response
+                    .items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|item| Some(item.get("guild_id")?.as_s().ok()?.to_string())),
+            );
+
+            start_key = response.last_evaluated_key;
+
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(guilds.into_iter().collect())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn get_autocomplete_min_length(&self, guild_id: &str) -> Result<Option<u32>> {
+        if let Some(length) = cached_min_length(&self.table_name, guild_id) {
+            return Ok(length);
+        }
+
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get guild config", err))?;
+
+        let length = response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("autocomplete_min_length"))
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse().ok());
+
+        cache_min_length(&self.table_name, guild_id, length);
+
+        Ok(length)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn set_autocomplete_min_length(&self, guild_id: &str, length: u32) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .update_expression("SET autocomplete_min_length = :length")
+            .expression_attribute_values(":length", AttributeValue::N(length.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("update guild config", err))?;
+
+        cache_min_length(&self.table_name, guild_id, Some(length));
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn get_role_limit(&self, guild_id: &str) -> Result<Option<u32>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .projection_expression("role_limit")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get role limit", err))?;
+
+        Ok(response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("role_limit"))
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse().ok()))
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn set_role_limit(&self, guild_id: &str, limit: Option<u32>) -> Result<()> {
+        let request = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            );
+
+        let request = match limit {
+            Some(limit) => request
+                .update_expression("SET role_limit = :limit")
+                .expression_attribute_values(":limit", AttributeValue::N(limit.to_string())),
+            None => request.update_expression("REMOVE role_limit"),
+        };
+
+        request
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("set role limit", err))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn get_age_requirement(&self, guild_id: &str) -> Result<Option<AgeRequirement>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .projection_expression("min_age_hours, min_age_account")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get age requirement", err))?;
+
+        let Some(item) = response.item else {
+            return Ok(None);
+        };
+
+        Ok(item
+            .get("min_age_hours")
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse().ok())
+            .map(|hours| AgeRequirement {
+                hours,
+                account: item
+                    .get("min_age_account")
+                    .and_then(|v| v.as_bool().ok())
+                    .copied()
+                    .unwrap_or(false),
+            }))
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn set_age_requirement(
+        &self,
+        guild_id: &str,
+        requirement: Option<AgeRequirement>,
+    ) -> Result<()> {
+        let request = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            );
+
+        let request = match requirement {
+            Some(requirement) => request
+                .update_expression("SET min_age_hours = :hours, min_age_account = :account")
+                .expression_attribute_values(
+                    ":hours",
+                    AttributeValue::N(requirement.hours.to_string()),
+                )
+                .expression_attribute_values(":account", AttributeValue::Bool(requirement.account)),
+            None => request.update_expression("REMOVE min_age_hours, min_age_account"),
+        };
+
+        request
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("set age requirement", err))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn get_member_counts(&self, guild_id: &str) -> Result<HashMap<String, u64>> {
+        if let Some(counts) = cached_member_counts(&self.table_name, guild_id) {
+            return Ok(counts);
+        }
+
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .projection_expression("member_counts")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get member counts", err))?;
+
+        let counts: MemberCounts = response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("member_counts"))
+            .and_then(|v| v.as_m().ok())
+            .map(|counts| {
+                counts
+                    .iter()
+                    .filter_map(|(role_id, n)| {
+                        Some((role_id.clone(), n.as_n().ok()?.parse().ok()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        cache_member_counts(&self.table_name, guild_id, counts.clone());
+
+        Ok(counts)
+    }
+
+    #[instrument(skip(self, counts), fields(table = %self.table_name, roles = counts.len()))]
+    async fn set_member_counts(&self, guild_id: &str, counts: &HashMap<String, u64>) -> Result<()> {
+        let request = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            );
+
+        let request = if counts.is_empty() {
+            request.update_expression("REMOVE member_counts")
+        } else {
+            let counts = counts
+                .iter()
+                .map(|(role_id, n)| (role_id.clone(), AttributeValue::N(n.to_string())))
+                .collect();
+
+            request
+                .update_expression("SET member_counts = :counts")
+                .expression_attribute_values(":counts", AttributeValue::M(counts))
+        };
+
+        request
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("set member counts", err))?;
+
+        cache_member_counts(&self.table_name, guild_id, counts.clone());
+
+        Ok(())
+    }
 }
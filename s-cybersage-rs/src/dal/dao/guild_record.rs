@@ -0,0 +1,213 @@
+use anyhow::Result;
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, Select},
+    Client,
+};
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+/// Sort key of the item recording when the bot was added to and removed from
+/// a guild.
+pub(crate) const GUILD_RECORD_KEY: &str = "GUILD";
+
+/// Reads and writes each guild's `GUILD` item. A guild that removed the bot
+/// keeps its data, tombstoned with `removed_at`, until the retention job
+/// purges it.
+#[derive(Clone)]
+pub struct GuildRecordDao {
+    client: Client,
+    table_name: String,
+}
+
+impl GuildRecordDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    /// Records an install, clearing any tombstone from an earlier removal.
+    /// `application_id` is the white-label application installed, or `None`
+    /// for the default bot.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn record_install(
+        &self,
+        guild_id: &str,
+        application_id: Option<&str>,
+        guild_name: Option<&str>,
+        installed_by: Option<&str>,
+        installed_at: i64,
+    ) -> Result<()> {
+        let mut update = "SET installed_at = :installed_at".to_string();
+        let mut request = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_RECORD_KEY.to_string()),
+            )
+            .expression_attribute_values(
+                ":installed_at",
+                AttributeValue::N(installed_at.to_string()),
+            );
+
+        if let Some(name) = guild_name {
+            update.push_str(", guild_name = :name");
+            request = request.expression_attribute_values(":name", AttributeValue::S(name.into()));
+        }
+        if let Some(user_id) = installed_by {
+            update.push_str(", installed_by = :user_id");
+            request =
+                request.expression_attribute_values(":user_id", AttributeValue::S(user_id.into()));
+        }
+        match application_id {
+            Some(application_id) => {
+                update.push_str(", application_id = :application_id REMOVE removed_at");
+                request = request.expression_attribute_values(
+                    ":application_id",
+                    AttributeValue::S(application_id.into()),
+                );
+            }
+            None => update.push_str(" REMOVE removed_at, application_id"),
+        }
+
+        request
+            .update_expression(update)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("record guild install", err))?;
+
+        Ok(())
+    }
+
+    /// Records that the guild uses white-label application `application_id`,
+    /// for guilds installed before installs recorded it.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn record_application(&self, guild_id: &str, application_id: &str) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_RECORD_KEY.to_string()),
+            )
+            .update_expression("SET application_id = :application_id")
+            .expression_attribute_values(
+                ":application_id",
+                AttributeValue::S(application_id.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("record guild application", err))?;
+
+        Ok(())
+    }
+
+    /// The white-label application the guild installed, or `None` for the
+    /// default bot and guilds with no record.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn application_id(&self, guild_id: &str) -> Result<Option<String>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_RECORD_KEY.to_string()),
+            )
+            .projection_expression("application_id")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get guild application", err))?;
+
+        Ok(response
+            .item
+            .and_then(|mut item| item.remove("application_id"))
+            .and_then(|value| value.as_s().ok().cloned()))
+    }
+
+    /// Marks the guild removed as of `removed_at`, keeping the earliest time
+    /// if it's reported more than once.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn tombstone(&self, guild_id: &str, removed_at: i64) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_RECORD_KEY.to_string()),
+            )
+            .update_expression("SET removed_at = if_not_exists(removed_at, :removed_at)")
+            .expression_attribute_values(":removed_at", AttributeValue::N(removed_at.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("tombstone guild", err))?;
+
+        Ok(())
+    }
+
+    /// How many guilds have a `GUILD` record without a tombstone, i.e. the
+    /// servers the bot is in as far as install events have told it.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn count_installed(&self) -> Result<usize> {
+        let mut count = 0;
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("mapping_key = :key AND attribute_not_exists(removed_at)")
+                .expression_attribute_values(
+                    ":key",
+                    AttributeValue::S(GUILD_RECORD_KEY.to_string()),
+                )
+                .select(Select::Count)
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("count installed guilds", err))?;
+
+            count += response.count as usize;
+            start_key = response.last_evaluated_key;
+
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Clears the tombstone of a guild the bot turns out to still be in.
+    /// Does nothing if there's none.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn clear_tombstone(&self, guild_id: &str) -> Result<()> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_RECORD_KEY.to_string()),
+            )
+            .update_expression("REMOVE removed_at")
+            .condition_expression("attribute_exists(removed_at)")
+            .send()
+            .await;
+
+        match result.map_err(|err| StorageError::from_sdk("clear guild tombstone", err)) {
+            Ok(_) | Err(StorageError::ConditionFailed { .. }) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use aws_sdk_dynamodb::Client;
+
+use super::{
+    audit::AuditDao, favorite::FavoriteDao, idempotency::IdempotencyDao,
+    log_channel::LogChannelDao, role_menu::RoleMenuDao, webhook::WebhookDao,
+};
+
+/// The per-guild settings, menus, favorites, command audit and interaction claims kept in the role table
+/// alongside the roles, grouped so routers take one dependency instead of one per store.
+#[derive(Clone)]
+pub struct GuildStores {
+    pub webhooks: WebhookDao,
+    pub log_channels: LogChannelDao,
+    pub role_menus: RoleMenuDao,
+    pub favorites: FavoriteDao,
+    pub audit: AuditDao,
+    pub idempotency: IdempotencyDao,
+}
+
+impl GuildStores {
+    pub fn new(client: Client, table_name: &str, audit_days: i64) -> Self {
+        Self {
+            webhooks: WebhookDao::new(client.clone(), table_name),
+            log_channels: LogChannelDao::new(client.clone(), table_name),
+            role_menus: RoleMenuDao::new(client.clone(), table_name),
+            favorites: FavoriteDao::new(client.clone(), table_name),
+            audit: AuditDao::new(client.clone(), table_name, audit_days),
+            idempotency: IdempotencyDao::new(client, table_name),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use chrono::Utc;
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+const IDEMPOTENCY_KEY_PREFIX: &str = "IDEMPOTENCY#";
+/// Discord only redelivers within the interaction token's 15 minutes, so
+/// claims can expire well after that.
+const CLAIM_TTL_SECONDS: i64 = 3_600;
+
+/// Claims on interactions whose writes have started, stored in the role
+/// table as `IDEMPOTENCY#<interaction_id>` under the guild, so a duplicate
+/// delivery of the same interaction can't apply its change twice. The `ttl`
+/// attribute lets DynamoDB delete them.
+#[derive(Clone)]
+pub struct IdempotencyDao {
+    client: Client,
+    table_name: String,
+}
+
+impl IdempotencyDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    /// Claims `interaction_id` with a conditional put. Returns `false` if
+    /// it was already claimed, i.e. this delivery is a duplicate.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn claim(&self, guild_id: &str, interaction_id: &str) -> Result<bool> {
+        let now = Utc::now().timestamp();
+
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("guild_id", AttributeValue::S(guild_id.to_string()))
+            .item(
+                "mapping_key",
+                AttributeValue::S(format!("{}{}", IDEMPOTENCY_KEY_PREFIX, interaction_id)),
+            )
+            .item("claimed_at", AttributeValue::N(now.to_string()))
+            .item(
+                "ttl",
+                AttributeValue::N((now + CLAIM_TTL_SECONDS).to_string()),
+            )
+            .condition_expression("attribute_not_exists(mapping_key)")
+            .send()
+            .await;
+
+        match result.map_err(|err| StorageError::from_sdk("claim interaction", err)) {
+            Ok(_) => Ok(true),
+            Err(StorageError::ConditionFailed { .. }) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Drops a claim whose write failed, so Discord's redelivery of the
+    /// interaction can try again instead of being answered as a duplicate.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn release(&self, guild_id: &str, interaction_id: &str) -> Result<()> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("{}{}", IDEMPOTENCY_KEY_PREFIX, interaction_id)),
+            )
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("release interaction", err))?;
+
+        Ok(())
+    }
+}
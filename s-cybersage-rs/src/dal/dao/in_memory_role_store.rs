@@ -0,0 +1,450 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+
+use crate::dal::model::{age_requirement::AgeRequirement, role_icon::RoleIcon};
+
+use super::role_store::RoleStore;
+
+const PREFIX_QUERY_LIMIT: usize = 25;
+const RANKED_CANDIDATE_LIMIT: usize = 100;
+
+/// `RoleStore` backed by a process-local map, for tests and local runs
+/// without DynamoDB. Mirrors `GuildDao`'s matching and ordering rules.
+#[derive(Debug, Default)]
+pub struct InMemoryRoleStore {
+    /// guild ID -> role ID -> role name
+    roles: Mutex<HashMap<String, BTreeMap<String, String>>>,
+    /// (guild ID, role ID) -> icon, for roles that have one
+    icons: Mutex<HashMap<(String, String), RoleIcon>>,
+    /// (guild ID, role ID) -> roles required to take it
+    prerequisites: Mutex<HashMap<(String, String), Vec<String>>>,
+    /// (guild ID, role ID) -> channels it may be toggled from
+    allowed_channels: Mutex<HashMap<(String, String), Vec<String>>>,
+    /// (guild ID, role ID) -> times toggled
+    toggle_counts: Mutex<HashMap<(String, String), u64>>,
+    /// guild ID -> autocomplete minimum prefix length
+    autocomplete_min_lengths: Mutex<HashMap<String, u32>>,
+    /// guild ID -> self-assignable roles a member may hold
+    role_limits: Mutex<HashMap<String, u32>>,
+    /// guild ID -> how long members must have been around to take roles
+    age_requirements: Mutex<HashMap<String, AgeRequirement>>,
+    /// guild ID -> role ID -> members holding it
+    member_counts: Mutex<HashMap<String, HashMap<String, u64>>>,
+}
+
+impl InMemoryRoleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a store pre-populated with `(guild_id, role_id, role_name)` rows.
+    pub fn with_roles<'a>(rows: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>) -> Self {
+        let store = Self::new();
+
+        if let Ok(mut roles) = store.roles.lock() {
+            for (guild_id, role_id, role_name) in rows {
+                roles
+                    .entry(guild_id.to_string())
+                    .or_default()
+                    .insert(role_id.to_string(), role_name.to_string());
+            }
+        }
+
+        store
+    }
+
+    fn with_guild<T>(
+        &self,
+        guild_id: &str,
+        f: impl FnOnce(&BTreeMap<String, String>) -> T,
+    ) -> Result<T> {
+        let roles = self
+            .roles
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        match roles.get(guild_id) {
+            Some(guild_roles) => Ok(f(guild_roles)),
+            None => Ok(f(&BTreeMap::new())),
+        }
+    }
+
+    /// Roles ordered by normalized name, matching the `GuildRoleNameIndex`
+    /// sort order.
+    fn sorted_by_name(roles: &BTreeMap<String, String>) -> Vec<(String, String)> {
+        let mut sorted: Vec<(String, String)> = roles
+            .iter()
+            .map(|(id, name)| (name.clone(), id.clone()))
+            .collect();
+        sorted.sort_by_key(|(name, _)| name.to_lowercase());
+        sorted
+    }
+}
+
+#[async_trait]
+impl RoleStore for InMemoryRoleStore {
+    async fn get_role_by_id(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+    ) -> Result<Option<(String, String)>> {
+        self.with_guild(guild_id, |roles| {
+            roles
+                .get(role_id)
+                .map(|name| (name.clone(), role_id.to_string()))
+        })
+    }
+
+    async fn query_roles_by_prefix(
+        &self,
+        guild_id: &str,
+        prefix: &str,
+        favorites: &[String],
+    ) -> Result<Vec<(String, String)>> {
+        if prefix.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let normalized_prefix = prefix.to_lowercase();
+
+        let candidates: Vec<(String, String)> = self.with_guild(guild_id, |roles| {
+            Self::sorted_by_name(roles)
+                .into_iter()
+                .filter(|(name, _)| name.to_lowercase().starts_with(&normalized_prefix))
+                .take(RANKED_CANDIDATE_LIMIT)
+                .collect()
+        })?;
+
+        let toggle_counts = self
+            .toggle_counts
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+        let toggles = |role_id: &str| {
+            toggle_counts
+                .get(&(guild_id.to_string(), role_id.to_string()))
+                .copied()
+                .unwrap_or_default()
+        };
+
+        let mut ranked = candidates;
+        ranked
+            .sort_by_key(|(_, role_id)| (!favorites.contains(role_id), Reverse(toggles(role_id))));
+        ranked.truncate(PREFIX_QUERY_LIMIT);
+
+        Ok(ranked)
+    }
+
+    async fn save_role(&self, guild_id: &str, role_id: &str, role_name: &str) -> Result<()> {
+        self.save_role_with_icon(guild_id, role_id, role_name, &RoleIcon::default())
+            .await
+    }
+
+    async fn save_role_with_icon(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        role_name: &str,
+        icon: &RoleIcon,
+    ) -> Result<()> {
+        let mut icons = self
+            .icons
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        let key = (guild_id.to_string(), role_id.to_string());
+        if icon.is_empty() {
+            icons.remove(&key);
+        } else {
+            icons.insert(key, icon.clone());
+        }
+
+        let mut roles = self
+            .roles
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        roles
+            .entry(guild_id.to_string())
+            .or_default()
+            .insert(role_id.to_string(), role_name.to_string());
+
+        Ok(())
+    }
+
+    async fn delete_role(&self, guild_id: &str, role_id: &str) -> Result<()> {
+        let mut roles = self
+            .roles
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        if let Some(guild_roles) = roles.get_mut(guild_id) {
+            guild_roles.remove(role_id);
+        }
+
+        // The rest lives on the mapping item in DynamoDB, so goes with it.
+        let key = (guild_id.to_string(), role_id.to_string());
+        if let Ok(mut icons) = self.icons.lock() {
+            icons.remove(&key);
+        }
+        if let Ok(mut prerequisites) = self.prerequisites.lock() {
+            prerequisites.remove(&key);
+        }
+        if let Ok(mut allowed_channels) = self.allowed_channels.lock() {
+            allowed_channels.remove(&key);
+        }
+        if let Ok(mut toggle_counts) = self.toggle_counts.lock() {
+            toggle_counts.remove(&key);
+        }
+
+        Ok(())
+    }
+
+    async fn get_role_by_name(
+        &self,
+        guild_id: &str,
+        role_name: &str,
+    ) -> Result<Option<(String, String)>> {
+        let normalized_name = role_name.to_lowercase();
+
+        self.with_guild(guild_id, |roles| {
+            Self::sorted_by_name(roles)
+                .into_iter()
+                .find(|(name, _)| name.to_lowercase() == normalized_name)
+        })
+    }
+
+    async fn list_roles(&self, guild_id: &str) -> Result<Vec<(String, String)>> {
+        self.with_guild(guild_id, |roles| {
+            roles
+                .iter()
+                .map(|(id, name)| (name.clone(), id.clone()))
+                .collect()
+        })
+    }
+
+    async fn get_prerequisites(&self, guild_id: &str, role_id: &str) -> Result<Vec<String>> {
+        let prerequisites = self
+            .prerequisites
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        let mut required = prerequisites
+            .get(&(guild_id.to_string(), role_id.to_string()))
+            .cloned()
+            .unwrap_or_default();
+        required.sort();
+
+        Ok(required)
+    }
+
+    async fn set_prerequisites(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        required_role_ids: &[String],
+    ) -> Result<()> {
+        if self.get_role_by_id(guild_id, role_id).await?.is_none() {
+            return Err(anyhow!("Role is not registered"));
+        }
+
+        let mut prerequisites = self
+            .prerequisites
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        let key = (guild_id.to_string(), role_id.to_string());
+        if required_role_ids.is_empty() {
+            prerequisites.remove(&key);
+        } else {
+            prerequisites.insert(key, required_role_ids.to_vec());
+        }
+
+        Ok(())
+    }
+
+    async fn get_allowed_channels(&self, guild_id: &str, role_id: &str) -> Result<Vec<String>> {
+        let allowed_channels = self
+            .allowed_channels
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        let mut channels = allowed_channels
+            .get(&(guild_id.to_string(), role_id.to_string()))
+            .cloned()
+            .unwrap_or_default();
+        channels.sort();
+
+        Ok(channels)
+    }
+
+    async fn set_allowed_channels(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        channel_ids: &[String],
+    ) -> Result<()> {
+        if self.get_role_by_id(guild_id, role_id).await?.is_none() {
+            return Err(anyhow!("Role is not registered"));
+        }
+
+        let mut allowed_channels = self
+            .allowed_channels
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        let key = (guild_id.to_string(), role_id.to_string());
+        if channel_ids.is_empty() {
+            allowed_channels.remove(&key);
+        } else {
+            allowed_channels.insert(key, channel_ids.to_vec());
+        }
+
+        Ok(())
+    }
+
+    async fn increment_toggle_count(&self, guild_id: &str, role_id: &str) -> Result<()> {
+        if self.get_role_by_id(guild_id, role_id).await?.is_none() {
+            return Err(anyhow!("Role is not registered"));
+        }
+
+        let mut toggle_counts = self
+            .toggle_counts
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        *toggle_counts
+            .entry((guild_id.to_string(), role_id.to_string()))
+            .or_default() += 1;
+
+        Ok(())
+    }
+
+    async fn list_role_icons(&self, guild_id: &str) -> Result<HashMap<String, RoleIcon>> {
+        let icons = self
+            .icons
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        Ok(icons
+            .iter()
+            .filter(|((guild, _), _)| guild == guild_id)
+            .map(|((_, role_id), icon)| (role_id.clone(), icon.clone()))
+            .collect())
+    }
+
+    async fn list_guilds(&self) -> Result<Vec<String>> {
+        let roles = self
+            .roles
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        let mut guilds: Vec<String> = roles
+            .iter()
+            .filter(|(_, guild_roles)| !guild_roles.is_empty())
+            .map(|(guild_id, _)| guild_id.clone())
+            .collect();
+        guilds.sort();
+
+        Ok(guilds)
+    }
+
+    async fn get_autocomplete_min_length(&self, guild_id: &str) -> Result<Option<u32>> {
+        let lengths = self
+            .autocomplete_min_lengths
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        Ok(lengths.get(guild_id).copied())
+    }
+
+    async fn set_autocomplete_min_length(&self, guild_id: &str, length: u32) -> Result<()> {
+        let mut lengths = self
+            .autocomplete_min_lengths
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        lengths.insert(guild_id.to_string(), length);
+
+        Ok(())
+    }
+
+    async fn get_role_limit(&self, guild_id: &str) -> Result<Option<u32>> {
+        let limits = self
+            .role_limits
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        Ok(limits.get(guild_id).copied())
+    }
+
+    async fn set_role_limit(&self, guild_id: &str, limit: Option<u32>) -> Result<()> {
+        let mut limits = self
+            .role_limits
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        match limit {
+            Some(limit) => limits.insert(guild_id.to_string(), limit),
+            None => limits.remove(guild_id),
+        };
+
+        Ok(())
+    }
+
+    async fn get_age_requirement(&self, guild_id: &str) -> Result<Option<AgeRequirement>> {
+        let requirements = self
+            .age_requirements
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        Ok(requirements.get(guild_id).copied())
+    }
+
+    async fn set_age_requirement(
+        &self,
+        guild_id: &str,
+        requirement: Option<AgeRequirement>,
+    ) -> Result<()> {
+        let mut requirements = self
+            .age_requirements
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        match requirement {
+            Some(requirement) => requirements.insert(guild_id.to_string(), requirement),
+            None => requirements.remove(guild_id),
+        };
+
+        Ok(())
+    }
+
+    async fn get_member_counts(&self, guild_id: &str) -> Result<HashMap<String, u64>> {
+        let counts = self
+            .member_counts
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        Ok(counts.get(guild_id).cloned().unwrap_or_default())
+    }
+
+    async fn set_member_counts(&self, guild_id: &str, counts: &HashMap<String, u64>) -> Result<()> {
+        let mut stored = self
+            .member_counts
+            .lock()
+            .map_err(|_| anyhow!("Role store lock poisoned"))?;
+
+        if counts.is_empty() {
+            stored.remove(guild_id);
+        } else {
+            stored.insert(guild_id.to_string(), counts.clone());
+        }
+
+        Ok(())
+    }
+}
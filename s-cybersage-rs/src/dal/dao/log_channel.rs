@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use once_cell::sync::Lazy;
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+use super::guild::GUILD_CONFIG_KEY;
+
+const LOG_CHANNEL_CACHE_TTL: Duration = Duration::from_secs(60);
+const LOG_CHANNEL_CACHE_MAX_ENTRIES: usize = 1024;
+
+type LogChannelCache = Lazy<Mutex<HashMap<(String, String), (Option<String>, Instant)>>>;
+
+/// Log channels keyed by (table, guild). Looked up for every role toggle, so
+/// other instances may keep logging to an old channel for up to the TTL.
+static LOG_CHANNEL_CACHE: LogChannelCache = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cached_log_channel(table_name: &str, guild_id: &str) -> Option<Option<String>> {
+    let cache = LOG_CHANNEL_CACHE.lock().ok()?;
+    let (channel_id, cached_at) = cache.get(&(table_name.to_string(), guild_id.to_string()))?;
+
+    if cached_at.elapsed() > LOG_CHANNEL_CACHE_TTL {
+        return None;
+    }
+
+    Some(channel_id.clone())
+}
+
+fn cache_log_channel(table_name: &str, guild_id: &str, channel_id: Option<String>) {
+    if let Ok(mut cache) = LOG_CHANNEL_CACHE.lock() {
+        if cache.len() >= LOG_CHANNEL_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (_, cached_at)| cached_at.elapsed() <= LOG_CHANNEL_CACHE_TTL);
+        }
+
+        cache.insert(
+            (table_name.to_string(), guild_id.to_string()),
+            (channel_id, Instant::now()),
+        );
+    }
+}
+
+/// The channel a guild's role activity is logged to, stored as
+/// `log_channel_id` on its `CONFIG` item in the role table.
+#[derive(Clone)]
+pub struct LogChannelDao {
+    client: Client,
+    table_name: String,
+}
+
+impl LogChannelDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_log_channel(&self, guild_id: &str) -> Result<Option<String>> {
+        if let Some(channel_id) = cached_log_channel(&self.table_name, guild_id) {
+            return Ok(channel_id);
+        }
+
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .projection_expression("log_channel_id")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get log channel", err))?;
+
+        let channel_id = response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("log_channel_id"))
+            .and_then(|v| v.as_s().ok())
+            .cloned();
+
+        cache_log_channel(&self.table_name, guild_id, channel_id.clone());
+
+        Ok(channel_id)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn set_log_channel(&self, guild_id: &str, channel_id: &str) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .update_expression("SET log_channel_id = :channel")
+            .expression_attribute_values(":channel", AttributeValue::S(channel_id.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("set log channel", err))?;
+
+        cache_log_channel(&self.table_name, guild_id, Some(channel_id.to_string()));
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn clear_log_channel(&self, guild_id: &str) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .update_expression("REMOVE log_channel_id")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("clear log channel", err))?;
+
+        cache_log_channel(&self.table_name, guild_id, None);
+
+        Ok(())
+    }
+}
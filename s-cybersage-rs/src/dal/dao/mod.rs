@@ -1,2 +1,26 @@
+pub mod audit;
+#[cfg(feature = "billing")]
+pub mod bundle;
+pub mod favorite;
+pub mod feature_flag;
+pub mod global_stats;
 pub mod guild;
-pub mod subscription;
\ No newline at end of file
+pub mod guild_record;
+pub mod guild_stores;
+pub mod idempotency;
+#[cfg(any(test, feature = "test-util"))]
+pub mod in_memory_role_store;
+pub mod log_channel;
+pub mod rate_limit;
+pub mod retention;
+pub mod role_connection;
+pub mod role_menu;
+pub mod role_store;
+pub mod session;
+#[cfg(feature = "billing")]
+pub mod settings;
+#[cfg(feature = "billing")]
+pub mod subscription;
+#[cfg(feature = "billing")]
+pub mod usage;
+pub mod webhook;
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, ReturnValue},
+    Client,
+};
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+const RATE_KEY_PREFIX: &str = "RATE#";
+
+/// Per-user, per-minute counters shared by every instance, stored in the
+/// role table as `RATE#<user_id>#<minute>` under the guild. The `ttl`
+/// attribute lets DynamoDB delete them once the minute is long past.
+pub struct RateLimitDao {
+    client: Client,
+    table_name: String,
+}
+
+impl RateLimitDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    /// Counts a hit in the user's counter for `minute` (Unix seconds / 60)
+    /// and returns the new count.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn hit(&self, guild_id: &str, user_id: &str, minute: i64) -> Result<u64> {
+        let response = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(format!("{}{}#{}", RATE_KEY_PREFIX, user_id, minute)),
+            )
+            .update_expression("ADD hits :one SET #ttl = :ttl")
+            .expression_attribute_names("#ttl", "ttl")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":ttl", AttributeValue::N(((minute + 2) * 60).to_string()))
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("count rate limit hit", err))?;
+
+        let hits = response
+            .attributes
+            .as_ref()
+            .and_then(|attrs| attrs.get("hits"))
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        Ok(hits)
+    }
+}
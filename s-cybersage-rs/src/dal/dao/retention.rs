@@ -0,0 +1,279 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{bail, Result};
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, DeleteRequest, WriteRequest},
+    Client,
+};
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+use super::{feature_flag::GLOBAL_PARTITION, guild_record::GUILD_RECORD_KEY};
+
+/// BatchWriteItem's limit.
+const MAX_BATCH_DELETES: usize = 25;
+/// Attempts at deleting a batch's unprocessed items before giving up.
+const MAX_BATCH_ATTEMPTS: u32 = 4;
+
+/// An item's primary key.
+pub type ItemKey = HashMap<String, AttributeValue>;
+
+/// Finds and deletes items past their retention in one table. The role and
+/// subscription tables share `guild_id` as partition key but name their sort
+/// keys differently, hence `sort_key`.
+pub struct RetentionDao {
+    client: Client,
+    table_name: String,
+    sort_key: &'static str,
+}
+
+impl RetentionDao {
+    pub fn new(client: Client, table_name: impl Into<String>, sort_key: &'static str) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+            sort_key,
+        }
+    }
+
+    /// Keys of up to `limit` `GLOBAL` items whose sort key starts with
+    /// `prefix` and sorts before `prefix` + `cutoff`, oldest first.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn global_keys_before(
+        &self,
+        prefix: &str,
+        cutoff: &str,
+        limit: usize,
+    ) -> Result<Vec<ItemKey>> {
+        let response = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .key_condition_expression("guild_id = :global AND #sk BETWEEN :from AND :to")
+            .expression_attribute_names("#sk", self.sort_key)
+            .expression_attribute_values(":global", AttributeValue::S(GLOBAL_PARTITION.to_string()))
+            .expression_attribute_values(":from", AttributeValue::S(prefix.to_string()))
+            .expression_attribute_values(":to", AttributeValue::S(format!("{}{}", prefix, cutoff)))
+            .projection_expression("guild_id, #sk")
+            .limit(limit as i32 + 1)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("find expired global items", err))?;
+
+        // BETWEEN includes `to` itself, which is kept.
+        let to = format!("{}{}", prefix, cutoff);
+        Ok(response
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|item| {
+                item.get(self.sort_key)
+                    .and_then(|v| v.as_s().ok())
+                    .is_some_and(|key| *key < to)
+            })
+            .take(limit)
+            .collect())
+    }
+
+    /// Keys of up to `limit` items in any guild whose sort key starts with
+    /// `prefix` and sorts before `prefix` + `cutoff`. Scans the table, so
+    /// it's only for the purge job.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn keys_before(
+        &self,
+        prefix: &str,
+        cutoff: &str,
+        limit: usize,
+    ) -> Result<Vec<ItemKey>> {
+        let mut keys = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("begins_with(#sk, :prefix) AND #sk < :to")
+                .expression_attribute_names("#sk", self.sort_key)
+                .expression_attribute_values(":prefix", AttributeValue::S(prefix.to_string()))
+                .expression_attribute_values(
+                    ":to",
+                    AttributeValue::S(format!("{}{}", prefix, cutoff)),
+                )
+                .projection_expression("guild_id, #sk")
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("find expired items", err))?;
+
+            keys.extend(response.items.unwrap_or_default());
+            start_key = response.last_evaluated_key;
+
+            if keys.len() >= limit || start_key.is_none() {
+                break;
+            }
+        }
+
+        keys.truncate(limit);
+        Ok(keys)
+    }
+
+    /// Guilds tombstoned before `cutoff` (Unix seconds). Scans the table, so
+    /// it's only for scheduled jobs.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn tombstoned_before(&self, cutoff: i64) -> Result<Vec<String>> {
+        let mut guilds = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("#sk = :record AND removed_at < :cutoff")
+                .expression_attribute_names("#sk", self.sort_key)
+                .expression_attribute_values(":record", AttributeValue::S(GUILD_RECORD_KEY.into()))
+                .expression_attribute_values(":cutoff", AttributeValue::N(cutoff.to_string()))
+                .projection_expression("guild_id")
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("find tombstoned guilds", err))?;
+
+            guilds.extend(
+                response
+                    .items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|item| Some(item.get("guild_id")?.as_s().ok()?.to_string())),
+            );
+
+            start_key = response.last_evaluated_key;
+
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(guilds)
+    }
+
+    /// Keys of up to `limit` of the guild's items.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn guild_keys(&self, guild_id: &str, limit: usize) -> Result<Vec<ItemKey>> {
+        let response = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .key_condition_expression("guild_id = :guild_id")
+            .expression_attribute_values(":guild_id", AttributeValue::S(guild_id.to_string()))
+            .projection_expression("guild_id, #sk")
+            .expression_attribute_names("#sk", self.sort_key)
+            .limit(limit as i32)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("list guild items", err))?;
+
+        Ok(response.items.unwrap_or_default())
+    }
+
+    /// Whether the guild's `GUILD` record is still a tombstone from before
+    /// `cutoff`, i.e. the guild hasn't rejoined since it was found expired.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn is_tombstoned_before(&self, guild_id: &str, cutoff: i64) -> Result<bool> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(self.sort_key, AttributeValue::S(GUILD_RECORD_KEY.into()))
+            .projection_expression("removed_at")
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get guild tombstone", err))?;
+
+        Ok(response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("removed_at")?.as_n().ok()?.parse::<i64>().ok())
+            .is_some_and(|removed_at| removed_at < cutoff))
+    }
+
+    /// Deletes the guild's `GUILD` record if it's still a tombstone from
+    /// before `cutoff`. Returns `false` when the guild rejoined meanwhile
+    /// and the record was kept.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn delete_guild_record(&self, guild_id: &str, cutoff: i64) -> Result<bool> {
+        let result = self
+            .client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(self.sort_key, AttributeValue::S(GUILD_RECORD_KEY.into()))
+            .condition_expression("removed_at < :cutoff")
+            .expression_attribute_values(":cutoff", AttributeValue::N(cutoff.to_string()))
+            .send()
+            .await;
+
+        match result.map_err(|err| StorageError::from_sdk("delete guild tombstone", err)) {
+            Ok(_) => Ok(true),
+            Err(StorageError::ConditionFailed { .. }) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Whether `key` is the guild's `GUILD` record.
+    pub fn is_guild_record(&self, key: &ItemKey) -> bool {
+        key.get(self.sort_key)
+            .and_then(|v| v.as_s().ok())
+            .is_some_and(|sort_key| sort_key == GUILD_RECORD_KEY)
+    }
+
+    /// Deletes up to 25 items in one BatchWriteItem, retrying the items
+    /// DynamoDB leaves unprocessed when the table is busy.
+    #[instrument(skip_all, fields(table = %self.table_name, items = keys.len()))]
+    pub async fn delete_batch(&self, keys: Vec<ItemKey>) -> Result<()> {
+        if keys.len() > MAX_BATCH_DELETES {
+            bail!("at most {} items can be deleted at once", MAX_BATCH_DELETES);
+        }
+
+        let mut requests = keys
+            .into_iter()
+            .map(|key| {
+                let delete = DeleteRequest::builder().set_key(Some(key)).build()?;
+                Ok(WriteRequest::builder().delete_request(delete).build())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for attempt in 0..MAX_BATCH_ATTEMPTS {
+            if requests.is_empty() {
+                return Ok(());
+            }
+
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_millis(100 << attempt)).await;
+            }
+
+            let response = self
+                .client
+                .batch_write_item()
+                .request_items(&self.table_name, requests)
+                .send()
+                .await
+                .map_err(|err| StorageError::from_sdk("delete expired items", err))?;
+
+            requests = response
+                .unprocessed_items
+                .and_then(|mut unprocessed| unprocessed.remove(&self.table_name))
+                .unwrap_or_default();
+        }
+
+        if !requests.is_empty() {
+            bail!("{} items were left unprocessed", requests.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+const USER_PREFIX: &str = "USER#";
+const ROLE_CONNECTION_KEY: &str = "ROLE_CONNECTION";
+
+/// The OAuth2 grant a user gave when linking their account, kept so the
+/// bot can push fresh metadata when its criteria change.
+///
+/// Stored in the role table under `USER#<user_id>` / `ROLE_CONNECTION`.
+#[derive(Debug, Clone)]
+pub struct RoleConnectionGrant {
+    pub user_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Unix seconds after which `access_token` must be refreshed.
+    pub expires_at: i64,
+}
+
+#[derive(Clone)]
+pub struct RoleConnectionDao {
+    client: Client,
+    table_name: String,
+}
+
+impl RoleConnectionDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    fn partition(user_id: &str) -> AttributeValue {
+        AttributeValue::S(format!("{}{}", USER_PREFIX, user_id))
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_grant(&self, user_id: &str) -> Result<Option<RoleConnectionGrant>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", Self::partition(user_id))
+            .key(
+                "mapping_key",
+                AttributeValue::S(ROLE_CONNECTION_KEY.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get role connection", err))?;
+
+        let item = match response.item {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        let string = |name: &str| {
+            item.get(name)
+                .and_then(|v| v.as_s().ok())
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        Ok(Some(RoleConnectionGrant {
+            user_id: user_id.to_string(),
+            access_token: string("access_token"),
+            refresh_token: string("refresh_token"),
+            expires_at: item
+                .get("expires_at")
+                .and_then(|v| v.as_n().ok())
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(0),
+        }))
+    }
+
+    #[instrument(skip(self, grant), fields(table = %self.table_name, user_id = %grant.user_id))]
+    pub async fn save_grant(&self, grant: &RoleConnectionGrant) -> Result<()> {
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("guild_id", Self::partition(&grant.user_id))
+            .item(
+                "mapping_key",
+                AttributeValue::S(ROLE_CONNECTION_KEY.to_string()),
+            )
+            .item(
+                "access_token",
+                AttributeValue::S(grant.access_token.clone()),
+            )
+            .item(
+                "refresh_token",
+                AttributeValue::S(grant.refresh_token.clone()),
+            )
+            .item(
+                "expires_at",
+                AttributeValue::N(grant.expires_at.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("save role connection", err))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use tracing::instrument;
+
+use crate::{
+    dal::model::role_menu::{EmojiBinding, RoleMenu},
+    error::StorageError,
+};
+
+fn menu_key(message_id: &str) -> AttributeValue {
+    AttributeValue::S(format!("MENU#{}", message_id))
+}
+
+/// Role menus posted by `/rolemenu post`, stored in the role table as
+/// `MENU#<message_id>` items with a `bindings` map of emoji to role.
+#[derive(Clone)]
+pub struct RoleMenuDao {
+    client: Client,
+    table_name: String,
+}
+
+impl RoleMenuDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn create_menu(
+        &self,
+        guild_id: &str,
+        message_id: &str,
+        channel_id: &str,
+    ) -> Result<()> {
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("guild_id", AttributeValue::S(guild_id.to_string()))
+            .item("mapping_key", menu_key(message_id))
+            .item("channel_id", AttributeValue::S(channel_id.to_string()))
+            .item("bindings", AttributeValue::M(HashMap::new()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("create role menu", err))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_menu(&self, guild_id: &str, message_id: &str) -> Result<Option<RoleMenu>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key("mapping_key", menu_key(message_id))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get role menu", err))?;
+
+        let Some(item) = response.item else {
+            return Ok(None);
+        };
+
+        let channel_id = item
+            .get("channel_id")
+            .and_then(|v| v.as_s().ok())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut bindings: Vec<EmojiBinding> = item
+            .get("bindings")
+            .and_then(|v| v.as_m().ok())
+            .into_iter()
+            .flatten()
+            .filter_map(|(emoji, binding)| {
+                let binding = binding.as_m().ok()?;
+                let field = |name: &str| binding.get(name).and_then(|v| v.as_s().ok()).cloned();
+
+                Some(EmojiBinding {
+                    emoji: emoji.clone(),
+                    role_id: field("role_id")?,
+                    role_name: field("role_name").unwrap_or_default(),
+                    bound_at: binding
+                        .get("bound_at")
+                        .and_then(|v| v.as_n().ok())
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect();
+        bindings.sort_by_key(|binding| binding.bound_at);
+
+        Ok(Some(RoleMenu {
+            message_id: message_id.to_string(),
+            channel_id,
+            bindings,
+        }))
+    }
+
+    /// Binds `binding.emoji`, replacing whatever role it was bound to. Fails
+    /// with `StorageError::ConditionFailed` if the menu doesn't exist.
+    #[instrument(skip(self, binding), fields(table = %self.table_name, emoji = %binding.emoji))]
+    pub async fn bind_emoji(
+        &self,
+        guild_id: &str,
+        message_id: &str,
+        binding: &EmojiBinding,
+    ) -> Result<()> {
+        let value = AttributeValue::M(HashMap::from([
+            (
+                "role_id".to_string(),
+                AttributeValue::S(binding.role_id.clone()),
+            ),
+            (
+                "role_name".to_string(),
+                AttributeValue::S(binding.role_name.clone()),
+            ),
+            (
+                "bound_at".to_string(),
+                AttributeValue::N(binding.bound_at.to_string()),
+            ),
+        ]));
+
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key("mapping_key", menu_key(message_id))
+            .update_expression("SET bindings.#emoji = :binding")
+            .condition_expression("attribute_exists(mapping_key)")
+            .expression_attribute_names("#emoji", &binding.emoji)
+            .expression_attribute_values(":binding", value)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("bind role menu emoji", err))?;
+
+        Ok(())
+    }
+
+    /// Unbinding an emoji that isn't bound is not an error.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn unbind_emoji(&self, guild_id: &str, message_id: &str, emoji: &str) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key("mapping_key", menu_key(message_id))
+            .update_expression("REMOVE bindings.#emoji")
+            .condition_expression("attribute_exists(mapping_key)")
+            .expression_attribute_names("#emoji", emoji)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("unbind role menu emoji", err))?;
+
+        Ok(())
+    }
+}
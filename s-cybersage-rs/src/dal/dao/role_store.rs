@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::dal::model::{age_requirement::AgeRequirement, role_icon::RoleIcon};
+
+/// Storage for a guild's self-assignable roles. Roles are returned as
+/// `(name, id)` pairs; name lookups are case-insensitive.
+#[async_trait]
+pub trait RoleStore: Send + Sync {
+    async fn get_role_by_id(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+    ) -> Result<Option<(String, String)>>;
+
+    /// Up to 25 roles whose name starts with `prefix`, for autocomplete. Roles
+    /// in `favorites` come first, then the most-toggled, then by name.
+    async fn query_roles_by_prefix(
+        &self,
+        guild_id: &str,
+        prefix: &str,
+        favorites: &[String],
+    ) -> Result<Vec<(String, String)>>;
+
+    async fn save_role(&self, guild_id: &str, role_id: &str, role_name: &str) -> Result<()>;
+
+    /// Saves the role along with its icon, as read from Discord.
+    async fn save_role_with_icon(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        role_name: &str,
+        icon: &RoleIcon,
+    ) -> Result<()>;
+
+    /// Stops a role being self-assignable. Deleting an unknown role is not
+    /// an error.
+    async fn delete_role(&self, guild_id: &str, role_id: &str) -> Result<()>;
+
+    async fn get_role_by_name(
+        &self,
+        guild_id: &str,
+        role_name: &str,
+    ) -> Result<Option<(String, String)>>;
+
+    /// The other role already registered under `role_name`, if any. Saving
+    /// a second role under the same name would make toggling by name
+    /// ambiguous.
+    async fn name_conflict(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        role_name: &str,
+    ) -> Result<Option<(String, String)>> {
+        Ok(self
+            .get_role_by_name(guild_id, role_name)
+            .await?
+            .filter(|(_, id)| id != role_id))
+    }
+
+    async fn list_roles(&self, guild_id: &str) -> Result<Vec<(String, String)>>;
+
+    /// Roles a member must already hold to take `role_id`, by ID.
+    async fn get_prerequisites(&self, guild_id: &str, role_id: &str) -> Result<Vec<String>>;
+
+    /// Replaces the role's prerequisites; an empty slice clears them. Fails
+    /// if the role isn't registered.
+    async fn set_prerequisites(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        required_role_ids: &[String],
+    ) -> Result<()>;
+
+    /// Channels `role_id` may be toggled from, by ID. Empty means anywhere.
+    async fn get_allowed_channels(&self, guild_id: &str, role_id: &str) -> Result<Vec<String>>;
+
+    /// Replaces the role's allowed channels; an empty slice clears them.
+    /// Fails if the role isn't registered.
+    async fn set_allowed_channels(
+        &self,
+        guild_id: &str,
+        role_id: &str,
+        channel_ids: &[String],
+    ) -> Result<()>;
+
+    /// Counts a toggle of the role, for ranking autocomplete. Fails if the
+    /// role isn't registered.
+    async fn increment_toggle_count(&self, guild_id: &str, role_id: &str) -> Result<()>;
+
+    /// Icons of the guild's roles that have one, keyed by role ID.
+    async fn list_role_icons(&self, guild_id: &str) -> Result<HashMap<String, RoleIcon>>;
+
+    /// Every guild with at least one registered role.
+    async fn list_guilds(&self) -> Result<Vec<String>>;
+
+    /// Characters a user must type before autocomplete queries roles, if the
+    /// guild has set it.
+    async fn get_autocomplete_min_length(&self, guild_id: &str) -> Result<Option<u32>>;
+
+    async fn set_autocomplete_min_length(&self, guild_id: &str, length: u32) -> Result<()>;
+
+    /// How many self-assignable roles a member may hold at once, if the
+    /// guild has set a limit.
+    async fn get_role_limit(&self, guild_id: &str) -> Result<Option<u32>>;
+
+    /// Sets the role limit; `None` removes it.
+    async fn set_role_limit(&self, guild_id: &str, limit: Option<u32>) -> Result<()>;
+
+    /// How long members must have been around to take roles, if the guild
+    /// has set a minimum.
+    async fn get_age_requirement(&self, guild_id: &str) -> Result<Option<AgeRequirement>>;
+
+    /// Sets the minimum age; `None` removes it.
+    async fn set_age_requirement(
+        &self,
+        guild_id: &str,
+        requirement: Option<AgeRequirement>,
+    ) -> Result<()>;
+
+    /// How many members hold each registered role, keyed by role ID, as of
+    /// the last scheduled count. Empty if the guild hasn't been counted.
+    async fn get_member_counts(&self, guild_id: &str) -> Result<HashMap<String, u64>>;
+
+    /// Replaces the guild's member counts; an empty map clears them.
+    async fn set_member_counts(&self, guild_id: &str, counts: &HashMap<String, u64>) -> Result<()>;
+}
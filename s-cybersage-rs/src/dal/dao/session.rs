@@ -0,0 +1,118 @@
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+const SESSION_PREFIX: &str = "SESSION#";
+const SESSION_KEY: &str = "SESSION";
+
+/// A signed-in dashboard user. Stored in the role table under
+/// `SESSION#<token hash>` / `SESSION`; the token itself is never stored.
+/// The `ttl` attribute lets DynamoDB delete expired sessions.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub user_id: String,
+    /// The user's Discord access token, for checking which guilds they
+    /// manage.
+    pub access_token: String,
+    /// Unix seconds.
+    pub expires_at: i64,
+}
+
+#[derive(Clone)]
+pub struct SessionDao {
+    client: Client,
+    table_name: String,
+}
+
+impl SessionDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    fn partition(token_hash: &str) -> AttributeValue {
+        AttributeValue::S(format!("{}{}", SESSION_PREFIX, token_hash))
+    }
+
+    /// The session, unless it doesn't exist or has expired but not yet been
+    /// deleted by the TTL sweep.
+    #[instrument(skip_all, fields(table = %self.table_name))]
+    pub async fn get_session(&self, token_hash: &str, now: i64) -> Result<Option<Session>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", Self::partition(token_hash))
+            .key("mapping_key", AttributeValue::S(SESSION_KEY.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get session", err))?;
+
+        let item = match response.item {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        let string = |name: &str| item.get(name).and_then(|v| v.as_s().ok()).cloned();
+
+        let expires_at = item
+            .get("expires_at")
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse::<i64>().ok())
+            .unwrap_or(0);
+
+        if expires_at <= now {
+            return Ok(None);
+        }
+
+        Ok(string("user_id")
+            .zip(string("access_token"))
+            .map(|(user_id, access_token)| Session {
+                user_id,
+                access_token,
+                expires_at,
+            }))
+    }
+
+    #[instrument(skip_all, fields(table = %self.table_name, user_id = %session.user_id))]
+    pub async fn save_session(&self, token_hash: &str, session: &Session) -> Result<()> {
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("guild_id", Self::partition(token_hash))
+            .item("mapping_key", AttributeValue::S(SESSION_KEY.to_string()))
+            .item("user_id", AttributeValue::S(session.user_id.clone()))
+            .item(
+                "access_token",
+                AttributeValue::S(session.access_token.clone()),
+            )
+            .item(
+                "expires_at",
+                AttributeValue::N(session.expires_at.to_string()),
+            )
+            .item("ttl", AttributeValue::N(session.expires_at.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("save session", err))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(table = %self.table_name))]
+    pub async fn delete_session(&self, token_hash: &str) -> Result<()> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("guild_id", Self::partition(token_hash))
+            .key("mapping_key", AttributeValue::S(SESSION_KEY.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("delete session", err))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use once_cell::sync::Lazy;
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+use super::feature_flag::GLOBAL_PARTITION;
+
+const SETTINGS_KEY: &str = "SETTINGS";
+const SETTINGS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Operator-tunable values stored on the `GLOBAL`/`SETTINGS` item. Absent
+/// attributes fall back to the function's environment.
+#[derive(Debug, Clone, Default)]
+pub struct StoredSettings {
+    pub free_tier_monthly_toggles: Option<u64>,
+    pub premium_sku_id: Option<String>,
+    pub subscribe_url: Option<String>,
+}
+
+static SETTINGS_CACHE: Lazy<Mutex<Option<(StoredSettings, Instant)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+fn cached_settings() -> Option<StoredSettings> {
+    let cache = SETTINGS_CACHE.lock().ok()?;
+    let (settings, cached_at) = cache.as_ref()?;
+
+    if cached_at.elapsed() > SETTINGS_CACHE_TTL {
+        return None;
+    }
+
+    Some(settings.clone())
+}
+
+fn cache_settings(settings: &StoredSettings) {
+    if let Ok(mut cache) = SETTINGS_CACHE.lock() {
+        *cache = Some((settings.clone(), Instant::now()));
+    }
+}
+
+pub struct SettingsDao {
+    client: Client,
+    table_name: String,
+}
+
+impl SettingsDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    pub async fn get_settings(&self) -> Result<StoredSettings> {
+        if let Some(settings) = cached_settings() {
+            return Ok(settings);
+        }
+
+        let settings = self.fetch_settings().await?;
+        cache_settings(&settings);
+
+        Ok(settings)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn fetch_settings(&self) -> Result<StoredSettings> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(GLOBAL_PARTITION.to_string()))
+            .key("mapping_key", AttributeValue::S(SETTINGS_KEY.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get settings", err))?;
+
+        let item = match response.item {
+            Some(item) => item,
+            None => return Ok(StoredSettings::default()),
+        };
+
+        let string = |name: &str| {
+            item.get(name)
+                .and_then(|v| v.as_s().ok())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        };
+
+        Ok(StoredSettings {
+            free_tier_monthly_toggles: item
+                .get("free_tier_monthly_toggles")
+                .and_then(|v| v.as_n().ok())
+                .and_then(|n| n.parse().ok()),
+            premium_sku_id: string("premium_sku_id"),
+            subscribe_url: string("subscribe_url"),
+        })
+    }
+}
@@ -1,7 +1,58 @@
-use anyhow::{Context, Result};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
 use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use once_cell::sync::Lazy;
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+use super::bundle::BundleDao;
 
 const SUBSCRIPTION_KEY: &str = "SUBSCRIPTION";
+const ACTIVE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Process-wide `is_active` results keyed by guild, shared across invocations
+/// on a warm Lambda. Writers must call `invalidate_cached_status` so changes
+/// are visible immediately rather than after the TTL.
+///
+/// That only reaches the writer's own instance: nothing can evict another
+/// warm function's cache, so there the TTL alone bounds how stale the status
+/// gets. A subscription changed elsewhere (by an operator's grant on another
+/// instance, or directly in the table) takes up to a minute to apply
+/// everywhere. This is intended; a lapsed subscription staying premium for
+/// that long is cheaper than a read per interaction.
+static ACTIVE_CACHE: Lazy<Mutex<HashMap<String, (bool, Instant)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Drops this instance's cached status for the guild; see `ACTIVE_CACHE`.
+pub fn invalidate_cached_status(guild_id: &str) {
+    if let Ok(mut cache) = ACTIVE_CACHE.lock() {
+        cache.remove(guild_id);
+    }
+}
+
+fn cached_status(guild_id: &str) -> Option<bool> {
+    let cache = ACTIVE_CACHE.lock().ok()?;
+    let (is_active, cached_at) = cache.get(guild_id)?;
+
+    if cached_at.elapsed() > ACTIVE_CACHE_TTL {
+        return None;
+    }
+
+    Some(*is_active)
+}
+
+fn cache_status(guild_id: &str, is_active: bool) {
+    if let Ok(mut cache) = ACTIVE_CACHE.lock() {
+        cache.retain(|_, (_, cached_at)| cached_at.elapsed() <= ACTIVE_CACHE_TTL);
+        cache.insert(guild_id.to_string(), (is_active, Instant::now()));
+    }
+}
 
 #[derive(Clone)]
 pub struct SubscriptionReader {
@@ -18,6 +69,43 @@ impl SubscriptionReader {
     }
 
     pub async fn is_active(&self, guild_id: &str) -> Result<bool> {
+        if let Some(is_active) = cached_status(guild_id) {
+            return Ok(is_active);
+        }
+
+        let is_active = self.fetch_is_active(guild_id).await?;
+        cache_status(guild_id, is_active);
+
+        Ok(is_active)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    async fn fetch_is_active(&self, guild_id: &str) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        if let Some(expires_at) = self.get_active_expiry(guild_id).await? {
+            if now <= expires_at {
+                return Ok(true);
+            }
+        }
+
+        let bundles = BundleDao::new(self.client.clone(), self.table_name.clone());
+
+        let owner_id = match bundles.get_attached_owner(guild_id).await? {
+            Some(owner_id) => owner_id,
+            None => return Ok(false),
+        };
+
+        Ok(bundles
+            .get_bundle(&owner_id)
+            .await?
+            .is_some_and(|bundle| bundle.is_active(now)))
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_active_expiry(&self, guild_id: &str) -> Result<Option<i64>> {
         let response = self
             .client
             .get_item()
@@ -29,33 +117,102 @@ impl SubscriptionReader {
             )
             .send()
             .await
-            .context("Failed to query subscription")?;
+            .map_err(|err| StorageError::from_sdk("query subscription", err))?;
 
         let item = match response.item {
             Some(item) => item,
-            None => return Ok(false),
+            None => return Ok(None),
         };
 
-        let status = item
+        let is_active = item
             .get("status")
             .and_then(|v| v.as_s().ok())
-            .map(|s| s.as_str())
-            .unwrap_or("inactive");
+            .is_some_and(|s| s == "active");
 
-        if status != "active" {
-            return Ok(false);
+        if !is_active {
+            return Ok(None);
         }
 
-        let expires_at = item
+        Ok(item
             .get("expires_at")
             .and_then(|v| v.as_n().ok())
-            .and_then(|n| n.parse::<i64>().ok())
-            .unwrap_or(0);
+            .and_then(|n| n.parse::<i64>().ok()))
+    }
 
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)?
-            .as_secs() as i64;
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_customer_id(&self, guild_id: &str) -> Result<Option<String>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "subscription_key",
+                AttributeValue::S(SUBSCRIPTION_KEY.to_string()),
+            )
+            .projection_expression("stripe_customer_id")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("query subscription customer", err))?;
+
+        Ok(response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("stripe_customer_id"))
+            .and_then(|v| v.as_s().ok())
+            .map(|s| s.to_string()))
+    }
+}
+
+#[derive(Clone)]
+pub struct SubscriptionWriter {
+    client: Client,
+    table_name: String,
+}
+
+impl SubscriptionWriter {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    /// Marks the guild's subscription active until `expires_at`. Only these
+    /// attributes are set, so others on the item (e.g. the Stripe customer
+    /// ID) survive a manual grant.
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn put_active(
+        &self,
+        guild_id: &str,
+        expires_at: i64,
+        source: &str,
+        updated_by: &str,
+    ) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "subscription_key",
+                AttributeValue::S(SUBSCRIPTION_KEY.to_string()),
+            )
+            .update_expression(
+                "SET #status = :status, expires_at = :expires_at, #source = :source, \
+                 updated_by = :updated_by",
+            )
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_names("#source", "source")
+            .expression_attribute_values(":status", AttributeValue::S("active".to_string()))
+            .expression_attribute_values(":expires_at", AttributeValue::N(expires_at.to_string()))
+            .expression_attribute_values(":source", AttributeValue::S(source.to_string()))
+            .expression_attribute_values(":updated_by", AttributeValue::S(updated_by.to_string()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("write subscription", err))?;
+
+        invalidate_cached_status(guild_id);
 
-        Ok(now <= expires_at)
+        Ok(())
     }
 }
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, ReturnValue},
+    Client,
+};
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+pub(crate) const USAGE_KEY_PREFIX: &str = "USAGE#";
+
+#[derive(Clone)]
+pub struct UsageDao {
+    client: Client,
+    table_name: String,
+}
+
+impl UsageDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_toggle_count(&self, guild_id: &str, period: &str) -> Result<u64> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "subscription_key",
+                AttributeValue::S(format!("{}{}", USAGE_KEY_PREFIX, period)),
+            )
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get usage item", err))?;
+
+        let count = response
+            .item
+            .as_ref()
+            .and_then(|item| item.get("toggle_count"))
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        Ok(count)
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn increment_toggle_count(&self, guild_id: &str, period: &str) -> Result<u64> {
+        let response = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "subscription_key",
+                AttributeValue::S(format!("{}{}", USAGE_KEY_PREFIX, period)),
+            )
+            .update_expression("ADD toggle_count :one")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("increment usage counter", err))?;
+
+        let count = response
+            .attributes
+            .as_ref()
+            .and_then(|attrs| attrs.get("toggle_count"))
+            .and_then(|v| v.as_n().ok())
+            .and_then(|n| n.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        Ok(count)
+    }
+}
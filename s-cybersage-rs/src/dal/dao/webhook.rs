@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use aws_sdk_dynamodb::{types::AttributeValue, Client};
+use once_cell::sync::Lazy;
+use tracing::instrument;
+
+use crate::error::StorageError;
+
+use super::guild::GUILD_CONFIG_KEY;
+
+const CONFIGURED_CACHE_TTL: Duration = Duration::from_secs(60);
+const CONFIGURED_CACHE_MAX_ENTRIES: usize = 1024;
+
+type ConfiguredCache = Lazy<Mutex<HashMap<(String, String), (bool, Instant)>>>;
+
+/// Whether each guild has a webhook, keyed by (table, guild). Checked on
+/// every role toggle, so events for a just-registered webhook may be missed
+/// on other instances for up to the TTL.
+static CONFIGURED_CACHE: ConfiguredCache = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cached_configured(table_name: &str, guild_id: &str) -> Option<bool> {
+    let cache = CONFIGURED_CACHE.lock().ok()?;
+    let (configured, cached_at) = cache.get(&(table_name.to_string(), guild_id.to_string()))?;
+
+    if cached_at.elapsed() > CONFIGURED_CACHE_TTL {
+        return None;
+    }
+
+    Some(*configured)
+}
+
+fn cache_configured(table_name: &str, guild_id: &str, configured: bool) {
+    if let Ok(mut cache) = CONFIGURED_CACHE.lock() {
+        if cache.len() >= CONFIGURED_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (_, cached_at)| cached_at.elapsed() <= CONFIGURED_CACHE_TTL);
+        }
+
+        cache.insert(
+            (table_name.to_string(), guild_id.to_string()),
+            (configured, Instant::now()),
+        );
+    }
+}
+
+/// An external endpoint a guild's events are posted to.
+#[derive(Debug, Clone)]
+pub struct GuildWebhook {
+    pub url: String,
+    /// Signs each delivery so the receiver can check it came from the bot.
+    pub secret: String,
+}
+
+/// Reads and writes a guild's webhook, stored as `webhook_url` and
+/// `webhook_secret` on its `CONFIG` item in the role table.
+#[derive(Clone)]
+pub struct WebhookDao {
+    client: Client,
+    table_name: String,
+}
+
+impl WebhookDao {
+    pub fn new(client: Client, table_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            table_name: table_name.into(),
+        }
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn get_webhook(&self, guild_id: &str) -> Result<Option<GuildWebhook>> {
+        let response = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .projection_expression("webhook_url, webhook_secret")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("get guild webhook", err))?;
+
+        let webhook = response.item.and_then(|item| {
+            let string = |name: &str| item.get(name).and_then(|v| v.as_s().ok()).cloned();
+
+            string("webhook_url")
+                .zip(string("webhook_secret"))
+                .map(|(url, secret)| GuildWebhook { url, secret })
+        });
+
+        cache_configured(&self.table_name, guild_id, webhook.is_some());
+
+        Ok(webhook)
+    }
+
+    /// Whether the guild has a webhook, without reading its secret.
+    pub async fn is_configured(&self, guild_id: &str) -> Result<bool> {
+        if let Some(configured) = cached_configured(&self.table_name, guild_id) {
+            return Ok(configured);
+        }
+
+        Ok(self.get_webhook(guild_id).await?.is_some())
+    }
+
+    #[instrument(skip(self, webhook), fields(table = %self.table_name))]
+    pub async fn set_webhook(&self, guild_id: &str, webhook: &GuildWebhook) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .update_expression("SET webhook_url = :url, webhook_secret = :secret")
+            .expression_attribute_values(":url", AttributeValue::S(webhook.url.clone()))
+            .expression_attribute_values(":secret", AttributeValue::S(webhook.secret.clone()))
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("set guild webhook", err))?;
+
+        cache_configured(&self.table_name, guild_id, true);
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(table = %self.table_name))]
+    pub async fn clear_webhook(&self, guild_id: &str) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("guild_id", AttributeValue::S(guild_id.to_string()))
+            .key(
+                "mapping_key",
+                AttributeValue::S(GUILD_CONFIG_KEY.to_string()),
+            )
+            .update_expression("REMOVE webhook_url, webhook_secret")
+            .send()
+            .await
+            .map_err(|err| StorageError::from_sdk("clear guild webhook", err))?;
+
+        cache_configured(&self.table_name, guild_id, false);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
 pub mod dao;
+pub mod model;
+pub mod queue;
 pub mod reader;
-pub mod model;
\ No newline at end of file
@@ -0,0 +1,94 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleActivity {
+    Saved,
+    Removed,
+    Toggled,
+    Imported,
+    Pruned,
+    MassAssigned,
+    MassRemoved,
+}
+
+impl RoleActivity {
+    pub fn title(&self) -> &'static str {
+        match self {
+            RoleActivity::Saved => "Role saved",
+            RoleActivity::Removed => "Role removed",
+            RoleActivity::Toggled => "Role toggled",
+            RoleActivity::Imported => "Roles imported",
+            RoleActivity::Pruned => "Deleted roles removed",
+            RoleActivity::MassAssigned => "Role mass-assigned",
+            RoleActivity::MassRemoved => "Role mass-removed",
+        }
+    }
+}
+
+/// One line of role activity for a guild's log channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    pub guild_id: String,
+    pub activity: RoleActivity,
+    /// Who did it. Unset for the bot's own work.
+    pub actor_id: Option<String>,
+    /// What was acted on, as shown in the log, e.g. a role mention.
+    pub target: String,
+    pub result: String,
+    pub succeeded: bool,
+    /// Unix seconds.
+    pub occurred_at: i64,
+    /// Shown as the embed's thumbnail, e.g. the role's icon.
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+}
+
+impl ActivityEntry {
+    pub fn new(
+        guild_id: impl Into<String>,
+        activity: RoleActivity,
+        actor_id: Option<&str>,
+        target: impl Into<String>,
+    ) -> Self {
+        Self {
+            guild_id: guild_id.into(),
+            activity,
+            actor_id: actor_id.map(str::to_string),
+            target: target.into(),
+            result: String::new(),
+            succeeded: true,
+            occurred_at: Utc::now().timestamp(),
+            thumbnail_url: None,
+        }
+    }
+
+    pub fn with_thumbnail(self, thumbnail_url: Option<String>) -> Self {
+        Self {
+            thumbnail_url,
+            ..self
+        }
+    }
+
+    pub fn succeeded(self, result: impl Into<String>) -> Self {
+        Self {
+            result: result.into(),
+            succeeded: true,
+            ..self
+        }
+    }
+
+    pub fn failed(self, result: impl Into<String>) -> Self {
+        Self {
+            result: result.into(),
+            succeeded: false,
+            ..self
+        }
+    }
+}
+
+/// Renders as the role's mention, which doesn't ping inside an embed.
+pub fn role_mention(role_id: &str) -> String {
+    format!("<@&{}>", role_id)
+}
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Duration, Utc};
+
+use super::interaction_request::Member;
+
+/// Discord snowflakes count milliseconds from 2015-01-01T00:00:00Z in their
+/// top 42 bits.
+const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+
+/// How long a member must have been around before taking self-assignable
+/// roles, so accounts made or joined during a raid can't grab them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AgeRequirement {
+    pub hours: u32,
+    /// Counts from account creation rather than from joining the guild.
+    pub account: bool,
+}
+
+impl AgeRequirement {
+    /// When `member` meets the requirement. `None` if Discord didn't say
+    /// when they joined, in which case they aren't held back.
+    pub fn eligible_at(&self, member: &Member) -> Option<DateTime<Utc>> {
+        let since = if self.account {
+            snowflake_time(&member.user.id)?
+        } else {
+            member.joined()?
+        };
+
+        Some(since + Duration::hours(self.hours.into()))
+    }
+
+    /// E.g. "members who joined at least 24 hour(s) ago".
+    pub fn describe(&self) -> String {
+        if self.account {
+            format!("accounts at least {} hour(s) old", self.hours)
+        } else {
+            format!("members who joined at least {} hour(s) ago", self.hours)
+        }
+    }
+}
+
+/// When the user, role or message with ID `id` was created.
+pub fn snowflake_time(id: &str) -> Option<DateTime<Utc>> {
+    let id = id.parse::<u64>().ok()?;
+    DateTime::from_timestamp_millis(DISCORD_EPOCH_MS + (id >> 22) as i64)
+}
@@ -0,0 +1,73 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+pub const APPLICATION_AUTHORIZED: &str = "APPLICATION_AUTHORIZED";
+pub const APPLICATION_DEAUTHORIZED: &str = "APPLICATION_DEAUTHORIZED";
+
+/// `integration_type` of an authorization that installed the app to a guild,
+/// as opposed to a user.
+pub const GUILD_INSTALL: u8 = 0;
+
+/// A request to the application's Webhook Events URL.
+#[derive(Debug, Deserialize)]
+pub struct AppEventRequest {
+    #[serde(rename = "type")]
+    pub kind: AppEventKind,
+    /// The application the event is for: the default bot or a white-label
+    /// tenant.
+    #[serde(default)]
+    pub application_id: Option<String>,
+    #[serde(default)]
+    pub event: Option<AppEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "u8")]
+pub enum AppEventKind {
+    Ping,
+    Event,
+    Unknown,
+}
+
+impl From<u8> for AppEventKind {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => AppEventKind::Ping,
+            1 => AppEventKind::Event,
+            _ => AppEventKind::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppEvent {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Left as JSON until the type is known; see `AuthorizationData`.
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// `data` of `APPLICATION_AUTHORIZED` and `APPLICATION_DEAUTHORIZED`.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuthorizationData {
+    #[serde(default)]
+    pub integration_type: Option<u8>,
+    #[serde(default)]
+    pub user: Option<EventUser>,
+    /// Only sent for guild installs.
+    #[serde(default)]
+    pub guild: Option<EventGuild>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventUser {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventGuild {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum CommandOptionType {
+    SubCommand = 1,
+    SubCommandGroup = 2,
+    String = 3,
+    Integer = 4,
+    Boolean = 5,
+    User = 6,
+    Channel = 7,
+    Role = 8,
+    Mentionable = 9,
+    Number = 10,
+    Attachment = 11,
+}
+
+/// A slash command as registered with Discord's application command
+/// endpoints.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApplicationCommand {
+    pub name: String,
+    pub description: String,
+
+    /// Locale (e.g. `de`, `pt-BR`) to translated name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_localizations: Option<HashMap<String, String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_localizations: Option<HashMap<String, String>>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<CommandOptionDefinition>,
+
+    /// Permission bitfield, as a decimal string, required to see and use
+    /// the command by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_member_permissions: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandOptionDefinition {
+    #[serde(rename = "type")]
+    pub kind: CommandOptionType,
+
+    pub name: String,
+    pub description: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_localizations: Option<HashMap<String, String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_localizations: Option<HashMap<String, String>>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub autocomplete: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<CommandOptionDefinition>,
+}
+
+impl ApplicationCommand {
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            name_localizations: None,
+            description_localizations: None,
+            options: Vec::new(),
+            default_member_permissions: None,
+        }
+    }
+
+    pub fn localized(
+        mut self,
+        locale: &str,
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.name_localizations
+            .get_or_insert_with(HashMap::new)
+            .insert(locale.to_string(), name.into());
+        self.description_localizations
+            .get_or_insert_with(HashMap::new)
+            .insert(locale.to_string(), description.into());
+        self
+    }
+
+    pub fn option(mut self, option: CommandOptionDefinition) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    pub fn default_member_permissions(mut self, permissions: u64) -> Self {
+        self.default_member_permissions = Some(permissions.to_string());
+        self
+    }
+}
+
+impl CommandOptionDefinition {
+    pub fn new(
+        kind: CommandOptionType,
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            description: description.into(),
+            name_localizations: None,
+            description_localizations: None,
+            required: false,
+            autocomplete: false,
+            min_value: None,
+            max_value: None,
+            options: Vec::new(),
+        }
+    }
+
+    pub fn subcommand(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self::new(CommandOptionType::SubCommand, name, description)
+    }
+
+    pub fn localized(
+        mut self,
+        locale: &str,
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.name_localizations
+            .get_or_insert_with(HashMap::new)
+            .insert(locale.to_string(), name.into());
+        self.description_localizations
+            .get_or_insert_with(HashMap::new)
+            .insert(locale.to_string(), description.into());
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn autocomplete(mut self) -> Self {
+        self.autocomplete = true;
+        self
+    }
+
+    pub fn range(mut self, min: i64, max: i64) -> Self {
+        self.min_value = Some(min);
+        self.max_value = Some(max);
+        self
+    }
+
+    pub fn option(mut self, option: CommandOptionDefinition) -> Self {
+        self.options.push(option);
+        self
+    }
+}
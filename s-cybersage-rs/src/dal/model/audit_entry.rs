@@ -0,0 +1,74 @@
+use sha2::{Digest, Sha256};
+
+use super::interaction_request::CommandOption;
+
+/// How the router answered a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOutcome {
+    /// The handler ran and replied.
+    Success,
+    /// Access, a middleware or the route lookup answered instead.
+    Refused,
+    /// The handler failed.
+    Error,
+}
+
+impl AuditOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Refused => "refused",
+            AuditOutcome::Error => "error",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        [
+            AuditOutcome::Success,
+            AuditOutcome::Refused,
+            AuditOutcome::Error,
+        ]
+        .into_iter()
+        .find(|outcome| outcome.as_str() == key)
+    }
+}
+
+/// One command invocation, as kept for `/role history` and abuse
+/// investigations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub interaction_id: String,
+    pub user_id: String,
+    /// The command and subcommand, e.g. `role toggle`.
+    pub command: String,
+    /// Identifies the options given without storing what members typed.
+    pub options_hash: String,
+    pub outcome: AuditOutcome,
+    pub duration_ms: u64,
+    /// Unix milliseconds.
+    pub occurred_at: i64,
+}
+
+impl AuditEntry {
+    /// A SHA-256 over the options' names and values in the order given,
+    /// shortened to 16 hex characters. Equal options hash equally, so
+    /// repeated invocations stand out.
+    pub fn hash_options(options: &[CommandOption]) -> String {
+        fn feed(hasher: &mut Sha256, options: &[CommandOption]) {
+            for option in options {
+                hasher.update(option.name.as_bytes());
+                hasher.update(b"=");
+                if let Some(value) = &option.value {
+                    hasher.update(value.to_string().as_bytes());
+                }
+                hasher.update(b";");
+                feed(hasher, &option.options);
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        feed(&mut hasher, options);
+
+        hex::encode(hasher.finalize())[..16].to_string()
+    }
+}
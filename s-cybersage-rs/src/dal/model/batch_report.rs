@@ -0,0 +1,63 @@
+use crate::markdown;
+
+/// Failures listed before the rest are only counted, so a summary fits in a
+/// message or an embed field.
+const MAX_LISTED_FAILURES: usize = 5;
+
+/// Per-item results of an operation that changes several roles, so one
+/// failure doesn't hide what did work.
+#[derive(Debug, Default, Clone)]
+pub struct BatchReport {
+    pub succeeded: usize,
+    /// The item and why it failed, e.g. `("Mods", "missing permission")`.
+    pub failures: Vec<(String, String)>,
+}
+
+impl BatchReport {
+    pub fn record_success(&mut self) {
+        self.succeeded += 1;
+    }
+
+    pub fn record_failure(&mut self, item: impl Into<String>, reason: impl Into<String>) {
+        self.failures.push((item.into(), reason.into()));
+    }
+
+    pub fn has_failures(&self) -> bool {
+        !self.failures.is_empty()
+    }
+
+    /// E.g. `"3 roles imported, 1 failed: 'Mods' — missing permission."`,
+    /// with each failure on its own line when there are several.
+    pub fn summary(&self, done: &str) -> String {
+        let mut summary = format!("{} {}", self.succeeded, done);
+
+        match self.failures.as_slice() {
+            [] => {
+                summary.push('.');
+                return summary;
+            }
+            [(item, reason)] => {
+                summary.push_str(&format!(
+                    ", 1 failed: '{}' — {}.",
+                    markdown::escape(item),
+                    reason
+                ));
+                return summary;
+            }
+            failures => summary.push_str(&format!(", {} failed:", failures.len())),
+        }
+
+        for (item, reason) in self.failures.iter().take(MAX_LISTED_FAILURES) {
+            summary.push_str(&format!("\n'{}' — {}", markdown::escape(item), reason));
+        }
+
+        if self.failures.len() > MAX_LISTED_FAILURES {
+            summary.push_str(&format!(
+                "\n…and {} more.",
+                self.failures.len() - MAX_LISTED_FAILURES
+            ));
+        }
+
+        summary
+    }
+}
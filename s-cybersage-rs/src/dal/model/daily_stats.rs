@@ -0,0 +1,34 @@
+/// One UTC day of bot-wide usage, summed from every guild with registered
+/// roles by the `aggregate_stats` job.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DailyStats {
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    /// Guilds with registered roles.
+    pub guilds: u64,
+    /// Guilds whose roles were toggled that day.
+    pub active_guilds: u64,
+    pub roles: u64,
+    pub toggles: u64,
+    /// Always 0 in builds without billing.
+    pub premium_guilds: u64,
+}
+
+impl DailyStats {
+    /// The share of guilds with premium, from 0 to 1.
+    pub fn premium_conversion(&self) -> f64 {
+        if self.guilds == 0 {
+            return 0.0;
+        }
+
+        self.premium_guilds as f64 / self.guilds as f64
+    }
+}
+
+/// One guild's share of a day's `DailyStats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GuildDayStats {
+    pub roles: u64,
+    pub toggles: u64,
+    pub premium: bool,
+}
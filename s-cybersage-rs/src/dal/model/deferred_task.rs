@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    activity_entry::ActivityEntry, guild_event::GuildEventDelivery, mass_role_job::MassRoleJob,
+    member_count::MemberCountJob, purge_job::PurgeJob, role_announcement::RoleAnnouncement,
+    role_job::RoleModificationJob,
+};
+
+/// Identifies the interaction a deferred task reports back to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskOrigin {
+    pub guild_id: String,
+    pub application_id: String,
+    pub interaction_token: String,
+    /// Who asked for the task, for the guild's log channel.
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// The roles of who asked, so an import only makes self-assignable the
+    /// roles they could hand out themselves.
+    #[serde(default)]
+    pub member_roles: Vec<String>,
+    /// Whether who asked is an administrator, who may hand out any role.
+    #[serde(default)]
+    pub administrator: bool,
+}
+
+/// Work executed by the queue worker instead of within the interaction's
+/// 3-second response window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeferredTask {
+    RoleModification(RoleModificationJob),
+    ImportRoles(TaskOrigin),
+    ExportRoles(TaskOrigin),
+    GuildEvent(GuildEventDelivery),
+    ActivityLog(ActivityEntry),
+    /// Queued for each guild by the daily `reconcile_roles` job.
+    ReconcileRoles {
+        guild_id: String,
+    },
+    /// Queued for each guild by the `count_role_members` job.
+    CountRoleMembers(MemberCountJob),
+    /// `/role mass-assign` and `/role mass-remove`.
+    MassRole(MassRoleJob),
+    /// `/role announce` with a time, sent by its one-shot schedule.
+    RoleAnnouncement(RoleAnnouncement),
+    /// Queued for each guild by the daily `aggregate_stats` job, to add its
+    /// figures for `date` (`YYYY-MM-DD`) to the global stats.
+    AggregateStats {
+        guild_id: String,
+        date: String,
+    },
+    /// Queued by the daily `purge_expired` job.
+    PurgeExpired(PurgeJob),
+}
+
+impl DeferredTask {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeferredTask::RoleModification(_) => "role_modification",
+            DeferredTask::ImportRoles(_) => "import_roles",
+            DeferredTask::ExportRoles(_) => "export_roles",
+            DeferredTask::GuildEvent(_) => "guild_event",
+            DeferredTask::ActivityLog(_) => "activity_log",
+            DeferredTask::ReconcileRoles { .. } => "reconcile_roles",
+            DeferredTask::CountRoleMembers(_) => "count_role_members",
+            DeferredTask::MassRole(_) => "mass_role",
+            DeferredTask::RoleAnnouncement(_) => "role_announcement",
+            DeferredTask::AggregateStats { .. } => "aggregate_stats",
+            DeferredTask::PurgeExpired(_) => "purge_expired",
+        }
+    }
+
+    /// The application whose interaction queued the task, for tasks that
+    /// report back to one.
+    pub fn application_id(&self) -> Option<&str> {
+        match self {
+            DeferredTask::RoleModification(job) => Some(&job.application_id),
+            DeferredTask::ImportRoles(origin) | DeferredTask::ExportRoles(origin) => {
+                Some(&origin.application_id)
+            }
+            DeferredTask::MassRole(job) => Some(&job.origin.application_id),
+            _ => None,
+        }
+    }
+
+    /// The guild a task calls Discord in without an interaction behind it,
+    /// so it runs as whichever application that guild installed.
+    pub fn bot_guild_id(&self) -> Option<&str> {
+        match self {
+            DeferredTask::ReconcileRoles { guild_id } => Some(guild_id),
+            DeferredTask::CountRoleMembers(job) => Some(&job.guild_id),
+            DeferredTask::RoleAnnouncement(announcement) => Some(&announcement.guild_id),
+            DeferredTask::ActivityLog(entry) => Some(&entry.guild_id),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+/// Something that changed in a guild, published to the guild's webhook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GuildEvent {
+    RoleRegistered {
+        role_id: String,
+        role_name: String,
+    },
+    RoleToggled {
+        user_id: String,
+        role_id: String,
+        role_name: String,
+        /// `true` if the member gained the role, `false` if they lost it.
+        added: bool,
+    },
+    SubscriptionChanged {
+        active: bool,
+        /// When the guild's own subscription ends, in unix seconds. Unset
+        /// when it's only covered by a bundle.
+        expires_at: Option<i64>,
+    },
+}
+
+impl GuildEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GuildEvent::RoleRegistered { .. } => "role_registered",
+            GuildEvent::RoleToggled { .. } => "role_toggled",
+            GuildEvent::SubscriptionChanged { .. } => "subscription_changed",
+        }
+    }
+}
+
+/// A guild event queued for delivery to the guild's webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildEventDelivery {
+    pub guild_id: String,
+    /// Unix seconds.
+    pub occurred_at: i64,
+    pub event: GuildEvent,
+
+    #[serde(default)]
+    pub attempt: u32,
+}
@@ -1,11 +1,15 @@
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde_repr::Deserialize_repr;
 
+use super::role_icon::RoleIcon;
+
 #[derive(Debug, Deserialize_repr)]
 #[repr(u8)]
 pub enum InteractionType {
     Ping = 1,
     ApplicationCommand = 2,
+    MessageComponent = 3,
     ApplicationCommandAutocomplete = 4,
 
     #[serde(other)]
@@ -22,21 +26,82 @@ pub struct InteractionRequest {
     #[serde(rename = "type")]
     pub interaction_type: InteractionType,
 
+    #[serde(default)]
+    pub token: String,
+
     #[serde(default)]
     pub data: Option<ApplicationCommandData>,
 
     #[serde(default)]
     pub guild_id: Option<String>,
 
+    #[serde(default)]
+    pub channel_id: Option<String>,
+
+    /// The channel the interaction came from, partially.
+    #[serde(default)]
+    pub channel: Option<InteractionChannel>,
+
+    /// The bot's permission bitfield in the channel, as a decimal string.
+    #[serde(default)]
+    pub app_permissions: Option<String>,
+
+    /// The message a clicked component is on.
+    #[serde(default)]
+    pub message: Option<InteractionMessage>,
+
     #[serde(default)]
     pub member: Option<Member>,
+
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    #[serde(default)]
+    pub guild_locale: Option<String>,
+}
+
+impl InteractionRequest {
+    /// Whether the bot is known to lack any of `permission` in the channel.
+    /// Payloads without `app_permissions` aren't taken as lacking anything.
+    pub fn app_lacks_permission(&self, permission: u64) -> bool {
+        self.app_permissions
+            .as_deref()
+            .is_some_and(|permissions| !has_permission(permissions, permission))
+    }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct InteractionChannel {
+    pub id: String,
+
+    #[serde(rename = "type", default)]
+    pub kind: u8,
+
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InteractionMessage {
+    pub id: String,
+}
+
+/// Data of a command, autocomplete or component interaction. Components have
+/// no command `id` or `name`, only the `custom_id` of what was clicked.
 #[derive(Debug, Deserialize)]
 pub struct ApplicationCommandData {
+    #[serde(default)]
     pub id: String,
+    #[serde(default)]
     pub name: String,
 
+    #[serde(default)]
+    pub custom_id: Option<String>,
+
+    /// What was picked in a select menu component.
+    #[serde(default)]
+    pub values: Vec<String>,
+
     #[serde(default)]
     pub options: Vec<CommandOption>,
 
@@ -61,6 +126,39 @@ pub struct Member {
 
     #[serde(default)]
     pub roles: Vec<String>,
+
+    /// The member's permission bitfield in the channel, as a decimal string.
+    #[serde(default)]
+    pub permissions: String,
+
+    /// The member's nickname in the guild, if they set one.
+    #[serde(default)]
+    pub nick: Option<String>,
+
+    /// When the member joined the guild, as an ISO 8601 timestamp.
+    #[serde(default)]
+    pub joined_at: Option<String>,
+}
+
+impl Member {
+    /// `joined_at`, parsed. `None` if Discord didn't send it.
+    pub fn joined(&self) -> Option<DateTime<Utc>> {
+        self.joined_at
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| at.with_timezone(&Utc))
+    }
+
+    pub fn has_permission(&self, permission: u64) -> bool {
+        has_permission(&self.permissions, permission)
+    }
+}
+
+/// Whether the decimal bitfield `permissions` includes all of `permission`.
+fn has_permission(permissions: &str, permission: u64) -> bool {
+    permissions
+        .parse::<u64>()
+        .is_ok_and(|permissions| permissions & permission == permission)
 }
 
 #[derive(Debug, Deserialize)]
@@ -78,4 +176,17 @@ pub struct ResolvedData {
 pub struct ResolvedRole {
     pub id: String,
     pub name: String,
+
+    /// Decimal bitfield, as Discord sends it.
+    #[serde(default)]
+    pub permissions: String,
+
+    #[serde(default)]
+    pub position: i64,
+
+    #[serde(default)]
+    pub managed: bool,
+
+    #[serde(flatten)]
+    pub icon: RoleIcon,
 }
@@ -1,6 +1,9 @@
 use serde::Serialize;
+use serde_json::Value;
 use serde_repr::Serialize_repr;
 
+use super::role_menu::PartialEmoji;
+
 bitflags::bitflags! {
     pub struct MessageFlags: u64 {
         const EPHEMERAL = 1 << 6;
@@ -12,9 +15,31 @@ bitflags::bitflags! {
 pub enum InteractionCallbackType {
     Pong = 1,
     ChannelMessageWithSource = 4,
+    DeferredChannelMessageWithSource = 5,
+    UpdateMessage = 7,
     ApplicationCommandAutocompleteResult = 8,
 }
 
+#[derive(Debug, Copy, Clone, Serialize_repr)]
+#[repr(u8)]
+pub enum ComponentType {
+    ActionRow = 1,
+    Button = 2,
+    StringSelect = 3,
+    ChannelSelect = 8,
+}
+
+#[derive(Debug, Copy, Clone, Serialize_repr)]
+#[repr(u8)]
+pub enum ButtonStyle {
+    Primary = 1,
+    Secondary = 2,
+    Success = 3,
+    Danger = 4,
+    Link = 5,
+    Premium = 6,
+}
+
 #[derive(Debug, Serialize)]
 pub struct InteractionResponse {
     #[serde(rename = "type")]
@@ -34,6 +59,54 @@ pub struct InteractionCallbackData {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub choices: Option<Vec<ApplicationCommandOptionChoice>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<Component>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embeds: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Component {
+    #[serde(rename = "type")]
+    pub kind: ComponentType,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<ButtonStyle>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sku_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<PartialEmoji>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<SelectOption>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_types: Option<Vec<u8>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<Component>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SelectOption {
+    pub label: String,
+    pub value: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -57,6 +130,8 @@ impl InteractionResponse {
                 content: Some(content.into()),
                 flags: None,
                 choices: None,
+                components: None,
+                embeds: None,
             }),
         }
     }
@@ -68,6 +143,52 @@ impl InteractionResponse {
                 content: Some(content.into()),
                 flags: Some(MessageFlags::EPHEMERAL.bits()),
                 choices: None,
+                components: None,
+                embeds: None,
+            }),
+        }
+    }
+
+    /// Acknowledges the interaction with a "thinking…" placeholder that the
+    /// queue worker later replaces via the interaction webhook.
+    pub fn deferred_ephemeral() -> Self {
+        Self {
+            kind: InteractionCallbackType::DeferredChannelMessageWithSource,
+            data: Some(InteractionCallbackData {
+                content: None,
+                flags: Some(MessageFlags::EPHEMERAL.bits()),
+                choices: None,
+                components: None,
+                embeds: None,
+            }),
+        }
+    }
+
+    /// An ephemeral message made of a single embed, e.g. from `json!`.
+    pub fn ephemeral_embed(embed: Value) -> Self {
+        Self {
+            kind: InteractionCallbackType::ChannelMessageWithSource,
+            data: Some(InteractionCallbackData {
+                content: None,
+                flags: Some(MessageFlags::EPHEMERAL.bits()),
+                choices: None,
+                components: None,
+                embeds: Some(vec![embed]),
+            }),
+        }
+    }
+
+    /// Replaces the message a clicked component is on. An empty
+    /// `components` removes the old ones.
+    pub fn update_message(content: impl Into<String>, components: Vec<Component>) -> Self {
+        Self {
+            kind: InteractionCallbackType::UpdateMessage,
+            data: Some(InteractionCallbackData {
+                content: Some(content.into()),
+                flags: None,
+                choices: None,
+                components: Some(components),
+                embeds: None,
             }),
         }
     }
@@ -79,7 +200,121 @@ impl InteractionResponse {
                 content: None,
                 flags: None,
                 choices: Some(choices),
+                components: None,
+                embeds: None,
             }),
         }
     }
+
+    /// Appends a line to the message content, if the response has any.
+    pub fn with_footer(mut self, footer: &str) -> Self {
+        if let Some(content) = self.data.as_mut().and_then(|d| d.content.as_mut()) {
+            content.push('\n');
+            content.push_str(footer);
+        }
+        self
+    }
+
+    pub fn with_components(mut self, components: Vec<Component>) -> Self {
+        if let Some(data) = self.data.as_mut() {
+            data.components = Some(components);
+        }
+        self
+    }
+}
+
+impl Component {
+    fn empty(kind: ComponentType) -> Self {
+        Self {
+            kind,
+            style: None,
+            label: None,
+            custom_id: None,
+            url: None,
+            sku_id: None,
+            emoji: None,
+            placeholder: None,
+            options: None,
+            channel_types: None,
+            components: None,
+        }
+    }
+
+    pub fn action_row(components: Vec<Component>) -> Self {
+        Self {
+            components: Some(components),
+            ..Self::empty(ComponentType::ActionRow)
+        }
+    }
+
+    pub fn button(
+        style: ButtonStyle,
+        label: impl Into<String>,
+        custom_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            style: Some(style),
+            label: Some(label.into()),
+            custom_id: Some(custom_id.into()),
+            ..Self::empty(ComponentType::Button)
+        }
+    }
+
+    /// A button showing only an emoji.
+    pub fn emoji_button(
+        style: ButtonStyle,
+        emoji: PartialEmoji,
+        custom_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            style: Some(style),
+            emoji: Some(emoji),
+            custom_id: Some(custom_id.into()),
+            ..Self::empty(ComponentType::Button)
+        }
+    }
+
+    pub fn link_button(label: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            style: Some(ButtonStyle::Link),
+            label: Some(label.into()),
+            url: Some(url.into()),
+            ..Self::empty(ComponentType::Button)
+        }
+    }
+
+    pub fn string_select(
+        custom_id: impl Into<String>,
+        placeholder: impl Into<String>,
+        options: Vec<SelectOption>,
+    ) -> Self {
+        Self {
+            custom_id: Some(custom_id.into()),
+            placeholder: Some(placeholder.into()),
+            options: Some(options),
+            ..Self::empty(ComponentType::StringSelect)
+        }
+    }
+
+    /// A select menu of the guild's channels, limited to `channel_types`.
+    pub fn channel_select(
+        custom_id: impl Into<String>,
+        placeholder: impl Into<String>,
+        channel_types: Vec<u8>,
+    ) -> Self {
+        Self {
+            custom_id: Some(custom_id.into()),
+            placeholder: Some(placeholder.into()),
+            channel_types: Some(channel_types),
+            ..Self::empty(ComponentType::ChannelSelect)
+        }
+    }
+
+    pub fn premium_button(sku_id: impl Into<String>) -> Self {
+        Self {
+            style: Some(ButtonStyle::Premium),
+            sku_id: Some(sku_id.into()),
+            ..Self::empty(ComponentType::Button)
+        }
+    }
 }
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use super::deferred_task::TaskOrigin;
+
+/// Adds or removes one role across a guild's members. Each task changes a
+/// bounded number of members, then re-queues the job with its cursor and
+/// running totals, so the job outlives any single worker invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassRoleJob {
+    pub origin: TaskOrigin,
+    pub role_id: String,
+    pub role_name: String,
+    /// `true` to remove the role, `false` to add it.
+    pub remove: bool,
+
+    /// Only members holding this role are changed.
+    #[serde(default)]
+    pub filter_role_id: Option<String>,
+
+    /// Last user ID checked; unset until the first member is.
+    #[serde(default)]
+    pub after: Option<String>,
+
+    #[serde(default)]
+    pub progress: MassRoleProgress,
+
+    /// Consecutive failed attempts at the current member.
+    #[serde(default)]
+    pub attempt: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MassRoleProgress {
+    /// Members looked at, whether or not they needed changing.
+    pub checked: u64,
+    pub changed: u64,
+    /// Members who left, or couldn't be changed, mid-job.
+    pub failed: u64,
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A guild's role member count in progress. Large guilds take more pages
+/// than one task should fetch, so the job is re-queued with its cursor and
+/// running totals until the member list is exhausted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemberCountJob {
+    pub guild_id: String,
+
+    /// Last user ID counted; unset on the first page.
+    #[serde(default)]
+    pub after: Option<String>,
+
+    /// Members holding each registered role so far, keyed by role ID.
+    #[serde(default)]
+    pub counts: HashMap<String, u64>,
+
+    /// Consecutive failed attempts at the current page.
+    #[serde(default)]
+    pub attempt: u32,
+}
+
+impl MemberCountJob {
+    pub fn new(guild_id: impl Into<String>) -> Self {
+        Self {
+            guild_id: guild_id.into(),
+            after: None,
+            counts: HashMap::new(),
+            attempt: 0,
+        }
+    }
+}
@@ -1,2 +1,20 @@
+pub mod activity_entry;
+pub mod age_requirement;
+pub mod app_event;
+pub mod application_command;
+pub mod audit_entry;
+pub mod batch_report;
+pub mod daily_stats;
+pub mod deferred_task;
+pub mod guild_event;
 pub mod interaction_request;
-pub mod interaction_response;
\ No newline at end of file
+pub mod interaction_response;
+pub mod mass_role_job;
+pub mod member_count;
+pub mod purge_job;
+pub mod role_announcement;
+pub mod role_connection;
+pub mod role_icon;
+pub mod role_job;
+pub mod role_menu;
+pub mod role_name;
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// What a `purge_expired` task deletes. A task deletes a bounded number of
+/// items and re-queues itself while more remain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "target", rename_all = "snake_case")]
+pub enum PurgeJob {
+    /// `GLOBAL`/`STATS#<date>` items past the stats retention.
+    Stats,
+    /// `USAGE#<month>` counters past the usage retention.
+    #[cfg(feature = "billing")]
+    Usage,
+    /// Every role table item of a guild tombstoned longer than the tombstone
+    /// retention.
+    Guild { guild_id: String },
+}
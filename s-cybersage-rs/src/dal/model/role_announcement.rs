@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::markdown;
+
+use super::interaction_response::{ButtonStyle, Component};
+
+/// Prefix of the `custom_id` of announcement Join buttons; the rest is the
+/// role ID.
+pub const JOIN_BUTTON_ID_PREFIX: &str = "roleannounce:";
+
+/// A public message advertising a self-assignable role, posted by
+/// `/role announce` right away or at a scheduled time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleAnnouncement {
+    pub guild_id: String,
+    pub channel_id: String,
+    pub role_id: String,
+    pub role_name: String,
+    /// Replaces the default blurb when set.
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+impl RoleAnnouncement {
+    /// The message body, with a Join button and mentions disabled so the
+    /// text can't ping anyone.
+    pub fn message(&self) -> Value {
+        let content = match &self.text {
+            Some(text) => text.clone(),
+            None => format!(
+                "**{}** is open to everyone. Press Join to pick it up.",
+                markdown::escape(&self.role_name)
+            ),
+        };
+
+        json!({
+            "content": content,
+            "allowed_mentions": { "parse": [] },
+            "components": [Component::action_row(vec![Component::button(
+                ButtonStyle::Primary,
+                "Join",
+                format!("{}{}", JOIN_BUTTON_ID_PREFIX, self.role_id),
+            )])],
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+/// How Discord compares a user's metadata value with the value a guild sets
+/// on a linked role.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum RoleConnectionMetadataType {
+    IntegerLessThanOrEqual = 1,
+    IntegerGreaterThanOrEqual = 2,
+    IntegerEqual = 3,
+    IntegerNotEqual = 4,
+    DatetimeLessThanOrEqual = 5,
+    DatetimeGreaterThanOrEqual = 6,
+    BooleanEqual = 7,
+    BooleanNotEqual = 8,
+}
+
+/// A criterion guilds can require on a linked role, as registered with
+/// Discord's role-connection metadata endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoleConnectionMetadata {
+    #[serde(rename = "type")]
+    pub kind: RoleConnectionMetadataType,
+
+    pub key: String,
+    pub name: String,
+    pub description: String,
+}
+
+impl RoleConnectionMetadata {
+    pub fn new(
+        kind: RoleConnectionMetadataType,
+        key: impl Into<String>,
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            key: key.into(),
+            name: name.into(),
+            description: description.into(),
+        }
+    }
+}
+
+/// A user's values for the registered metadata, as pushed to Discord.
+/// Booleans are sent as `1` or `0`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoleConnection {
+    pub platform_name: String,
+
+    pub metadata: BTreeMap<String, i64>,
+}
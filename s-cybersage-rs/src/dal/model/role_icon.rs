@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+const ROLE_ICON_CDN: &str = "https://cdn.discordapp.com/role-icons";
+
+/// A role's icon: an uploaded image, a unicode emoji, or neither. Guilds
+/// need boost level 2 to set either.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleIcon {
+    /// Image hash, for the CDN URL.
+    #[serde(default)]
+    pub icon: Option<String>,
+
+    #[serde(default)]
+    pub unicode_emoji: Option<String>,
+}
+
+impl RoleIcon {
+    pub fn is_empty(&self) -> bool {
+        self.icon.is_none() && self.unicode_emoji.is_none()
+    }
+
+    pub fn url(&self, role_id: &str) -> Option<String> {
+        self.icon
+            .as_deref()
+            .map(|hash| format!("{}/{}/{}.png", ROLE_ICON_CDN, role_id, hash))
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// A role modification that couldn't be applied within the interaction's
+/// response budget, queued for the retry worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleModificationJob {
+    pub guild_id: String,
+    pub user_id: String,
+    pub role_id: String,
+    pub role_name: String,
+    /// `true` to remove the role, `false` to add it.
+    pub remove: bool,
+    pub application_id: String,
+    pub interaction_token: String,
+
+    #[serde(default)]
+    pub attempt: u32,
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+
+use super::interaction_response::{ButtonStyle, Component};
+
+/// Discord allows five rows of five buttons on a message.
+pub const MAX_MENU_BUTTONS: usize = 25;
+const BUTTONS_PER_ROW: usize = 5;
+
+/// Prefix of the `custom_id` of role menu buttons; the rest is the role ID.
+pub const BUTTON_ID_PREFIX: &str = "rolemenu:";
+
+/// A bot-posted message whose buttons toggle roles, like classic reaction
+/// roles.
+#[derive(Debug, Clone)]
+pub struct RoleMenu {
+    pub message_id: String,
+    pub channel_id: String,
+    /// In the order they were bound, which is the order of the buttons.
+    pub bindings: Vec<EmojiBinding>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmojiBinding {
+    /// As typed, e.g. `🎮` or `<:pepe:123>`.
+    pub emoji: String,
+    pub role_id: String,
+    pub role_name: String,
+    /// Unix seconds.
+    pub bound_at: i64,
+}
+
+impl RoleMenu {
+    pub fn binding_for_role(&self, role_id: &str) -> Option<&EmojiBinding> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.role_id == role_id)
+    }
+
+    pub fn binding_for_emoji(&self, emoji: &str) -> Option<&EmojiBinding> {
+        self.bindings.iter().find(|binding| binding.emoji == emoji)
+    }
+
+    /// The menu's buttons, one per binding, as rows for the message's
+    /// `components`.
+    pub fn components(&self) -> Vec<Component> {
+        let buttons: Vec<Component> = self
+            .bindings
+            .iter()
+            .filter_map(|binding| {
+                Some(Component::emoji_button(
+                    ButtonStyle::Secondary,
+                    PartialEmoji::parse(&binding.emoji)?,
+                    format!("{}{}", BUTTON_ID_PREFIX, binding.role_id),
+                ))
+            })
+            .collect();
+
+        let mut rows = Vec::new();
+        let mut buttons = buttons.into_iter().peekable();
+        while buttons.peek().is_some() {
+            rows.push(Component::action_row(
+                buttons.by_ref().take(BUTTONS_PER_ROW).collect(),
+            ));
+        }
+        rows
+    }
+}
+
+/// An emoji as Discord expects it on a button: `name` alone for unicode,
+/// `id` and `name` for custom emoji.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PartialEmoji {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub animated: bool,
+}
+
+impl PartialEmoji {
+    /// Parses a custom emoji as sent by the client (`<:name:id>` or
+    /// `<a:name:id>`) or a unicode emoji. Plain words are rejected, since a
+    /// button can't show them as an emoji.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+
+        if let Some(inner) = input.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            let (animated, rest) = match inner.strip_prefix("a:") {
+                Some(rest) => (true, rest),
+                None => (false, inner.strip_prefix(':')?),
+            };
+            let (name, id) = rest.split_once(':')?;
+
+            if name.is_empty() || id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+
+            return Some(Self {
+                id: Some(id.to_string()),
+                name: name.to_string(),
+                animated,
+            });
+        }
+
+        // Keycaps such as 1️⃣ start with an ASCII digit, so only letters and
+        // whitespace rule out an emoji.
+        let is_unicode_emoji = input.chars().count() <= 8
+            && !input.is_ascii()
+            && input
+                .chars()
+                .all(|c| !c.is_ascii_alphabetic() && !c.is_whitespace());
+
+        is_unicode_emoji.then(|| Self {
+            id: None,
+            name: input.to_string(),
+            animated: false,
+        })
+    }
+}
@@ -0,0 +1,42 @@
+/// Discord caps autocomplete choice names at 100 characters, and a role is
+/// offered under its stored name.
+pub const MAX_ROLE_NAME_CHARS: usize = 100;
+
+/// Why a role's name can't be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidRoleName {
+    Blank,
+    TooLong,
+    ControlCharacters,
+}
+
+impl InvalidRoleName {
+    pub fn reason(self) -> &'static str {
+        match self {
+            InvalidRoleName::Blank => "the name is blank",
+            InvalidRoleName::TooLong => "the name is longer than 100 characters",
+            InvalidRoleName::ControlCharacters => "the name contains control characters",
+        }
+    }
+}
+
+/// The name to store for a role: trimmed, so lookups by typed name and the
+/// `role_name_normalized` index key match what members see, and checked to
+/// be a valid autocomplete choice name.
+pub fn normalize(name: &str) -> Result<&str, InvalidRoleName> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err(InvalidRoleName::Blank);
+    }
+
+    if name.chars().count() > MAX_ROLE_NAME_CHARS {
+        return Err(InvalidRoleName::TooLong);
+    }
+
+    if name.chars().any(char::is_control) {
+        return Err(InvalidRoleName::ControlCharacters);
+    }
+
+    Ok(name)
+}
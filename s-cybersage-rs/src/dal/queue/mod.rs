@@ -0,0 +1,2 @@
+pub mod task_queue;
+pub mod task_scheduler;
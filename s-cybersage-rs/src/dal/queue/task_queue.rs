@@ -0,0 +1,80 @@
+use anyhow::{bail, Context, Result};
+use aws_sdk_sqs::{types::SendMessageBatchRequestEntry, Client};
+use tracing::instrument;
+
+use crate::dal::model::deferred_task::DeferredTask;
+
+/// SQS accepts at most this many messages per batch request.
+const MAX_BATCH_SIZE: usize = 10;
+
+/// Producer side of the deferred work queue consumed by `sqs_handler`.
+#[derive(Clone)]
+pub struct TaskQueue {
+    client: Client,
+    queue_url: String,
+}
+
+impl TaskQueue {
+    pub fn new(client: Client, queue_url: impl Into<String>) -> Self {
+        Self {
+            client,
+            queue_url: queue_url.into(),
+        }
+    }
+
+    #[instrument(skip(self, task), fields(kind = task.kind()))]
+    pub async fn enqueue(&self, task: &DeferredTask, delay_seconds: i32) -> Result<()> {
+        let body = serde_json::to_string(task).context("Failed to serialize deferred task")?;
+
+        self.client
+            .send_message()
+            .queue_url(&self.queue_url)
+            .message_body(body)
+            .delay_seconds(delay_seconds)
+            .send()
+            .await
+            .context("Failed to enqueue deferred task")?;
+
+        Ok(())
+    }
+
+    /// Enqueues many tasks with as few requests as possible.
+    #[instrument(skip(self, tasks), fields(tasks = tasks.len()))]
+    pub async fn enqueue_all(&self, tasks: &[DeferredTask]) -> Result<()> {
+        for chunk in tasks.chunks(MAX_BATCH_SIZE) {
+            let entries = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, task)| {
+                    let body =
+                        serde_json::to_string(task).context("Failed to serialize deferred task")?;
+
+                    SendMessageBatchRequestEntry::builder()
+                        .id(i.to_string())
+                        .message_body(body)
+                        .build()
+                        .context("Failed to build batch entry")
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let response = self
+                .client
+                .send_message_batch()
+                .queue_url(&self.queue_url)
+                .set_entries(Some(entries))
+                .send()
+                .await
+                .context("Failed to enqueue deferred tasks")?;
+
+            if !response.failed.is_empty() {
+                bail!(
+                    "Failed to enqueue {} of {} deferred tasks",
+                    response.failed.len(),
+                    chunk.len()
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use aws_sdk_scheduler::{
+    types::{ActionAfterCompletion, FlexibleTimeWindow, FlexibleTimeWindowMode, Target},
+    Client,
+};
+use chrono::{DateTime, Utc};
+use tracing::instrument;
+
+use crate::dal::model::deferred_task::DeferredTask;
+
+/// Queues deferred tasks for a later time than SQS's 15-minute delay allows,
+/// as one-shot EventBridge Scheduler schedules that send the task to the
+/// task queue and then delete themselves.
+#[derive(Clone)]
+pub struct TaskScheduler {
+    client: Client,
+    queue_arn: String,
+    /// The role Scheduler assumes to send to the queue.
+    role_arn: String,
+}
+
+impl TaskScheduler {
+    pub fn new(client: Client, queue_arn: impl Into<String>, role_arn: impl Into<String>) -> Self {
+        Self {
+            client,
+            queue_arn: queue_arn.into(),
+            role_arn: role_arn.into(),
+        }
+    }
+
+    /// `name` must be unique among pending schedules; an interaction ID
+    /// works.
+    #[instrument(skip(self, task), fields(kind = task.kind()))]
+    pub async fn schedule(&self, name: &str, at: DateTime<Utc>, task: &DeferredTask) -> Result<()> {
+        let body = serde_json::to_string(task).context("Failed to serialize deferred task")?;
+
+        let target = Target::builder()
+            .arn(&self.queue_arn)
+            .role_arn(&self.role_arn)
+            .input(body)
+            .build()
+            .context("Failed to build schedule target")?;
+
+        let window = FlexibleTimeWindow::builder()
+            .mode(FlexibleTimeWindowMode::Off)
+            .build()
+            .context("Failed to build schedule window")?;
+
+        self.client
+            .create_schedule()
+            .name(name)
+            .schedule_expression(format!("at({})", at.format("%Y-%m-%dT%H:%M:%S")))
+            .schedule_expression_timezone("UTC")
+            .flexible_time_window(window)
+            .target(target)
+            .action_after_completion(ActionAfterCompletion::Delete)
+            .send()
+            .await
+            .context("Failed to create schedule")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::secrets_provider::{parse_secret_string, SecretsProvider};
+
+/// Reads secrets from environment variables, for local development and
+/// self-hosting. The secret ID is the variable name, e.g.
+/// `DISCORD_TOKEN_SECRET_ARN=DISCORD_TOKEN` reads `$DISCORD_TOKEN`.
+#[derive(Debug, Default)]
+pub struct EnvProvider;
+
+#[async_trait]
+impl SecretsProvider for EnvProvider {
+    async fn fetch_secret(&self, secret_id: &str) -> Result<Value> {
+        let raw = std::env::var(secret_id)
+            .with_context(|| format!("Environment variable '{}' is not set", secret_id))?;
+
+        Ok(parse_secret_string(&raw))
+    }
+}
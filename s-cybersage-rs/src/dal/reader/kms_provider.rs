@@ -0,0 +1,119 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_kms::primitives::Blob;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use tracing::instrument;
+
+use super::secrets_provider::{parse_secret_string, SecretsProvider};
+use crate::{dal::dao::feature_flag::GLOBAL_PARTITION, error::StorageError};
+
+/// Where `KmsProvider` reads a secret's ciphertext from.
+pub enum CiphertextSource {
+    /// A parameter named by the secret ID, holding base64 ciphertext. Plain
+    /// `String` parameters are enough, since the value is already encrypted.
+    Ssm(aws_sdk_ssm::Client),
+    /// The `ciphertext` attribute (base64 string or binary) of the
+    /// `GLOBAL`/`SECRET#<secret_id>` item in the role table.
+    DynamoDb {
+        client: aws_sdk_dynamodb::Client,
+        table_name: String,
+    },
+}
+
+/// Reads secrets stored as KMS ciphertext, for deployments that would rather
+/// pay for KMS requests than Secrets Manager's per-secret fee. Each secret is
+/// encrypted directly with the KMS key (up to 4 KB of plaintext, plenty for a
+/// token or key document) and decrypted on first use, i.e. during init for
+/// the prefetched ones.
+pub struct KmsProvider {
+    kms: aws_sdk_kms::Client,
+    source: CiphertextSource,
+    /// Refuses ciphertext made with any other key, when set.
+    key_id: Option<String>,
+}
+
+impl KmsProvider {
+    pub fn new(kms: aws_sdk_kms::Client, source: CiphertextSource, key_id: Option<String>) -> Self {
+        Self {
+            kms,
+            source,
+            key_id,
+        }
+    }
+
+    async fn ciphertext(&self, secret_id: &str) -> Result<Vec<u8>> {
+        match &self.source {
+            CiphertextSource::Ssm(client) => {
+                let response = client
+                    .get_parameter()
+                    .name(secret_id)
+                    .send()
+                    .await
+                    .context("Failed to retrieve ciphertext parameter from SSM")?;
+
+                let value = response
+                    .parameter()
+                    .and_then(|p| p.value())
+                    .context("Parameter value is missing")?;
+
+                decode_base64(value)
+            }
+            CiphertextSource::DynamoDb { client, table_name } => {
+                let response = client
+                    .get_item()
+                    .table_name(table_name)
+                    .key("guild_id", AttributeValue::S(GLOBAL_PARTITION.to_string()))
+                    .key(
+                        "mapping_key",
+                        AttributeValue::S(format!("SECRET#{}", secret_id)),
+                    )
+                    .send()
+                    .await
+                    .map_err(|err| StorageError::from_sdk("get secret ciphertext", err))?;
+
+                match response
+                    .item
+                    .as_ref()
+                    .and_then(|item| item.get("ciphertext"))
+                {
+                    Some(AttributeValue::B(blob)) => Ok(blob.clone().into_inner()),
+                    Some(AttributeValue::S(encoded)) => decode_base64(encoded),
+                    _ => bail!("No ciphertext stored for secret '{}'", secret_id),
+                }
+            }
+        }
+    }
+}
+
+fn decode_base64(encoded: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(encoded.trim())
+        .context("Ciphertext is not valid base64")
+}
+
+#[async_trait]
+impl SecretsProvider for KmsProvider {
+    #[instrument(skip(self))]
+    async fn fetch_secret(&self, secret_id: &str) -> Result<Value> {
+        let ciphertext = self.ciphertext(secret_id).await?;
+
+        let response = self
+            .kms
+            .decrypt()
+            .ciphertext_blob(Blob::new(ciphertext))
+            .set_key_id(self.key_id.clone())
+            .send()
+            .await
+            .context("Failed to decrypt secret with KMS")?;
+
+        let plaintext = response
+            .plaintext
+            .context("KMS returned no plaintext")?
+            .into_inner();
+        let plaintext = String::from_utf8(plaintext).context("Decrypted secret is not UTF-8")?;
+
+        Ok(parse_secret_string(&plaintext))
+    }
+}
@@ -1 +1,9 @@
-pub mod secrets_reader;
\ No newline at end of file
+pub mod env_provider;
+pub mod kms_provider;
+pub mod parameter_reader;
+pub mod secret_bundle;
+pub mod secrets_manager_provider;
+pub mod secrets_provider;
+pub mod secrets_reader;
+pub mod ssm_provider;
+pub mod tenant_secrets;
@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use aws_sdk_ssm::Client;
+use once_cell::sync::Lazy;
+use tracing::{instrument, warn};
+
+/// Parameter names under this prefix (relative to the path) are feature flag
+/// overrides, e.g. `flags/toggle_retry_queue`.
+const FLAG_PREFIX: &str = "flags/";
+const PARAMETERS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+static PARAMETERS_CACHE: Lazy<Mutex<Option<(ConfigParameters, Instant)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+fn cached_parameters(max_age: Duration) -> Option<ConfigParameters> {
+    let cache = PARAMETERS_CACHE.lock().ok()?;
+    let (parameters, cached_at) = cache.as_ref()?;
+
+    if cached_at.elapsed() > max_age {
+        return None;
+    }
+
+    Some(parameters.clone())
+}
+
+fn cache_parameters(parameters: &ConfigParameters) {
+    if let Ok(mut cache) = PARAMETERS_CACHE.lock() {
+        *cache = Some((parameters.clone(), Instant::now()));
+    }
+}
+
+/// Non-secret settings read from Parameter Store, keyed by their name
+/// relative to `CONFIG_PARAMETER_PATH`, e.g. `free_tier_monthly_toggles`.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigParameters {
+    values: HashMap<String, String>,
+}
+
+impl ConfigParameters {
+    pub fn new(values: HashMap<String, String>) -> Self {
+        Self { values }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .get(name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    pub fn parse<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        let value = self.get(name)?;
+
+        match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                warn!(name, value, "Ignoring unparseable config parameter");
+                None
+            }
+        }
+    }
+
+    /// The `flags/<key>` parameters set to `true` or `false`.
+    pub fn flag_overrides(&self) -> HashMap<String, bool> {
+        self.values
+            .keys()
+            .filter_map(|name| {
+                let key = name.strip_prefix(FLAG_PREFIX)?;
+                Some((key.to_string(), self.parse(name)?))
+            })
+            .collect()
+    }
+}
+
+/// Reads every parameter under a path, cached per warm function for a
+/// minute. Without a path it reads nothing, so deployments that don't use
+/// Parameter Store make no calls.
+#[derive(Clone)]
+pub struct ParameterReader {
+    client: Client,
+    path: Option<String>,
+}
+
+impl ParameterReader {
+    pub fn new(client: Client, path: Option<String>) -> Self {
+        Self { client, path }
+    }
+
+    /// The current parameters. If they can't be read, the last ones read
+    /// are used, or none, so a Parameter Store outage never blocks
+    /// interactions.
+    pub async fn load(&self) -> ConfigParameters {
+        let Some(path) = self.path.as_deref() else {
+            return ConfigParameters::default();
+        };
+
+        if let Some(parameters) = cached_parameters(PARAMETERS_CACHE_TTL) {
+            return parameters;
+        }
+
+        match self.fetch(path).await {
+            Ok(parameters) => {
+                cache_parameters(&parameters);
+                parameters
+            }
+            Err(err) => {
+                warn!(
+                    error = format!("{:#}", err),
+                    "Failed to load config parameters"
+                );
+                cached_parameters(Duration::MAX).unwrap_or_default()
+            }
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn fetch(&self, path: &str) -> Result<ConfigParameters> {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        let mut values = HashMap::new();
+        let mut next_token = None;
+
+        loop {
+            let response = self
+                .client
+                .get_parameters_by_path()
+                .path(&prefix)
+                .recursive(true)
+                .set_next_token(next_token)
+                .send()
+                .await
+                .context("Failed to read parameters from SSM")?;
+
+            for parameter in response.parameters() {
+                if let (Some(name), Some(value)) = (parameter.name(), parameter.value()) {
+                    let name = name.strip_prefix(&prefix).unwrap_or(name);
+                    values.insert(name.to_string(), value.to_string());
+                }
+            }
+
+            next_token = response.next_token().map(str::to_string);
+
+            if next_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(ConfigParameters::new(values))
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+
+use anyhow::{bail, Context, Result};
+use ed25519_dalek::VerifyingKey;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::bal::auth::verify::AuthManager;
+
+/// The consolidated secret's document, as stored.
+#[derive(Deserialize)]
+struct StoredBundle {
+    discord_token: String,
+    /// More than one while the application's key is being rotated.
+    discord_public_keys: Vec<String>,
+    application_id: String,
+    #[serde(default)]
+    oauth_client_secret: Option<String>,
+    #[serde(default)]
+    stripe_secret_key: Option<String>,
+}
+
+/// Every credential a function needs, read from one secret
+/// (`SECRET_BUNDLE_ARN`) and validated during init, so a malformed secret
+/// fails the deploy's first cold start instead of a user's command.
+#[derive(Clone)]
+pub struct SecretBundle {
+    pub discord_token: String,
+    pub discord_public_keys: Vec<VerifyingKey>,
+    pub application_id: String,
+    pub oauth_client_secret: Option<String>,
+    pub stripe_secret_key: Option<String>,
+}
+
+impl SecretBundle {
+    pub fn from_document(document: Value) -> Result<Self> {
+        let stored: StoredBundle =
+            serde_json::from_value(document).context("Secret bundle has the wrong shape")?;
+
+        if stored.discord_token.trim().is_empty() {
+            bail!("Secret bundle's discord_token is empty");
+        }
+        if stored.application_id.trim().is_empty() {
+            bail!("Secret bundle's application_id is empty");
+        }
+        if stored.discord_public_keys.is_empty() {
+            bail!("Secret bundle has no discord_public_keys");
+        }
+
+        let discord_public_keys = stored
+            .discord_public_keys
+            .iter()
+            .map(|hex| AuthManager::parse_public_key(hex))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Secret bundle has an invalid public key")?;
+
+        let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+
+        Ok(Self {
+            discord_token: stored.discord_token,
+            discord_public_keys,
+            application_id: stored.application_id,
+            oauth_client_secret: non_empty(stored.oauth_client_secret),
+            stripe_secret_key: non_empty(stored.stripe_secret_key),
+        })
+    }
+}
+
+/// Keeps the credentials out of logs.
+impl fmt::Debug for SecretBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretBundle")
+            .field("application_id", &self.application_id)
+            .field("discord_public_keys", &self.discord_public_keys.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_sdk_secretsmanager::Client;
+use serde_json::Value;
+use tracing::{instrument, warn};
+
+use super::secrets_provider::SecretsProvider;
+
+/// The most secret IDs one `BatchGetSecretValue` call accepts.
+const MAX_BATCH_SECRETS: usize = 20;
+
+/// Reads secrets from AWS Secrets Manager by ARN or name.
+pub struct SecretsManagerProvider {
+    client: Client,
+}
+
+impl SecretsManagerProvider {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for SecretsManagerProvider {
+    #[instrument(skip(self))]
+    async fn fetch_secret(&self, secret_id: &str) -> Result<Value> {
+        let response = self
+            .client
+            .get_secret_value()
+            .secret_id(secret_id)
+            .send()
+            .await
+            .context("Failed to retrieve secret value from Secrets Manager")?;
+
+        let secret_str = response
+            .secret_string()
+            .context("Secret value is missing or not a string")?;
+
+        serde_json::from_str(secret_str).context("Failed to parse secret string as JSON")
+    }
+
+    /// One `BatchGetSecretValue` call per 20 secrets, the most its
+    /// `SecretIdList` takes. Each entry is matched back to the ID asked for
+    /// by its ARN or name.
+    #[instrument(skip(self))]
+    async fn fetch_secrets(&self, secret_ids: &[String]) -> Result<HashMap<String, Value>> {
+        let mut documents = HashMap::new();
+
+        for chunk in secret_ids.chunks(MAX_BATCH_SECRETS) {
+            let mut next_token = None;
+
+            loop {
+                let response = self
+                    .client
+                    .batch_get_secret_value()
+                    .set_secret_id_list(Some(chunk.to_vec()))
+                    .set_next_token(next_token)
+                    .send()
+                    .await
+                    .context("Failed to batch retrieve secret values from Secrets Manager")?;
+
+                for error in response.errors() {
+                    warn!(
+                        secret_id = error.secret_id().unwrap_or_default(),
+                        code = error.error_code().unwrap_or_default(),
+                        "Secrets Manager couldn't return a secret in the batch"
+                    );
+                }
+
+                for entry in response.secret_values() {
+                    let Some(secret_id) = chunk.iter().find(|id| {
+                        Some(id.as_str()) == entry.arn() || Some(id.as_str()) == entry.name()
+                    }) else {
+                        continue;
+                    };
+
+                    let secret_str = entry
+                        .secret_string()
+                        .context("Secret value is missing or not a string")?;
+                    let json = serde_json::from_str(secret_str)
+                        .context("Failed to parse secret string as JSON")?;
+
+                    documents.insert(secret_id.clone(), json);
+                }
+
+                next_token = response.next_token().map(str::to_string);
+
+                if next_token.is_none() {
+                    break;
+                }
+            }
+        }
+
+        Ok(documents)
+    }
+}
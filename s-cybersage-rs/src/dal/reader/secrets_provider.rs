@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// A source of secret documents. Documents are usually JSON objects whose
+/// fields `SecretsReader` extracts; backends may also return a plain string,
+/// which is used as the value for any field.
+#[async_trait]
+pub trait SecretsProvider: Send + Sync {
+    async fn fetch_secret(&self, secret_id: &str) -> Result<Value>;
+
+    /// Fetches several documents, keyed by the IDs asked for. Backends with
+    /// a batch API override this to make a single call; IDs they couldn't
+    /// read are left out.
+    async fn fetch_secrets(&self, secret_ids: &[String]) -> Result<HashMap<String, Value>> {
+        let mut documents = HashMap::new();
+
+        for secret_id in secret_ids {
+            documents.insert(secret_id.clone(), self.fetch_secret(secret_id).await?);
+        }
+
+        Ok(documents)
+    }
+}
+
+/// Which `SecretsProvider` to use, chosen by `SECRETS_BACKEND`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SecretsBackend {
+    #[default]
+    SecretsManager,
+    Ssm,
+    Env,
+    /// KMS ciphertext stored in SSM parameters.
+    KmsSsm,
+    /// KMS ciphertext stored on `GLOBAL`/`SECRET#<id>` items in the role
+    /// table.
+    KmsDynamoDb,
+}
+
+impl SecretsBackend {
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "" | "secretsmanager" | "secrets-manager" => Ok(SecretsBackend::SecretsManager),
+            "ssm" => Ok(SecretsBackend::Ssm),
+            "env" => Ok(SecretsBackend::Env),
+            "kms-ssm" => Ok(SecretsBackend::KmsSsm),
+            "kms-dynamodb" => Ok(SecretsBackend::KmsDynamoDb),
+            other => bail!("Unknown secrets backend '{}'", other),
+        }
+    }
+}
+
+/// Parses a stored secret string: JSON documents are returned as-is, anything
+/// else as a plain string value.
+pub fn parse_secret_string(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
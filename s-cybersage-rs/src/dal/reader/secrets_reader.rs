@@ -1,47 +1,199 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use super::secrets_provider::SecretsProvider;
+use crate::snapshot::RestoreScoped;
 use anyhow::{Context, Result};
-use aws_sdk_secretsmanager::Client;
 use serde_json::Value;
-use tokio::sync::OnceCell;
+use tracing::{info, warn};
 
-#[derive(Clone)]
-pub struct SecretsReader {
-    client: Client,
+/// How long a secret document is served before it's refetched, so a rotated
+/// secret reaches warm functions without a restart.
+pub const SECRET_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// One secret document, cached for `SECRET_TTL` and until the process is
+/// restored from a snapshot.
+pub struct SecretCache {
+    document: RestoreScoped<(Value, Instant)>,
+    refreshing: AtomicBool,
 }
 
-impl SecretsReader {
-    pub fn new(client: Client) -> Self {
-        Self { client }
+impl SecretCache {
+    pub const fn new() -> Self {
+        Self {
+            document: RestoreScoped::new(),
+            refreshing: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether a document is cached, fresh or not.
+    pub fn is_current(&self) -> bool {
+        self.document.is_current()
+    }
+
+    /// The cached document, and whether it's older than `SECRET_TTL`.
+    fn get(&self) -> Option<(Value, bool)> {
+        self.document
+            .get()
+            .map(|(json, fetched_at)| (json, fetched_at.elapsed() >= SECRET_TTL))
+    }
+
+    fn set(&self, json: Value) {
+        self.document.set((json, Instant::now()));
     }
+}
+
+impl Default for SecretCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    async fn fetch_secret_json(&self, secret_id: &str) -> Result<Value> {
-        let response = self
-            .client
-            .get_secret_value()
-            .secret_id(secret_id)
-            .send()
-            .await
-            .context("Failed to retrieve secret value from Secrets Manager")?;
+/// Where one credential comes from: a field of its own secret, read through
+/// that secret's cache, or the value already loaded from the secret bundle.
+#[derive(Clone)]
+pub enum SecretSource {
+    Field {
+        reader: SecretsReader,
+        secret_id: String,
+        key: &'static str,
+        cache: &'static SecretCache,
+    },
+    Loaded(String),
+}
 
-        let secret_str = response
-            .secret_string()
-            .context("Secret value is missing or not a string")?;
+impl SecretSource {
+    pub async fn get(&self) -> Result<String> {
+        match self {
+            SecretSource::Field {
+                reader,
+                secret_id,
+                key,
+                cache,
+            } => reader.get_secret_value(secret_id, key, cache).await,
+            SecretSource::Loaded(value) => Ok(value.clone()),
+        }
+    }
+}
+
+/// Looks up fields of secret documents from the configured provider. Once a
+/// cached document is past its TTL it's still served while one background
+/// task refetches it, so callers never wait on a refresh; a failed refresh
+/// keeps the old document and is retried on the next lookup.
+#[derive(Clone)]
+pub struct SecretsReader {
+    provider: Arc<dyn SecretsProvider>,
+}
 
-        serde_json::from_str(secret_str).context("Failed to parse secret string as JSON")
+impl SecretsReader {
+    pub fn new(provider: Arc<dyn SecretsProvider>) -> Self {
+        Self { provider }
     }
 
     pub async fn get_secret_value(
         &self,
         secret_id: &str,
         key: &str,
-        cache: &OnceCell<Value>,
+        cache: &'static SecretCache,
     ) -> Result<String> {
-        let json = cache
-            .get_or_try_init(|| async { self.fetch_secret_json(secret_id).await })
-            .await?;
+        let json = match cache.get() {
+            Some((json, stale)) => {
+                if stale {
+                    self.refresh_in_background(secret_id, cache);
+                }
+                json
+            }
+            None => {
+                let json = self.provider.fetch_secret(secret_id).await?;
+                cache.set(json.clone());
+                json
+            }
+        };
+
+        field(&json, key)
+    }
+
+    /// Reads several fields at once, fetching the documents that aren't
+    /// cached in a single batch where the backend supports it. Values come
+    /// back in the order asked for.
+    pub async fn get_secrets(
+        &self,
+        secrets: &[(&str, &str, &'static SecretCache)],
+    ) -> Result<Vec<String>> {
+        let mut missing: Vec<String> = Vec::new();
+        for (secret_id, _, cache) in secrets {
+            if !cache.is_current() && !missing.iter().any(|id| id == secret_id) {
+                missing.push(secret_id.to_string());
+            }
+        }
+
+        if !missing.is_empty() {
+            let documents = self.provider.fetch_secrets(&missing).await?;
+
+            for (secret_id, _, cache) in secrets {
+                if let Some(json) = documents.get(*secret_id) {
+                    cache.set(json.clone());
+                }
+            }
+        }
+
+        let mut values = Vec::with_capacity(secrets.len());
+        for (secret_id, key, cache) in secrets {
+            values.push(self.get_secret_value(secret_id, key, cache).await?);
+        }
+
+        Ok(values)
+    }
 
-        json.get(key)
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .context(format!("Key '{}' not found in secret JSON", key))
+    /// Reads a whole document without caching; callers that need it again
+    /// keep their own copy.
+    pub async fn fetch_secret(&self, secret_id: &str) -> Result<Value> {
+        self.provider.fetch_secret(secret_id).await
     }
+
+    /// Reads a field without caching, for one-off checks.
+    pub async fn fetch_secret_value(&self, secret_id: &str, key: &str) -> Result<String> {
+        field(&self.provider.fetch_secret(secret_id).await?, key)
+    }
+
+    fn refresh_in_background(&self, secret_id: &str, cache: &'static SecretCache) {
+        if cache.refreshing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let provider = self.provider.clone();
+        let secret_id = secret_id.to_string();
+
+        tokio::spawn(async move {
+            match provider.fetch_secret(&secret_id).await {
+                Ok(json) => {
+                    cache.set(json);
+                    info!(secret_id, "Refreshed cached secret");
+                }
+                Err(err) => warn!(
+                    secret_id,
+                    error = format!("{:#}", err),
+                    "Failed to refresh secret; serving the cached one"
+                ),
+            }
+
+            cache.refreshing.store(false, Ordering::Release);
+        });
+    }
+}
+
+fn field(json: &Value, key: &str) -> Result<String> {
+    if let Some(value) = json.as_str() {
+        return Ok(value.to_string());
+    }
+
+    json.get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .context(format!("Key '{}' not found in secret JSON", key))
 }
@@ -0,0 +1,41 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_sdk_ssm::Client;
+use serde_json::Value;
+use tracing::instrument;
+
+use super::secrets_provider::{parse_secret_string, SecretsProvider};
+
+/// Reads secrets from SSM Parameter Store. The secret ID is the parameter
+/// name; `SecureString` parameters are decrypted.
+pub struct SsmProvider {
+    client: Client,
+}
+
+impl SsmProvider {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for SsmProvider {
+    #[instrument(skip(self))]
+    async fn fetch_secret(&self, secret_id: &str) -> Result<Value> {
+        let response = self
+            .client
+            .get_parameter()
+            .name(secret_id)
+            .with_decryption(true)
+            .send()
+            .await
+            .context("Failed to retrieve parameter from SSM")?;
+
+        let value = response
+            .parameter()
+            .and_then(|p| p.value())
+            .context("Parameter value is missing")?;
+
+        Ok(parse_secret_string(value))
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use ed25519_dalek::VerifyingKey;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tracing::{instrument, warn};
+
+use super::secrets_reader::{SecretsReader, SECRET_TTL};
+use crate::bal::auth::verify::AuthManager;
+
+/// How long an application ID without a readable secret is remembered, so
+/// requests naming unknown applications don't each cost a secrets call.
+const MISSING_TENANT_TTL: Duration = Duration::from_secs(60);
+const TENANT_CACHE_MAX_ENTRIES: usize = 256;
+
+/// A lookup's result, `None` when the application had no readable secret.
+type CachedTenant = (Option<TenantCredentials>, Instant);
+
+static TENANT_CACHE: Lazy<Mutex<HashMap<String, CachedTenant>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A tenant's secret document, as stored.
+#[derive(Deserialize)]
+struct StoredTenant {
+    token: String,
+    key: String,
+}
+
+/// One white-label application's bot token and interactions public key.
+#[derive(Clone)]
+pub struct TenantCredentials {
+    pub token: String,
+    pub public_key: VerifyingKey,
+}
+
+/// Keeps the token out of logs.
+impl fmt::Debug for TenantCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TenantCredentials").finish_non_exhaustive()
+    }
+}
+
+/// Credentials for white-label applications, each in its own secret named
+/// `<TENANT_SECRET_PREFIX><application_id>`. Looked up by the application ID
+/// of the interaction being handled and cached per warm function like the
+/// other secrets. Only applications on the allowlist are looked up.
+#[derive(Clone)]
+pub struct TenantSecrets {
+    reader: SecretsReader,
+    prefix: String,
+    application_ids: HashSet<String>,
+}
+
+impl TenantSecrets {
+    pub fn new(
+        reader: SecretsReader,
+        prefix: impl Into<String>,
+        application_ids: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Self {
+            reader,
+            prefix: prefix.into(),
+            application_ids: application_ids.into_iter().collect(),
+        }
+    }
+
+    /// The application's credentials. The ID comes from a request body that
+    /// may not be verified yet, so anything off the allowlist is refused
+    /// before the cache or Secrets Manager is touched.
+    pub async fn get(&self, application_id: &str) -> Result<TenantCredentials> {
+        if !self.application_ids.contains(application_id) {
+            bail!("Application {} is not a configured tenant", application_id);
+        }
+
+        if let Some(cached) = cached_tenant(application_id) {
+            return cached.context("No credentials for this application");
+        }
+
+        let credentials = self.fetch(application_id).await;
+
+        match &credentials {
+            Ok(credentials) => cache_tenant(application_id, Some(credentials.clone())),
+            Err(err) => {
+                warn!(
+                    application_id,
+                    error = format!("{:#}", err),
+                    "Failed to load tenant credentials"
+                );
+                cache_tenant(application_id, None);
+            }
+        }
+
+        credentials
+    }
+
+    #[instrument(skip(self))]
+    async fn fetch(&self, application_id: &str) -> Result<TenantCredentials> {
+        let document = self
+            .reader
+            .fetch_secret(&format!("{}{}", self.prefix, application_id))
+            .await?;
+
+        let stored: StoredTenant =
+            serde_json::from_value(document).context("Tenant secret has the wrong shape")?;
+
+        let public_key = AuthManager::parse_public_key(&stored.key)
+            .context("Tenant secret has an invalid public key")?;
+
+        Ok(TenantCredentials {
+            token: stored.token,
+            public_key,
+        })
+    }
+}
+
+/// The cached lookup for `application_id`: `Some(None)` when it recently
+/// had no readable secret.
+fn cached_tenant(application_id: &str) -> Option<Option<TenantCredentials>> {
+    let cache = TENANT_CACHE.lock().ok()?;
+    let (credentials, cached_at) = cache.get(application_id)?;
+
+    if cached_at.elapsed() > ttl(credentials) {
+        return None;
+    }
+
+    Some(credentials.clone())
+}
+
+fn ttl(credentials: &Option<TenantCredentials>) -> Duration {
+    match credentials {
+        Some(_) => SECRET_TTL,
+        None => MISSING_TENANT_TTL,
+    }
+}
+
+fn cache_tenant(application_id: &str, credentials: Option<TenantCredentials>) {
+    if let Ok(mut cache) = TENANT_CACHE.lock() {
+        if cache.len() >= TENANT_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (credentials, cached_at)| cached_at.elapsed() <= ttl(credentials));
+        }
+
+        cache.insert(application_id.to_string(), (credentials, Instant::now()));
+    }
+}
@@ -0,0 +1,493 @@
+//! JSON API for a web dashboard. The dashboard signs users in with Discord
+//! OAuth2 (scopes `identify guilds`, redirecting to `DASHBOARD_REDIRECT_URI`)
+//! and posts the code here for a session token, sent back as a bearer token.
+//! Guild routes check with `/users/@me/guilds` that the caller manages the
+//! guild; changing which roles are self-assignable also needs Manage Roles
+//! there, and saving one is refused for roles `/role save` would refuse.
+//!
+//! ```text
+//! POST   /api/session                       {"code"} -> {"token", "expires_at", "user"}
+//! DELETE /api/session
+//! GET    /api/guilds                        guilds the caller manages
+//! GET    /api/guilds/{id}/roles             self-assignable roles
+//! PUT    /api/guilds/{id}/roles/{role_id}   make a Discord role self-assignable
+//! DELETE /api/guilds/{id}/roles/{role_id}
+//! GET    /api/guilds/{id}/settings
+//! PUT    /api/guilds/{id}/settings          {"autocomplete_min_length"}
+//! GET    /api/guilds/{id}/subscription      (billing builds only)
+//! ```
+
+use lambda_http::{http::Method, Body, Request, Response};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{error, info};
+
+#[cfg(feature = "billing")]
+use crate::dal::dao::{bundle::BundleDao, subscription::SubscriptionReader};
+use crate::{
+    app_state::AppState,
+    bal::{
+        activity::activity_recorder::ActivityRecorder,
+        dashboard::session_manager::SessionManager,
+        discord::{
+            discord_api::DiscordApi, lazy_discord_api::LazyDiscordApi, oauth_client::OAuthGuild,
+            role_hierarchy::RoleHierarchy,
+        },
+        events::event_publisher::EventPublisher,
+    },
+    commands::{config, ADMINISTRATOR, MANAGE_ROLES},
+    correlation,
+    dal::{
+        dao::{
+            guild::GuildDao,
+            guild_record::GuildRecordDao,
+            log_channel::LogChannelDao,
+            role_store::RoleStore,
+            session::{Session, SessionDao},
+            webhook::WebhookDao,
+        },
+        model::{
+            activity_entry::{role_mention, ActivityEntry, RoleActivity},
+            guild_event::GuildEvent,
+            role_icon::RoleIcon,
+            role_name,
+        },
+        queue::task_queue::TaskQueue,
+    },
+    error::{ApiError, CommandError},
+    http_handler::{self, json_response, raw_body, required},
+    json, markdown,
+};
+
+const API_PREFIX: &str = "/api/";
+const PREFLIGHT_MAX_AGE_SECONDS: u32 = 600;
+
+#[derive(Deserialize)]
+struct SessionRequest {
+    code: String,
+}
+
+#[derive(Serialize)]
+struct RoleBody {
+    id: String,
+    name: String,
+    icon_url: Option<String>,
+    unicode_emoji: Option<String>,
+    /// Premium guilds only, as of the last scheduled count.
+    member_count: Option<u64>,
+}
+
+impl RoleBody {
+    fn new(id: String, name: String, icon: Option<&RoleIcon>) -> Self {
+        Self {
+            icon_url: icon.and_then(|icon| icon.url(&id)),
+            unicode_emoji: icon.and_then(|icon| icon.unicode_emoji.clone()),
+            member_count: None,
+            id,
+            name,
+        }
+    }
+
+    fn with_member_count(self, member_count: Option<u64>) -> Self {
+        Self {
+            member_count,
+            ..self
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GuildSettingsBody {
+    #[serde(default)]
+    autocomplete_min_length: Option<u32>,
+}
+
+#[cfg(feature = "billing")]
+#[derive(Serialize)]
+struct SubscriptionBody {
+    active: bool,
+    expires_at: Option<i64>,
+    /// Set when the guild is covered by a user's bundle.
+    bundle_owner_id: Option<String>,
+}
+
+pub fn is_dashboard_request(request: &Request) -> bool {
+    request.uri().path().starts_with(API_PREFIX)
+}
+
+pub async fn function_handler(request: &Request, state: &AppState) -> Response<Body> {
+    let mut response = if request.method() == Method::OPTIONS {
+        Response::builder().status(204).body(Body::Empty).unwrap()
+    } else {
+        route(request, state).await.unwrap_or_else(error_response)
+    };
+
+    // The dashboard is served from its own origin.
+    if let Some(origin) = state.config.dashboard_origin.as_deref() {
+        let headers = response.headers_mut();
+        let cors = [
+            ("access-control-allow-origin", origin.to_string()),
+            (
+                "access-control-allow-headers",
+                "authorization, content-type".to_string(),
+            ),
+            (
+                "access-control-allow-methods",
+                "GET, PUT, POST, DELETE, OPTIONS".to_string(),
+            ),
+            (
+                "access-control-max-age",
+                PREFLIGHT_MAX_AGE_SECONDS.to_string(),
+            ),
+            ("vary", "origin".to_string()),
+        ];
+
+        for (name, value) in cors {
+            if let Ok(value) = value.parse() {
+                headers.insert(name, value);
+            }
+        }
+    }
+
+    response
+}
+
+async fn route(request: &Request, state: &AppState) -> Result<Response<Body>, ApiError> {
+    let path = request.uri().path()[API_PREFIX.len()..].trim_end_matches('/');
+    let segments: Vec<&str> = path.split('/').collect();
+    let method = request.method();
+
+    let sessions = session_manager(state).await?;
+
+    match (method, segments.as_slice()) {
+        (&Method::POST, ["session"]) => {
+            let body: SessionRequest = parse_body(request)?;
+            let issued = sessions.issue(&body.code).await?;
+            info!(user_id = %issued.user.id, "Issued dashboard session");
+
+            return Ok(json_response(200, &issued));
+        }
+
+        (&Method::DELETE, ["session"]) => {
+            sessions.revoke(bearer_token(request)?).await?;
+            return Ok(no_content());
+        }
+
+        _ => {}
+    }
+
+    let session = sessions
+        .authenticate(bearer_token(request)?)
+        .await?
+        .ok_or(ApiError::Unauthenticated)?;
+
+    match (method, segments.as_slice()) {
+        (&Method::GET, ["guilds"]) => Ok(json_response(
+            200,
+            &sessions.managed_guilds(&session).await?,
+        )),
+
+        (method, ["guilds", guild_id, rest @ ..]) => {
+            let guild = sessions
+                .managed_guild(&session, guild_id)
+                .await?
+                .ok_or(ApiError::Forbidden)?;
+
+            guild_route(request, state, &session, &guild, method, rest).await
+        }
+
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+async fn guild_route(
+    request: &Request,
+    state: &AppState,
+    session: &Session,
+    guild: &OAuthGuild,
+    method: &Method,
+    rest: &[&str],
+) -> Result<Response<Body>, ApiError> {
+    let guild_id = guild.id.as_str();
+    let role_table = required(&state.config.role_table, "ROLE_MAPPINGS_TABLE_NAME")?;
+    let role_store = GuildDao::new(state.dynamo_client.clone(), role_table);
+
+    match (method, rest) {
+        (&Method::GET, ["roles"]) => {
+            let (mut roles, icons, member_counts) = tokio::try_join!(
+                role_store.list_roles(guild_id),
+                role_store.list_role_icons(guild_id),
+                role_store.get_member_counts(guild_id),
+            )?;
+            roles.sort_by_key(|(name, _)| name.to_lowercase());
+
+            let roles: Vec<RoleBody> = roles
+                .into_iter()
+                .map(|(name, id)| {
+                    let icon = icons.get(&id);
+                    let member_count = member_counts.get(&id).copied();
+                    RoleBody::new(id, name, icon).with_member_count(member_count)
+                })
+                .collect();
+
+            Ok(json_response(200, &roles))
+        }
+
+        (&Method::PUT, ["roles", role_id]) => {
+            // As for `/role save`, which needs Manage Roles too.
+            require_manage_roles(guild)?;
+
+            // The name comes from Discord, as it does for `/role save`, and
+            // so do the caller's roles, which OAuth doesn't include.
+            let (discord_api, application_id) = guild_bot(state, role_table, guild_id).await?;
+            let (hierarchy, member_roles) = tokio::try_join!(
+                RoleHierarchy::load(&discord_api, guild_id, &application_id),
+                discord_api.fetch_member_roles(guild_id, &session.user_id),
+            )?;
+            let role = hierarchy.role(role_id).cloned().ok_or(ApiError::NotFound)?;
+
+            if role.managed {
+                return Err(ApiError::BadRequest("managed roles can't be self-assigned"));
+            }
+
+            let refusal = hierarchy.role_refusal(&role).or_else(|| {
+                hierarchy.member_refusal(role.position, &member_roles, guild.grants(ADMINISTRATOR))
+            });
+            if let Some(reason) = refusal {
+                return Err(ApiError::BadRequest(reason));
+            }
+
+            let role_name = role_name::normalize(&role.name)
+                .map_err(|invalid| ApiError::BadRequest(invalid.reason()))?
+                .to_string();
+
+            if role_store
+                .name_conflict(guild_id, &role.id, &role_name)
+                .await?
+                .is_some()
+            {
+                return Err(ApiError::BadRequest(
+                    "another role is registered under this name; save it with /role save and an alias",
+                ));
+            }
+
+            role_store
+                .save_role_with_icon(guild_id, &role.id, &role_name, &role.icon)
+                .await?;
+            info!(user_id = %session.user_id, guild_id, role_id, "Dashboard saved role");
+
+            activity_recorder(state, role_table)
+                .record(
+                    ActivityEntry::new(
+                        guild_id,
+                        RoleActivity::Saved,
+                        Some(&session.user_id),
+                        role_mention(&role.id),
+                    )
+                    .succeeded(format!(
+                        "'{}' is self-assignable (dashboard)",
+                        markdown::escape(&role_name)
+                    ))
+                    .with_thumbnail(role.icon.url(&role.id)),
+                )
+                .await;
+
+            event_publisher(state, role_table)
+                .publish(
+                    guild_id,
+                    GuildEvent::RoleRegistered {
+                        role_id: role.id.clone(),
+                        role_name: role_name.clone(),
+                    },
+                )
+                .await;
+
+            Ok(json_response(
+                200,
+                &RoleBody::new(role.id, role_name, Some(&role.icon)),
+            ))
+        }
+
+        (&Method::DELETE, ["roles", role_id]) => {
+            require_manage_roles(guild)?;
+
+            role_store.delete_role(guild_id, role_id).await?;
+            info!(user_id = %session.user_id, guild_id, role_id, "Dashboard deleted role");
+
+            activity_recorder(state, role_table)
+                .record(
+                    ActivityEntry::new(
+                        guild_id,
+                        RoleActivity::Removed,
+                        Some(&session.user_id),
+                        role_mention(role_id),
+                    )
+                    .succeeded("No longer self-assignable (dashboard)"),
+                )
+                .await;
+
+            Ok(no_content())
+        }
+
+        (&Method::GET, ["settings"]) => Ok(json_response(
+            200,
+            &GuildSettingsBody {
+                autocomplete_min_length: role_store.get_autocomplete_min_length(guild_id).await?,
+            },
+        )),
+
+        (&Method::PUT, ["settings"]) => {
+            let body: GuildSettingsBody = parse_body(request)?;
+
+            if let Some(length) = body.autocomplete_min_length {
+                if !(config::MIN_LENGTH..=config::MAX_LENGTH).contains(&length) {
+                    return Err(ApiError::BadRequest(
+                        "autocomplete_min_length is out of range",
+                    ));
+                }
+
+                role_store
+                    .set_autocomplete_min_length(guild_id, length)
+                    .await?;
+                info!(user_id = %session.user_id, guild_id, length, "Dashboard updated settings");
+            }
+
+            Ok(json_response(200, &body))
+        }
+
+        #[cfg(feature = "billing")]
+        (&Method::GET, ["subscription"]) => subscription(state, guild_id).await,
+
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+/// Changing which roles are self-assignable needs Manage Roles, as the
+/// `/role` subcommands that do it do.
+fn require_manage_roles(guild: &OAuthGuild) -> Result<(), ApiError> {
+    if guild.grants(MANAGE_ROLES) {
+        Ok(())
+    } else {
+        Err(ApiError::MissingPermission("Manage Roles"))
+    }
+}
+
+#[cfg(feature = "billing")]
+async fn subscription(state: &AppState, guild_id: &str) -> Result<Response<Body>, ApiError> {
+    let table = required(
+        &state.config.subscription_table,
+        "GUILD_SUBSCRIPTIONS_TABLE_NAME",
+    )?;
+    let reader = SubscriptionReader::new(state.dynamo_client.clone(), table);
+    let bundles = BundleDao::new(state.dynamo_client.clone(), table);
+
+    let (active, expires_at, bundle_owner_id) = tokio::try_join!(
+        reader.is_active(guild_id),
+        reader.get_active_expiry(guild_id),
+        bundles.get_attached_owner(guild_id),
+    )?;
+
+    Ok(json_response(
+        200,
+        &SubscriptionBody {
+            active,
+            expires_at,
+            bundle_owner_id,
+        },
+    ))
+}
+
+/// The Discord client of the bot installed in the guild, and its
+/// application ID: the white-label application on the guild's `GUILD`
+/// record, or the default bot.
+async fn guild_bot(
+    state: &AppState,
+    role_table: &str,
+    guild_id: &str,
+) -> Result<(LazyDiscordApi, String), ApiError> {
+    let default_application_id = required(&state.config.application_id, "DISCORD_APPLICATION_ID")?;
+
+    let application_id = match state.tenants {
+        Some(_) => GuildRecordDao::new(state.dynamo_client.clone(), role_table)
+            .application_id(guild_id)
+            .await?
+            .unwrap_or_else(|| default_application_id.to_string()),
+        None => default_application_id.to_string(),
+    };
+
+    let discord_token = state
+        .discord_token_for(&application_id)
+        .await?
+        .ok_or(CommandError::Misconfigured("DISCORD_TOKEN_SECRET_ARN"))?;
+
+    Ok((
+        LazyDiscordApi::new(state.http_client.clone(), discord_token),
+        application_id,
+    ))
+}
+
+async fn session_manager(state: &AppState) -> Result<SessionManager, ApiError> {
+    let redirect_uri = required(
+        &state.config.dashboard_redirect_uri,
+        "DASHBOARD_REDIRECT_URI",
+    )?;
+    let role_table = required(&state.config.role_table, "ROLE_MAPPINGS_TABLE_NAME")?;
+
+    let oauth = http_handler::oauth_client(state, redirect_uri).await?;
+    let sessions = SessionDao::new(state.dynamo_client.clone(), role_table);
+
+    Ok(SessionManager::new(oauth, sessions))
+}
+
+fn task_queue(state: &AppState) -> Option<TaskQueue> {
+    state
+        .config
+        .task_queue_url
+        .clone()
+        .map(|url| TaskQueue::new(state.sqs_client.clone(), url))
+}
+
+fn event_publisher(state: &AppState, role_table: &str) -> EventPublisher {
+    EventPublisher::new(
+        WebhookDao::new(state.dynamo_client.clone(), role_table),
+        task_queue(state),
+    )
+}
+
+fn activity_recorder(state: &AppState, role_table: &str) -> ActivityRecorder {
+    ActivityRecorder::new(
+        LogChannelDao::new(state.dynamo_client.clone(), role_table),
+        task_queue(state),
+    )
+}
+
+fn bearer_token(request: &Request) -> Result<&str, ApiError> {
+    request
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty())
+        .ok_or(ApiError::Unauthenticated)
+}
+
+fn parse_body<T: DeserializeOwned>(request: &Request) -> Result<T, ApiError> {
+    json::from_slice(raw_body(request)).map_err(|_| ApiError::BadRequest("invalid JSON"))
+}
+
+fn no_content() -> Response<Body> {
+    Response::builder().status(204).body(Body::Empty).unwrap()
+}
+
+fn error_response(err: ApiError) -> Response<Body> {
+    let status = err.status();
+
+    if status >= 500 {
+        error!(
+            reference = %correlation::reference(),
+            error = format!("{:#}", err),
+            "Dashboard request failed"
+        );
+    }
+
+    json_response(status, &serde_json::json!({ "error": err.user_message() }))
+}
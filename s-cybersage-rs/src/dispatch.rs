@@ -0,0 +1,80 @@
+use aws_lambda_events::{dynamodb, eventbridge::EventBridgeEvent, sqs::SqsEvent};
+use lambda_http::{request::LambdaRequest, Adapter, Error, LambdaEvent, Service};
+use serde_json::Value;
+
+use crate::{
+    app_state::AppState, http_handler::HttpService, schedule_handler, snapshot, sqs_handler,
+    stream_handler, telemetry,
+};
+
+/// The event sources a single deployment of the binary can be wired to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Http,
+    Sqs,
+    Schedule,
+    DynamoStream,
+}
+
+impl EventKind {
+    /// Identifies the event source from the payload shape. Anything that is
+    /// not a recognised AWS event is treated as an HTTP request.
+    pub fn classify(payload: &Value) -> Self {
+        let record_source = payload
+            .get("Records")
+            .and_then(|r| r.get(0))
+            .and_then(|r| r.get("eventSource"))
+            .and_then(Value::as_str);
+
+        match record_source {
+            Some("aws:sqs") => EventKind::Sqs,
+            Some("aws:dynamodb") => EventKind::DynamoStream,
+            _ if payload.get("detail-type").is_some() => EventKind::Schedule,
+            _ => EventKind::Http,
+        }
+    }
+}
+
+/// Routes a raw Lambda event to the handler for its source and serializes
+/// that handler's response. HTTP requests go through `http`, the handler
+/// with its middleware.
+pub async fn function_handler(
+    event: LambdaEvent<Value>,
+    state: AppState,
+    http: HttpService,
+) -> Result<Value, Error> {
+    snapshot::check_restore();
+
+    let (payload, context) = event.into_parts();
+
+    let response = match EventKind::classify(&payload) {
+        EventKind::Sqs => {
+            let event: SqsEvent = serde_json::from_value(payload)?;
+            let response =
+                sqs_handler::function_handler(LambdaEvent::new(event, context), state).await?;
+            serde_json::to_value(response)?
+        }
+        EventKind::DynamoStream => {
+            let event: dynamodb::Event = serde_json::from_value(payload)?;
+            stream_handler::function_handler(LambdaEvent::new(event, context)).await?;
+            Value::Null
+        }
+        EventKind::Schedule => {
+            let event: EventBridgeEvent = serde_json::from_value(payload)?;
+            schedule_handler::function_handler(LambdaEvent::new(event, context), state).await?;
+            Value::Null
+        }
+        EventKind::Http => {
+            let request: LambdaRequest = serde_json::from_value(payload)?;
+
+            let mut adapter = Adapter::from(http);
+
+            let response = adapter.call(LambdaEvent::new(request, context)).await?;
+            serde_json::to_value(response)?
+        }
+    };
+
+    telemetry::flush();
+
+    Ok(response)
+}
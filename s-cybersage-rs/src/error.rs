@@ -0,0 +1,343 @@
+//! Typed failures at the layer boundaries, and the single place that decides
+//! what a user sees for each of them.
+//!
+//! DAOs and the Discord client still return `anyhow::Result`, but the error
+//! at the root of the chain is one of these types, so context added on the
+//! way up doesn't hide what went wrong. `CommandError` collects them for the
+//! HTTP handler, which turns it into a status and an ephemeral message.
+
+use aws_sdk_dynamodb::error::{ProvideErrorMetadata, SdkError};
+use reqwest::StatusCode;
+use thiserror::Error;
+
+use crate::correlation;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Why a request failed Discord's signature check.
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Missing required Discord signature headers")]
+    MissingHeaders,
+
+    #[error("X-Signature-Timestamp is not a valid integer")]
+    InvalidTimestamp,
+
+    #[error("Request timestamp is too old or too far in the future")]
+    StaleTimestamp,
+
+    #[error("Discord public key is malformed")]
+    InvalidPublicKey,
+
+    #[error("Signature verification failed")]
+    InvalidSignature,
+
+    #[error("No credentials for the request's application")]
+    UnknownApplication,
+}
+
+/// A failed DynamoDB call, classified by what the caller can do about it.
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("Failed to {operation}: throttled")]
+    Throttled {
+        operation: &'static str,
+        #[source]
+        source: BoxError,
+    },
+
+    #[error("Failed to {operation}: condition check failed")]
+    ConditionFailed {
+        operation: &'static str,
+        #[source]
+        source: BoxError,
+    },
+
+    #[error("Failed to {operation}")]
+    Request {
+        operation: &'static str,
+        #[source]
+        source: BoxError,
+    },
+}
+
+impl StorageError {
+    /// Wraps an SDK error from `operation` (e.g. "save role"), using the
+    /// service error code to tell throttling and failed conditions apart.
+    pub fn from_sdk<E, R>(operation: &'static str, err: SdkError<E, R>) -> Self
+    where
+        E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+        R: std::fmt::Debug + Send + Sync + 'static,
+    {
+        let code = err.code().unwrap_or_default().to_string();
+        let source: BoxError = Box::new(err);
+
+        match code.as_str() {
+            "ThrottlingException"
+            | "ProvisionedThroughputExceededException"
+            | "RequestLimitExceeded" => StorageError::Throttled { operation, source },
+            "ConditionalCheckFailedException" | "TransactionCanceledException" => {
+                StorageError::ConditionFailed { operation, source }
+            }
+            _ => StorageError::Request { operation, source },
+        }
+    }
+}
+
+/// Discord's JSON error code for a guild that doesn't exist, or that the bot
+/// isn't in.
+const UNKNOWN_GUILD: u64 = 10004;
+/// Discord's JSON error code for a resource the bot can't see at all.
+const MISSING_ACCESS: u64 = 50001;
+
+/// A failed Discord REST call.
+#[derive(Debug, Error)]
+pub enum DiscordApiError {
+    #[error("Bot lacks permission (check role hierarchy)")]
+    Forbidden,
+
+    #[error("Role, member or guild not found")]
+    NotFound,
+
+    /// Discord's Unknown Guild or Missing Access on a call about the guild
+    /// itself: the bot was removed, or the guild is gone.
+    #[error("Guild is unknown or inaccessible to the bot")]
+    GuildUnavailable,
+
+    #[error("Rate limited by Discord")]
+    RateLimited,
+
+    #[error("Discord returned {0}")]
+    ServerError(StatusCode),
+
+    #[error("Discord API error: {0}")]
+    Unexpected(StatusCode),
+
+    #[error("Failed to reach Discord")]
+    Transport(#[source] reqwest::Error),
+}
+
+impl DiscordApiError {
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::FORBIDDEN => DiscordApiError::Forbidden,
+            StatusCode::NOT_FOUND => DiscordApiError::NotFound,
+            StatusCode::TOO_MANY_REQUESTS => DiscordApiError::RateLimited,
+            s if s.is_server_error() => DiscordApiError::ServerError(s),
+            s => DiscordApiError::Unexpected(s),
+        }
+    }
+
+    /// Like `from_status`, but for calls about the guild itself, where
+    /// Discord's JSON error `code` tells a guild the bot lost from a missing
+    /// permission or a deleted role.
+    pub fn from_guild_status(status: StatusCode, code: Option<u64>) -> Self {
+        match (status, code) {
+            (StatusCode::NOT_FOUND, Some(UNKNOWN_GUILD))
+            | (StatusCode::FORBIDDEN, Some(MISSING_ACCESS)) => DiscordApiError::GuildUnavailable,
+            _ => DiscordApiError::from_status(status),
+        }
+    }
+
+    /// Rate limits, Discord 5xx responses, and transport errors such as
+    /// timeouts may succeed if retried later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            DiscordApiError::RateLimited
+                | DiscordApiError::ServerError(_)
+                | DiscordApiError::Transport(_)
+        )
+    }
+
+    /// Whether the `DiscordApiError` anywhere in `err`'s chain is retryable.
+    pub fn is_retryable_error(err: &anyhow::Error) -> bool {
+        find::<DiscordApiError>(err).is_some_and(DiscordApiError::is_retryable)
+    }
+
+    /// Whether `err` says the bot can't reach what it asked for, usually
+    /// because it was removed from the guild.
+    pub fn is_missing_access_error(err: &anyhow::Error) -> bool {
+        matches!(
+            find::<DiscordApiError>(err),
+            Some(
+                DiscordApiError::Forbidden
+                    | DiscordApiError::NotFound
+                    | DiscordApiError::GuildUnavailable
+            )
+        )
+    }
+
+    /// Whether `err` says the guild is gone for the bot, as opposed to any
+    /// other 403 or 404.
+    pub fn is_guild_unavailable_error(err: &anyhow::Error) -> bool {
+        matches!(
+            find::<DiscordApiError>(err),
+            Some(DiscordApiError::GuildUnavailable)
+        )
+    }
+}
+
+/// Everything that can stop an interaction from being handled.
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error(transparent)]
+    Discord(#[from] DiscordApiError),
+
+    #[error("Malformed request: {0}")]
+    BadRequest(&'static str),
+
+    #[error("Server misconfiguration: {0} is not set")]
+    Misconfigured(&'static str),
+
+    #[error(transparent)]
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(err: anyhow::Error) -> Self {
+        if find::<AuthError>(&err).is_some() {
+            return match err.downcast::<AuthError>() {
+                Ok(e) => CommandError::Auth(e),
+                Err(err) => CommandError::Internal(err),
+            };
+        }
+
+        if find::<StorageError>(&err).is_some() {
+            return match err.downcast::<StorageError>() {
+                Ok(e) => CommandError::Storage(e),
+                Err(err) => CommandError::Internal(err),
+            };
+        }
+
+        if find::<DiscordApiError>(&err).is_some() {
+            return match err.downcast::<DiscordApiError>() {
+                Ok(e) => CommandError::Discord(e),
+                Err(err) => CommandError::Internal(err),
+            };
+        }
+
+        CommandError::Internal(err)
+    }
+}
+
+impl CommandError {
+    /// Status for the HTTP response. Failures inside a verified interaction
+    /// are still answered with 200 so Discord shows the ephemeral message
+    /// instead of "This interaction failed".
+    pub fn status(&self) -> u16 {
+        match self {
+            CommandError::Auth(_) => 401,
+            CommandError::BadRequest(_) => 400,
+            CommandError::Misconfigured(_) => 500,
+            _ => 200,
+        }
+    }
+
+    /// A few words on why one item of a batch failed, for summaries that
+    /// list several failures.
+    pub fn short_reason(&self) -> &'static str {
+        match self {
+            CommandError::Discord(
+                DiscordApiError::Forbidden | DiscordApiError::GuildUnavailable,
+            ) => "missing permission",
+            CommandError::Discord(DiscordApiError::NotFound) => "no longer exists",
+            CommandError::Discord(err) if err.is_retryable() => "Discord was busy",
+            CommandError::Storage(StorageError::Throttled { .. }) => "the bot was busy",
+            _ => "internal error",
+        }
+    }
+
+    /// The message shown to the user. Failures they can't act on carry the
+    /// correlation reference so a report can be matched to the logs.
+    pub fn user_message(&self) -> String {
+        match self {
+            CommandError::Auth(_) => "Invalid request signature".to_string(),
+            CommandError::BadRequest(_) => "Invalid JSON".to_string(),
+            CommandError::Misconfigured(_) => "Server misconfiguration".to_string(),
+
+            CommandError::Discord(
+                DiscordApiError::Forbidden | DiscordApiError::GuildUnavailable,
+            ) => "I don't have permission to do that. Ask an admin to move my role above the \
+                 roles I manage."
+                .to_string(),
+            CommandError::Discord(DiscordApiError::NotFound) => {
+                "That role or member no longer exists.".to_string()
+            }
+            CommandError::Discord(err) if err.is_retryable() => {
+                correlation::tag("Discord is busy right now, please try again shortly")
+            }
+            CommandError::Storage(StorageError::Throttled { .. }) => {
+                correlation::tag("The bot is busy right now, please try again shortly")
+            }
+            CommandError::Storage(StorageError::ConditionFailed { .. }) => {
+                "That changed while you were using it, please try again.".to_string()
+            }
+
+            _ => correlation::tag("Internal error"),
+        }
+    }
+}
+
+/// Why a dashboard API request failed, mapped to an HTTP status.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Missing, invalid or expired session")]
+    Unauthenticated,
+
+    #[error("You don't manage this guild")]
+    Forbidden,
+
+    #[error("You need the {0} permission in this guild")]
+    MissingPermission(&'static str),
+
+    #[error("Not found")]
+    NotFound,
+
+    #[error("Invalid request: {0}")]
+    BadRequest(&'static str),
+
+    #[error(transparent)]
+    Command(#[from] CommandError),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        ApiError::Command(CommandError::from(err))
+    }
+}
+
+impl ApiError {
+    pub fn status(&self) -> u16 {
+        match self {
+            ApiError::Unauthenticated => 401,
+            ApiError::Forbidden | ApiError::MissingPermission(_) => 403,
+            ApiError::NotFound => 404,
+            ApiError::BadRequest(_) => 400,
+            ApiError::Command(CommandError::Discord(DiscordApiError::RateLimited))
+            | ApiError::Command(CommandError::Storage(StorageError::Throttled { .. })) => 503,
+            ApiError::Command(CommandError::Discord(_)) => 502,
+            ApiError::Command(err) => match err.status() {
+                200 => 500,
+                status => status,
+            },
+        }
+    }
+
+    pub fn user_message(&self) -> String {
+        match self {
+            ApiError::Command(err) => err.user_message(),
+            err => err.to_string(),
+        }
+    }
+}
+
+fn find<T: std::error::Error + Send + Sync + 'static>(err: &anyhow::Error) -> Option<&T> {
+    err.chain().find_map(|cause| cause.downcast_ref::<T>())
+}
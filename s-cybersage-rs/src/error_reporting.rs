@@ -0,0 +1,71 @@
+//! Optional Sentry reporting, enabled with the `sentry` feature and a
+//! `SENTRY_DSN` environment variable. Without the feature every function here
+//! is a no-op, so call sites don't need their own `cfg` guards.
+
+use crate::dal::model::interaction_request::InteractionRequest;
+
+#[cfg(feature = "sentry")]
+pub type Guard = sentry::ClientInitGuard;
+
+#[cfg(not(feature = "sentry"))]
+pub type Guard = ();
+
+/// Initializes the Sentry client. The returned guard flushes pending events
+/// when dropped and must be held for the life of the process.
+#[cfg(feature = "sentry")]
+pub fn init() -> Option<Guard> {
+    let dsn = std::env::var("SENTRY_DSN").ok().filter(|v| !v.is_empty())?;
+
+    let mut options = sentry::ClientOptions::default();
+    options.release = sentry::release_name!();
+    options.environment = std::env::var("SENTRY_ENVIRONMENT").ok().map(Into::into);
+    options.send_default_pii = false;
+
+    Some(sentry::init((dsn, options)))
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn init() -> Option<Guard> {
+    None
+}
+
+/// Captures a router error tagged with the identifying parts of the
+/// interaction. Option values, resolved data, and anything token-like are
+/// deliberately left out.
+#[cfg(feature = "sentry")]
+pub fn capture_error(err: &anyhow::Error, interaction: &InteractionRequest) {
+    sentry::with_scope(
+        |scope| {
+            scope.set_tag("interaction_id", &interaction.id);
+            scope.set_tag("application_id", &interaction.application_id);
+            scope.set_tag(
+                "interaction_type",
+                format!("{:?}", interaction.interaction_type),
+            );
+            scope.set_tag("reference", crate::correlation::reference());
+
+            if let Some(guild_id) = interaction.guild_id.as_deref() {
+                scope.set_tag("guild_id", guild_id);
+            }
+
+            if let Some(member) = interaction.member.as_ref() {
+                scope.set_user(Some(sentry::User {
+                    id: Some(member.user.id.clone()),
+                    ..Default::default()
+                }));
+            }
+
+            if let Some(data) = interaction.data.as_ref() {
+                scope.set_tag("command", &data.name);
+
+                if let Some(sub) = data.options.first() {
+                    scope.set_tag("subcommand", &sub.name);
+                }
+            }
+        },
+        || sentry::integrations::anyhow::capture_anyhow(err),
+    );
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn capture_error(_err: &anyhow::Error, _interaction: &InteractionRequest) {}
@@ -1,138 +1,766 @@
-use aws_sdk_dynamodb::{Client as DynamoClient};
-use aws_sdk_secretsmanager::Client as SecretsClient;
-use lambda_http::{Body, Error, Request, Response};
+use std::{
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use aws_sdk_dynamodb::Client as DynamoClient;
+use lambda_http::{
+    http::Method, tower::util::BoxCloneService, Body, Error, Request, RequestExt, Response,
+};
+use once_cell::sync::Lazy;
 use serde_json::json;
-use tokio::sync::OnceCell;
+use tracing::{error, warn, Span};
 
 use crate::{
+    app_events_handler,
+    app_state::{self, AppConfig, AppState},
     bal::{
-        auth::verify::AuthManager,
-        discord::role_manager::RoleManager,
-        route::{command_router::CommandRouter, interaction_router::InteractionRouter},
+        auth::{
+            operator::OperatorContext,
+            rate_limiter::{RateDecision, RateLimiter},
+        },
+        config::feature_flags::FeatureFlags,
+        discord::{
+            lazy_discord_api::LazyDiscordApi, oauth_client::OAuthClient, role_manager::RoleManager,
+        },
+        route::{
+            command_router::CommandRouter, component_router::ComponentRouter,
+            interaction_router::InteractionRouter, request_context::RequestContext,
+        },
     },
+    commands, correlation,
     dal::{
-        dao::{guild::GuildDao, subscription::SubscriptionReader},
-        model::interaction_request::InteractionRequest,
-        reader::secrets_reader::SecretsReader,
+        dao::{
+            favorite::FavoriteDao, feature_flag::FeatureFlagDao, global_stats::GlobalStatsDao,
+            guild::GuildDao, guild_record::GuildRecordDao, guild_stores::GuildStores,
+            rate_limit::RateLimitDao,
+        },
+        model::{
+            interaction_request::{InteractionRequest, InteractionType},
+            interaction_response::InteractionResponse,
+        },
+        queue::{task_queue::TaskQueue, task_scheduler::TaskScheduler},
+        reader::{parameter_reader::ConfigParameters, secrets_reader::SecretCache},
+    },
+    dashboard_handler,
+    error::CommandError,
+    error_reporting, json, linked_roles_handler,
+    middleware::signature,
+    ops_alert, stage, timestamp,
+};
+#[cfg(feature = "billing")]
+use crate::{
+    bal::{
+        billing::{
+            context::BillingContext, premium_gate::PremiumGate, stripe_client::StripeClient,
+            subscription_manager::SubscriptionManager, usage_meter::UsageMeter,
+        },
+        config::settings::Settings,
+    },
+    dal::{
+        dao::{
+            bundle::BundleDao,
+            settings::SettingsDao,
+            subscription::{SubscriptionReader, SubscriptionWriter},
+            usage::UsageDao,
+        },
+        reader::secrets_reader::SecretSource,
     },
 };
 
-static DISCORD_PUBLIC_KEY_CACHE: OnceCell<serde_json::Value> = OnceCell::const_new();
-static DISCORD_TOKEN_CACHE: OnceCell<serde_json::Value> = OnceCell::const_new();
+const HEALTH_PATH: &str = "/healthz";
+/// How long a deep check's DynamoDB result is reused, so a monitor polling
+/// `?deep=1` doesn't cost a DescribeTable per request.
+const DEEP_HEALTH_TTL: Duration = Duration::from_secs(30);
 
-pub(crate) async fn function_handler(
-    event: Request,
-    dynamo_client: DynamoClient,
-    secrets_client: SecretsClient,
-    http_client: reqwest::Client,
-) -> Result<Response<Body>, Error> {
-    let body_bytes = event.body().as_ref();
-    let body_str = std::str::from_utf8(body_bytes).unwrap_or("");
+static DEEP_HEALTH: Lazy<Mutex<Option<(&'static str, Instant)>>> = Lazy::new(|| Mutex::new(None));
 
-    let headers = event.headers();
+const TENANT_GUILDS_MAX_ENTRIES: usize = 1024;
 
-    let signature = headers
-        .get("x-signature-ed25519")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+/// Guilds whose white-label application this warm function has already put
+/// on their `GUILD` record.
+static TENANT_GUILDS: Lazy<Mutex<HashSet<(String, String)>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
 
-    let timestamp = headers
-        .get("x-signature-timestamp")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+/// Boxed future returned by the HTTP middleware in `crate::middleware`.
+pub type BoxResponseFuture = Pin<Box<dyn Future<Output = Result<Response<Body>, Error>> + Send>>;
 
-    let secrets_reader = SecretsReader::new(secrets_client.clone());
+/// The HTTP handler wrapped in its middleware, as composed in `main`.
+pub type HttpService = BoxCloneService<Request, Response<Body>, Error>;
 
-    let public_key_secret_arn = match std::env::var("DISCORD_PUBLIC_KEY_SECRET_ARN") {
-        Ok(v) => v,
-        Err(_) => return Ok(server_error()),
+/// Handles a request that already passed the middleware in `main`: the body
+/// is within limits and, unless it's one of the unsigned routes, the
+/// signature has been verified.
+pub async fn function_handler(event: Request, state: AppState) -> Result<Response<Body>, Error> {
+    if linked_roles_handler::is_linked_roles_request(&event) {
+        return Ok(linked_roles_handler::function_handler(&event, &state).await);
+    }
+
+    if dashboard_handler::is_dashboard_request(&event) {
+        return Ok(dashboard_handler::function_handler(&event, &state).await);
+    }
+
+    if app_events_handler::is_app_event_request(&event) {
+        return Ok(app_events_handler::function_handler(&event, &state).await);
+    }
+
+    let AppState {
+        dynamo_client,
+        parameters,
+        sqs_client,
+        scheduler_client,
+        http_client,
+        config,
+        ..
+    } = state.clone();
+
+    if is_health_check(&event) {
+        let deep = event
+            .query_string_parameters_ref()
+            .and_then(|params| params.first("deep"))
+            .is_some_and(|v| v == "1" || v == "true");
+
+        return Ok(health_check(&dynamo_client, config.role_table.as_deref(), deep).await);
+    }
+
+    let body_str = match std::str::from_utf8(raw_body(&event)) {
+        Ok(s) => s,
+        Err(_) => {
+            return Ok(error_response(CommandError::BadRequest(
+                "body is not UTF-8",
+            )))
+        }
+    };
+
+    let interaction: InteractionRequest = match json::from_slice(body_str.as_bytes()) {
+        Ok(i) => i,
+        Err(_) => return Ok(error_response(CommandError::BadRequest("invalid JSON"))),
+    };
+
+    record_interaction_fields(&interaction);
+
+    // PINGs and autocomplete never touch Discord or billing, so they're
+    // answered before the bot token, flags and billing services are loaded.
+    if matches!(interaction.interaction_type, InteractionType::Ping) {
+        return Ok(json_response(200, &InteractionResponse::pong()));
+    }
+
+    let guild_id = match interaction.guild_id.clone() {
+        Some(id) => id,
+        None => return Ok(ephemeral_response("Guild ID missing.")),
+    };
+
+    let role_table = match config.role_table.clone() {
+        Some(v) => v,
+        None => {
+            return Ok(error_response(CommandError::Misconfigured(
+                "ROLE_MAPPINGS_TABLE_NAME",
+            )))
+        }
+    };
+
+    if matches!(
+        interaction.interaction_type,
+        InteractionType::ApplicationCommandAutocomplete
+    ) {
+        let role_store = GuildDao::new(dynamo_client.clone(), role_table.clone());
+        let favorites = FavoriteDao::new(dynamo_client, role_table);
+        let response = CommandRouter::autocomplete(&role_store, &favorites, &interaction).await;
+        return Ok(json_response(200, &response));
+    }
+
+    // Autocomplete fires on every keystroke, so only commands and component
+    // clicks spend tokens.
+    let user_id = interaction.member.as_ref().map(|m| m.user.id.as_str());
+    if let Some(user_id) = user_id {
+        let limiter = RateLimiter::new(RateLimitDao::new(dynamo_client.clone(), &role_table));
+
+        if let RateDecision::Limited(wait) = limiter.check(&guild_id, user_id).await {
+            return Ok(ephemeral_response(&format!(
+                "Slow down! Try again {}.",
+                timestamp::relative_in(wait)
+            )));
+        }
+    }
+
+    let ctx = match request_context(
+        &dynamo_client,
+        &config,
+        &parameters.load().await,
+        &role_table,
+        interaction,
+        guild_id,
+    )
+    .await
+    {
+        Ok(ctx) => ctx,
+        Err(err) => return Ok(error_response(err)),
     };
 
-    let discord_public_key = match secrets_reader
-        .get_secret_value(&public_key_secret_arn, "key", &DISCORD_PUBLIC_KEY_CACHE)
+    let stores = GuildStores::new(
+        dynamo_client.clone(),
+        &role_table,
+        config.retention.audit_days,
+    );
+    let role_store = Arc::new(GuildDao::new(dynamo_client.clone(), role_table.clone()));
+
+    // White-label applications each have their own token.
+    let discord_token = match state
+        .discord_token_for(&ctx.interaction.application_id)
         .await
     {
-        Ok(v) => v,
-        Err(_) => return Ok(server_error()),
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return Ok(error_response(CommandError::Misconfigured(
+                "DISCORD_TOKEN_SECRET_ARN",
+            )))
+        }
+        Err(err) => return Ok(error_response(err.into())),
     };
 
-    let subscription_table = match std::env::var("GUILD_SUBSCRIPTIONS_TABLE_NAME") {
-        Ok(v) => v,
-        Err(_) => return Ok(server_error()),
+    let role_manager = Arc::new(LazyDiscordApi::new(http_client.clone(), discord_token));
+
+    if state.tenants.is_some()
+        && config.application_id.as_deref() != Some(&ctx.interaction.application_id)
+    {
+        record_tenant_guild(
+            &dynamo_client,
+            &role_table,
+            &ctx.guild_id,
+            &ctx.interaction.application_id,
+        )
+        .await;
+    }
+
+    let task_queue = config
+        .task_queue_url
+        .clone()
+        .map(|url| TaskQueue::new(sqs_client.clone(), url));
+
+    let task_scheduler = config
+        .task_queue_arn
+        .clone()
+        .zip(config.task_scheduler_role_arn.clone())
+        .map(|(queue_arn, role_arn)| TaskScheduler::new(scheduler_client, queue_arn, role_arn));
+
+    let operators = OperatorContext {
+        allowlist: config.operators.clone(),
+        flag_store: FeatureFlagDao::new(dynamo_client.clone(), role_table.clone()),
+        stats_store: GlobalStatsDao::new(dynamo_client.clone(), role_table),
+    };
+
+    #[cfg(feature = "billing")]
+    let command_router = {
+        let billing = match billing_context(
+            &dynamo_client,
+            state.stripe_secret(),
+            &http_client,
+            &config,
+            &ctx,
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err(err) => return Ok(error_response(err)),
+        };
+
+        CommandRouter::new(
+            role_store.clone(),
+            role_manager,
+            billing,
+            operators,
+            task_queue,
+            task_scheduler,
+            stores.clone(),
+        )
+    };
+
+    #[cfg(not(feature = "billing"))]
+    let command_router = CommandRouter::new(
+        role_store.clone(),
+        role_manager,
+        operators,
+        task_queue,
+        task_scheduler,
+        stores.clone(),
+    );
+
+    let interaction_router = InteractionRouter::new(
+        command_router,
+        ComponentRouter::new(stores.role_menus, role_store),
+    );
+
+    let response = match interaction_router.route(&ctx).await {
+        Ok(r) => r,
+        Err(err) => {
+            error!(
+                reference = %correlation::reference(),
+                error = format!("{:#}", err),
+                "Interaction failed"
+            );
+            error_reporting::capture_error(&err, &ctx.interaction);
+            ops_alert::report_in_background(
+                &http_client,
+                "Interaction failed",
+                &format!("{:#}", err),
+            );
+            InteractionResponse::ephemeral(CommandError::from(err).user_message())
+        }
     };
 
-    let subscription_reader = SubscriptionReader::new(dynamo_client.clone(), subscription_table);
+    let response = match stage::footer() {
+        Some(footer) => response.with_footer(&footer),
+        None => response,
+    };
 
-    let auth_manager = AuthManager::new(subscription_reader.clone());
+    Ok(json_response(200, &response))
+}
 
-    if auth_manager
-        .verify_signature(signature, timestamp, body_bytes, &discord_public_key)
-        .is_err()
+/// Puts a white-label application on its guild's `GUILD` record, once per
+/// warm function, so the guild's scheduled tasks run as its bot even if it
+/// was installed before installs recorded the application.
+async fn record_tenant_guild(
+    dynamo_client: &DynamoClient,
+    role_table: &str,
+    guild_id: &str,
+    application_id: &str,
+) {
+    let key = (guild_id.to_string(), application_id.to_string());
+    if TENANT_GUILDS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&key)
     {
-        return Ok(json_response(
-            401,
-            &json!({ "error": "Invalid request signature" }),
-        ));
+        return;
     }
 
-    let interaction: InteractionRequest = match serde_json::from_str(body_str) {
-        Ok(i) => i,
-        Err(_) => return Ok(json_response(400, &json!({ "error": "Invalid JSON" }))),
+    let records = GuildRecordDao::new(dynamo_client.clone(), role_table);
+    if let Err(err) = records.record_application(guild_id, application_id).await {
+        warn!(
+            guild_id,
+            application_id,
+            error = format!("{:#}", err),
+            "Could not record the guild's application"
+        );
+        return;
+    }
+
+    let mut recorded = TENANT_GUILDS.lock().unwrap_or_else(|e| e.into_inner());
+    if recorded.len() >= TENANT_GUILDS_MAX_ENTRIES {
+        recorded.clear();
+    }
+    recorded.insert(key);
+}
+
+/// Resolves the interaction's guild configuration once, for the routers:
+/// its feature flags and, with billing, its settings and premium status.
+#[cfg_attr(not(feature = "billing"), allow(unused_variables))]
+async fn request_context(
+    dynamo_client: &DynamoClient,
+    config: &AppConfig,
+    parameters: &ConfigParameters,
+    role_table: &str,
+    interaction: InteractionRequest,
+    guild_id: String,
+) -> Result<RequestContext, CommandError> {
+    let flag_store = FeatureFlagDao::new(dynamo_client.clone(), role_table);
+    let flags = FeatureFlags::load(&flag_store, parameters);
+
+    #[cfg(feature = "billing")]
+    let (flags, settings, is_premium) = {
+        let settings_store = SettingsDao::new(dynamo_client.clone(), role_table);
+        let subscription_reader =
+            SubscriptionReader::new(dynamo_client.clone(), subscription_table(config)?);
+
+        tokio::join!(flags, Settings::load(&settings_store, parameters), async {
+            // A failed lookup treats the guild as free for this request, but
+            // unlike a lapsed subscription it's worth an error in the logs.
+            match subscription_reader.is_active(&guild_id).await {
+                Ok(is_active) => is_active,
+                Err(err) => {
+                    error!(
+                        guild_id,
+                        error = format!("{:#}", err),
+                        "Failed to check guild subscription"
+                    );
+                    false
+                }
+            }
+        },)
     };
 
-    let guild_id = match interaction.guild_id.as_deref() {
-        Some(id) => id,
-        None => return Ok(ephemeral_response("Guild ID missing.")),
+    #[cfg(not(feature = "billing"))]
+    let flags = flags.await;
+
+    Ok(RequestContext {
+        locale: RequestContext::resolve_locale(&interaction),
+        interaction,
+        guild_id,
+        flags,
+        #[cfg(feature = "billing")]
+        settings,
+        #[cfg(feature = "billing")]
+        is_premium,
+    })
+}
+
+#[cfg(feature = "billing")]
+fn subscription_table(config: &AppConfig) -> Result<String, CommandError> {
+    config
+        .subscription_table
+        .clone()
+        .ok_or(CommandError::Misconfigured(
+            "GUILD_SUBSCRIPTIONS_TABLE_NAME",
+        ))
+}
+
+/// Assembles the billing services for the context's guild: the usage meter
+/// for its quota, and the Stripe and subscription clients.
+#[cfg(feature = "billing")]
+async fn billing_context(
+    dynamo_client: &DynamoClient,
+    stripe_secret: Option<SecretSource>,
+    http_client: &reqwest::Client,
+    config: &AppConfig,
+    ctx: &RequestContext,
+) -> Result<BillingContext, CommandError> {
+    let subscription_table = subscription_table(config)?;
+
+    let subscription_reader =
+        SubscriptionReader::new(dynamo_client.clone(), subscription_table.clone());
+
+    let monthly_quota = if ctx.is_premium {
+        None
+    } else {
+        Some(ctx.settings.free_tier_monthly_toggles)
     };
 
-    if let Err(_) = auth_manager.verify_subscription(guild_id).await {
-        return Ok(ephemeral_response(
-            "This guild does not have an active subscription.",
-        ));
+    let usage_meter = UsageMeter::new(
+        UsageDao::new(dynamo_client.clone(), subscription_table.clone()),
+        monthly_quota,
+    );
+
+    let subscription_manager = SubscriptionManager::new(
+        subscription_reader,
+        SubscriptionWriter::new(dynamo_client.clone(), subscription_table.clone()),
+        BundleDao::new(dynamo_client.clone(), subscription_table),
+    );
+
+    let stripe_client = StripeClient::new(http_client.clone(), stripe_secret);
+
+    let premium_gate = PremiumGate::new(
+        ctx.is_premium,
+        ctx.settings.premium_sku_id.clone(),
+        ctx.settings.subscribe_url.clone(),
+    );
+
+    Ok(BillingContext {
+        usage_meter,
+        subscription_manager,
+        stripe_client,
+        premium_gate,
+    })
+}
+
+/// Moves the first interaction's setup into Lambda init, where it's free
+/// under provisioned concurrency: the public key and the bot token (in one
+/// batched secrets call), the config parameters, and the cached flag and
+/// settings items, which also opens the DynamoDB connection.
+/// Only the interactions function has a public key configured, so the other
+/// functions skip this unless they share a secret bundle. Nothing here fails
+/// init.
+pub async fn warm(state: &AppState) {
+    if state.secrets.is_none() && state.config.discord_public_key_secret_arn.is_none() {
+        return;
     }
 
-    let role_table = match std::env::var("ROLE_MAPPINGS_TABLE_NAME") {
-        Ok(v) => v,
-        Err(_) => return Ok(server_error()),
+    let warm_tables = async {
+        let Some(role_table) = state.config.role_table.as_deref() else {
+            return;
+        };
+
+        let parameters = state.parameters.load().await;
+
+        FeatureFlags::load(
+            &FeatureFlagDao::new(state.dynamo_client.clone(), role_table),
+            &parameters,
+        )
+        .await;
+
+        #[cfg(feature = "billing")]
+        Settings::load(
+            &SettingsDao::new(state.dynamo_client.clone(), role_table),
+            &parameters,
+        )
+        .await;
+    };
+
+    let warm_secrets = async {
+        prefetch_secrets(state).await;
+        signature::prefetch_public_key(state).await;
+    };
+
+    tokio::join!(warm_secrets, warm_tables);
+}
+
+/// Fetches the interaction path's secrets in one batch. Failures are logged;
+/// each secret is fetched again on its own when it's needed. With a secret
+/// bundle everything was already read during init.
+async fn prefetch_secrets(state: &AppState) {
+    if state.secrets.is_some() {
+        return;
+    }
+
+    let config = &state.config;
+    let secrets: Vec<(&str, &str, &'static SecretCache)> = [
+        (
+            config.discord_public_key_secret_arn.as_deref(),
+            "key",
+            &signature::DISCORD_PUBLIC_KEY_CACHE,
+        ),
+        (
+            config.discord_token_secret_arn.as_deref(),
+            "token",
+            &app_state::DISCORD_TOKEN_CACHE,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(arn, key, cache)| Some((arn?, key, cache)))
+    .collect();
+
+    if let Err(err) = state.secrets_reader.get_secrets(&secrets).await {
+        warn!(error = format!("{:#}", err), "Failed to prefetch secrets");
+    }
+}
+
+/// Loads the bot token into the process cache. The signature middleware runs
+/// this alongside the public key fetch so a cold command doesn't wait for the
+/// two secrets one after the other. Failures are left for the handler, which
+/// fetches again and reports them.
+pub async fn prefetch_discord_token(state: &AppState) {
+    if app_state::DISCORD_TOKEN_CACHE.is_current() {
+        return;
+    }
+
+    if let Some(discord_token) = state.discord_token() {
+        let _ = discord_token.get().await;
+    }
+}
+
+/// The bot's Discord client for the routes outside the interaction path,
+/// sharing its token cache.
+pub fn discord_api(state: &AppState) -> Result<LazyDiscordApi, CommandError> {
+    let discord_token = state
+        .discord_token()
+        .ok_or(CommandError::Misconfigured("DISCORD_TOKEN_SECRET_ARN"))?;
+
+    Ok(LazyDiscordApi::new(
+        state.http_client.clone(),
+        discord_token,
+    ))
+}
+
+/// The application's OAuth2 client, for the Linked Roles and dashboard
+/// routes. Each registers its own redirect URI with Discord.
+pub async fn oauth_client(
+    state: &AppState,
+    redirect_uri: &str,
+) -> Result<OAuthClient, CommandError> {
+    let client_id = required(&state.config.application_id, "DISCORD_APPLICATION_ID")?;
+    let client_secret = state
+        .oauth_client_secret()
+        .ok_or(CommandError::Misconfigured("DISCORD_OAUTH_SECRET_ARN"))?
+        .get()
+        .await
+        .context("Failed to load OAuth2 client secret")?;
+
+    Ok(OAuthClient::new(
+        state.http_client.clone(),
+        client_id,
+        client_secret,
+        redirect_uri,
+    ))
+}
+
+/// A configuration value the route can't run without.
+pub fn required<'a>(
+    value: &'a Option<String>,
+    name: &'static str,
+) -> Result<&'a str, CommandError> {
+    value.as_deref().ok_or(CommandError::Misconfigured(name))
+}
+
+/// Compares the registered slash commands with what the router handles and
+/// logs any drift. Runs once during init when `DISCORD_APPLICATION_ID` is set;
+/// never fails startup.
+pub async fn check_command_registration(state: &AppState) {
+    let Some(application_id) = state.config.application_id.as_deref() else {
+        return;
     };
 
-    let guild_dao = GuildDao::new(dynamo_client.clone(), role_table);
+    let Some(discord_token) = state.discord_token() else {
+        return;
+    };
 
-    let token_secret_arn = match std::env::var("DISCORD_TOKEN_SECRET_ARN") {
+    let discord_token = match discord_token.get().await {
         Ok(v) => v,
-        Err(_) => return Ok(server_error()),
+        Err(err) => {
+            warn!(
+                error = format!("{:#}", err),
+                "Skipping command registration check"
+            );
+            return;
+        }
+    };
+
+    let role_manager = RoleManager::new(state.http_client.clone(), discord_token);
+
+    commands::log_registration_drift(
+        &role_manager,
+        application_id,
+        state.config.commands_guild_id.as_deref(),
+    )
+    .await;
+}
+
+/// The request body exactly as Discord sent (and signed) it. API Gateway and
+/// Function URLs may deliver it base64-encoded; lambda_http decodes those
+/// into `Body::Binary`, while plain payloads arrive as `Body::Text`.
+pub fn raw_body(request: &Request) -> &[u8] {
+    body_bytes(request.body())
+}
+
+pub fn body_bytes(body: &Body) -> &[u8] {
+    match body {
+        Body::Empty => &[],
+        Body::Text(text) => text.as_bytes(),
+        Body::Binary(bytes) => bytes,
+    }
+}
+
+pub fn is_health_check(request: &Request) -> bool {
+    request.method() == Method::GET && request.uri().path() == HEALTH_PATH
+}
+
+/// Requests that don't come from Discord's interaction webhook and so carry
+/// no signature: the health check, the Linked Roles browser routes, and the
+/// dashboard API (which authenticates with its own session tokens).
+pub fn is_unsigned(request: &Request) -> bool {
+    is_health_check(request)
+        || linked_roles_handler::is_linked_roles_request(request)
+        || dashboard_handler::is_dashboard_request(request)
+}
+
+/// Unsigned liveness endpoint for uptime monitors and load tests. With
+/// `?deep=1` it also confirms the role table is reachable via DescribeTable,
+/// at most once per `DEEP_HEALTH_TTL` per warm function.
+async fn health_check(
+    dynamo_client: &DynamoClient,
+    role_table: Option<&str>,
+    deep: bool,
+) -> Response<Body> {
+    let mut body = json!({
+        "status": "ok",
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "commit": option_env!("GIT_SHA").unwrap_or("unknown"),
+        "stage": stage::name(),
+    });
+
+    if !deep {
+        return json_response(200, &body);
+    }
+
+    let dynamo_status = match role_table {
+        Some(table) => dynamo_status(dynamo_client, table).await,
+        None => "unconfigured",
     };
 
-    let discord_token = match secrets_reader
-        .get_secret_value(&token_secret_arn, "token", &DISCORD_TOKEN_CACHE)
+    body["checks"] = json!({ "dynamodb": dynamo_status });
+
+    if dynamo_status == "ok" {
+        json_response(200, &body)
+    } else {
+        body["status"] = json!("degraded");
+        json_response(503, &body)
+    }
+}
+
+async fn dynamo_status(dynamo_client: &DynamoClient, table: &str) -> &'static str {
+    let cached = DEEP_HEALTH
+        .lock()
+        .ok()
+        .and_then(|cache| *cache)
+        .filter(|(_, checked_at)| checked_at.elapsed() <= DEEP_HEALTH_TTL);
+
+    if let Some((status, _)) = cached {
+        return status;
+    }
+
+    let status = match dynamo_client
+        .describe_table()
+        .table_name(table)
+        .send()
         .await
     {
-        Ok(v) => v,
-        Err(_) => return Ok(server_error()),
+        Ok(_) => "ok",
+        Err(_) => "unreachable",
     };
 
-    let role_manager = RoleManager::new(http_client.clone(), discord_token);
+    if let Ok(mut cache) = DEEP_HEALTH.lock() {
+        *cache = Some((status, Instant::now()));
+    }
 
-    let command_router = CommandRouter::new(guild_dao, role_manager);
+    status
+}
 
-    let interaction_router = InteractionRouter::new(command_router);
+/// Attaches the interaction's identifiers to the handler span so every log
+/// line emitted while serving it carries them as structured fields.
+fn record_interaction_fields(interaction: &InteractionRequest) {
+    let span = Span::current();
 
-    let response = match interaction_router.route(&interaction).await {
-        Ok(r) => r,
-        Err(_) => crate::dal::model::interaction_response::InteractionResponse::ephemeral(
-            "Internal error.",
-        ),
-    };
+    span.record("interaction_id", interaction.id.as_str());
 
-    Ok(json_response(200, &response))
+    if let Some(guild_id) = interaction.guild_id.as_deref() {
+        span.record("guild_id", guild_id);
+    }
+
+    if let Some(member) = interaction.member.as_ref() {
+        span.record("user_id", member.user.id.as_str());
+    }
+
+    if let Some(data) = interaction.data.as_ref() {
+        span.record("command", data.name.as_str());
+    }
 }
 
-fn server_error() -> Response<Body> {
-    json_response(500, &json!({ "error": "Server misconfiguration" }))
+/// Answers a request that failed before it reached the router: a JSON error
+/// with the mapped status, or an ephemeral message for 200.
+pub fn error_response(err: CommandError) -> Response<Body> {
+    let status = err.status();
+    let message = err.user_message();
+
+    if status >= 500 || status == 200 {
+        error!(
+            reference = %correlation::reference(),
+            error = format!("{:#}", err),
+            "Request failed"
+        );
+    }
+
+    if status == 200 {
+        return ephemeral_response(&message);
+    }
+
+    json_response(status, &json!({ "error": message }))
 }
 
-fn json_response<T: serde::Serialize>(status: u16, body: &T) -> Response<Body> {
-    let body_str = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+pub fn json_response<T: serde::Serialize>(status: u16, body: &T) -> Response<Body> {
+    let body_str = json::to_string(body).unwrap_or_else(|_| "{}".to_string());
 
     Response::builder()
         .status(status)
@@ -142,8 +770,5 @@ fn json_response<T: serde::Serialize>(status: u16, body: &T) -> Response<Body> {
 }
 
 fn ephemeral_response(content: &str) -> Response<Body> {
-    json_response(
-        200,
-        &crate::dal::model::interaction_response::InteractionResponse::ephemeral(content),
-    )
+    json_response(200, &InteractionResponse::ephemeral(content))
 }
@@ -0,0 +1,29 @@
+//! JSON on the interaction path. Bursts of autocomplete interactions spend a
+//! noticeable share of their CPU parsing bodies and writing responses, so the
+//! `simd-json` feature swaps serde_json for simd-json here. Both drive the
+//! same serde models; `benches/json.rs` compares them.
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(feature = "simd-json")]
+pub fn from_slice<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    // simd-json parses in place, so it needs a mutable copy of the body.
+    let mut buffer = body.to_vec();
+    Ok(simd_json::serde::from_slice(&mut buffer)?)
+}
+
+#[cfg(not(feature = "simd-json"))]
+pub fn from_slice<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    Ok(serde_json::from_slice(body)?)
+}
+
+#[cfg(feature = "simd-json")]
+pub fn to_string<T: Serialize>(value: &T) -> Result<String> {
+    Ok(simd_json::serde::to_string(value)?)
+}
+
+#[cfg(not(feature = "simd-json"))]
+pub fn to_string<T: Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
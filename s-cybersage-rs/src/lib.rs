@@ -0,0 +1,34 @@
+//! Core of the S-CyberSage bot: business logic, data access, and the Lambda
+//! event handlers. The `s-cybersage-rs` binary wires these into the Lambda
+//! runtime; tests and tooling can use them directly.
+
+pub mod app_events_handler;
+pub mod app_state;
+pub mod bal;
+pub mod commands;
+pub mod correlation;
+pub mod dal;
+pub mod dashboard_handler;
+pub mod dispatch;
+pub mod error;
+pub mod error_reporting;
+pub mod http_handler;
+pub mod json;
+pub mod linked_roles_handler;
+pub mod markdown;
+pub mod metrics;
+pub mod middleware;
+pub mod ops_alert;
+pub mod schedule_handler;
+pub mod snapshot;
+pub mod sqs_handler;
+pub mod stage;
+pub mod stream_handler;
+pub mod telemetry;
+pub mod timestamp;
+
+/// Declared here rather than in `main` so the other binaries and the
+/// benchmarks use it too when the feature is on.
+#[cfg(feature = "mimalloc")]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
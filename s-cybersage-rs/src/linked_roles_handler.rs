@@ -0,0 +1,178 @@
+//! Browser routes for Discord Linked Roles. The application's verification
+//! URL points at `/linked-roles`, which sends the user to Discord to
+//! authorize; Discord redirects back to `/linked-roles/callback`, where the
+//! bot records the grant and pushes the user's metadata. Neither request
+//! comes from Discord's interaction webhook, so neither is signed.
+
+use anyhow::anyhow;
+use lambda_http::{http::Method, Body, Request, RequestExt, Response};
+use tracing::{error, info};
+
+#[cfg(feature = "billing")]
+use crate::dal::dao::bundle::BundleDao;
+use crate::{
+    app_state::{AppConfig, AppState},
+    bal::{
+        discord::oauth_client::OAuthClient,
+        linked_roles::{metadata::PLATFORM_NAME, role_connection_manager::RoleConnectionManager},
+    },
+    correlation,
+    dal::dao::role_connection::RoleConnectionDao,
+    error::CommandError,
+    http_handler::{self, required},
+};
+
+const VERIFY_PATH: &str = "/linked-roles";
+const CALLBACK_PATH: &str = "/linked-roles/callback";
+
+/// Ties the callback to the browser that started the flow.
+const STATE_COOKIE: &str = "linked_roles_state";
+const STATE_MAX_AGE_SECONDS: u32 = 600;
+
+/// `identify` to learn who linked, `role_connections.write` to set their
+/// metadata.
+const SCOPES: &str = "identify role_connections.write";
+
+pub fn is_linked_roles_request(request: &Request) -> bool {
+    request.method() == Method::GET && matches!(request.uri().path(), VERIFY_PATH | CALLBACK_PATH)
+}
+
+pub async fn function_handler(request: &Request, state: &AppState) -> Response<Body> {
+    let result = match request.uri().path() {
+        VERIFY_PATH => authorize(&state.config),
+        _ => callback(request, state).await,
+    };
+
+    result.unwrap_or_else(error_page)
+}
+
+/// Redirects to Discord's consent screen with a fresh `state`, remembered in
+/// a short-lived cookie.
+fn authorize(config: &AppConfig) -> Result<Response<Body>, CommandError> {
+    let client_id = required(&config.application_id, "DISCORD_APPLICATION_ID")?;
+    let redirect_uri = required(
+        &config.linked_roles_redirect_uri,
+        "LINKED_ROLES_REDIRECT_URI",
+    )?;
+
+    let mut nonce = [0u8; 16];
+    getrandom::fill(&mut nonce).map_err(|err| anyhow!("Failed to generate state: {}", err))?;
+    let state = hex::encode(nonce);
+
+    let url = OAuthClient::authorize_url(client_id, redirect_uri, SCOPES, &state)?;
+
+    Ok(Response::builder()
+        .status(302)
+        .header("location", url.as_str())
+        .header("set-cookie", state_cookie(&state, STATE_MAX_AGE_SECONDS))
+        .body(Body::Empty)
+        .unwrap())
+}
+
+async fn callback(request: &Request, state: &AppState) -> Result<Response<Body>, CommandError> {
+    let params = request.query_string_parameters_ref();
+    let param = |name: &str| params.and_then(|p| p.first(name));
+
+    if param("error").is_some() {
+        return Ok(page(200, "Linking was cancelled. You can close this tab."));
+    }
+
+    let (Some(code), Some(returned_state)) = (param("code"), param("state")) else {
+        return Ok(page(400, "The authorization response was incomplete."));
+    };
+
+    if cookie(request, STATE_COOKIE) != Some(returned_state) {
+        return Ok(page(
+            400,
+            "This link has expired or was opened in a different browser. Start again from \
+             Discord.",
+        ));
+    }
+
+    let user = role_connection_manager(state).await?.link(code).await?;
+    info!(user_id = %user.id, "Linked role connection");
+
+    let mut response = page(
+        200,
+        "Your account is linked. Return to Discord to claim your roles.",
+    );
+    response
+        .headers_mut()
+        .insert("set-cookie", state_cookie("", 0).parse().unwrap());
+
+    Ok(response)
+}
+
+async fn role_connection_manager(state: &AppState) -> Result<RoleConnectionManager, CommandError> {
+    let config = &state.config;
+
+    let redirect_uri = required(
+        &config.linked_roles_redirect_uri,
+        "LINKED_ROLES_REDIRECT_URI",
+    )?;
+    let role_table = required(&config.role_table, "ROLE_MAPPINGS_TABLE_NAME")?;
+
+    let oauth = http_handler::oauth_client(state, redirect_uri).await?;
+    let grants = RoleConnectionDao::new(state.dynamo_client.clone(), role_table);
+
+    #[cfg(feature = "billing")]
+    let manager = {
+        let subscription_table =
+            required(&config.subscription_table, "GUILD_SUBSCRIPTIONS_TABLE_NAME")?;
+        let bundles = BundleDao::new(state.dynamo_client.clone(), subscription_table);
+
+        RoleConnectionManager::new(oauth, grants, bundles)
+    };
+
+    #[cfg(not(feature = "billing"))]
+    let manager = RoleConnectionManager::new(oauth, grants);
+
+    Ok(manager)
+}
+
+fn state_cookie(value: &str, max_age: u32) -> String {
+    format!(
+        "{}={}; Max-Age={}; Path=/; HttpOnly; Secure; SameSite=Lax",
+        STATE_COOKIE, value, max_age
+    )
+}
+
+fn cookie<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .get_all("cookie")
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|header| header.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(name)?.strip_prefix('='))
+}
+
+fn error_page(err: CommandError) -> Response<Body> {
+    error!(
+        reference = %correlation::reference(),
+        error = format!("{:#}", err),
+        "Linked roles request failed"
+    );
+
+    // A 200 status means "answer Discord with an ephemeral message"; in a
+    // browser the failure should look like one.
+    let status = match err.status() {
+        200 => 502,
+        status => status,
+    };
+
+    page(status, &err.user_message())
+}
+
+fn page(status: u16, message: &str) -> Response<Body> {
+    let html = format!(
+        "<!doctype html><meta charset=\"utf-8\"><title>{}</title><p>{}</p>",
+        PLATFORM_NAME, message
+    );
+
+    Response::builder()
+        .status(status)
+        .header("content-type", "text/html; charset=utf-8")
+        .body(html.into())
+        .unwrap()
+}
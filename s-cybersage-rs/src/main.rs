@@ -1,34 +1,94 @@
-use lambda_http::{run, service_fn, Error};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use lambda_http::tower::{util::BoxCloneService, ServiceBuilder};
+use lambda_runtime::{run, service_fn, Error};
+use s_cybersage_rs::{
+    app_state::AppState,
+    dispatch, error_reporting, http_handler, metrics,
+    middleware::{
+        body_limit::{BodyLimitLayer, MAX_BODY_BYTES},
+        catch_panic::CatchPanicLayer,
+        compression::{CompressionLayer, MIN_COMPRESS_BYTES},
+        normalize::NormalizeLayer,
+        request_log::RequestLogLayer,
+        request_metrics::RequestMetricsLayer,
+        signature::SignatureLayer,
+    },
+    ops_alert, snapshot,
+};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
-pub mod bal;
-pub mod dal;
-pub mod http_handler;
-
-#[tokio::main]
+// An execution environment handles one invocation at a time on a fraction of
+// a vCPU, so a multi-threaded runtime's workers would only add thread
+// handoffs. Background secret refreshes still run while the runtime waits
+// for the next event.
+#[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Error> {
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .init();
+    let init_started = std::time::Instant::now();
+
+    let fmt_layer = match std::env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(true)
+            .boxed(),
+        _ => tracing_subscriber::fmt::layer().boxed(),
+    };
+
+    let registry = tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")));
+
+    #[cfg(feature = "otel")]
+    let registry = registry.with(s_cybersage_rs::telemetry::otel_layer()?);
+
+    registry.init();
+
+    let _sentry_guard = error_reporting::init();
+    ops_alert::install_panic_hook();
+
+    let state = AppState::from_env().await?;
+
+    http_handler::warm(&state).await;
+    http_handler::check_command_registration(&state).await;
+
+    metrics::mark_init_complete(init_started);
+    snapshot::mark_init();
 
-    let shared_config = aws_config::load_from_env().await;
-    let dynamo_client = aws_sdk_dynamodb::Client::new(&shared_config);
-    let secrets_client = aws_sdk_secretsmanager::Client::new(&shared_config);
+    // Outermost first: requests are normalized before anything reads the
+    // path, a panic still produces a logged, measured 500, and oversized
+    // bodies are rejected before the signature is checked. Only verified
+    // requests reach fixture recording.
+    #[cfg(feature = "fixture-recording")]
+    let fixture_recording =
+        s_cybersage_rs::middleware::fixture_recording::FixtureRecordingLayer::from_env().await;
+    #[cfg(not(feature = "fixture-recording"))]
+    let fixture_recording = lambda_http::tower::layer::util::Identity::new();
 
-    let http_client = reqwest::Client::builder()
-        .user_agent("cybersage-bot")
-        .pool_idle_timeout(std::time::Duration::from_secs(90))
-        .pool_max_idle_per_host(5)
-        .build()?;
+    let handler_state = state.clone();
+    let http = BoxCloneService::new(
+        ServiceBuilder::new()
+            .layer(NormalizeLayer)
+            .layer(RequestLogLayer)
+            .layer(CompressionLayer::new(MIN_COMPRESS_BYTES))
+            .layer(RequestMetricsLayer)
+            .layer(CatchPanicLayer)
+            .layer(BodyLimitLayer::new(MAX_BODY_BYTES))
+            .layer(SignatureLayer::new(state.clone()))
+            .layer(fixture_recording)
+            .service(lambda_http::service_fn(move |request| {
+                http_handler::function_handler(request, handler_state.clone())
+            })),
+    );
 
+    // One binary serves every event source; each function's triggers decide
+    // which handler an invocation reaches. The handler future is boxed so its
+    // (deeply nested) type doesn't have to be laid out inside `main`.
     run(service_fn(move |event| {
-        http_handler::function_handler(
+        Box::pin(dispatch::function_handler(
             event,
-            dynamo_client.clone(),
-            secrets_client.clone(),
-            http_client.clone(),
-        )
+            state.clone(),
+            http.clone(),
+        ))
     }))
     .await
 }
@@ -0,0 +1,24 @@
+//! Escaping for role names and other user-provided text interpolated into
+//! message content, so it renders as typed instead of as formatting.
+
+/// Characters Discord reads as markdown or mention syntax. Discord drops
+/// the backslash before any punctuation, so escaping these is invisible.
+const SPECIAL_CHARS: &[char] = &[
+    '\\', '*', '_', '~', '`', '|', '>', '#', '-', '[', ']', '(', ')', '<', '@', ':',
+];
+
+/// `text` with every markdown and mention character backslash-escaped,
+/// e.g. `*Mods*` becomes `\*Mods\*` and `@everyone` becomes `\@everyone`.
+/// Not for text inside code blocks, where backslashes show.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if SPECIAL_CHARS.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
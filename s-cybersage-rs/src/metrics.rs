@@ -0,0 +1,167 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+
+use crate::dal::model::daily_stats::GuildDayStats;
+
+const DEFAULT_NAMESPACE: &str = "S-CyberSage";
+
+static INIT_DURATION: OnceCell<Duration> = OnceCell::new();
+static COLD_START: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    Error,
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CommandMetric<'a> {
+    pub command: &'a str,
+    pub subcommand: &'a str,
+    pub outcome: Outcome,
+    pub latency: Duration,
+    pub guild_tier: &'a str,
+}
+
+/// Metric records are dropped when the `analytics` feature is off.
+fn emit(record: &Value) {
+    if cfg!(feature = "analytics") {
+        println!("{}", record);
+    }
+}
+
+fn namespace() -> String {
+    std::env::var("METRICS_NAMESPACE").unwrap_or_else(|_| DEFAULT_NAMESPACE.to_string())
+}
+
+/// Writes a CloudWatch embedded-metric-format record to stdout. Lambda ships
+/// stdout to CloudWatch Logs, which extracts the metrics without a PutMetricData call.
+pub fn emit_command(metric: &CommandMetric) {
+    let record = json!({
+        "_aws": {
+            "Timestamp": Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": namespace(),
+                "Dimensions": [
+                    ["Command", "Outcome"],
+                    ["Command", "Subcommand", "Outcome"],
+                    ["GuildTier"],
+                ],
+                "Metrics": [
+                    { "Name": "Invocations", "Unit": "Count" },
+                    { "Name": "Latency", "Unit": "Milliseconds" },
+                ],
+            }],
+        },
+        "Command": metric.command,
+        "Subcommand": metric.subcommand,
+        "Outcome": metric.outcome.as_str(),
+        "GuildTier": metric.guild_tier,
+        "Invocations": 1,
+        "Latency": metric.latency.as_millis() as u64,
+    });
+
+    emit(&record);
+}
+
+/// Records how long process initialization took, measured from `started`.
+/// Call once from `main` right before entering the runtime loop.
+pub fn mark_init_complete(started: Instant) {
+    let _ = INIT_DURATION.set(started.elapsed());
+}
+
+/// Emits one record per invocation flagging whether it was a cold start. The
+/// init duration is only attached to the cold start, since that's the only
+/// invocation that paid for it.
+pub fn emit_invocation() {
+    let cold_start = COLD_START.swap(false, Ordering::Relaxed);
+
+    let mut record = json!({
+        "_aws": {
+            "Timestamp": Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": namespace(),
+                "Dimensions": [["ColdStart"]],
+                "Metrics": [
+                    { "Name": "Invocations", "Unit": "Count" },
+                    { "Name": "InitDuration", "Unit": "Milliseconds" },
+                ],
+            }],
+        },
+        "ColdStart": if cold_start { "true" } else { "false" },
+        "Invocations": 1,
+    });
+
+    if cold_start {
+        if let Some(init) = INIT_DURATION.get() {
+            record["InitDuration"] = json!(init.as_millis() as u64);
+        }
+    }
+
+    emit(&record);
+}
+
+/// Emits one record per HTTP response, dimensioned by status class
+/// (`2xx`, `4xx`, `5xx`), with the time spent producing it.
+pub fn emit_http_response(status: u16, latency: Duration) {
+    let record = json!({
+        "_aws": {
+            "Timestamp": Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": namespace(),
+                "Dimensions": [["StatusClass"]],
+                "Metrics": [
+                    { "Name": "Responses", "Unit": "Count" },
+                    { "Name": "ResponseLatency", "Unit": "Milliseconds" },
+                ],
+            }],
+        },
+        "StatusClass": format!("{}xx", status / 100),
+        "Responses": 1,
+        "ResponseLatency": latency.as_millis() as u64,
+    });
+
+    emit(&record);
+}
+
+/// Emits one record per guild per day from the `aggregate_stats` job, without
+/// dimensions, so each metric's daily Sum is the bot-wide total and
+/// `PremiumGuilds / Guilds` is the premium conversion.
+pub fn emit_guild_day(stats: &GuildDayStats) {
+    let record = json!({
+        "_aws": {
+            "Timestamp": Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": namespace(),
+                "Dimensions": [[]],
+                "Metrics": [
+                    { "Name": "Guilds", "Unit": "Count" },
+                    { "Name": "ActiveGuilds", "Unit": "Count" },
+                    { "Name": "Toggles", "Unit": "Count" },
+                    { "Name": "PremiumGuilds", "Unit": "Count" },
+                ],
+            }],
+        },
+        "Guilds": 1,
+        "ActiveGuilds": u64::from(stats.toggles > 0),
+        "Toggles": stats.toggles,
+        "PremiumGuilds": u64::from(stats.premium),
+    });
+
+    emit(&record);
+}
@@ -0,0 +1,74 @@
+use std::task::{Context, Poll};
+
+use lambda_http::{tower::Layer, Body, Error, Request, Response, Service};
+use serde_json::json;
+use tracing::warn;
+
+use crate::http_handler::{json_response, raw_body, BoxResponseFuture};
+
+/// Discord interaction payloads are a few KiB; anything much larger isn't
+/// one and isn't worth verifying.
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Rejects requests whose body exceeds `max_bytes` with a 413.
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimitLayer {
+    max_bytes: usize,
+}
+
+impl BodyLimitLayer {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl<S> Layer<S> for BodyLimitLayer {
+    type Service = BodyLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BodyLimit {
+            inner,
+            max_bytes: self.max_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BodyLimit<S> {
+    inner: S,
+    max_bytes: usize,
+}
+
+impl<S> Service<Request> for BodyLimit<S>
+where
+    S: Service<Request, Response = Response<Body>, Error = Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = BoxResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let len = raw_body(&request).len();
+
+        if len > self.max_bytes {
+            warn!(
+                len,
+                max = self.max_bytes,
+                "Rejecting oversized request body"
+            );
+            return Box::pin(async {
+                Ok(json_response(
+                    413,
+                    &json!({ "error": "Request body too large" }),
+                ))
+            });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use lambda_http::{tower::Layer, Body, Error, Request, Response, Service};
+use serde_json::json;
+use tracing::error;
+
+use crate::{
+    correlation,
+    http_handler::{json_response, BoxResponseFuture},
+};
+
+/// Turns a panic inside the wrapped service into a 500, so one bad request
+/// doesn't take the invocation down. The panic hook has already reported it
+/// by the time the response is built.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CatchPanicLayer;
+
+impl<S> Layer<S> for CatchPanicLayer {
+    type Service = CatchPanic<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CatchPanic { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CatchPanic<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for CatchPanic<S>
+where
+    S: Service<Request, Response = Response<Body>, Error = Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = BoxResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let future = CatchUnwind(Box::pin(self.inner.call(request)));
+
+        Box::pin(async move {
+            match future.await {
+                Ok(result) => result,
+                Err(()) => {
+                    error!(reference = %correlation::reference(), "Handler panicked");
+                    Ok(json_response(500, &json!({ "error": "Internal error" })))
+                }
+            }
+        })
+    }
+}
+
+/// Polls the inner future under `catch_unwind`, resolving to `Err(())` if a
+/// poll panicked.
+struct CatchUnwind<F>(Pin<Box<F>>);
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = Result<F::Output, ()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = self.0.as_mut();
+
+        match std::panic::catch_unwind(AssertUnwindSafe(|| inner.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(_) => Poll::Ready(Err(())),
+        }
+    }
+}
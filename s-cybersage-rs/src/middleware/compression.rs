@@ -0,0 +1,132 @@
+use std::{
+    io::Write,
+    task::{Context, Poll},
+};
+
+use flate2::{write::GzEncoder, Compression as GzLevel};
+use lambda_http::{
+    http::{header, HeaderValue},
+    tower::Layer,
+    Body, Error, Request, Response, Service,
+};
+use tracing::warn;
+
+use crate::http_handler::{body_bytes, BoxResponseFuture};
+
+/// Responses smaller than this aren't worth the CPU; most interaction
+/// replies are well under it.
+pub const MIN_COMPRESS_BYTES: usize = 1024;
+
+/// Gzips response bodies of at least `min_bytes` when the request's
+/// `accept-encoding` allows it.
+#[derive(Debug, Clone, Copy)]
+pub struct CompressionLayer {
+    min_bytes: usize,
+}
+
+impl CompressionLayer {
+    pub fn new(min_bytes: usize) -> Self {
+        Self { min_bytes }
+    }
+}
+
+impl<S> Layer<S> for CompressionLayer {
+    type Service = Compression<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Compression {
+            inner,
+            min_bytes: self.min_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Compression<S> {
+    inner: S,
+    min_bytes: usize,
+}
+
+impl<S> Service<Request> for Compression<S>
+where
+    S: Service<Request, Response = Response<Body>, Error = Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = BoxResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let gzip = request
+            .headers()
+            .get(header::ACCEPT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(accepts_gzip);
+
+        let min_bytes = self.min_bytes;
+        let future = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = future.await?;
+
+            if !gzip {
+                return Ok(response);
+            }
+
+            Ok(compress(response, min_bytes))
+        })
+    }
+}
+
+/// Whether an `accept-encoding` value allows gzip, honouring `q=0` opt-outs.
+pub fn accepts_gzip(accept_encoding: &str) -> bool {
+    accept_encoding.split(',').any(|entry| {
+        let mut parts = entry.split(';').map(str::trim);
+        let coding = parts.next().unwrap_or("");
+
+        if !coding.eq_ignore_ascii_case("gzip") && coding != "*" {
+            return false;
+        }
+
+        parts
+            .filter_map(|p| p.strip_prefix("q="))
+            .all(|q| q.parse::<f32>().map_or(true, |q| q > 0.0))
+    })
+}
+
+fn compress(response: Response<Body>, min_bytes: usize) -> Response<Body> {
+    if response.headers().contains_key(header::CONTENT_ENCODING) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+
+    let bytes = body_bytes(&body);
+
+    if bytes.len() < min_bytes {
+        return Response::from_parts(parts, body);
+    }
+
+    let mut encoder = GzEncoder::new(Vec::with_capacity(bytes.len() / 4), GzLevel::fast());
+    let compressed = match encoder.write_all(bytes).and_then(|_| encoder.finish()) {
+        Ok(compressed) => compressed,
+        Err(err) => {
+            warn!(error = %err, "Failed to gzip response; sending it uncompressed");
+            return Response::from_parts(parts, body);
+        }
+    };
+
+    parts
+        .headers
+        .insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+    parts
+        .headers
+        .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    Response::from_parts(parts, Body::Binary(compressed))
+}
@@ -0,0 +1,227 @@
+use std::{
+    path::PathBuf,
+    task::{Context, Poll},
+};
+
+use anyhow::{Context as _, Result};
+use aws_sdk_s3::primitives::ByteStream;
+use lambda_http::{tower::Layer, Body, Error, Request, Response, Service};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{
+    http_handler::{body_bytes, is_unsigned, raw_body, BoxResponseFuture},
+    stage,
+};
+
+/// Credentials, replaced wherever they appear: an interaction's `token`
+/// authorizes follow-ups to it for 15 minutes, and the others grant access
+/// to a user or the application.
+const SECRET_FIELDS: &[&str] = &["token", "access_token", "refresh_token", "client_secret"];
+
+/// User profile fields, replaced so fixtures don't carry anyone's name or
+/// avatar. IDs are kept so a fixture's guild, user and roles stay consistent.
+const PERSONAL_FIELDS: &[&str] = &[
+    "username",
+    "global_name",
+    "nick",
+    "avatar",
+    "banner",
+    "email",
+    "avatar_decoration_data",
+];
+
+/// Where recorded fixtures are written.
+#[derive(Clone)]
+pub enum FixtureSink {
+    Disk(PathBuf),
+    S3 {
+        client: aws_sdk_s3::Client,
+        bucket: String,
+        prefix: String,
+    },
+}
+
+impl FixtureSink {
+    async fn write(&self, name: &str, fixture: &Value) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(fixture)?;
+
+        match self {
+            FixtureSink::Disk(dir) => {
+                tokio::fs::create_dir_all(dir)
+                    .await
+                    .context("Failed to create the fixture directory")?;
+                tokio::fs::write(dir.join(name), bytes)
+                    .await
+                    .context("Failed to write fixture")?;
+            }
+            FixtureSink::S3 {
+                client,
+                bucket,
+                prefix,
+            } => {
+                client
+                    .put_object()
+                    .bucket(bucket)
+                    .key(format!("{}{}", prefix, name))
+                    .content_type("application/json")
+                    .body(ByteStream::from(bytes))
+                    .send()
+                    .await
+                    .context("Failed to upload fixture to S3")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Saves each verified Discord request and the response it got, both
+/// sanitized, as a fixture for the test harness. The unsigned routes (health
+/// check, Linked Roles, dashboard) are never recorded. Only built with the
+/// `fixture-recording` feature, enabled by `FIXTURE_RECORDING` (a directory,
+/// or `s3://<bucket>/<prefix>`), and never in production.
+#[derive(Clone)]
+pub struct FixtureRecordingLayer {
+    sink: Option<FixtureSink>,
+}
+
+impl FixtureRecordingLayer {
+    pub async fn from_env() -> Self {
+        let target = std::env::var("FIXTURE_RECORDING")
+            .ok()
+            .filter(|v| !v.is_empty());
+
+        let Some(target) = target else {
+            return Self { sink: None };
+        };
+
+        if stage::is_production() {
+            warn!("Ignoring FIXTURE_RECORDING in production");
+            return Self { sink: None };
+        }
+
+        let sink = match target.strip_prefix("s3://") {
+            Some(location) => {
+                let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+                let shared_config = aws_config::load_from_env().await;
+
+                FixtureSink::S3 {
+                    client: aws_sdk_s3::Client::new(&shared_config),
+                    bucket: bucket.to_string(),
+                    prefix: match prefix {
+                        "" => String::new(),
+                        prefix => format!("{}/", prefix.trim_end_matches('/')),
+                    },
+                }
+            }
+            None => FixtureSink::Disk(PathBuf::from(target)),
+        };
+
+        info!("Recording request fixtures");
+        Self { sink: Some(sink) }
+    }
+}
+
+impl<S> Layer<S> for FixtureRecordingLayer {
+    type Service = FixtureRecording<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FixtureRecording {
+            inner,
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct FixtureRecording<S> {
+    inner: S,
+    sink: Option<FixtureSink>,
+}
+
+impl<S> Service<Request> for FixtureRecording<S>
+where
+    S: Service<Request, Response = Response<Body>, Error = Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = BoxResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let recorded = self.sink.clone().filter(|_| !is_unsigned(&request));
+        let recorded = recorded.and_then(|sink| {
+            let payload: Value = serde_json::from_slice(raw_body(&request)).ok()?;
+            Some((sink, payload))
+        });
+
+        let future = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = future.await?;
+
+            // Written before responding, since the environment may be frozen
+            // as soon as the response is sent.
+            if let Some((sink, payload)) = recorded {
+                let (name, fixture) = fixture(payload, &response);
+
+                if let Err(err) = sink.write(&name, &fixture).await {
+                    warn!(error = format!("{:#}", err), "Failed to record fixture");
+                }
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+/// The fixture's file name (`<command or type>-<interaction id>.json`) and
+/// its document.
+fn fixture(mut payload: Value, response: &Response<Body>) -> (String, Value) {
+    sanitize(&mut payload);
+
+    let kind = match payload.pointer("/data/name").and_then(Value::as_str) {
+        Some(name) => name.replace(' ', "_"),
+        None => format!("type{}", payload["type"]),
+    };
+    let id = payload["id"].as_str().unwrap_or("unknown").to_string();
+
+    let body = body_bytes(response.body());
+    let mut response_body = serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()));
+    sanitize(&mut response_body);
+
+    let fixture = json!({
+        "request": payload,
+        "response": {
+            "status": response.status().as_u16(),
+            "body": response_body,
+        },
+    });
+
+    (format!("{}-{}.json", kind, id), fixture)
+}
+
+/// Replaces credentials and user profile fields throughout `value`.
+pub fn sanitize(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) && field.is_string() {
+                    *field = Value::String("redacted-token".to_string());
+                } else if PERSONAL_FIELDS.contains(&key.as_str()) && !field.is_null() {
+                    *field = Value::String("redacted".to_string());
+                } else {
+                    sanitize(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(sanitize),
+        _ => {}
+    }
+}
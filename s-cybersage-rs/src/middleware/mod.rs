@@ -0,0 +1,9 @@
+pub mod body_limit;
+pub mod catch_panic;
+pub mod compression;
+#[cfg(feature = "fixture-recording")]
+pub mod fixture_recording;
+pub mod normalize;
+pub mod request_log;
+pub mod request_metrics;
+pub mod signature;
@@ -0,0 +1,129 @@
+use std::task::{Context, Poll};
+
+use lambda_http::{
+    http::Uri, request::RequestContext, tower::Layer, Body, Error, Request, RequestExt, Response,
+    Service,
+};
+
+use crate::http_handler::BoxResponseFuture;
+
+/// Which AWS front end delivered the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpSource {
+    /// Lambda Function URL (payload format 2.0).
+    FunctionUrl,
+    /// API Gateway HTTP API (payload format 2.0).
+    HttpApi,
+    /// API Gateway REST API (payload format 1.0).
+    RestApi,
+    Alb,
+    Unknown,
+}
+
+impl HttpSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpSource::FunctionUrl => "function_url",
+            HttpSource::HttpApi => "http_api",
+            HttpSource::RestApi => "rest_api",
+            HttpSource::Alb => "alb",
+            HttpSource::Unknown => "unknown",
+        }
+    }
+}
+
+/// Makes requests look the same whichever front end delivered them, so the
+/// handler can route on `path()` alone:
+///
+/// - API Gateway stages other than `$default` prefix the path (`/prod/healthz`);
+///   the prefix is stripped.
+/// - Payload format 1.0 splits headers into `headers` and `multiValueHeaders`;
+///   lambda_http merges both into one case-insensitive `HeaderMap`, so header
+///   lookups always use lowercase names.
+/// - The detected `HttpSource` is attached as a request extension.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizeLayer;
+
+impl<S> Layer<S> for NormalizeLayer {
+    type Service = Normalize<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Normalize { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Normalize<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for Normalize<S>
+where
+    S: Service<Request, Response = Response<Body>, Error = Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = BoxResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        Box::pin(self.inner.call(normalize(request)))
+    }
+}
+
+/// Strips the stage prefix and tags the request with its `HttpSource`.
+pub fn normalize(mut request: Request) -> Request {
+    let (source, stage) = match request.request_context_ref() {
+        Some(RequestContext::ApiGatewayV2(ctx)) => {
+            let is_function_url = ctx
+                .domain_name
+                .as_deref()
+                .is_some_and(|d| d.contains(".lambda-url."));
+
+            let source = if is_function_url {
+                HttpSource::FunctionUrl
+            } else {
+                HttpSource::HttpApi
+            };
+
+            (source, ctx.stage.clone())
+        }
+        Some(RequestContext::ApiGatewayV1(ctx)) => (HttpSource::RestApi, ctx.stage.clone()),
+        Some(RequestContext::Alb(_)) => (HttpSource::Alb, None),
+        _ => (HttpSource::Unknown, None),
+    };
+
+    if let Some(stage) = stage.filter(|s| s != "$default") {
+        if let Some(uri) = strip_stage(request.uri(), &stage) {
+            *request.uri_mut() = uri;
+        }
+    }
+
+    request.extensions_mut().insert(source);
+    request
+}
+
+fn strip_stage(uri: &Uri, stage: &str) -> Option<Uri> {
+    let prefix = format!("/{}", stage);
+    let rest = uri.path().strip_prefix(&prefix)?;
+
+    let path = match rest {
+        "" => "/",
+        r if r.starts_with('/') => r,
+        // `/prodfoo` isn't under the `prod` stage.
+        _ => return None,
+    };
+
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
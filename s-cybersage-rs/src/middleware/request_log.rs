@@ -0,0 +1,96 @@
+use std::{
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use lambda_http::{tower::Layer, Body, Error, Request, RequestExt, Response, Service};
+use tracing::{info, info_span, Instrument};
+
+use super::normalize::HttpSource;
+use crate::{correlation, http_handler::BoxResponseFuture};
+
+/// Opens the per-request span that the handler records interaction fields
+/// into, scopes the correlation reference, and logs one line per request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestLogLayer;
+
+impl<S> Layer<S> for RequestLogLayer {
+    type Service = RequestLog<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestLog { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestLog<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for RequestLog<S>
+where
+    S: Service<Request, Response = Response<Body>, Error = Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = BoxResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let request_id = request
+            .lambda_context_ref()
+            .map(|ctx| ctx.request_id.clone())
+            .unwrap_or_default();
+
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let source = request
+            .extensions()
+            .get::<HttpSource>()
+            .copied()
+            .unwrap_or(HttpSource::Unknown);
+
+        let span = info_span!(
+            "http_request",
+            request_id = %request_id,
+            source = source.as_str(),
+            interaction_id = tracing::field::Empty,
+            guild_id = tracing::field::Empty,
+            user_id = tracing::field::Empty,
+            command = tracing::field::Empty,
+        );
+
+        let future = self.inner.call(request);
+
+        Box::pin(
+            async move {
+                let started = Instant::now();
+                let result = correlation::scope(&request_id, future).await;
+
+                match &result {
+                    Ok(response) => info!(
+                        %method,
+                        path,
+                        status = response.status().as_u16(),
+                        latency_ms = started.elapsed().as_millis() as u64,
+                        "Handled request"
+                    ),
+                    Err(err) => info!(
+                        %method,
+                        path,
+                        error = %err,
+                        latency_ms = started.elapsed().as_millis() as u64,
+                        "Request errored"
+                    ),
+                }
+
+                result
+            }
+            .instrument(span),
+        )
+    }
+}
@@ -0,0 +1,59 @@
+use std::{
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use lambda_http::{tower::Layer, Body, Error, Request, Response, Service};
+
+use crate::{http_handler::BoxResponseFuture, metrics};
+
+/// Emits the invocation (cold start) metric and a status-class metric per
+/// request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestMetricsLayer;
+
+impl<S> Layer<S> for RequestMetricsLayer {
+    type Service = RequestMetrics<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestMetrics { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestMetrics<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for RequestMetrics<S>
+where
+    S: Service<Request, Response = Response<Body>, Error = Error> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = BoxResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        metrics::emit_invocation();
+
+        let future = self.inner.call(request);
+
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = future.await;
+
+            let status = match &result {
+                Ok(response) => response.status().as_u16(),
+                Err(_) => 500,
+            };
+            metrics::emit_http_response(status, started.elapsed());
+
+            result
+        })
+    }
+}
@@ -0,0 +1,187 @@
+use std::task::{Context, Poll};
+
+use ed25519_dalek::VerifyingKey;
+use lambda_http::{tower::Layer, Body, Error, Request, Response, Service};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{
+    app_state::AppState,
+    bal::auth::verify::AuthManager,
+    dal::model::interaction_request::InteractionType,
+    dal::reader::secrets_reader::SecretCache,
+    error::{AuthError, CommandError},
+    http_handler::{
+        error_response, is_unsigned, prefetch_discord_token, raw_body, BoxResponseFuture,
+    },
+    snapshot::RestoreScoped,
+};
+
+pub(crate) static DISCORD_PUBLIC_KEY_CACHE: SecretCache = SecretCache::new();
+/// The parsed key and the hex it was parsed from, reparsed when the secret
+/// is rotated.
+static DISCORD_PUBLIC_KEY: RestoreScoped<(String, VerifyingKey)> = RestoreScoped::new();
+
+/// Verifies Discord's Ed25519 request signature before the request reaches
+/// the handler. Only the routes `is_unsigned` names are let through without
+/// one.
+#[derive(Clone)]
+pub struct SignatureLayer {
+    state: AppState,
+}
+
+impl SignatureLayer {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+}
+
+impl<S> Layer<S> for SignatureLayer {
+    type Service = Signature<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Signature {
+            inner,
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Signature<S> {
+    inner: S,
+    state: AppState,
+}
+
+impl<S> Service<Request> for Signature<S>
+where
+    S: Service<Request, Response = Response<Body>, Error = Error> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Error;
+    type Future = BoxResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        if is_unsigned(&request) {
+            return Box::pin(self.inner.call(request));
+        }
+
+        // The inner service was polled ready, so take it and leave the clone.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+
+        Box::pin(async move {
+            if let Err(err) = verify(&request, &state).await {
+                return Ok(error_response(err));
+            }
+
+            inner.call(request).await
+        })
+    }
+}
+
+async fn verify(request: &Request, state: &AppState) -> Result<(), CommandError> {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+    };
+
+    let kind = serde_json::from_slice::<InteractionKind>(raw_body(request)).ok();
+    let application_id = kind.as_ref().and_then(|k| k.application_id);
+    let is_command = kind
+        .as_ref()
+        .is_some_and(|k| matches!(k.interaction_type, InteractionType::ApplicationCommand));
+
+    let public_keys = async {
+        match application_id {
+            Some(application_id) => match state.tenant(application_id).await {
+                Some(Ok(credentials)) => Ok(vec![credentials.public_key]),
+                Some(Err(_)) => Err(AuthError::UnknownApplication.into()),
+                None => discord_public_keys(state).await,
+            },
+            None => discord_public_keys(state).await,
+        }
+    };
+
+    let (public_keys, ()) = tokio::join!(public_keys, async {
+        if is_command {
+            prefetch_discord_token(state).await;
+        }
+    });
+
+    AuthManager::verify_signature_with_keys(
+        header("x-signature-ed25519"),
+        header("x-signature-timestamp"),
+        raw_body(request),
+        &public_keys?,
+    )?;
+
+    Ok(())
+}
+
+/// The secret bundle's public keys, or else the Discord public key from the
+/// cached secret, parsed again only when the secret's value changes.
+async fn discord_public_keys(state: &AppState) -> Result<Vec<VerifyingKey>, CommandError> {
+    if let Some(bundle) = &state.secrets {
+        return Ok(bundle.discord_public_keys.clone());
+    }
+
+    let public_key_secret_arn = state
+        .config
+        .discord_public_key_secret_arn
+        .as_deref()
+        .ok_or(CommandError::Misconfigured("DISCORD_PUBLIC_KEY_SECRET_ARN"))?;
+
+    let public_key_hex = state
+        .secrets_reader
+        .get_secret_value(public_key_secret_arn, "key", &DISCORD_PUBLIC_KEY_CACHE)
+        .await?;
+
+    if let Some((hex, public_key)) = DISCORD_PUBLIC_KEY.get() {
+        if hex == public_key_hex {
+            return Ok(vec![public_key]);
+        }
+    }
+
+    let public_key = AuthManager::parse_public_key(&public_key_hex)?;
+    DISCORD_PUBLIC_KEY.set((public_key_hex, public_key));
+
+    Ok(vec![public_key])
+}
+
+/// Loads the public key into the process cache during init, so the first
+/// request doesn't wait on the secrets backend. Failures are logged and the
+/// request path fetches again.
+pub async fn prefetch_public_key(state: &AppState) {
+    if state.secrets.is_some() || state.config.discord_public_key_secret_arn.is_none() {
+        return;
+    }
+
+    if let Err(err) = discord_public_keys(state).await {
+        warn!(
+            error = format!("{:#}", err),
+            "Failed to prefetch Discord public key"
+        );
+    }
+}
+
+/// Just the interaction type and application; every other field is skipped
+/// without being allocated. The body hasn't been verified yet, so these only
+/// decide which key to verify with and what to prefetch: slash commands are
+/// the only interactions that can need the bot token.
+#[derive(Deserialize)]
+struct InteractionKind<'a> {
+    #[serde(rename = "type")]
+    interaction_type: InteractionType,
+    #[serde(default, borrow)]
+    application_id: Option<&'a str>,
+}
@@ -0,0 +1,160 @@
+//! Optional operational alerts posted to a Discord webhook (`OPS_WEBHOOK_URL`)
+//! for unexpected failures, so breakage is noticed before users report it.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::correlation;
+
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_DETAIL_CHARS: usize = 1_800;
+const EMBED_COLOR_RED: u32 = 0xE7_4C_3C;
+
+/// Always unset when the `webhooks` feature is off, which disables alerts.
+static OPS_WEBHOOK_URL: Lazy<Option<String>> = Lazy::new(|| {
+    if !cfg!(feature = "webhooks") {
+        return None;
+    }
+
+    std::env::var("OPS_WEBHOOK_URL")
+        .ok()
+        .filter(|v| !v.is_empty())
+});
+
+static LAST_SENT: Mutex<Option<Instant>> = Mutex::new(None);
+static SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of alerts suppressed since the last one sent, or `None`
+/// if this alert should itself be suppressed.
+fn acquire_slot() -> Option<u64> {
+    let mut last_sent = LAST_SENT.lock().ok()?;
+
+    if last_sent.is_some_and(|at| at.elapsed() < MIN_INTERVAL) {
+        SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+        return None;
+    }
+
+    *last_sent = Some(Instant::now());
+    Some(SUPPRESSED.swap(0, Ordering::Relaxed))
+}
+
+fn truncate(detail: &str) -> String {
+    match detail.char_indices().nth(MAX_DETAIL_CHARS) {
+        Some((idx, _)) => format!("{}…", &detail[..idx]),
+        None => detail.to_string(),
+    }
+}
+
+fn payload(summary: &str, detail: &str, reference: &str, suppressed: u64) -> Value {
+    let function_name =
+        std::env::var("AWS_LAMBDA_FUNCTION_NAME").unwrap_or_else(|_| "unknown".to_string());
+
+    let mut fields = vec![
+        json!({ "name": "Function", "value": function_name, "inline": true }),
+        json!({ "name": "Ref", "value": reference, "inline": true }),
+    ];
+
+    if suppressed > 0 {
+        fields.push(json!({
+            "name": "Suppressed",
+            "value": format!("{} similar alerts since last report", suppressed),
+            "inline": true,
+        }));
+    }
+
+    json!({
+        "embeds": [{
+            "title": summary,
+            "description": format!("```\n{}\n```", truncate(detail)),
+            "color": EMBED_COLOR_RED,
+            "fields": fields,
+            "timestamp": Utc::now().to_rfc3339(),
+        }]
+    })
+}
+
+/// The webhook and alert body, if a webhook is configured and the rate limit
+/// allows an alert now.
+fn prepare(summary: &str, detail: &str) -> Option<(&'static str, Value)> {
+    let url = OPS_WEBHOOK_URL.as_deref()?;
+    let suppressed = acquire_slot()?;
+
+    Some((
+        url,
+        payload(summary, detail, &correlation::reference(), suppressed),
+    ))
+}
+
+/// Posts a summarized alert if a webhook is configured and the rate limit
+/// allows it. Failures to deliver are logged and otherwise ignored.
+pub async fn report(client: &reqwest::Client, summary: &str, detail: &str) {
+    if let Some((url, body)) = prepare(summary, detail) {
+        send(client, url, &body).await;
+    }
+}
+
+/// Like `report`, but posts from a spawned task so a request path never
+/// waits on the webhook. The reference is taken before spawning, while the
+/// request's scope is still current.
+pub fn report_in_background(client: &reqwest::Client, summary: &str, detail: &str) {
+    if let Some((url, body)) = prepare(summary, detail) {
+        let client = client.clone();
+        tokio::spawn(async move { send(&client, url, &body).await });
+    }
+}
+
+async fn send(client: &reqwest::Client, url: &str, body: &Value) {
+    let result = client
+        .post(url)
+        .timeout(SEND_TIMEOUT)
+        .json(body)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status());
+
+    if let Err(err) = result {
+        warn!("Failed to deliver ops alert: {}", err);
+    }
+}
+
+/// Reports panics before they unwind, whether or not a layer catches them
+/// afterwards. The alert is sent from a
+/// dedicated thread with its own runtime since the panicking thread may be
+/// inside the async executor.
+pub fn install_panic_hook() {
+    if OPS_WEBHOOK_URL.is_none() {
+        return;
+    }
+
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let detail = info.to_string();
+
+        let _ = std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(_) => return,
+            };
+
+            runtime.block_on(report(&reqwest::Client::new(), "Panic", &detail));
+        })
+        .join();
+
+        default_hook(info);
+    }));
+}
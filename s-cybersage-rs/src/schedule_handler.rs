@@ -0,0 +1,218 @@
+use anyhow::Context;
+use aws_lambda_events::eventbridge::EventBridgeEvent;
+use chrono::{Duration, Utc};
+use lambda_runtime::{Error, LambdaEvent};
+use serde::Deserialize;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    app_state::AppState,
+    bal::{
+        bot_lists::bot_list_publisher::BotListPublisher,
+        config::feature_flags::{FeatureFlags, Flag},
+    },
+    dal::{
+        dao::{
+            feature_flag::FeatureFlagDao, guild::GuildDao, guild_record::GuildRecordDao,
+            retention::RetentionDao, role_store::RoleStore,
+        },
+        model::{deferred_task::DeferredTask, member_count::MemberCountJob, purge_job::PurgeJob},
+        queue::task_queue::TaskQueue,
+    },
+};
+
+/// Prunes mappings for roles deleted in Discord, daily.
+pub const RECONCILE_ROLES: &str = "reconcile_roles";
+
+/// Refreshes the member counts shown for premium guilds' roles, every six
+/// hours.
+pub const COUNT_ROLE_MEMBERS: &str = "count_role_members";
+
+/// Adds each guild's previous UTC day to the global daily stats, shortly
+/// after midnight UTC.
+pub const AGGREGATE_STATS: &str = "aggregate_stats";
+
+/// Deletes stats, usage counters and tombstoned guilds past their
+/// retention, daily.
+pub const PURGE_EXPIRED: &str = "purge_expired";
+
+/// Posts the server count to the configured bot lists, twice a day, while
+/// the `bot_list_stats` flag is on.
+pub const PUBLISH_BOT_STATS: &str = "publish_bot_stats";
+
+/// Detail payload of the EventBridge rules that drive scheduled jobs. Each
+/// rule names the job it triggers.
+#[derive(Debug, Default, Deserialize)]
+struct ScheduleDetail {
+    #[serde(default)]
+    job: Option<String>,
+}
+
+/// Entry point for EventBridge schedules.
+#[instrument(name = "schedule_handler", skip_all, fields(source, detail_type))]
+pub async fn function_handler(
+    event: LambdaEvent<EventBridgeEvent>,
+    state: AppState,
+) -> Result<(), Error> {
+    let span = tracing::Span::current();
+    span.record("source", event.payload.source.as_str());
+    span.record("detail_type", event.payload.detail_type.as_str());
+
+    let detail: ScheduleDetail = serde_json::from_value(event.payload.detail).unwrap_or_default();
+
+    match detail.job.as_deref() {
+        Some(RECONCILE_ROLES) => {
+            queue_per_guild(&state, RECONCILE_ROLES, |guild_id| {
+                DeferredTask::ReconcileRoles { guild_id }
+            })
+            .await?
+        }
+        Some(COUNT_ROLE_MEMBERS) => {
+            queue_per_guild(&state, COUNT_ROLE_MEMBERS, |guild_id| {
+                DeferredTask::CountRoleMembers(MemberCountJob::new(guild_id))
+            })
+            .await?
+        }
+        Some(AGGREGATE_STATS) => {
+            let date = (Utc::now() - Duration::days(1))
+                .format("%Y-%m-%d")
+                .to_string();
+
+            queue_per_guild(&state, AGGREGATE_STATS, |guild_id| {
+                DeferredTask::AggregateStats {
+                    guild_id,
+                    date: date.clone(),
+                }
+            })
+            .await?
+        }
+        Some(PURGE_EXPIRED) => queue_purges(&state).await?,
+        Some(PUBLISH_BOT_STATS) => publish_bot_stats(&state).await?,
+        Some(job) => warn!(job, "No scheduled job registered under this name"),
+        None => info!("Ignoring scheduled event without a job name"),
+    }
+
+    Ok(())
+}
+
+/// Queues one task per guild, so the work is spread across the task worker's
+/// invocations instead of racing this one's timeout.
+async fn queue_per_guild(
+    state: &AppState,
+    job: &str,
+    task: impl Fn(String) -> DeferredTask,
+) -> Result<(), Error> {
+    let config = &state.config;
+
+    let table_name = config
+        .role_table
+        .as_deref()
+        .context("ROLE_MAPPINGS_TABLE_NAME is not set")?;
+    let queue_url = config
+        .task_queue_url
+        .clone()
+        .context("TASK_QUEUE_URL is not set")?;
+
+    let guilds = GuildDao::new(state.dynamo_client.clone(), table_name)
+        .list_guilds()
+        .await?;
+
+    let tasks: Vec<DeferredTask> = guilds.into_iter().map(task).collect();
+
+    TaskQueue::new(state.sqs_client.clone(), queue_url)
+        .enqueue_all(&tasks)
+        .await?;
+
+    info!(job, guilds = tasks.len(), "Queued scheduled job");
+
+    Ok(())
+}
+
+/// Counts the guilds the bot is installed in and posts the count to each bot
+/// list. Quick enough to run here rather than on the task queue.
+async fn publish_bot_stats(state: &AppState) -> Result<(), Error> {
+    let config = &state.config;
+
+    let table_name = config
+        .role_table
+        .as_deref()
+        .context("ROLE_MAPPINGS_TABLE_NAME is not set")?;
+
+    let flags = FeatureFlags::load(
+        &FeatureFlagDao::new(state.dynamo_client.clone(), table_name),
+        &state.parameters.load().await,
+    )
+    .await;
+    if !flags.is_enabled(Flag::BotListStats) {
+        info!(job = PUBLISH_BOT_STATS, "Bot list stats are switched off");
+        return Ok(());
+    }
+
+    if config.bot_lists.is_empty() {
+        warn!(
+            job = PUBLISH_BOT_STATS,
+            "BOT_LIST_ENDPOINTS names no bot lists"
+        );
+        return Ok(());
+    }
+    let secret_arn = config
+        .bot_list_secret_arn
+        .as_deref()
+        .context("BOT_LIST_SECRET_ARN is not set")?;
+
+    let server_count = GuildRecordDao::new(state.dynamo_client.clone(), table_name)
+        .count_installed()
+        .await?;
+
+    BotListPublisher::new(
+        state.http_client.clone(),
+        state.secrets_reader.clone(),
+        secret_arn,
+    )
+    .publish_all(&config.bot_lists, server_count)
+    .await;
+
+    Ok(())
+}
+
+/// Queues a purge of expired stats and usage counters, and one per guild
+/// tombstoned longer than the retention.
+async fn queue_purges(state: &AppState) -> Result<(), Error> {
+    let config = &state.config;
+
+    let table_name = config
+        .role_table
+        .as_deref()
+        .context("ROLE_MAPPINGS_TABLE_NAME is not set")?;
+    let queue_url = config
+        .task_queue_url
+        .clone()
+        .context("TASK_QUEUE_URL is not set")?;
+
+    let guilds = RetentionDao::new(state.dynamo_client.clone(), table_name, "mapping_key")
+        .tombstoned_before(config.retention.tombstone_cutoff(Utc::now()))
+        .await?;
+
+    let mut tasks = vec![DeferredTask::PurgeExpired(PurgeJob::Stats)];
+    #[cfg(feature = "billing")]
+    if config.subscription_table.is_some() {
+        tasks.push(DeferredTask::PurgeExpired(PurgeJob::Usage));
+    }
+    tasks.extend(
+        guilds
+            .into_iter()
+            .map(|guild_id| DeferredTask::PurgeExpired(PurgeJob::Guild { guild_id })),
+    );
+
+    TaskQueue::new(state.sqs_client.clone(), queue_url)
+        .enqueue_all(&tasks)
+        .await?;
+
+    info!(
+        job = PURGE_EXPIRED,
+        tasks = tasks.len(),
+        "Queued scheduled job"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Snapshot-restore detection, so SnapStart (or any snapshot of an
+//! initialized process) can't serve from values cached before the snapshot.
+//!
+//! A restored process resumes with the wall clock set to the current time
+//! while the monotonic clock only counts the time the process actually ran.
+//! Comparing how far each has moved since init tells the two apart; when
+//! they disagree the cache generation is bumped, and every `RestoreScoped`
+//! value cached under the old generation reads as empty. A false positive
+//! only costs a refetch. The AWS SDK clients refresh their own credentials
+//! by expiry, so only values this crate caches need the check.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use tracing::info;
+
+/// More wall-clock drift than NTP would ever apply between invocations.
+const MAX_CLOCK_DIVERGENCE: Duration = Duration::from_secs(10);
+
+static BASELINE: Mutex<Option<(Instant, SystemTime)>> = Mutex::new(None);
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Records the clocks at the end of init. Call once from `main`, right
+/// before entering the runtime loop.
+pub fn mark_init() {
+    if let Ok(mut baseline) = BASELINE.lock() {
+        *baseline = Some((Instant::now(), SystemTime::now()));
+    }
+}
+
+/// Checks for a restore since init or the last restore, invalidating cached
+/// values if there was one. Call at the start of every invocation.
+pub fn check_restore() -> bool {
+    let Ok(mut baseline) = BASELINE.lock() else {
+        return false;
+    };
+    let Some((monotonic, wall)) = *baseline else {
+        return false;
+    };
+
+    let monotonic_elapsed = monotonic.elapsed();
+    let wall_elapsed = SystemTime::now().duration_since(wall).unwrap_or_default();
+
+    if wall_elapsed.abs_diff(monotonic_elapsed) <= MAX_CLOCK_DIVERGENCE {
+        return false;
+    }
+
+    *baseline = Some((Instant::now(), SystemTime::now()));
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+
+    info!(
+        suspended_secs = wall_elapsed.saturating_sub(monotonic_elapsed).as_secs(),
+        "Restored from snapshot; dropping cached secrets"
+    );
+
+    true
+}
+
+fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// A process-wide cached value that is discarded when the process is
+/// restored from a snapshot.
+pub struct RestoreScoped<T> {
+    slot: Mutex<Option<(T, u64)>>,
+}
+
+impl<T: Clone> RestoreScoped<T> {
+    pub const fn new() -> Self {
+        Self {
+            slot: Mutex::new(None),
+        }
+    }
+
+    /// The cached value, unless it was cached before the latest restore.
+    pub fn get(&self) -> Option<T> {
+        let slot = self.slot.lock().ok()?;
+        let (value, cached_generation) = slot.as_ref()?;
+
+        (*cached_generation == generation()).then(|| value.clone())
+    }
+
+    pub fn is_current(&self) -> bool {
+        self.slot
+            .lock()
+            .is_ok_and(|slot| matches!(*slot, Some((_, g)) if g == generation()))
+    }
+
+    pub fn set(&self, value: T) {
+        if let Ok(mut slot) = self.slot.lock() {
+            *slot = Some((value, generation()));
+        }
+    }
+}
+
+impl<T: Clone> Default for RestoreScoped<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,302 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Context;
+use aws_lambda_events::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent};
+use lambda_runtime::{Error, LambdaEvent};
+use tracing::{error, instrument};
+
+#[cfg(feature = "billing")]
+use crate::dal::dao::subscription::SubscriptionReader;
+use crate::{
+    app_state::AppState,
+    bal::{
+        activity::{activity_log_poster::ActivityLogPoster, activity_recorder::ActivityRecorder},
+        deferred::{
+            mass_role_worker::MassRoleWorker, member_counter::MemberCounter,
+            retention_purger::RetentionPurger, stats_aggregator::StatsAggregator,
+            task_credentials::TaskCredentials, task_executor::TaskExecutor,
+        },
+        discord::{
+            channel_client::ChannelClient, interaction_client::InteractionClient,
+            role_manager::RoleManager,
+        },
+        events::{event_publisher::EventPublisher, webhook_sender::WebhookSender},
+        retry::role_retry_worker::{JobOutcome, RoleRetryWorker},
+    },
+    dal::{
+        dao::{
+            global_stats::GlobalStatsDao, guild::GuildDao, guild_record::GuildRecordDao,
+            log_channel::LogChannelDao, retention::RetentionDao, webhook::WebhookDao,
+        },
+        model::{activity_entry::ActivityEntry, deferred_task::DeferredTask},
+        queue::task_queue::TaskQueue,
+    },
+};
+
+/// Entry point for the deferred task queue. Tasks that fail permanently are
+/// reported as batch item failures so SQS moves them to the dead-letter queue.
+#[instrument(name = "sqs_handler", skip_all)]
+pub async fn function_handler(
+    event: LambdaEvent<SqsEvent>,
+    state: AppState,
+) -> Result<SqsBatchResponse, Error> {
+    let config = &state.config;
+
+    let discord_token = state
+        .discord_token()
+        .context("DISCORD_TOKEN_SECRET_ARN is not set")?;
+    let queue_url = config
+        .task_queue_url
+        .clone()
+        .context("TASK_QUEUE_URL is not set")?;
+    let table_name = config
+        .role_table
+        .clone()
+        .context("ROLE_MAPPINGS_TABLE_NAME is not set")?;
+
+    let http_client = state.http_client.clone();
+
+    let discord_token = discord_token.get().await?;
+
+    let task_queue = TaskQueue::new(state.sqs_client.clone(), queue_url);
+    let webhooks = WebhookDao::new(state.dynamo_client.clone(), table_name.clone());
+    let log_channels = LogChannelDao::new(state.dynamo_client.clone(), table_name.clone());
+    let activity = ActivityRecorder::new(log_channels.clone(), Some(task_queue.clone()));
+
+    let stats_aggregator = StatsAggregator::new(
+        GlobalStatsDao::new(state.dynamo_client.clone(), table_name.clone()),
+        #[cfg(feature = "billing")]
+        config
+            .subscription_table
+            .as_deref()
+            .map(|table| SubscriptionReader::new(state.dynamo_client.clone(), table)),
+    );
+
+    let purger = RetentionPurger::new(
+        RetentionDao::new(
+            state.dynamo_client.clone(),
+            table_name.clone(),
+            "mapping_key",
+        ),
+        #[cfg(feature = "billing")]
+        config
+            .subscription_table
+            .as_deref()
+            .map(|table| RetentionDao::new(state.dynamo_client.clone(), table, "subscription_key")),
+        task_queue.clone(),
+        config.retention,
+    );
+
+    let role_store = Arc::new(GuildDao::new(
+        state.dynamo_client.clone(),
+        table_name.clone(),
+    ));
+
+    let guild_records = GuildRecordDao::new(state.dynamo_client.clone(), table_name.clone());
+    let mut credentials = TaskCredentials::new(
+        state.tenants.clone(),
+        config.application_id.clone(),
+        guild_records.clone(),
+    );
+
+    let webhook_sender = WebhookSender::new(webhooks.clone(), task_queue.clone())?;
+
+    let deps = WorkerDeps {
+        http_client,
+        role_store,
+        guild_records,
+        task_queue,
+        webhooks,
+        log_channels,
+        activity,
+        #[cfg(feature = "billing")]
+        subscriptions: config
+            .subscription_table
+            .as_deref()
+            .map(|table| SubscriptionReader::new(state.dynamo_client.clone(), table)),
+    };
+    let workers = DiscordWorkers::new(&deps, discord_token);
+    // White-label applications' workers, built for the first task of each.
+    let mut tenant_workers: HashMap<String, DiscordWorkers> = HashMap::new();
+
+    let mut response = SqsBatchResponse::default();
+
+    // Posted together after the batch, per application, so a burst of
+    // activity becomes a few log messages rather than one per entry.
+    let mut activity_entries: HashMap<Option<String>, Vec<ActivityEntry>> = HashMap::new();
+
+    for record in event.payload.records {
+        let message_id = record.message_id.clone().unwrap_or_default();
+
+        let task: DeferredTask = match record.body.as_deref().map(serde_json::from_str).transpose()
+        {
+            Ok(Some(task)) => task,
+            _ => {
+                error!(message_id, "Discarding malformed deferred task");
+                continue;
+            }
+        };
+
+        let tenant = match credentials.tenant_for(&task).await {
+            Ok(tenant) => tenant,
+            Err(err) => {
+                error!(
+                    message_id,
+                    kind = task.kind(),
+                    error = format!("{:#}", err),
+                    "Deferred task's application has no credentials"
+                );
+                response.batch_item_failures.push(BatchItemFailure {
+                    item_identifier: message_id,
+                });
+                continue;
+            }
+        };
+        let application_id = tenant.as_ref().map(|(id, _)| id.clone());
+
+        let DiscordWorkers {
+            role_worker,
+            executor,
+            mass_role_worker,
+            member_counter,
+            ..
+        } = match tenant {
+            Some((application_id, token)) => tenant_workers
+                .entry(application_id)
+                .or_insert_with(|| DiscordWorkers::new(&deps, token)),
+            None => &workers,
+        };
+
+        let result = match &task {
+            DeferredTask::RoleModification(job) => role_worker
+                .process(job)
+                .await
+                .map(|outcome| outcome == JobOutcome::Failed),
+            DeferredTask::ImportRoles(origin) => executor.import_roles(origin).await.map(|_| false),
+            DeferredTask::ExportRoles(origin) => executor.export_roles(origin).await.map(|_| false),
+            DeferredTask::GuildEvent(delivery) => webhook_sender
+                .deliver(delivery)
+                .await
+                .map(|outcome| outcome == JobOutcome::Failed),
+            DeferredTask::ReconcileRoles { guild_id } => {
+                executor.reconcile_roles(guild_id).await.map(|_| false)
+            }
+            DeferredTask::CountRoleMembers(job) => member_counter
+                .count(job)
+                .await
+                .map(|outcome| outcome == JobOutcome::Failed),
+            DeferredTask::MassRole(job) => mass_role_worker
+                .process(job)
+                .await
+                .map(|outcome| outcome == JobOutcome::Failed),
+            DeferredTask::RoleAnnouncement(announcement) => executor
+                .post_announcement(announcement)
+                .await
+                .map(|_| false),
+            DeferredTask::AggregateStats { guild_id, date } => stats_aggregator
+                .aggregate(guild_id, date)
+                .await
+                .map(|_| false),
+            DeferredTask::PurgeExpired(job) => purger.purge(job).await.map(|_| false),
+            DeferredTask::ActivityLog(entry) => {
+                activity_entries
+                    .entry(application_id)
+                    .or_default()
+                    .push(entry.clone());
+                Ok(false)
+            }
+        };
+
+        let failed = match result {
+            Ok(failed) => failed,
+            Err(err) => {
+                error!(
+                    message_id,
+                    kind = task.kind(),
+                    error = format!("{:#}", err),
+                    "Deferred task errored"
+                );
+                true
+            }
+        };
+
+        if failed {
+            response.batch_item_failures.push(BatchItemFailure {
+                item_identifier: message_id,
+            });
+        }
+    }
+
+    for (application_id, entries) in activity_entries {
+        let workers = application_id
+            .and_then(|id| tenant_workers.get(&id))
+            .unwrap_or(&workers);
+        workers.activity_poster.post(entries).await;
+    }
+
+    Ok(response)
+}
+
+/// What the Discord workers share whichever bot they run as.
+struct WorkerDeps {
+    http_client: reqwest::Client,
+    role_store: Arc<GuildDao>,
+    guild_records: GuildRecordDao,
+    task_queue: TaskQueue,
+    webhooks: WebhookDao,
+    log_channels: LogChannelDao,
+    activity: ActivityRecorder,
+    #[cfg(feature = "billing")]
+    subscriptions: Option<SubscriptionReader>,
+}
+
+/// The workers that call Discord, built with the bot token of the
+/// application their tasks run as.
+struct DiscordWorkers {
+    role_worker: RoleRetryWorker,
+    executor: TaskExecutor,
+    mass_role_worker: MassRoleWorker,
+    member_counter: MemberCounter,
+    activity_poster: ActivityLogPoster,
+}
+
+impl DiscordWorkers {
+    fn new(deps: &WorkerDeps, discord_token: String) -> Self {
+        let http_client = &deps.http_client;
+        let role_manager = Arc::new(RoleManager::new(http_client.clone(), discord_token.clone()));
+
+        Self {
+            role_worker: RoleRetryWorker::new(
+                role_manager.clone(),
+                InteractionClient::new(http_client.clone()),
+                deps.task_queue.clone(),
+                EventPublisher::new(deps.webhooks.clone(), Some(deps.task_queue.clone())),
+                deps.activity.clone(),
+            ),
+            executor: TaskExecutor::new(
+                deps.role_store.clone(),
+                role_manager.clone(),
+                InteractionClient::new(http_client.clone()),
+                deps.activity.clone(),
+                deps.guild_records.clone(),
+            ),
+            mass_role_worker: MassRoleWorker::new(
+                role_manager.clone(),
+                InteractionClient::new(http_client.clone()),
+                deps.task_queue.clone(),
+                deps.activity.clone(),
+            ),
+            member_counter: MemberCounter::new(
+                deps.role_store.clone(),
+                role_manager,
+                deps.task_queue.clone(),
+                #[cfg(feature = "billing")]
+                deps.subscriptions.clone(),
+            ),
+            activity_poster: ActivityLogPoster::new(
+                deps.log_channels.clone(),
+                ChannelClient::new(http_client.clone(), discord_token),
+            ),
+        }
+    }
+}
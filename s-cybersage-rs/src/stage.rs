@@ -0,0 +1,40 @@
+//! Deployment stage, so a staging bot can run from the same codebase against
+//! its own tables, secrets and Discord application. Resource names are
+//! provided per stage by the CDK stack; only user-visible output differs here.
+
+use once_cell::sync::Lazy;
+
+const PRODUCTION: &str = "prod";
+
+static STAGE: Lazy<String> = Lazy::new(|| {
+    std::env::var("STAGE")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| PRODUCTION.to_string())
+});
+
+pub fn name() -> &'static str {
+    STAGE.as_str()
+}
+
+pub fn is_production() -> bool {
+    name() == PRODUCTION
+}
+
+/// Subtext line appended to bot messages outside production so testers can
+/// tell the staging bot's replies apart.
+pub fn footer() -> Option<String> {
+    if is_production() {
+        None
+    } else {
+        Some(format!("-# {} build", name()))
+    }
+}
+
+/// Appends the stage footer to `content` outside production.
+pub fn mark(content: &str) -> String {
+    match footer() {
+        Some(footer) => format!("{}\n{}", content, footer),
+        None => content.to_string(),
+    }
+}
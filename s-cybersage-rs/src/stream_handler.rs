@@ -0,0 +1,18 @@
+use aws_lambda_events::dynamodb::Event;
+use lambda_runtime::{Error, LambdaEvent};
+use tracing::{debug, instrument};
+
+/// Entry point for DynamoDB Streams on the bot's tables.
+#[instrument(name = "stream_handler", skip_all, fields(records = event.payload.records.len()))]
+pub async fn function_handler(event: LambdaEvent<Event>) -> Result<(), Error> {
+    for record in &event.payload.records {
+        debug!(
+            event_id = %record.event_id,
+            event_name = %record.event_name,
+            source_arn = record.event_source_arn.as_deref().unwrap_or(""),
+            "Received stream record"
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Optional OTLP trace export, enabled with the `otel` feature.
+//!
+//! Spans are sent to the ADOT collector extension (which forwards them to
+//! X-Ray) and flushed at the end of every invocation, because the sandbox can
+//! be frozen as soon as the response is returned.
+
+#[cfg(feature = "otel")]
+use once_cell::sync::OnceCell;
+#[cfg(feature = "otel")]
+use opentelemetry::trace::TracerProvider;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+#[cfg(feature = "otel")]
+use tracing::Subscriber;
+#[cfg(feature = "otel")]
+use tracing_subscriber::{registry::LookupSpan, Layer};
+
+#[cfg(feature = "otel")]
+static PROVIDER: OnceCell<SdkTracerProvider> = OnceCell::new();
+
+/// Builds the tracing layer that exports spans over OTLP/HTTP. The endpoint is
+/// taken from `OTEL_EXPORTER_OTLP_ENDPOINT` (defaults to the local collector).
+#[cfg(feature = "otel")]
+pub fn otel_layer<S>() -> anyhow::Result<impl Layer<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .build()?;
+
+    let service_name = std::env::var("AWS_LAMBDA_FUNCTION_NAME")
+        .unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    let _ = PROVIDER.set(provider);
+
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Exports any buffered spans. A no-op when the `otel` feature is disabled.
+pub fn flush() {
+    #[cfg(feature = "otel")]
+    if let Some(provider) = PROVIDER.get() {
+        if let Err(err) = provider.force_flush() {
+            tracing::warn!("Failed to flush spans: {}", err);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Discord timestamp tags for times shown in replies. Clients render each tag
+//! in the reader's own locale and time zone, so replies never name a zone.
+
+use chrono::Utc;
+
+/// How a client renders a `<t:…>` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// E.g. "in 5 minutes" or "3 days ago", kept current by the client.
+    Relative,
+    /// E.g. "Tuesday, 20 April 2021 16:20".
+    LongDateTime,
+}
+
+impl Style {
+    fn flag(self) -> char {
+        match self {
+            Style::Relative => 'R',
+            Style::LongDateTime => 'F',
+        }
+    }
+}
+
+/// A tag for `unix_seconds` in `style`.
+pub fn tag(unix_seconds: i64, style: Style) -> String {
+    format!("<t:{}:{}>", unix_seconds, style.flag())
+}
+
+/// E.g. "in 5 minutes", for cooldown ends and scheduled times.
+pub fn relative(unix_seconds: i64) -> String {
+    tag(unix_seconds, Style::Relative)
+}
+
+/// A relative tag for `seconds` from now.
+pub fn relative_in(seconds: u64) -> String {
+    relative(Utc::now().timestamp().saturating_add_unsigned(seconds))
+}
+
+/// The date and time followed by how far off it is, for expirations such
+/// as a subscription's.
+pub fn expiry(unix_seconds: i64) -> String {
+    format!(
+        "{} ({})",
+        tag(unix_seconds, Style::LongDateTime),
+        relative(unix_seconds)
+    )
+}
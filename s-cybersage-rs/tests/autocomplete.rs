@@ -0,0 +1,108 @@
+//! Role suggestions while `/role toggle` is being typed.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{autocomplete, command, content, router, FakeDynamo, GUILD_ID};
+use s_cybersage_rs::{
+    bal::{
+        discord::recording_discord_api::RecordingDiscordApi,
+        route::{command_router::CommandRouter, request_context::RequestContext},
+    },
+    dal::dao::{
+        favorite::FavoriteDao, in_memory_role_store::InMemoryRoleStore, role_store::RoleStore,
+    },
+};
+use serde_json::{json, Value};
+
+fn roles() -> Arc<InMemoryRoleStore> {
+    Arc::new(InMemoryRoleStore::with_roles([
+        (GUILD_ID, "500000000000000005", "Gamer"),
+        (GUILD_ID, "500000000000000006", "Gardener"),
+        (GUILD_ID, "500000000000000007", "Artist"),
+    ]))
+}
+
+async fn suggest(role_store: &InMemoryRoleStore, ctx: &RequestContext) -> Vec<Value> {
+    let favorites = FavoriteDao::new(FakeDynamo::default().client(), "role-mappings");
+    let response = CommandRouter::autocomplete(role_store, &favorites, &ctx.interaction).await;
+
+    serde_json::to_value(response).unwrap()["data"]["choices"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn one_character_is_enough_by_default() {
+    let choices = suggest(&roles(), &autocomplete("1", "g")).await;
+
+    assert_eq!(
+        choices,
+        [
+            json!({ "name": "Gamer", "value": "Gamer" }),
+            json!({ "name": "Gardener", "value": "Gardener" }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn short_prefixes_ask_for_more_characters() {
+    let role_store = roles();
+    role_store
+        .set_autocomplete_min_length(GUILD_ID, 3)
+        .await
+        .unwrap();
+
+    let choices = suggest(&role_store, &autocomplete("1", "ga")).await;
+
+    assert_eq!(
+        choices,
+        [json!({ "name": "Keep typing… (3 characters to search)", "value": "ga" })]
+    );
+    assert_eq!(
+        suggest(&role_store, &autocomplete("2", "")).await,
+        Vec::<Value>::new()
+    );
+    assert_eq!(
+        suggest(&role_store, &autocomplete("3", "gar")).await.len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn the_minimum_is_set_with_config() {
+    let role_store = roles();
+    let router = router(
+        role_store.clone(),
+        Arc::new(RecordingDiscordApi::new()),
+        &FakeDynamo::default(),
+    );
+
+    let set = |interaction_id, length: u64| {
+        command(
+            interaction_id,
+            "config",
+            "autocomplete-min-length",
+            json!([{ "name": "length", "type": 4, "value": length }]),
+            json!({}),
+        )
+    };
+
+    let response = router.handle_command(&set("1", 2)).await.unwrap();
+    assert_eq!(
+        content(&response),
+        "Role suggestions now appear after 2 typed character(s)."
+    );
+    assert_eq!(
+        role_store
+            .get_autocomplete_min_length(GUILD_ID)
+            .await
+            .unwrap(),
+        Some(2)
+    );
+
+    let response = router.handle_command(&set("2", 6)).await.unwrap();
+    assert_eq!(content(&response), "Length must be between 1 and 5.");
+}
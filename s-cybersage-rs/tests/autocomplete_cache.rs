@@ -0,0 +1,90 @@
+//! Prefix queries are cached for a few seconds per guild, since every
+//! keystroke of a role name sends its own autocomplete interaction. The
+//! cache is process-wide, so each test queries its own guild.
+
+mod common;
+
+use common::FakeDynamo;
+use s_cybersage_rs::dal::dao::{guild::GuildDao, role_store::RoleStore};
+use serde_json::json;
+
+fn dao(dynamo: &FakeDynamo) -> GuildDao {
+    GuildDao::new(dynamo.client(), "roles")
+}
+
+fn role(guild_id: &str, role_id: &str, name: &str) -> serde_json::Value {
+    json!({
+        "guild_id": { "S": guild_id },
+        "mapping_key": { "S": format!("ROLE#{}", role_id) },
+        "role_id": { "S": role_id },
+        "role_name": { "S": name },
+        "role_name_normalized": { "S": name.to_lowercase() },
+    })
+}
+
+fn names(roles: Vec<(String, String)>) -> Vec<String> {
+    roles.into_iter().map(|(name, _)| name).collect()
+}
+
+#[tokio::test]
+async fn repeated_and_longer_prefixes_are_served_from_the_cache() {
+    const GUILD_ID: &str = "100000000000000101";
+    let dynamo = FakeDynamo::default()
+        .with_item(role(GUILD_ID, "1", "Gamer"))
+        .with_item(role(GUILD_ID, "2", "Gardener"));
+    let dao = dao(&dynamo);
+
+    let first = dao
+        .query_roles_by_prefix(GUILD_ID, "ga", &[])
+        .await
+        .unwrap();
+    let again = dao
+        .query_roles_by_prefix(GUILD_ID, "Ga", &[])
+        .await
+        .unwrap();
+    let longer = dao
+        .query_roles_by_prefix(GUILD_ID, "gam", &[])
+        .await
+        .unwrap();
+
+    assert_eq!(first.len(), 2);
+    assert_eq!(again, first);
+    assert_eq!(names(longer), ["Gamer"]);
+    assert_eq!(dynamo.requests("Query").len(), 1);
+}
+
+#[tokio::test]
+async fn guilds_are_cached_separately() {
+    const GUILD_ID: &str = "100000000000000102";
+    const OTHER_GUILD_ID: &str = "100000000000000103";
+    let dynamo = FakeDynamo::default().with_item(role(GUILD_ID, "1", "Gamer"));
+    let dao = dao(&dynamo);
+
+    dao.query_roles_by_prefix(GUILD_ID, "ga", &[])
+        .await
+        .unwrap();
+    let other = dao
+        .query_roles_by_prefix(OTHER_GUILD_ID, "ga", &[])
+        .await
+        .unwrap();
+
+    assert!(other.is_empty());
+    assert_eq!(dynamo.requests("Query").len(), 2);
+}
+
+#[tokio::test]
+async fn saving_a_role_drops_the_guilds_cached_prefixes() {
+    const GUILD_ID: &str = "100000000000000104";
+    let dynamo = FakeDynamo::default();
+    let dao = dao(&dynamo);
+
+    dao.query_roles_by_prefix(GUILD_ID, "ga", &[])
+        .await
+        .unwrap();
+    dao.save_role(GUILD_ID, "1", "Gamer").await.unwrap();
+    dao.query_roles_by_prefix(GUILD_ID, "ga", &[])
+        .await
+        .unwrap();
+
+    assert_eq!(dynamo.requests("Query").len(), 2);
+}
@@ -0,0 +1,134 @@
+//! Bundles: one owner's subscription covering up to `max_guilds` guilds.
+
+#![cfg(feature = "billing")]
+
+mod common;
+
+use chrono::Utc;
+use common::{FakeDynamo, GUILD_ID, USER_ID};
+use s_cybersage_rs::{
+    bal::billing::subscription_manager::{AttachOutcome, DetachOutcome, SubscriptionManager},
+    dal::dao::{
+        bundle::BundleDao,
+        subscription::{SubscriptionReader, SubscriptionWriter},
+    },
+};
+use serde_json::{json, Value};
+
+const TABLE: &str = "subscriptions";
+const OTHER_GUILD_ID: &str = "100000000000000009";
+
+fn manager(dynamo: &FakeDynamo) -> SubscriptionManager {
+    let client = dynamo.client();
+
+    SubscriptionManager::new(
+        SubscriptionReader::new(client.clone(), TABLE),
+        SubscriptionWriter::new(client.clone(), TABLE),
+        BundleDao::new(client, TABLE),
+    )
+}
+
+fn bundle(expires_in: i64, guild_ids: &[&str]) -> Value {
+    let mut item = json!({
+        "guild_id": { "S": format!("USER#{}", USER_ID) },
+        "subscription_key": { "S": "BUNDLE" },
+        "status": { "S": "active" },
+        "expires_at": { "N": (Utc::now().timestamp() + expires_in).to_string() },
+        "max_guilds": { "N": "2" },
+    });
+    if !guild_ids.is_empty() {
+        item["guild_ids"] = json!({ "SS": guild_ids });
+    }
+
+    item
+}
+
+fn attachment(guild_id: &str, owner_id: &str) -> Value {
+    json!({
+        "guild_id": { "S": guild_id },
+        "subscription_key": { "S": format!("BUNDLE#{}", owner_id) },
+    })
+}
+
+#[tokio::test]
+async fn attaching_adds_the_guild_to_an_active_bundle() {
+    let dynamo = FakeDynamo::default().with_item(bundle(3_600, &[OTHER_GUILD_ID]));
+
+    let outcome = manager(&dynamo)
+        .attach_bundle(USER_ID, GUILD_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, AttachOutcome::Attached { used: 2, max: 2 });
+    let writes = dynamo.requests("TransactWriteItems");
+    assert_eq!(writes.len(), 1);
+    assert_eq!(
+        writes[0]["TransactItems"][1]["Put"]["Item"]["subscription_key"]["S"],
+        format!("BUNDLE#{}", USER_ID)
+    );
+}
+
+#[tokio::test]
+async fn expired_or_missing_bundles_cover_nothing() {
+    for dynamo in [
+        FakeDynamo::default(),
+        FakeDynamo::default().with_item(bundle(-60, &[])),
+    ] {
+        let outcome = manager(&dynamo)
+            .attach_bundle(USER_ID, GUILD_ID)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome, AttachOutcome::NoActiveBundle);
+        assert!(dynamo.requests("TransactWriteItems").is_empty());
+    }
+}
+
+#[tokio::test]
+async fn a_full_bundle_takes_no_more_guilds() {
+    let dynamo =
+        FakeDynamo::default().with_item(bundle(3_600, &[OTHER_GUILD_ID, "100000000000000010"]));
+
+    let outcome = manager(&dynamo)
+        .attach_bundle(USER_ID, GUILD_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, AttachOutcome::BundleFull { max: 2 });
+    assert!(dynamo.requests("TransactWriteItems").is_empty());
+}
+
+#[tokio::test]
+async fn a_guild_is_covered_by_one_bundle_at_a_time() {
+    let dynamo = FakeDynamo::default()
+        .with_item(bundle(3_600, &[]))
+        .with_item(attachment(GUILD_ID, "900000000000000009"));
+
+    let outcome = manager(&dynamo)
+        .attach_bundle(USER_ID, GUILD_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, AttachOutcome::AttachedElsewhere);
+}
+
+#[tokio::test]
+async fn only_the_attached_owner_can_detach() {
+    let dynamo = FakeDynamo::default().with_item(attachment(GUILD_ID, USER_ID));
+    let manager = manager(&dynamo);
+
+    assert_eq!(
+        manager
+            .detach_bundle("900000000000000009", GUILD_ID)
+            .await
+            .unwrap(),
+        DetachOutcome::NotAttached
+    );
+    assert!(dynamo.requests("TransactWriteItems").is_empty());
+
+    assert_eq!(
+        manager.detach_bundle(USER_ID, GUILD_ID).await.unwrap(),
+        DetachOutcome::Detached
+    );
+    assert_eq!(dynamo.requests("TransactWriteItems").len(), 1);
+}
@@ -0,0 +1,51 @@
+//! Which commands members can run without any permission. Saving a role makes
+//! it self-assignable, so a subcommand landing here by accident lets members
+//! grant themselves moderator roles.
+
+use s_cybersage_rs::commands::{access, role, Access, MANAGE_GUILD, MANAGE_ROLES};
+
+#[test]
+fn role_subcommands_open_to_everyone() {
+    let subcommands = [
+        role::TOGGLE,
+        role::SAVE,
+        role::IMPORT_ALL,
+        role::EXPORT,
+        role::MASS_ASSIGN,
+        role::MASS_REMOVE,
+        role::ANNOUNCE,
+        role::FAVORITE,
+        role::MINE,
+        role::HISTORY,
+    ];
+
+    let open: Vec<&str> = subcommands
+        .into_iter()
+        .filter(|subcommand| access(role::NAME, subcommand) == Access::Everyone)
+        .collect();
+
+    assert_eq!(open, [role::TOGGLE, role::FAVORITE, role::MINE]);
+}
+
+#[test]
+fn handing_out_roles_needs_manage_roles() {
+    for subcommand in [
+        role::SAVE,
+        role::IMPORT_ALL,
+        role::EXPORT,
+        role::MASS_ASSIGN,
+        role::MASS_REMOVE,
+    ] {
+        assert_eq!(
+            access(role::NAME, subcommand),
+            Access::Permissions(MANAGE_ROLES),
+            "{}",
+            subcommand
+        );
+    }
+
+    assert_eq!(
+        access(role::NAME, role::ANNOUNCE),
+        Access::Permissions(MANAGE_GUILD)
+    );
+}
@@ -0,0 +1,291 @@
+//! `CommandRouter` branches, run against the in-memory role store.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{content, role_command, router, FakeDynamo, APPLICATION_ID, GUILD_ID, USER_ID};
+use s_cybersage_rs::{
+    bal::{
+        discord::{
+            recording_discord_api::{DiscordCall, RecordingDiscordApi},
+            role_manager::GuildRole,
+        },
+        route::request_context::RequestContext,
+    },
+    commands::{ADMINISTRATOR, MANAGE_ROLES},
+    dal::dao::{in_memory_role_store::InMemoryRoleStore, role_store::RoleStore},
+};
+use serde_json::json;
+
+const ROLE_ID: &str = "500000000000000005";
+const BOT_ROLE_ID: &str = "700000000000000007";
+
+fn toggle(interaction_id: &str, role_name: &str) -> RequestContext {
+    role_command(
+        interaction_id,
+        "toggle",
+        json!([{ "name": "role", "type": 3, "value": role_name }]),
+        json!({}),
+    )
+}
+
+fn save(interaction_id: &str, role_id: &str, role_name: &str) -> RequestContext {
+    save_role(
+        interaction_id,
+        json!({ "id": role_id, "name": role_name, "position": 1, "permissions": "0" }),
+    )
+}
+
+/// `/role save` of the resolved `role`.
+fn save_role(interaction_id: &str, role: serde_json::Value) -> RequestContext {
+    let role_id = role["id"].as_str().unwrap_or_default().to_string();
+
+    role_command(
+        interaction_id,
+        "save",
+        json!([{ "name": "role", "type": 8, "value": role_id }]),
+        json!({ "resolved": { "roles": { role_id: role } } }),
+    )
+}
+
+/// A guild where the bot's highest role sits at position 5.
+fn hierarchy() -> RecordingDiscordApi {
+    let bot_role: GuildRole =
+        serde_json::from_value(json!({ "id": BOT_ROLE_ID, "name": "Bot", "position": 5 })).unwrap();
+
+    RecordingDiscordApi::new()
+        .with_guild_roles(GUILD_ID, vec![bot_role])
+        .with_member_roles(GUILD_ID, APPLICATION_ID, &[BOT_ROLE_ID])
+}
+
+#[tokio::test]
+async fn toggle_refuses_unregistered_roles() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let dynamo = FakeDynamo::default();
+    let router = router(Arc::new(InMemoryRoleStore::new()), discord.clone(), &dynamo);
+
+    let response = router.handle_command(&toggle("1", "Gamer")).await.unwrap();
+
+    assert_eq!(content(&response), "Role not self-assignable.");
+    assert!(discord.calls().is_empty());
+}
+
+#[tokio::test]
+async fn toggle_matches_role_names_case_insensitively() {
+    let roles = Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]));
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let dynamo = FakeDynamo::default();
+    let router = router(roles, discord.clone(), &dynamo);
+
+    let response = router.handle_command(&toggle("1", "gAMER")).await.unwrap();
+
+    assert_eq!(content(&response), "Added 'Gamer'.");
+    assert_eq!(discord.member_roles(GUILD_ID, USER_ID), [ROLE_ID]);
+}
+
+#[tokio::test]
+async fn duplicate_deliveries_change_the_role_once() {
+    let roles = Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]));
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let dynamo = FakeDynamo::default();
+    let router = router(roles, discord.clone(), &dynamo);
+
+    router.handle_command(&toggle("1", "Gamer")).await.unwrap();
+    let duplicate = router.handle_command(&toggle("1", "Gamer")).await.unwrap();
+
+    assert_eq!(content(&duplicate), "That request was already handled.");
+    assert_eq!(discord.member_roles(GUILD_ID, USER_ID), [ROLE_ID]);
+
+    let modifications = discord
+        .calls()
+        .into_iter()
+        .filter(|call| matches!(call, DiscordCall::ModifyUserRole { .. }))
+        .count();
+    assert_eq!(modifications, 1);
+}
+
+#[tokio::test]
+async fn refused_changes_leave_no_claim() {
+    let roles = Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]));
+    roles
+        .set_prerequisites(GUILD_ID, ROLE_ID, &[BOT_ROLE_ID.to_string()])
+        .await
+        .unwrap();
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let dynamo = FakeDynamo::default();
+    let router = router(roles, discord, &dynamo);
+
+    let response = router.handle_command(&toggle("1", "Gamer")).await.unwrap();
+
+    assert!(content(&response).starts_with("You need"));
+    assert!(!dynamo.is_claimed("1"));
+}
+
+#[tokio::test]
+async fn duplicate_save_deliveries_are_answered_once() {
+    let roles = Arc::new(InMemoryRoleStore::new());
+    let dynamo = FakeDynamo::default();
+    let router = router(roles.clone(), Arc::new(hierarchy()), &dynamo);
+
+    router
+        .handle_command(&save("1", ROLE_ID, "Gamer"))
+        .await
+        .unwrap();
+    let duplicate = router
+        .handle_command(&save("1", ROLE_ID, "Gamer"))
+        .await
+        .unwrap();
+    let another = router
+        .handle_command(&save("2", ROLE_ID, "Gamer"))
+        .await
+        .unwrap();
+
+    assert_eq!(content(&duplicate), "That request was already handled.");
+    assert_eq!(content(&another), "Role registered successfully.");
+}
+
+#[tokio::test]
+async fn save_registers_the_role() {
+    let roles = Arc::new(InMemoryRoleStore::new());
+    let dynamo = FakeDynamo::default();
+    let router = router(roles.clone(), Arc::new(hierarchy()), &dynamo);
+
+    let response = router
+        .handle_command(&save("1", ROLE_ID, "Gamer"))
+        .await
+        .unwrap();
+
+    assert_eq!(content(&response), "Role registered successfully.");
+    assert_eq!(
+        roles.get_role_by_name(GUILD_ID, "gamer").await.unwrap(),
+        Some(("Gamer".to_string(), ROLE_ID.to_string()))
+    );
+    assert!(dynamo.is_claimed("1"));
+}
+
+#[tokio::test]
+async fn save_refuses_a_name_held_by_another_role() {
+    let roles = Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]));
+    let dynamo = FakeDynamo::default();
+    let router = router(roles.clone(), Arc::new(hierarchy()), &dynamo);
+
+    let response = router
+        .handle_command(&save("1", "600000000000000006", "gamer"))
+        .await
+        .unwrap();
+
+    assert!(content(&response).starts_with(&format!("<@&{}> is already registered", ROLE_ID)));
+    assert_eq!(roles.list_roles(GUILD_ID).await.unwrap().len(), 1);
+    // Refused before the write, so a corrected retry isn't a duplicate.
+    assert!(!dynamo.is_claimed("1"));
+}
+
+#[tokio::test]
+async fn save_refuses_moderator_roles() {
+    let roles = Arc::new(InMemoryRoleStore::new());
+    let dynamo = FakeDynamo::default();
+    let router = router(roles.clone(), Arc::new(hierarchy()), &dynamo);
+
+    let response = router
+        .handle_command(&save_role(
+            "1",
+            json!({
+                "id": ROLE_ID,
+                "name": "Mods",
+                "position": 1,
+                "permissions": ADMINISTRATOR.to_string(),
+            }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        content(&response),
+        "That role can't be self-assignable: grants moderation permissions."
+    );
+    assert!(roles.list_roles(GUILD_ID).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn save_refuses_roles_not_below_the_bot() {
+    let roles = Arc::new(InMemoryRoleStore::new());
+    let dynamo = FakeDynamo::default();
+    let router = router(roles.clone(), Arc::new(hierarchy()), &dynamo);
+
+    let response = router
+        .handle_command(&save_role(
+            "1",
+            json!({ "id": ROLE_ID, "name": "VIP", "position": 5, "permissions": "0" }),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        content(&response),
+        "That role can't be self-assignable: not below the bot's highest role."
+    );
+    assert!(roles.list_roles(GUILD_ID).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn save_refuses_roles_not_below_the_member() {
+    let roles = Arc::new(InMemoryRoleStore::new());
+    let dynamo = FakeDynamo::default();
+    let router = router(roles.clone(), Arc::new(hierarchy()), &dynamo);
+
+    // A member with Manage Roles but no roles of their own.
+    let mut ctx = save("1", ROLE_ID, "Gamer");
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.permissions = MANAGE_ROLES.to_string();
+    }
+
+    let response = router.handle_command(&ctx).await.unwrap();
+
+    assert_eq!(
+        content(&response),
+        "That role can't be self-assignable: not below your highest role."
+    );
+    assert!(roles.list_roles(GUILD_ID).await.unwrap().is_empty());
+}
+
+#[cfg(feature = "billing")]
+#[tokio::test]
+async fn grant_premium_refuses_durations_out_of_range() {
+    let dynamo = FakeDynamo::default();
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        Arc::new(RecordingDiscordApi::new()),
+        &dynamo,
+    );
+
+    for days in [0, -1, 3_651, i64::MAX] {
+        let ctx = common::command(
+            "1",
+            "sysadmin",
+            "grant-premium",
+            json!([
+                { "name": "guild_id", "type": 3, "value": GUILD_ID },
+                { "name": "duration", "type": 4, "value": days },
+            ]),
+            json!({}),
+        );
+
+        let response = router.handle_command(&ctx).await.unwrap();
+
+        assert_eq!(
+            content(&response),
+            "Duration must be between 1 and 3650 days."
+        );
+    }
+    // Nothing but the audit entries was written.
+    assert!(dynamo.requests("UpdateItem").is_empty());
+}
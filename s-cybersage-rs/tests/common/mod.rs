@@ -0,0 +1,490 @@
+//! A `CommandRouter` wired to the in-memory role store, the recording
+//! Discord API and a fake DynamoDB endpoint for the stores that have no
+//! in-memory version (and the task queue), so router tests run without AWS
+//! or Discord.
+
+// Each test crate uses a different subset.
+#![allow(dead_code)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use aws_sdk_dynamodb::{
+    config::{BehaviorVersion, Credentials, Region},
+    Client,
+};
+use aws_smithy_runtime_api::{
+    client::{
+        http::{
+            HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings,
+            SharedHttpConnector,
+        },
+        orchestrator::{HttpRequest, HttpResponse},
+        runtime_components::RuntimeComponents,
+    },
+    http::StatusCode,
+    shared::IntoShared,
+};
+use aws_smithy_types::body::SdkBody;
+use s_cybersage_rs::{
+    bal::{
+        auth::operator::{OperatorAllowlist, OperatorContext},
+        config::feature_flags::FeatureFlags,
+        discord::recording_discord_api::RecordingDiscordApi,
+        route::{
+            command_router::CommandRouter, component_router::ComponentRouter,
+            request_context::RequestContext,
+        },
+    },
+    dal::{
+        dao::{
+            feature_flag::FeatureFlagDao, global_stats::GlobalStatsDao, guild_stores::GuildStores,
+            in_memory_role_store::InMemoryRoleStore, role_menu::RoleMenuDao,
+        },
+        model::{
+            interaction_request::InteractionRequest, interaction_response::InteractionResponse,
+        },
+        queue::task_queue::TaskQueue,
+    },
+};
+#[cfg(feature = "billing")]
+use s_cybersage_rs::{
+    bal::{
+        billing::{
+            context::BillingContext, premium_gate::PremiumGate, stripe_client::StripeClient,
+            subscription_manager::SubscriptionManager, usage_meter::UsageMeter,
+        },
+        config::settings::Settings,
+    },
+    dal::dao::{
+        bundle::BundleDao,
+        subscription::{SubscriptionReader, SubscriptionWriter},
+        usage::UsageDao,
+    },
+};
+use serde_json::{json, Value};
+
+pub const GUILD_ID: &str = "100000000000000001";
+pub const USER_ID: &str = "200000000000000002";
+pub const APPLICATION_ID: &str = "300000000000000003";
+
+const TABLE: &str = "role-mappings";
+
+#[derive(Debug, Default)]
+struct FakeTable {
+    /// `guild_id` and `mapping_key` of items created with a condition.
+    claimed: HashSet<(String, String)>,
+    /// Items `GetItem` returns, by `guild_id` and `mapping_key`.
+    items: HashMap<(String, String), Value>,
+    /// Operation and request body of every call, in order.
+    requests: Vec<(String, Value)>,
+}
+
+/// Answers DynamoDB calls as if the table were empty, apart from items
+/// seeded with `with_item` (served to `GetItem` and prefix queries) and
+/// counters bumped by `UpdateItem`, except that conditional puts fail for
+/// keys already put, which is what interaction claims rely on.
+#[derive(Debug, Clone, Default)]
+pub struct FakeDynamo {
+    table: Arc<Mutex<FakeTable>>,
+}
+
+impl FakeDynamo {
+    pub fn client(&self) -> Client {
+        let config = aws_sdk_dynamodb::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .http_client(self.clone())
+            .build();
+
+        Client::from_conf(config)
+    }
+
+    /// A task queue on the same fake endpoint, so what it's sent shows up
+    /// in `requests("SendMessage")`.
+    pub fn task_queue(&self) -> TaskQueue {
+        let config = aws_sdk_sqs::Config::builder()
+            .behavior_version(aws_sdk_sqs::config::BehaviorVersion::latest())
+            .region(aws_sdk_sqs::config::Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .http_client(self.clone())
+            .build();
+
+        TaskQueue::new(aws_sdk_sqs::Client::from_conf(config), "tasks")
+    }
+
+    /// Serves `item`, in DynamoDB's JSON form, to `GetItem`.
+    pub fn with_item(self, item: Value) -> Self {
+        let key = item_key(&item);
+        self.lock().items.insert(key, item);
+        self
+    }
+
+    /// The bodies of the `operation` calls made so far.
+    pub fn requests(&self, operation: &str) -> Vec<Value> {
+        self.lock()
+            .requests
+            .iter()
+            .filter(|(name, _)| name == operation)
+            .map(|(_, body)| body.clone())
+            .collect()
+    }
+
+    pub fn is_claimed(&self, interaction_id: &str) -> bool {
+        self.lock().claimed.contains(&(
+            GUILD_ID.to_string(),
+            format!("IDEMPOTENCY#{}", interaction_id),
+        ))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FakeTable> {
+        self.table.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn respond(&self, request: &HttpRequest) -> (u16, Value) {
+        let operation = request
+            .headers()
+            .get("x-amz-target")
+            .and_then(|target| target.rsplit('.').next())
+            .unwrap_or_default()
+            .to_string();
+        let body: Value = request
+            .body()
+            .bytes()
+            .and_then(|bytes| serde_json::from_slice(bytes).ok())
+            .unwrap_or_default();
+
+        let mut table = self.lock();
+        table.requests.push((operation.clone(), body.clone()));
+
+        match operation.as_str() {
+            "PutItem" if body.get("ConditionExpression").is_some() => {
+                if !table.claimed.insert(item_key(&body["Item"])) {
+                    return (
+                        400,
+                        json!({
+                            "__type": "com.amazonaws.dynamodb.v20120810#ConditionalCheckFailedException",
+                            "message": "The conditional request failed",
+                        }),
+                    );
+                }
+                (200, json!({}))
+            }
+            "DeleteItem" => {
+                table.claimed.remove(&item_key(&body["Key"]));
+                (200, json!({}))
+            }
+            "GetItem" => match table.items.get(&item_key(&body["Key"])) {
+                Some(item) => (200, json!({ "Item": item })),
+                None => (200, json!({})),
+            },
+            "Query" => {
+                let items = prefix_query(&table.items, &body);
+                let count = items.len();
+                (
+                    200,
+                    json!({ "Items": items, "Count": count, "ScannedCount": count }),
+                )
+            }
+            "Scan" => (200, json!({ "Items": [], "Count": 0, "ScannedCount": 0 })),
+            "UpdateItem" => (
+                200,
+                json!({ "Attributes": increment(&mut table.items, &body) }),
+            ),
+            _ => (200, json!({})),
+        }
+    }
+}
+
+/// Seeded items a `begins_with` query on `:guild_id` and `:prefix` matches,
+/// by sort key or, on the name index, by `role_name_normalized`. Other
+/// queries find nothing.
+fn prefix_query(items: &HashMap<(String, String), Value>, body: &Value) -> Vec<Value> {
+    let values = &body["ExpressionAttributeValues"];
+    let (Some(guild_id), Some(prefix)) = (
+        values[":guild_id"]["S"].as_str(),
+        values[":prefix"]["S"].as_str(),
+    ) else {
+        return Vec::new();
+    };
+    let by_name = body["IndexName"] == "GuildRoleNameIndex";
+
+    items
+        .iter()
+        .filter(|((guild, key), item)| {
+            let key = match by_name {
+                true => item["role_name_normalized"]["S"]
+                    .as_str()
+                    .unwrap_or_default(),
+                false => key,
+            };
+            guild == guild_id && key.starts_with(prefix)
+        })
+        .map(|(_, item)| item.clone())
+        .collect()
+}
+
+/// Applies an `ADD <counter> :one` update to the item, creating it if
+/// needed, and returns the new count. Other updates change nothing.
+fn increment(items: &mut HashMap<(String, String), Value>, body: &Value) -> Value {
+    let Some(counter) = body["UpdateExpression"]
+        .as_str()
+        .and_then(|expression| expression.strip_prefix("ADD "))
+        .and_then(|rest| rest.split_once(" :one"))
+        .map(|(counter, _)| counter.to_string())
+    else {
+        return json!({});
+    };
+
+    let item = items
+        .entry(item_key(&body["Key"]))
+        .or_insert_with(|| body["Key"].clone());
+    let count = item[&counter]["N"]
+        .as_str()
+        .and_then(|n| n.parse::<u64>().ok())
+        .unwrap_or(0)
+        + 1;
+    item[&counter] = json!({ "N": count.to_string() });
+
+    json!({ &counter: { "N": count.to_string() } })
+}
+
+/// The item's key in either table: the role table's `mapping_key` or the
+/// subscription table's `subscription_key`.
+fn item_key(item: &Value) -> (String, String) {
+    let attribute = |name: &str| item[name]["S"].as_str().map(str::to_string);
+    (
+        attribute("guild_id").unwrap_or_default(),
+        attribute("mapping_key")
+            .or_else(|| attribute("subscription_key"))
+            .unwrap_or_default(),
+    )
+}
+
+impl HttpConnector for FakeDynamo {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let (status, body) = self.respond(&request);
+
+        let mut response = HttpResponse::new(
+            StatusCode::try_from(status).unwrap(),
+            SdkBody::from(body.to_string()),
+        );
+        response
+            .headers_mut()
+            .insert("content-type", "application/x-amz-json-1.0");
+
+        HttpConnectorFuture::ready(Ok(response))
+    }
+}
+
+impl HttpClient for FakeDynamo {
+    fn http_connector(
+        &self,
+        _settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        self.clone().into_shared()
+    }
+}
+
+/// A router with no task queue or scheduler, so nothing is deferred.
+pub fn router(
+    role_store: Arc<InMemoryRoleStore>,
+    discord_api: Arc<RecordingDiscordApi>,
+    dynamo: &FakeDynamo,
+) -> CommandRouter {
+    metered_router(role_store, discord_api, dynamo, None)
+}
+
+/// Like `router`, for a guild allowed `monthly_quota` toggles a month, or
+/// unmetered with `None`. Without billing nothing is metered.
+pub fn metered_router(
+    role_store: Arc<InMemoryRoleStore>,
+    discord_api: Arc<RecordingDiscordApi>,
+    dynamo: &FakeDynamo,
+    monthly_quota: Option<u64>,
+) -> CommandRouter {
+    build_router(role_store, discord_api, dynamo, monthly_quota, None)
+}
+
+/// Like `router`, queueing deferred work on `dynamo.task_queue()`.
+pub fn queued_router(
+    role_store: Arc<InMemoryRoleStore>,
+    discord_api: Arc<RecordingDiscordApi>,
+    dynamo: &FakeDynamo,
+) -> CommandRouter {
+    build_router(
+        role_store,
+        discord_api,
+        dynamo,
+        None,
+        Some(dynamo.task_queue()),
+    )
+}
+
+fn build_router(
+    role_store: Arc<InMemoryRoleStore>,
+    discord_api: Arc<RecordingDiscordApi>,
+    dynamo: &FakeDynamo,
+    #[cfg_attr(not(feature = "billing"), allow(unused_variables))] monthly_quota: Option<u64>,
+    task_queue: Option<TaskQueue>,
+) -> CommandRouter {
+    let client = dynamo.client();
+
+    let operators = OperatorContext {
+        // The default member is also a bot operator.
+        allowlist: OperatorAllowlist::parse(USER_ID),
+        flag_store: FeatureFlagDao::new(client.clone(), TABLE),
+        stats_store: GlobalStatsDao::new(client.clone(), TABLE),
+    };
+    let stores = GuildStores::new(client.clone(), TABLE, 90);
+
+    #[cfg(feature = "billing")]
+    let billing = BillingContext {
+        usage_meter: UsageMeter::new(UsageDao::new(client.clone(), TABLE), monthly_quota),
+        subscription_manager: SubscriptionManager::new(
+            SubscriptionReader::new(client.clone(), TABLE),
+            SubscriptionWriter::new(client.clone(), TABLE),
+            BundleDao::new(client, TABLE),
+        ),
+        stripe_client: StripeClient::new(reqwest::Client::new(), None),
+        premium_gate: PremiumGate::new(false, None, None),
+    };
+
+    CommandRouter::new(
+        role_store,
+        discord_api,
+        #[cfg(feature = "billing")]
+        billing,
+        operators,
+        task_queue,
+        None,
+        stores,
+    )
+}
+
+pub fn component_router(
+    role_store: Arc<InMemoryRoleStore>,
+    dynamo: &FakeDynamo,
+) -> ComponentRouter {
+    ComponentRouter::new(RoleMenuDao::new(dynamo.client(), TABLE), role_store)
+}
+
+/// A `/role <subcommand>` invocation by `USER_ID` with `options`, from a
+/// bot that has every permission it asks for. `data` is merged into the
+/// command data, e.g. for `resolved`.
+pub fn role_command(
+    interaction_id: &str,
+    subcommand: &str,
+    options: Value,
+    data: Value,
+) -> RequestContext {
+    command(interaction_id, "role", subcommand, options, data)
+}
+
+/// Like `role_command`, for any command.
+pub fn command(
+    interaction_id: &str,
+    name: &str,
+    subcommand: &str,
+    options: Value,
+    data: Value,
+) -> RequestContext {
+    let mut command = json!({
+        "name": name,
+        "options": [{ "name": subcommand, "type": 1, "options": options }],
+    });
+    if let (Some(command), Some(extra)) = (command.as_object_mut(), data.as_object()) {
+        command.extend(extra.clone());
+    }
+
+    request_context(interaction_id, 2, json!({ "data": command }))
+}
+
+/// `/role toggle` by `USER_ID` with `typed` in its focused `role` option,
+/// as sent while typing.
+pub fn autocomplete(interaction_id: &str, typed: &str) -> RequestContext {
+    request_context(
+        interaction_id,
+        4,
+        json!({
+            "data": {
+                "name": "role",
+                "options": [{
+                    "name": "toggle",
+                    "type": 1,
+                    "options": [{ "name": "role", "type": 3, "value": typed, "focused": true }],
+                }],
+            },
+        }),
+    )
+}
+
+/// A click by `USER_ID` on the component `custom_id` of message
+/// `message_id`.
+pub fn component(interaction_id: &str, custom_id: &str, message_id: &str) -> RequestContext {
+    request_context(
+        interaction_id,
+        3,
+        json!({
+            "data": { "custom_id": custom_id, "component_type": 2 },
+            "message": { "id": message_id },
+        }),
+    )
+}
+
+/// An interaction of `interaction_type` in `GUILD_ID`, with `fields` merged
+/// into the payload.
+fn request_context(interaction_id: &str, interaction_type: u8, fields: Value) -> RequestContext {
+    let mut payload = json!({
+        "id": interaction_id,
+        "application_id": APPLICATION_ID,
+        "type": interaction_type,
+        "token": "token",
+        "guild_id": GUILD_ID,
+        "channel_id": "400000000000000004",
+        "app_permissions": u64::MAX.to_string(),
+        "member": {
+            "user": { "id": USER_ID, "username": "member" },
+            "roles": [],
+            "permissions": u64::MAX.to_string(),
+        },
+    });
+    if let (Some(payload), Some(fields)) = (payload.as_object_mut(), fields.as_object()) {
+        payload.extend(fields.clone());
+    }
+
+    let interaction: InteractionRequest = serde_json::from_value(payload).unwrap();
+
+    RequestContext {
+        locale: RequestContext::resolve_locale(&interaction),
+        interaction,
+        guild_id: GUILD_ID.to_string(),
+        flags: FeatureFlags::new(Default::default()),
+        #[cfg(feature = "billing")]
+        settings: Settings::from_env(),
+        #[cfg(feature = "billing")]
+        is_premium: false,
+    }
+}
+
+/// An HTTP client that can only reach a closed local port, for clients
+/// such as `InteractionClient` whose reports the tests don't read.
+pub fn unreachable_http() -> reqwest::Client {
+    reqwest::Client::builder()
+        .proxy(reqwest::Proxy::all("http://127.0.0.1:9").unwrap())
+        .build()
+        .unwrap()
+}
+
+/// The message a response shows.
+pub fn content(response: &InteractionResponse) -> String {
+    let value = serde_json::to_value(response).unwrap();
+    value["data"]["content"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
+}
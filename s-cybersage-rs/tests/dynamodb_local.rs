@@ -0,0 +1,257 @@
+//! DAO tests against DynamoDB Local. They are compiled only with the
+//! `dynamodb-local` feature and ignored by default; run them with a local
+//! instance listening on `DYNAMODB_LOCAL_ENDPOINT` (default
+//! `http://localhost:8000`):
+//!
+//! ```sh
+//! docker run -p 8000:8000 amazon/dynamodb-local
+//! cargo test --features dynamodb-local -- --ignored
+//! ```
+
+#![cfg(feature = "dynamodb-local")]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aws_sdk_dynamodb::{
+    config::{BehaviorVersion, Credentials, Region},
+    types::{
+        AttributeDefinition, AttributeValue, BillingMode, GlobalSecondaryIndex, KeySchemaElement,
+        KeyType, Projection, ProjectionType, ScalarAttributeType,
+    },
+    Client,
+};
+#[cfg(feature = "billing")]
+use s_cybersage_rs::dal::dao::bundle::{Bundle, BundleDao};
+use s_cybersage_rs::dal::dao::{guild::GuildDao, role_store::RoleStore};
+
+const GUILD_ID: &str = "100000000000000001";
+
+fn client() -> Client {
+    let endpoint = std::env::var("DYNAMODB_LOCAL_ENDPOINT")
+        .unwrap_or_else(|_| "http://localhost:8000".to_string());
+
+    let config = aws_sdk_dynamodb::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .endpoint_url(endpoint)
+        .region(Region::new("us-east-1"))
+        .credentials_provider(Credentials::new("local", "local", None, None, "static"))
+        .build();
+
+    Client::from_conf(config)
+}
+
+fn unique_table_name(prefix: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    format!("{}-{}", prefix, nanos)
+}
+
+fn key(name: &str, key_type: KeyType) -> KeySchemaElement {
+    KeySchemaElement::builder()
+        .attribute_name(name)
+        .key_type(key_type)
+        .build()
+        .unwrap()
+}
+
+fn string_attribute(name: &str) -> AttributeDefinition {
+    AttributeDefinition::builder()
+        .attribute_name(name)
+        .attribute_type(ScalarAttributeType::S)
+        .build()
+        .unwrap()
+}
+
+/// Creates a table shaped like `GuildRoleMappings` in the CDK stack.
+async fn create_role_table(client: &Client) -> String {
+    let table_name = unique_table_name("GuildRoleMappings");
+
+    client
+        .create_table()
+        .table_name(&table_name)
+        .billing_mode(BillingMode::PayPerRequest)
+        .attribute_definitions(string_attribute("guild_id"))
+        .attribute_definitions(string_attribute("mapping_key"))
+        .attribute_definitions(string_attribute("role_name_normalized"))
+        .key_schema(key("guild_id", KeyType::Hash))
+        .key_schema(key("mapping_key", KeyType::Range))
+        .global_secondary_indexes(
+            GlobalSecondaryIndex::builder()
+                .index_name("GuildRoleNameIndex")
+                .key_schema(key("guild_id", KeyType::Hash))
+                .key_schema(key("role_name_normalized", KeyType::Range))
+                .projection(
+                    Projection::builder()
+                        .projection_type(ProjectionType::All)
+                        .build(),
+                )
+                .build()
+                .unwrap(),
+        )
+        .send()
+        .await
+        .expect("create role table");
+
+    table_name
+}
+
+/// Creates a table shaped like `GuildSubscriptions` in the CDK stack.
+#[cfg(feature = "billing")]
+async fn create_subscription_table(client: &Client) -> String {
+    let table_name = unique_table_name("GuildSubscriptions");
+
+    client
+        .create_table()
+        .table_name(&table_name)
+        .billing_mode(BillingMode::PayPerRequest)
+        .attribute_definitions(string_attribute("guild_id"))
+        .attribute_definitions(string_attribute("subscription_key"))
+        .key_schema(key("guild_id", KeyType::Hash))
+        .key_schema(key("subscription_key", KeyType::Range))
+        .send()
+        .await
+        .expect("create subscription table");
+
+    table_name
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn saves_and_looks_up_roles() {
+    let client = client();
+    let table = create_role_table(&client).await;
+    let dao = GuildDao::new(client, table);
+
+    dao.save_role(GUILD_ID, "1", "Artist").await.unwrap();
+    dao.save_role(GUILD_ID, "2", "Artificer").await.unwrap();
+    dao.save_role(GUILD_ID, "3", "Builder").await.unwrap();
+
+    assert_eq!(
+        dao.get_role_by_id(GUILD_ID, "2").await.unwrap(),
+        Some(("Artificer".to_string(), "2".to_string()))
+    );
+    assert_eq!(
+        dao.get_role_by_name(GUILD_ID, "bUiLdEr").await.unwrap(),
+        Some(("Builder".to_string(), "3".to_string()))
+    );
+    assert_eq!(
+        dao.get_role_by_name(GUILD_ID, "Nobody").await.unwrap(),
+        None
+    );
+    assert_eq!(dao.get_role_by_id("other-guild", "1").await.unwrap(), None);
+
+    let matches = dao
+        .query_roles_by_prefix(GUILD_ID, "ART", &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        matches,
+        vec![
+            ("Artificer".to_string(), "2".to_string()),
+            ("Artist".to_string(), "1".to_string()),
+        ]
+    );
+    assert!(dao
+        .query_roles_by_prefix(GUILD_ID, "  ", &[])
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Saving an existing role ID renames it rather than adding a duplicate.
+    dao.save_role(GUILD_ID, "1", "Painter").await.unwrap();
+    assert_eq!(
+        dao.get_role_by_name(GUILD_ID, "Artist").await.unwrap(),
+        None
+    );
+    assert_eq!(dao.list_roles(GUILD_ID).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn list_roles_follows_pagination() {
+    let client = client();
+    let table = create_role_table(&client).await;
+
+    // Queries return at most 1 MB per page, so a handful of padded items
+    // forces `list_roles` to follow `LastEvaluatedKey`.
+    let padding = "x".repeat(350 * 1024);
+
+    for i in 0..5 {
+        client
+            .put_item()
+            .table_name(&table)
+            .item("guild_id", AttributeValue::S(GUILD_ID.to_string()))
+            .item("mapping_key", AttributeValue::S(format!("ROLE#{}", i)))
+            .item("role_id", AttributeValue::S(i.to_string()))
+            .item("role_name", AttributeValue::S(format!("Role {}", i)))
+            .item(
+                "role_name_normalized",
+                AttributeValue::S(format!("role {}", i)),
+            )
+            .item("padding", AttributeValue::S(padding.clone()))
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let dao = GuildDao::new(client, table);
+    let mut roles = dao.list_roles(GUILD_ID).await.unwrap();
+    roles.sort();
+
+    assert_eq!(
+        roles,
+        (0..5)
+            .map(|i| (format!("Role {}", i), i.to_string()))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[cfg(feature = "billing")]
+#[tokio::test]
+#[ignore = "requires DynamoDB Local"]
+async fn bundle_attach_is_conditional_on_capacity() {
+    let client = client();
+    let table = create_subscription_table(&client).await;
+
+    client
+        .put_item()
+        .table_name(&table)
+        .item("guild_id", AttributeValue::S("USER#owner".to_string()))
+        .item("subscription_key", AttributeValue::S("BUNDLE".to_string()))
+        .item("status", AttributeValue::S("active".to_string()))
+        .item("expires_at", AttributeValue::N(i64::MAX.to_string()))
+        .item("max_guilds", AttributeValue::N("1".to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    let dao = BundleDao::new(client, table);
+
+    let bundle: Bundle = dao.get_bundle("owner").await.unwrap().unwrap();
+    assert!(bundle.has_capacity());
+
+    dao.attach(&bundle, "guild-a").await.unwrap();
+    assert_eq!(
+        dao.get_attached_owner("guild-a").await.unwrap(),
+        Some("owner".to_string())
+    );
+
+    // The bundle is full, and a guild can't be attached twice.
+    assert!(dao.attach(&bundle, "guild-b").await.is_err());
+    assert!(dao.attach(&bundle, "guild-a").await.is_err());
+    assert_eq!(dao.get_attached_owner("guild-b").await.unwrap(), None);
+
+    dao.detach("owner", "guild-a").await.unwrap();
+    assert_eq!(dao.get_attached_owner("guild-a").await.unwrap(), None);
+
+    let bundle = dao.get_bundle("owner").await.unwrap().unwrap();
+    assert!(bundle.guild_ids.is_empty());
+
+    dao.attach(&bundle, "guild-b").await.unwrap();
+    assert_eq!(
+        dao.get_attached_owner("guild-b").await.unwrap(),
+        Some("owner".to_string())
+    );
+}
@@ -0,0 +1,36 @@
+//! The CSV `/role export` sends, for role names that need quoting.
+
+use s_cybersage_rs::bal::deferred::task_executor::TaskExecutor;
+
+fn export(roles: &[(&str, &str)]) -> String {
+    let roles: Vec<(String, String)> = roles
+        .iter()
+        .map(|(name, id)| (name.to_string(), id.to_string()))
+        .collect();
+
+    TaskExecutor::format_export(&roles)
+}
+
+#[test]
+fn plain_names_are_left_unquoted() {
+    assert_eq!(
+        export(&[("Gamer", "1")]),
+        "```csv\nrole_id,role_name\n1,Gamer\n```"
+    );
+}
+
+#[test]
+fn names_with_commas_and_quotes_are_quoted() {
+    assert_eq!(
+        export(&[("Red, \"Blue\"", "1")]),
+        "```csv\nrole_id,role_name\n1,\"Red, \"\"Blue\"\"\"\n```"
+    );
+}
+
+#[test]
+fn backticks_cannot_close_the_code_block() {
+    let message = export(&[("```oops", "1")]);
+
+    assert_eq!(message.matches("```").count(), 2);
+    assert!(message.ends_with("```"));
+}
@@ -0,0 +1,113 @@
+//! Which roles `/role import-all` makes self-assignable, with the guild's
+//! roles scripted through `RecordingDiscordApi`.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{unreachable_http, FakeDynamo, APPLICATION_ID, GUILD_ID, USER_ID};
+use s_cybersage_rs::{
+    bal::{
+        activity::activity_recorder::ActivityRecorder,
+        deferred::task_executor::TaskExecutor,
+        discord::{
+            interaction_client::InteractionClient, recording_discord_api::RecordingDiscordApi,
+            role_manager::GuildRole,
+        },
+    },
+    dal::{
+        dao::{
+            guild_record::GuildRecordDao, in_memory_role_store::InMemoryRoleStore,
+            log_channel::LogChannelDao, role_store::RoleStore,
+        },
+        model::deferred_task::TaskOrigin,
+    },
+};
+use serde_json::json;
+
+const TABLE: &str = "role-mappings";
+const MEMBER_ROLE_ID: &str = "500000000000000001";
+const MODERATOR_ROLE_ID: &str = "500000000000000002";
+const EVENTS_ROLE_ID: &str = "500000000000000003";
+const BOT_ROLE_ID: &str = "500000000000000004";
+
+fn role(id: &str, name: &str, position: i64) -> GuildRole {
+    serde_json::from_value(json!({
+        "id": id,
+        "name": name,
+        "position": position,
+        "permissions": "0",
+    }))
+    .unwrap()
+}
+
+fn executor(role_store: Arc<InMemoryRoleStore>, dynamo: &FakeDynamo) -> TaskExecutor {
+    let client = dynamo.client();
+    let discord = RecordingDiscordApi::new()
+        .with_guild_roles(
+            GUILD_ID,
+            vec![
+                role(MEMBER_ROLE_ID, "Member", 1),
+                role(MODERATOR_ROLE_ID, "Helper", 2),
+                role(EVENTS_ROLE_ID, "Events", 3),
+                role(BOT_ROLE_ID, "Bot", 4),
+            ],
+        )
+        .with_member_roles(GUILD_ID, APPLICATION_ID, &[BOT_ROLE_ID]);
+
+    TaskExecutor::new(
+        role_store,
+        Arc::new(discord),
+        InteractionClient::new(unreachable_http()),
+        ActivityRecorder::new(LogChannelDao::new(client.clone(), TABLE), None),
+        GuildRecordDao::new(client, TABLE),
+    )
+}
+
+/// An import asked for by a member holding `member_roles`.
+fn origin(member_roles: &[&str], administrator: bool) -> TaskOrigin {
+    TaskOrigin {
+        guild_id: GUILD_ID.to_string(),
+        application_id: APPLICATION_ID.to_string(),
+        interaction_token: "token".to_string(),
+        user_id: Some(USER_ID.to_string()),
+        member_roles: member_roles.iter().map(|id| id.to_string()).collect(),
+        administrator,
+    }
+}
+
+async fn imported(role_store: &InMemoryRoleStore) -> Vec<String> {
+    let mut names: Vec<String> = role_store
+        .list_roles(GUILD_ID)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn roles_at_or_above_the_member_are_skipped() {
+    let role_store = Arc::new(InMemoryRoleStore::new());
+
+    executor(role_store.clone(), &FakeDynamo::default())
+        .import_roles(&origin(&[MODERATOR_ROLE_ID], false))
+        .await
+        .unwrap();
+
+    assert_eq!(imported(&role_store).await, ["Member"]);
+}
+
+#[tokio::test]
+async fn administrators_import_every_role_below_the_bot() {
+    let role_store = Arc::new(InMemoryRoleStore::new());
+
+    executor(role_store.clone(), &FakeDynamo::default())
+        .import_roles(&origin(&[], true))
+        .await
+        .unwrap();
+
+    assert_eq!(imported(&role_store).await, ["Events", "Helper", "Member"]);
+}
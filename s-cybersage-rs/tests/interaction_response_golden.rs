@@ -0,0 +1,190 @@
+//! The exact JSON each kind of `InteractionResponse` is sent as. Discord
+//! rejects a response with the wrong callback or component codes, and
+//! nothing else notices, so every variant is compared byte for byte with a
+//! file in `tests/golden/`. After an intended change, rewrite the files with
+//!
+//! ```sh
+//! UPDATE_GOLDEN=1 cargo test --test interaction_response_golden
+//! ```
+//!
+//! and review the diff.
+
+use std::path::PathBuf;
+
+use s_cybersage_rs::{
+    dal::model::{
+        interaction_response::{
+            ApplicationCommandOptionChoice, ButtonStyle, Component, ComponentType,
+            InteractionCallbackType, InteractionResponse, SelectOption,
+        },
+        role_menu::PartialEmoji,
+    },
+    json,
+};
+use serde_json::json;
+
+/// Compares `response`, as written on the interaction path, with
+/// `tests/golden/<name>.json`.
+fn assert_golden(name: &str, response: &InteractionResponse) {
+    let actual = json::to_string(response).unwrap();
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.json", name));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, format!("{}\n", actual)).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Missing {}; run with UPDATE_GOLDEN=1", path.display()));
+
+    assert_eq!(actual, expected.trim_end(), "{} changed", name);
+}
+
+#[test]
+fn pong() {
+    assert_golden("pong", &InteractionResponse::pong());
+}
+
+#[test]
+fn message() {
+    assert_golden("message", &InteractionResponse::message("Role saved."));
+}
+
+#[test]
+fn ephemeral() {
+    assert_golden(
+        "ephemeral",
+        &InteractionResponse::ephemeral("Only you can see this.").with_footer("-# staging build"),
+    );
+}
+
+#[test]
+fn deferred_ephemeral() {
+    assert_golden(
+        "deferred_ephemeral",
+        &InteractionResponse::deferred_ephemeral(),
+    );
+}
+
+#[test]
+fn autocomplete() {
+    let choices = ["Moderator", "Member \"Plus\""]
+        .into_iter()
+        .map(|name| ApplicationCommandOptionChoice {
+            name: name.to_string(),
+            value: name.to_string(),
+        })
+        .collect();
+
+    assert_golden("autocomplete", &InteractionResponse::autocomplete(choices));
+}
+
+#[test]
+fn ephemeral_embed() {
+    let embed = json!({
+        "title": "Server stats",
+        "color": 5793266,
+        "fields": [{"name": "Roles", "value": "12", "inline": true}],
+    });
+
+    assert_golden(
+        "ephemeral_embed",
+        &InteractionResponse::ephemeral_embed(embed),
+    );
+}
+
+#[test]
+fn buttons() {
+    let response = InteractionResponse::ephemeral("Pick a role.").with_components(vec![
+        Component::action_row(vec![
+            Component::button(ButtonStyle::Primary, "Moderator", "role:toggle:1"),
+            Component::emoji_button(
+                ButtonStyle::Secondary,
+                PartialEmoji {
+                    id: Some("1500000000000000001".to_string()),
+                    name: "sage".to_string(),
+                    animated: true,
+                },
+                "role:toggle:2",
+            ),
+            Component::link_button("Add to server", "https://discord.com/oauth2/authorize"),
+            Component::premium_button("1600000000000000001"),
+        ]),
+    ]);
+
+    assert_golden("buttons", &response);
+}
+
+#[test]
+fn selects() {
+    let response = InteractionResponse::update_message(
+        "Choose where to log.",
+        vec![
+            Component::action_row(vec![Component::string_select(
+                "setup:roles",
+                "Roles to offer",
+                vec![SelectOption {
+                    label: "Moderator".to_string(),
+                    value: "1".to_string(),
+                }],
+            )]),
+            Component::action_row(vec![Component::channel_select(
+                "setup:log_channel",
+                "Log channel",
+                vec![0, 5],
+            )]),
+        ],
+    );
+
+    assert_golden("selects", &response);
+}
+
+#[test]
+fn update_message_clearing_components() {
+    assert_golden(
+        "update_message_cleared",
+        &InteractionResponse::update_message("Done.", Vec::new()),
+    );
+}
+
+/// Discord only accepts the numeric codes, never the variant names.
+#[test]
+fn enums_serialize_as_discord_codes() {
+    let callback_types = [
+        (InteractionCallbackType::Pong, 1),
+        (InteractionCallbackType::ChannelMessageWithSource, 4),
+        (InteractionCallbackType::DeferredChannelMessageWithSource, 5),
+        (InteractionCallbackType::UpdateMessage, 7),
+        (
+            InteractionCallbackType::ApplicationCommandAutocompleteResult,
+            8,
+        ),
+    ];
+    for (kind, code) in callback_types {
+        assert_eq!(json::to_string(&kind).unwrap(), code.to_string());
+    }
+
+    let component_types = [
+        (ComponentType::ActionRow, 1),
+        (ComponentType::Button, 2),
+        (ComponentType::StringSelect, 3),
+        (ComponentType::ChannelSelect, 8),
+    ];
+    for (kind, code) in component_types {
+        assert_eq!(json::to_string(&kind).unwrap(), code.to_string());
+    }
+
+    let button_styles = [
+        (ButtonStyle::Primary, 1),
+        (ButtonStyle::Secondary, 2),
+        (ButtonStyle::Success, 3),
+        (ButtonStyle::Danger, 4),
+        (ButtonStyle::Link, 5),
+        (ButtonStyle::Premium, 6),
+    ];
+    for (style, code) in button_styles {
+        assert_eq!(json::to_string(&style).unwrap(), code.to_string());
+    }
+}
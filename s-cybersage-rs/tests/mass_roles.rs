@@ -0,0 +1,341 @@
+//! Which roles `/role mass-assign` and `/role mass-remove` agree to hand
+//! out across the guild, with the guild's roles scripted through
+//! `RecordingDiscordApi`, and how the queued job works through the members.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{
+    content, queued_router, role_command, router, unreachable_http, FakeDynamo, APPLICATION_ID,
+    GUILD_ID,
+};
+use s_cybersage_rs::{
+    bal::{
+        activity::activity_recorder::ActivityRecorder,
+        deferred::mass_role_worker::MassRoleWorker,
+        discord::{
+            interaction_client::InteractionClient,
+            recording_discord_api::{DiscordCall, RecordingDiscordApi},
+            role_manager::GuildRole,
+        },
+        retry::role_retry_worker::JobOutcome,
+        route::request_context::RequestContext,
+    },
+    commands::{ADMINISTRATOR, MANAGE_GUILD, MANAGE_ROLES},
+    dal::{
+        dao::{in_memory_role_store::InMemoryRoleStore, log_channel::LogChannelDao},
+        model::{
+            deferred_task::{DeferredTask, TaskOrigin},
+            mass_role_job::{MassRoleJob, MassRoleProgress},
+        },
+    },
+};
+use serde_json::{json, Value};
+
+const BOT_ROLE_ID: &str = "500000000000000005";
+const MEMBER_ROLE_ID: &str = "600000000000000006";
+const ADMIN_ROLE_ID: &str = "700000000000000007";
+const HIGH_ROLE_ID: &str = "800000000000000008";
+const EVENTS_ROLE_ID: &str = "900000000000000009";
+
+fn role(id: &str, position: i64, permissions: u64) -> GuildRole {
+    serde_json::from_value(json!({
+        "id": id,
+        "name": "Role",
+        "position": position,
+        "permissions": permissions.to_string(),
+    }))
+    .unwrap()
+}
+
+fn discord() -> Arc<RecordingDiscordApi> {
+    Arc::new(
+        RecordingDiscordApi::new()
+            .with_guild_roles(
+                GUILD_ID,
+                vec![
+                    role(MEMBER_ROLE_ID, 1, 0),
+                    role(EVENTS_ROLE_ID, 2, 0),
+                    role(ADMIN_ROLE_ID, 3, ADMINISTRATOR),
+                    role(BOT_ROLE_ID, 4, 0),
+                    role(HIGH_ROLE_ID, 5, 0),
+                ],
+            )
+            .with_member_roles(GUILD_ID, APPLICATION_ID, &[BOT_ROLE_ID]),
+    )
+}
+
+/// Each test runs as its own member, since the subcommand's cooldown is
+/// kept per member for the whole test binary.
+fn mass_assign(user_id: &str, role_id: &str) -> RequestContext {
+    let mut ctx = role_command(
+        "1",
+        "mass-assign",
+        json!([{ "name": "role", "type": 8, "value": role_id }]),
+        json!({
+            "resolved": {
+                "roles": { role_id: { "id": role_id, "name": "Role" } },
+            },
+        }),
+    );
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.user.id = user_id.to_string();
+    }
+    ctx
+}
+
+#[tokio::test]
+async fn roles_below_the_bot_are_queued() {
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        discord(),
+        &FakeDynamo::default(),
+    );
+
+    let response = router
+        .handle_command(&mass_assign("201", MEMBER_ROLE_ID))
+        .await
+        .unwrap();
+
+    // The router under test has no queue to hand the job to.
+    assert_eq!(content(&response), "Background tasks are not configured.");
+}
+
+#[tokio::test]
+async fn moderator_roles_are_refused() {
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        discord(),
+        &FakeDynamo::default(),
+    );
+
+    let response = router
+        .handle_command(&mass_assign("202", ADMIN_ROLE_ID))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        content(&response),
+        "'Role' can't be handed out in bulk: grants moderation permissions."
+    );
+}
+
+#[tokio::test]
+async fn roles_above_the_bot_are_refused() {
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        discord(),
+        &FakeDynamo::default(),
+    );
+
+    let response = router
+        .handle_command(&mass_assign("203", HIGH_ROLE_ID))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        content(&response),
+        "'Role' can't be handed out in bulk: not below the bot's highest role."
+    );
+}
+
+/// `mass_assign` by a moderator who isn't an administrator and whose
+/// highest role is `MEMBER_ROLE_ID`.
+fn moderator_mass_assign(user_id: &str, role_id: &str) -> RequestContext {
+    let mut ctx = mass_assign(user_id, role_id);
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.permissions = MANAGE_ROLES.to_string();
+        member.roles = vec![MEMBER_ROLE_ID.to_string()];
+    }
+    ctx
+}
+
+#[tokio::test]
+async fn roles_at_or_above_the_member_are_refused() {
+    let discord = discord();
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+
+    for (user_id, role_id) in [("205", MEMBER_ROLE_ID), ("206", EVENTS_ROLE_ID)] {
+        let response = router
+            .handle_command(&moderator_mass_assign(user_id, role_id))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            content(&response),
+            "'Role' can't be handed out in bulk: not below your highest role."
+        );
+    }
+    assert!(assigned(&discord).is_empty());
+}
+
+#[tokio::test]
+async fn administrators_may_hand_out_roles_above_their_own() {
+    let mut ctx = moderator_mass_assign("207", EVENTS_ROLE_ID);
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.permissions = ADMINISTRATOR.to_string();
+    }
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        discord(),
+        &FakeDynamo::default(),
+    );
+
+    let response = router.handle_command(&ctx).await.unwrap();
+
+    assert_eq!(content(&response), "Background tasks are not configured.");
+}
+
+#[tokio::test]
+async fn a_refused_run_can_be_retried_straight_away() {
+    let dynamo = FakeDynamo::default();
+    let router = queued_router(Arc::new(InMemoryRoleStore::new()), discord(), &dynamo);
+
+    let refused = router
+        .handle_command(&mass_assign("208", ADMIN_ROLE_ID))
+        .await
+        .unwrap();
+    assert_eq!(
+        content(&refused),
+        "'Role' can't be handed out in bulk: grants moderation permissions."
+    );
+
+    router
+        .handle_command(&mass_assign("208", MEMBER_ROLE_ID))
+        .await
+        .unwrap();
+    assert_eq!(dynamo.requests("SendMessage").len(), 1);
+
+    // Queuing the job is what starts the cooldown.
+    let again = router
+        .handle_command(&mass_assign("208", MEMBER_ROLE_ID))
+        .await
+        .unwrap();
+    assert!(content(&again).starts_with("You just ran this."));
+    assert_eq!(dynamo.requests("SendMessage").len(), 1);
+}
+
+#[tokio::test]
+async fn members_without_manage_roles_are_refused() {
+    let mut ctx = mass_assign("204", MEMBER_ROLE_ID);
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.permissions = MANAGE_GUILD.to_string();
+    }
+    let discord = discord();
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+
+    let response = router.handle_command(&ctx).await.unwrap();
+
+    assert_eq!(
+        content(&response),
+        "You need the Manage Roles permission to use this command."
+    );
+    assert!(discord.calls().is_empty());
+}
+
+/// Changes made by one task before the job re-queues itself.
+const CHANGES_PER_TASK: usize = 20;
+
+/// A worker whose progress reports go nowhere.
+fn worker(discord: Arc<RecordingDiscordApi>, dynamo: &FakeDynamo) -> MassRoleWorker {
+    MassRoleWorker::new(
+        discord,
+        InteractionClient::new(unreachable_http()),
+        dynamo.task_queue(),
+        ActivityRecorder::new(LogChannelDao::new(dynamo.client(), "role-mappings"), None),
+    )
+}
+
+fn mass_assign_job() -> MassRoleJob {
+    MassRoleJob {
+        origin: TaskOrigin {
+            guild_id: GUILD_ID.to_string(),
+            application_id: APPLICATION_ID.to_string(),
+            interaction_token: "token".to_string(),
+            user_id: None,
+            member_roles: vec![],
+            administrator: true,
+        },
+        role_id: MEMBER_ROLE_ID.to_string(),
+        role_name: "Role".to_string(),
+        remove: false,
+        filter_role_id: None,
+        after: None,
+        progress: MassRoleProgress::default(),
+        attempt: 0,
+    }
+}
+
+fn member_id(n: usize) -> String {
+    (210_000_000_000_000_000 + n).to_string()
+}
+
+/// The members given the role, in order.
+fn assigned(discord: &RecordingDiscordApi) -> Vec<String> {
+    discord
+        .calls()
+        .into_iter()
+        .filter_map(|call| match call {
+            DiscordCall::ModifyUserRole { user_id, .. } => Some(user_id),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The jobs re-queued so far.
+fn requeued(dynamo: &FakeDynamo) -> Vec<MassRoleJob> {
+    dynamo
+        .requests("SendMessage")
+        .into_iter()
+        .map(|body: Value| {
+            match serde_json::from_str(body["MessageBody"].as_str().unwrap()).unwrap() {
+                DeferredTask::MassRole(job) => job,
+                other => panic!("unexpected task {:?}", other),
+            }
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn a_job_resumes_after_the_last_member_it_handled() {
+    // One member already has the role, so the first task checks one more
+    // member than it changes.
+    let mut discord =
+        RecordingDiscordApi::new().with_member_roles(GUILD_ID, &member_id(0), &[MEMBER_ROLE_ID]);
+    for n in 1..=CHANGES_PER_TASK + 2 {
+        discord = discord.with_member_roles(GUILD_ID, &member_id(n), &[]);
+    }
+    let discord = Arc::new(discord);
+    let dynamo = FakeDynamo::default();
+    let worker = worker(discord.clone(), &dynamo);
+
+    let outcome = worker.process(&mass_assign_job()).await.unwrap();
+
+    assert_eq!(outcome, JobOutcome::Requeued);
+    let next = requeued(&dynamo).pop().unwrap();
+    assert_eq!(next.after, Some(member_id(CHANGES_PER_TASK)));
+    assert_eq!(
+        next.progress,
+        MassRoleProgress {
+            checked: CHANGES_PER_TASK as u64 + 1,
+            changed: CHANGES_PER_TASK as u64,
+            failed: 0,
+        }
+    );
+
+    let outcome = worker.process(&next).await.unwrap();
+
+    assert_eq!(outcome, JobOutcome::Applied);
+    let expected: Vec<String> = (1..=CHANGES_PER_TASK + 2).map(member_id).collect();
+    assert_eq!(assigned(&discord), expected);
+    assert_eq!(requeued(&dynamo).len(), 1);
+}
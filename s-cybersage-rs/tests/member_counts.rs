@@ -0,0 +1,145 @@
+//! How many members hold each registered role, counted page by page from
+//! the guild's member list and shown next to autocomplete suggestions.
+
+mod common;
+
+use std::{collections::HashMap, sync::Arc};
+
+use common::{autocomplete, FakeDynamo, GUILD_ID};
+#[cfg(feature = "billing")]
+use s_cybersage_rs::dal::dao::subscription::SubscriptionReader;
+use s_cybersage_rs::{
+    bal::{
+        deferred::member_counter::MemberCounter,
+        discord::recording_discord_api::RecordingDiscordApi, retry::role_retry_worker::JobOutcome,
+        route::command_router::CommandRouter,
+    },
+    dal::{
+        dao::{
+            favorite::FavoriteDao, in_memory_role_store::InMemoryRoleStore, role_store::RoleStore,
+        },
+        model::member_count::MemberCountJob,
+    },
+};
+use serde_json::json;
+
+const GAMER: &str = "500000000000000005";
+const ARTIST: &str = "500000000000000006";
+const UNREGISTERED: &str = "500000000000000007";
+
+fn roles(guild_id: &str) -> Arc<InMemoryRoleStore> {
+    Arc::new(InMemoryRoleStore::with_roles([
+        (guild_id, GAMER, "Gamer"),
+        (guild_id, ARTIST, "Artist"),
+    ]))
+}
+
+/// Three members of `guild_id`: two gamers, and one holding only a role
+/// that isn't registered.
+fn members(guild_id: &str) -> Arc<RecordingDiscordApi> {
+    Arc::new(
+        RecordingDiscordApi::new()
+            .with_member_roles(guild_id, "200000000000000001", &[GAMER])
+            .with_member_roles(guild_id, "200000000000000002", &[GAMER, UNREGISTERED])
+            .with_member_roles(guild_id, "200000000000000003", &[UNREGISTERED]),
+    )
+}
+
+/// A counter for which `GUILD_ID` is premium and every other guild isn't.
+fn counter(role_store: Arc<InMemoryRoleStore>, discord: Arc<RecordingDiscordApi>) -> MemberCounter {
+    let dynamo = FakeDynamo::default().with_item(json!({
+        "guild_id": { "S": GUILD_ID },
+        "subscription_key": { "S": "SUBSCRIPTION" },
+        "status": { "S": "active" },
+        "expires_at": { "N": i64::MAX.to_string() },
+    }));
+
+    MemberCounter::new(
+        role_store,
+        discord,
+        dynamo.task_queue(),
+        #[cfg(feature = "billing")]
+        Some(SubscriptionReader::new(dynamo.client(), "subscriptions")),
+    )
+}
+
+#[tokio::test]
+async fn members_are_counted_for_each_registered_role() {
+    let role_store = roles(GUILD_ID);
+    let counter = counter(role_store.clone(), members(GUILD_ID));
+
+    let outcome = counter.count(&MemberCountJob::new(GUILD_ID)).await.unwrap();
+
+    assert_eq!(outcome, JobOutcome::Applied);
+    assert_eq!(
+        role_store.get_member_counts(GUILD_ID).await.unwrap(),
+        HashMap::from([(GAMER.to_string(), 2), (ARTIST.to_string(), 0)])
+    );
+}
+
+#[tokio::test]
+async fn a_resumed_count_adds_to_the_totals_so_far() {
+    let role_store = roles(GUILD_ID);
+    let counter = counter(role_store.clone(), members(GUILD_ID));
+    let job = MemberCountJob {
+        after: Some("200000000000000001".to_string()),
+        counts: HashMap::from([(GAMER.to_string(), 10), (ARTIST.to_string(), 4)]),
+        ..MemberCountJob::new(GUILD_ID)
+    };
+
+    counter.count(&job).await.unwrap();
+
+    assert_eq!(
+        role_store.get_member_counts(GUILD_ID).await.unwrap(),
+        HashMap::from([(GAMER.to_string(), 11), (ARTIST.to_string(), 4)])
+    );
+}
+
+#[cfg(feature = "billing")]
+#[tokio::test]
+async fn guilds_without_premium_have_their_counts_cleared() {
+    const FREE_GUILD_ID: &str = "100000000000000009";
+    let role_store = roles(FREE_GUILD_ID);
+    role_store
+        .set_member_counts(FREE_GUILD_ID, &HashMap::from([(GAMER.to_string(), 3)]))
+        .await
+        .unwrap();
+    let discord = members(FREE_GUILD_ID);
+    let counter = counter(role_store.clone(), discord.clone());
+
+    counter
+        .count(&MemberCountJob::new(FREE_GUILD_ID))
+        .await
+        .unwrap();
+
+    assert!(role_store
+        .get_member_counts(FREE_GUILD_ID)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(discord.calls().is_empty());
+}
+
+#[tokio::test]
+async fn suggestions_show_how_many_members_hold_each_role() {
+    let role_store = roles(GUILD_ID);
+    role_store
+        .set_member_counts(
+            GUILD_ID,
+            &HashMap::from([(GAMER.to_string(), 1), (ARTIST.to_string(), 1234)]),
+        )
+        .await
+        .unwrap();
+    let favorites = FavoriteDao::new(FakeDynamo::default().client(), "role-mappings");
+
+    for (typed, name) in [("g", "Gamer (1 member)"), ("a", "Artist (1234 members)")] {
+        let ctx = autocomplete("1", typed);
+        let response =
+            CommandRouter::autocomplete(role_store.as_ref(), &favorites, &ctx.interaction).await;
+
+        assert_eq!(
+            serde_json::to_value(response).unwrap()["data"]["choices"][0]["name"],
+            name
+        );
+    }
+}
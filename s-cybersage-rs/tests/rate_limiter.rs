@@ -0,0 +1,88 @@
+//! The per-member token bucket that turns away command spam, and the shared
+//! per-minute counter behind it. Buckets live for the whole test binary, so
+//! each test uses its own member.
+
+mod common;
+
+use chrono::Utc;
+use common::{FakeDynamo, GUILD_ID};
+use s_cybersage_rs::{
+    bal::auth::rate_limiter::{RateDecision, RateLimiter},
+    dal::dao::rate_limit::RateLimitDao,
+};
+use serde_json::json;
+
+/// Commands a member can send back to back.
+const BURST: usize = 5;
+
+fn limiter(dynamo: &FakeDynamo) -> RateLimiter {
+    RateLimiter::new(RateLimitDao::new(dynamo.client(), "role-mappings"))
+}
+
+#[tokio::test]
+async fn a_burst_is_allowed_then_limited() {
+    const USER_ID: &str = "200000000000000101";
+    let limiter = limiter(&FakeDynamo::default());
+
+    for _ in 0..BURST {
+        assert_eq!(
+            limiter.check(GUILD_ID, USER_ID).await,
+            RateDecision::Allowed
+        );
+    }
+
+    // Tokens come back at one every two seconds.
+    assert_eq!(
+        limiter.check(GUILD_ID, USER_ID).await,
+        RateDecision::Limited(2)
+    );
+}
+
+#[tokio::test]
+async fn members_have_separate_buckets() {
+    const USER_ID: &str = "200000000000000102";
+    const OTHER_USER_ID: &str = "200000000000000103";
+    let limiter = limiter(&FakeDynamo::default());
+
+    for _ in 0..=BURST {
+        limiter.check(GUILD_ID, USER_ID).await;
+    }
+
+    assert_eq!(
+        limiter.check(GUILD_ID, OTHER_USER_ID).await,
+        RateDecision::Allowed
+    );
+}
+
+#[tokio::test]
+async fn only_a_first_sighting_is_counted_in_storage() {
+    const USER_ID: &str = "200000000000000104";
+    let dynamo = FakeDynamo::default();
+    let limiter = limiter(&dynamo);
+
+    for _ in 0..3 {
+        limiter.check(GUILD_ID, USER_ID).await;
+    }
+
+    assert_eq!(dynamo.requests("UpdateItem").len(), 1);
+}
+
+#[tokio::test]
+async fn a_member_seen_by_many_instances_is_limited() {
+    const USER_ID: &str = "200000000000000105";
+    // Five other instances saw the member this minute (or the next, should
+    // the minute turn over mid-test).
+    let minute = Utc::now().timestamp() / 60;
+    let mut dynamo = FakeDynamo::default();
+    for minute in [minute, minute + 1] {
+        dynamo = dynamo.with_item(json!({
+            "guild_id": { "S": GUILD_ID },
+            "mapping_key": { "S": format!("RATE#{}#{}", USER_ID, minute) },
+            "hits": { "N": "5" },
+        }));
+    }
+
+    let decision = limiter(&dynamo).check(GUILD_ID, USER_ID).await;
+
+    assert!(matches!(decision, RateDecision::Limited(1..=60)));
+}
@@ -0,0 +1,130 @@
+//! When reconciliation tombstones a guild, with Discord's answers scripted
+//! through `RecordingDiscordApi`.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{FakeDynamo, GUILD_ID};
+use reqwest::StatusCode;
+use s_cybersage_rs::{
+    bal::{
+        activity::activity_recorder::ActivityRecorder,
+        deferred::task_executor::TaskExecutor,
+        discord::{
+            interaction_client::InteractionClient,
+            recording_discord_api::{RecordingDiscordApi, ScriptedFailure},
+        },
+    },
+    dal::dao::{
+        guild_record::GuildRecordDao, in_memory_role_store::InMemoryRoleStore,
+        log_channel::LogChannelDao,
+    },
+    error::DiscordApiError,
+};
+
+const TABLE: &str = "role-mappings";
+
+fn executor(discord: RecordingDiscordApi, dynamo: &FakeDynamo) -> TaskExecutor {
+    let client = dynamo.client();
+
+    TaskExecutor::new(
+        Arc::new(InMemoryRoleStore::new()),
+        Arc::new(discord),
+        InteractionClient::new(reqwest::Client::new()),
+        ActivityRecorder::new(LogChannelDao::new(client.clone(), TABLE), None),
+        GuildRecordDao::new(client, TABLE),
+    )
+}
+
+/// The `UpdateItem` expressions sent, to tell a tombstone from a cleared one.
+fn updates(dynamo: &FakeDynamo) -> Vec<String> {
+    dynamo
+        .requests("UpdateItem")
+        .iter()
+        .map(|body| {
+            body["UpdateExpression"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn unknown_or_inaccessible_guilds_are_tombstoned() {
+    let discord =
+        RecordingDiscordApi::new().fail_next_fetch_roles(ScriptedFailure::GuildUnavailable);
+    let dynamo = FakeDynamo::default();
+
+    executor(discord, &dynamo)
+        .reconcile_roles(GUILD_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        updates(&dynamo),
+        ["SET removed_at = if_not_exists(removed_at, :removed_at)"]
+    );
+}
+
+#[tokio::test]
+async fn other_forbidden_responses_fail_without_a_tombstone() {
+    let discord = RecordingDiscordApi::new().fail_next_fetch_roles(ScriptedFailure::Forbidden);
+    let dynamo = FakeDynamo::default();
+
+    let result = executor(discord, &dynamo).reconcile_roles(GUILD_ID).await;
+
+    assert!(result.is_err());
+    assert!(updates(&dynamo).is_empty());
+}
+
+#[tokio::test]
+async fn other_not_found_responses_fail_without_a_tombstone() {
+    let discord = RecordingDiscordApi::new().fail_next_fetch_roles(ScriptedFailure::NotFound);
+    let dynamo = FakeDynamo::default();
+
+    let result = executor(discord, &dynamo).reconcile_roles(GUILD_ID).await;
+
+    assert!(result.is_err());
+    assert!(updates(&dynamo).is_empty());
+}
+
+#[tokio::test]
+async fn reachable_guilds_clear_their_tombstone() {
+    let dynamo = FakeDynamo::default();
+
+    executor(RecordingDiscordApi::new(), &dynamo)
+        .reconcile_roles(GUILD_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(updates(&dynamo), ["REMOVE removed_at"]);
+}
+
+#[test]
+fn only_unknown_guild_and_missing_access_mean_the_guild_is_gone() {
+    let classify = |status, code| DiscordApiError::from_guild_status(status, code);
+
+    assert!(matches!(
+        classify(StatusCode::NOT_FOUND, Some(10004)),
+        DiscordApiError::GuildUnavailable
+    ));
+    assert!(matches!(
+        classify(StatusCode::FORBIDDEN, Some(50001)),
+        DiscordApiError::GuildUnavailable
+    ));
+    // Missing Permissions and Unknown Role are ordinary failures.
+    assert!(matches!(
+        classify(StatusCode::FORBIDDEN, Some(50013)),
+        DiscordApiError::Forbidden
+    ));
+    assert!(matches!(
+        classify(StatusCode::NOT_FOUND, Some(10011)),
+        DiscordApiError::NotFound
+    ));
+    assert!(matches!(
+        classify(StatusCode::FORBIDDEN, None),
+        DiscordApiError::Forbidden
+    ));
+}
@@ -0,0 +1,108 @@
+//! The handler must verify the signature over the bytes Discord sent, whether
+//! the Function URL delivers the body as text or base64-encoded.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::{Signer, SigningKey};
+use lambda_http::{request::LambdaRequest, Body, Request};
+use s_cybersage_rs::{bal::auth::verify::AuthManager, http_handler::raw_body};
+use serde_json::{json, Value};
+
+const PAYLOAD: &str = r#"{"id":"1","application_id":"2","type":1,"token":"t"}"#;
+
+fn signing_key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32])
+}
+
+fn timestamp() -> String {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string()
+}
+
+fn sign(timestamp: &str, body: &[u8]) -> String {
+    let mut message = timestamp.as_bytes().to_vec();
+    message.extend_from_slice(body);
+    hex::encode(signing_key().sign(&message).to_bytes())
+}
+
+/// A Function URL (payload format 2.0) event carrying `body`.
+fn function_url_event(body: &str, is_base64_encoded: bool, timestamp: &str) -> Request {
+    let event: Value = json!({
+        "version": "2.0",
+        "routeKey": "$default",
+        "rawPath": "/",
+        "rawQueryString": "",
+        "headers": {
+            "content-type": "application/json",
+            "x-signature-ed25519": sign(timestamp, PAYLOAD.as_bytes()),
+            "x-signature-timestamp": timestamp,
+        },
+        "requestContext": {
+            "accountId": "anonymous",
+            "apiId": "example",
+            "domainName": "example.lambda-url.us-east-1.on.aws",
+            "domainPrefix": "example",
+            "http": {
+                "method": "POST",
+                "path": "/",
+                "protocol": "HTTP/1.1",
+                "sourceIp": "192.0.2.1",
+                "userAgent": "Discord-Interactions/1.0",
+            },
+            "requestId": "request-id",
+            "routeKey": "$default",
+            "stage": "$default",
+            "time": "01/Jan/2025:00:00:00 +0000",
+            "timeEpoch": 1735689600000u64,
+        },
+        "body": body,
+        "isBase64Encoded": is_base64_encoded,
+    });
+
+    let request: LambdaRequest = serde_json::from_value(event).expect("valid Function URL event");
+    Request::from(request)
+}
+
+fn verify(request: &Request, timestamp: &str) -> bool {
+    let header = |name: &str| request.headers()[name].to_str().unwrap().to_string();
+
+    AuthManager::verify_signature(
+        &header("x-signature-ed25519"),
+        timestamp,
+        raw_body(request),
+        &hex::encode(signing_key().verifying_key().to_bytes()),
+    )
+    .is_ok()
+}
+
+#[test]
+fn text_body_is_verified_as_is() {
+    let ts = timestamp();
+    let request = function_url_event(PAYLOAD, false, &ts);
+
+    assert!(matches!(request.body(), Body::Text(_)));
+    assert_eq!(raw_body(&request), PAYLOAD.as_bytes());
+    assert!(verify(&request, &ts));
+}
+
+#[test]
+fn base64_body_is_verified_after_decoding() {
+    let ts = timestamp();
+    let request = function_url_event(&STANDARD.encode(PAYLOAD), true, &ts);
+
+    assert!(matches!(request.body(), Body::Binary(_)));
+    assert_eq!(raw_body(&request), PAYLOAD.as_bytes());
+    assert!(verify(&request, &ts));
+}
+
+#[test]
+fn tampered_body_fails_verification() {
+    let ts = timestamp();
+    let request = function_url_event(&PAYLOAD.replace("\"t\"", "\"x\""), false, &ts);
+
+    assert!(!verify(&request, &ts));
+}
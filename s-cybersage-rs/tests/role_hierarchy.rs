@@ -0,0 +1,77 @@
+//! Which roles may be handed out through the bot: never moderator roles,
+//! and only roles below the bot's highest, as Discord itself requires.
+
+use s_cybersage_rs::{
+    bal::discord::{role_hierarchy::RoleHierarchy, role_manager::GuildRole},
+    commands::ADMINISTRATOR,
+};
+use serde_json::json;
+
+const BOT_ROLE_ID: &str = "500000000000000005";
+
+fn role(id: &str, position: i64, permissions: u64) -> GuildRole {
+    serde_json::from_value(json!({
+        "id": id,
+        "name": format!("Role {}", position),
+        "position": position,
+        "permissions": permissions.to_string(),
+    }))
+    .unwrap()
+}
+
+fn hierarchy() -> RoleHierarchy {
+    RoleHierarchy::new(
+        vec![role("1", 1, 0), role(BOT_ROLE_ID, 5, 0), role("6", 6, 0)],
+        &[BOT_ROLE_ID.to_string()],
+    )
+}
+
+#[test]
+fn roles_below_the_bot_are_allowed() {
+    let hierarchy = hierarchy();
+
+    assert_eq!(hierarchy.role_refusal(hierarchy.role("1").unwrap()), None);
+}
+
+#[test]
+fn roles_at_or_above_the_bot_are_refused() {
+    let hierarchy = hierarchy();
+
+    for role_id in [BOT_ROLE_ID, "6"] {
+        assert_eq!(
+            hierarchy.role_refusal(hierarchy.role(role_id).unwrap()),
+            Some("not below the bot's highest role")
+        );
+    }
+}
+
+#[test]
+fn moderator_roles_are_refused_wherever_they_sit() {
+    let hierarchy = hierarchy();
+
+    assert_eq!(
+        hierarchy.refusal(&ADMINISTRATOR.to_string(), 1),
+        Some("grants moderation permissions")
+    );
+}
+
+#[test]
+fn a_bot_without_roles_can_hand_out_nothing() {
+    let hierarchy = RoleHierarchy::new(vec![role("1", 1, 0)], &[]);
+
+    assert_eq!(hierarchy.highest_position(&[]), 0);
+    assert!(hierarchy.refusal("0", 1).is_some());
+}
+
+#[test]
+fn members_hand_out_only_roles_below_their_own() {
+    let hierarchy = hierarchy();
+    let member_roles = ["6".to_string()];
+
+    assert_eq!(hierarchy.member_refusal(1, &member_roles, false), None);
+    assert_eq!(
+        hierarchy.member_refusal(6, &member_roles, false),
+        Some("not below your highest role")
+    );
+    assert_eq!(hierarchy.member_refusal(6, &member_roles, true), None);
+}
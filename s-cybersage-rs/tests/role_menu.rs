@@ -0,0 +1,193 @@
+//! Role menu buttons can be clicked by anyone, so they only ever hand out
+//! roles that are self-assignable.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{command, component, component_router, content, router, FakeDynamo, GUILD_ID};
+use s_cybersage_rs::{
+    bal::{
+        discord::recording_discord_api::{DiscordCall, RecordingDiscordApi},
+        route::{component_router::ComponentAction, request_context::RequestContext},
+    },
+    dal::dao::in_memory_role_store::InMemoryRoleStore,
+};
+use serde_json::{json, Value};
+
+const ROLE_ID: &str = "500000000000000005";
+const MESSAGE_ID: &str = "700000000000000007";
+const CHANNEL_ID: &str = "400000000000000004";
+
+/// A menu item with `bindings`, in DynamoDB's JSON form.
+fn menu(bindings: Value) -> Value {
+    json!({
+        "guild_id": { "S": GUILD_ID },
+        "mapping_key": { "S": format!("MENU#{}", MESSAGE_ID) },
+        "channel_id": { "S": CHANNEL_ID },
+        "bindings": { "M": bindings },
+    })
+}
+
+fn bound_menu() -> Value {
+    menu(json!({
+        "🎮": { "M": {
+            "role_id": { "S": ROLE_ID },
+            "role_name": { "S": "Gamer" },
+            "bound_at": { "N": "1" },
+        } },
+    }))
+}
+
+fn bind(interaction_id: &str) -> RequestContext {
+    command(
+        interaction_id,
+        "rolemenu",
+        "bind-emoji",
+        json!([
+            { "name": "message", "type": 3, "value": MESSAGE_ID },
+            { "name": "emoji", "type": 3, "value": "🎮" },
+            { "name": "role", "type": 8, "value": ROLE_ID },
+        ]),
+        json!({
+            "resolved": {
+                "roles": { ROLE_ID: { "id": ROLE_ID, "name": "Gamer" } },
+            },
+        }),
+    )
+}
+
+fn click() -> RequestContext {
+    component("1", &format!("rolemenu:{}", ROLE_ID), MESSAGE_ID)
+}
+
+/// The menu message's edits, in order.
+fn edits(discord: &RecordingDiscordApi) -> Vec<Value> {
+    discord
+        .calls()
+        .into_iter()
+        .filter_map(|call| match call {
+            DiscordCall::EditMessage { message, .. } => Some(message),
+            _ => None,
+        })
+        .collect()
+}
+
+fn unbind(interaction_id: &str, emoji: &str) -> RequestContext {
+    command(
+        interaction_id,
+        "rolemenu",
+        "unbind-emoji",
+        json!([
+            { "name": "message", "type": 3, "value": MESSAGE_ID },
+            { "name": "emoji", "type": 3, "value": emoji },
+        ]),
+        json!({}),
+    )
+}
+
+#[tokio::test]
+async fn binding_a_registered_role_adds_its_button() {
+    let roles = Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]));
+    let discord = Arc::new(RecordingDiscordApi::new());
+    let dynamo = FakeDynamo::default().with_item(menu(json!({})));
+    let router = router(roles, discord.clone(), &dynamo);
+
+    let response = router.handle_command(&bind("1")).await.unwrap();
+
+    assert_eq!(content(&response), "🎮 now toggles 'Gamer'.");
+    let edits = edits(&discord);
+    assert_eq!(edits.len(), 1);
+    assert_eq!(
+        edits[0]["components"][0]["components"][0]["custom_id"],
+        format!("rolemenu:{}", ROLE_ID)
+    );
+    assert_eq!(dynamo.requests("UpdateItem").len(), 1);
+}
+
+#[tokio::test]
+async fn binding_an_unregistered_role_is_refused() {
+    let discord = Arc::new(RecordingDiscordApi::new());
+    let dynamo = FakeDynamo::default().with_item(menu(json!({})));
+    let router = router(Arc::new(InMemoryRoleStore::new()), discord.clone(), &dynamo);
+
+    let response = router.handle_command(&bind("1")).await.unwrap();
+
+    assert!(content(&response).starts_with("Only self-assignable roles"));
+    assert!(edits(&discord).is_empty());
+    assert!(dynamo.requests("UpdateItem").is_empty());
+}
+
+#[tokio::test]
+async fn unbinding_removes_the_emojis_button() {
+    let discord = Arc::new(RecordingDiscordApi::new());
+    let dynamo = FakeDynamo::default().with_item(bound_menu());
+    let router = router(Arc::new(InMemoryRoleStore::new()), discord.clone(), &dynamo);
+
+    let response = router.handle_command(&unbind("1", "🎲")).await.unwrap();
+    assert_eq!(content(&response), "That emoji isn't on this menu.");
+
+    let response = router.handle_command(&unbind("2", "🎮")).await.unwrap();
+    assert_eq!(content(&response), "Removed 🎮 from the menu.");
+    assert_eq!(edits(&discord), [json!({ "components": [] })]);
+    assert_eq!(dynamo.requests("UpdateItem").len(), 1);
+}
+
+#[tokio::test]
+async fn clicking_a_bound_registered_role_toggles_it() {
+    let roles = Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]));
+    let dynamo = FakeDynamo::default().with_item(bound_menu());
+
+    let action = component_router(roles, &dynamo)
+        .resolve(&click())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        action,
+        ComponentAction::ToggleRole { role_id, .. } if role_id == ROLE_ID
+    ));
+}
+
+#[tokio::test]
+async fn clicking_a_role_no_longer_registered_does_nothing() {
+    let dynamo = FakeDynamo::default().with_item(bound_menu());
+
+    let action = component_router(Arc::new(InMemoryRoleStore::new()), &dynamo)
+        .resolve(&click())
+        .await
+        .unwrap();
+
+    let ComponentAction::Reply(response) = action else {
+        panic!("expected a reply");
+    };
+    assert_eq!(
+        content(&response),
+        "That role is no longer self-assignable."
+    );
+}
+
+#[tokio::test]
+async fn clicking_an_unbound_button_does_nothing() {
+    let roles = Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]));
+    let dynamo = FakeDynamo::default().with_item(menu(json!({})));
+
+    let action = component_router(roles, &dynamo)
+        .resolve(&click())
+        .await
+        .unwrap();
+
+    let ComponentAction::Reply(response) = action else {
+        panic!("expected a reply");
+    };
+    assert_eq!(
+        content(&response),
+        "That button is no longer bound to a role."
+    );
+}
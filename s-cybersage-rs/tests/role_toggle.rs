@@ -0,0 +1,396 @@
+//! What `/role toggle` does to a member's roles, and when it refuses, with
+//! Discord's answers scripted through `RecordingDiscordApi`.
+
+mod common;
+
+use std::sync::Arc;
+
+use chrono::{Duration, SecondsFormat, Utc};
+use common::{command, content, role_command, router, FakeDynamo, GUILD_ID, USER_ID};
+use s_cybersage_rs::{
+    bal::{
+        discord::{
+            recording_discord_api::{DiscordCall, RecordingDiscordApi, ScriptedFailure},
+            role_manager::RoleAction,
+        },
+        route::request_context::RequestContext,
+    },
+    dal::{
+        dao::{in_memory_role_store::InMemoryRoleStore, role_store::RoleStore},
+        model::age_requirement::AgeRequirement,
+    },
+    timestamp,
+};
+use serde_json::json;
+
+const ROLE_ID: &str = "500000000000000005";
+const OTHER_ROLE_ID: &str = "600000000000000006";
+const MEMBER_ROLE_ID: &str = "700000000000000007";
+
+fn toggle(interaction_id: &str) -> RequestContext {
+    role_command(
+        interaction_id,
+        "toggle",
+        json!([{ "name": "role", "type": 3, "value": "Gamer" }]),
+        json!({}),
+    )
+}
+
+fn roles() -> Arc<InMemoryRoleStore> {
+    Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]))
+}
+
+fn modifications(discord: &RecordingDiscordApi) -> Vec<RoleAction> {
+    discord
+        .calls()
+        .into_iter()
+        .filter_map(|call| match call {
+            DiscordCall::ModifyUserRole { action, .. } => Some(action),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn adds_a_role_the_member_lacks() {
+    let discord =
+        Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[OTHER_ROLE_ID]));
+    let router = router(roles(), discord.clone(), &FakeDynamo::default());
+
+    let response = router.handle_command(&toggle("1")).await.unwrap();
+
+    assert_eq!(content(&response), "Added 'Gamer'.");
+    assert_eq!(modifications(&discord), [RoleAction::Add]);
+    assert_eq!(
+        discord.member_roles(GUILD_ID, USER_ID),
+        [OTHER_ROLE_ID, ROLE_ID]
+    );
+}
+
+#[tokio::test]
+async fn removes_a_role_the_member_has() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(
+        GUILD_ID,
+        USER_ID,
+        &[OTHER_ROLE_ID, ROLE_ID],
+    ));
+    let router = router(roles(), discord.clone(), &FakeDynamo::default());
+
+    let response = router.handle_command(&toggle("1")).await.unwrap();
+
+    assert_eq!(content(&response), "Removed 'Gamer'.");
+    assert_eq!(modifications(&discord), [RoleAction::Remove]);
+    assert_eq!(discord.member_roles(GUILD_ID, USER_ID), [OTHER_ROLE_ID]);
+}
+
+#[tokio::test]
+async fn toggling_twice_restores_the_member_roles() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let router = router(roles(), discord.clone(), &FakeDynamo::default());
+
+    router.handle_command(&toggle("1")).await.unwrap();
+    router.handle_command(&toggle("2")).await.unwrap();
+
+    assert_eq!(
+        modifications(&discord),
+        [RoleAction::Add, RoleAction::Remove]
+    );
+    assert!(discord.member_roles(GUILD_ID, USER_ID).is_empty());
+}
+
+/// Discord answers 403 when the role sits above the bot's highest role.
+#[tokio::test]
+async fn forbidden_asks_for_the_bot_role_to_be_moved() {
+    let discord = Arc::new(
+        RecordingDiscordApi::new()
+            .with_member_roles(GUILD_ID, USER_ID, &[])
+            .fail_next_modify(ScriptedFailure::Forbidden),
+    );
+    let dynamo = FakeDynamo::default();
+    let router = router(roles(), discord.clone(), &dynamo);
+
+    let response = router.handle_command(&toggle("1")).await.unwrap();
+
+    assert_eq!(
+        content(&response),
+        "I don't have permission to do that. Ask an admin to move my role above the roles I \
+         manage."
+    );
+    assert!(discord.member_roles(GUILD_ID, USER_ID).is_empty());
+    // Released, so Discord's redelivery can try again once it's fixed.
+    assert!(!dynamo.is_claimed("1"));
+}
+
+#[tokio::test]
+async fn a_retry_after_forbidden_applies_the_change() {
+    let discord = Arc::new(
+        RecordingDiscordApi::new()
+            .with_member_roles(GUILD_ID, USER_ID, &[])
+            .fail_next_modify(ScriptedFailure::Forbidden),
+    );
+    let router = router(roles(), discord.clone(), &FakeDynamo::default());
+
+    router.handle_command(&toggle("1")).await.unwrap();
+    let retry = router.handle_command(&toggle("1")).await.unwrap();
+
+    assert_eq!(content(&retry), "Added 'Gamer'.");
+    assert_eq!(modifications(&discord), [RoleAction::Add, RoleAction::Add]);
+    assert_eq!(discord.member_roles(GUILD_ID, USER_ID), [ROLE_ID]);
+}
+
+#[tokio::test]
+async fn missing_role_or_member_is_reported() {
+    let discord = Arc::new(
+        RecordingDiscordApi::new()
+            .with_member_roles(GUILD_ID, USER_ID, &[])
+            .fail_next_modify(ScriptedFailure::NotFound),
+    );
+    let router = router(roles(), discord.clone(), &FakeDynamo::default());
+
+    let response = router.handle_command(&toggle("1")).await.unwrap();
+
+    assert_eq!(content(&response), "That role or member no longer exists.");
+    assert!(discord.member_roles(GUILD_ID, USER_ID).is_empty());
+}
+
+/// `roles()` with 'Gamer' requiring `OTHER_ROLE_ID` and `MEMBER_ROLE_ID`.
+async fn with_prerequisites() -> Arc<InMemoryRoleStore> {
+    let role_store = roles();
+    role_store
+        .set_prerequisites(
+            GUILD_ID,
+            ROLE_ID,
+            &[OTHER_ROLE_ID.to_string(), MEMBER_ROLE_ID.to_string()],
+        )
+        .await
+        .unwrap();
+    role_store
+}
+
+#[tokio::test]
+async fn missing_prerequisites_are_listed() {
+    let discord =
+        Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[OTHER_ROLE_ID]));
+    let router = router(
+        with_prerequisites().await,
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+
+    let response = router.handle_command(&toggle("1")).await.unwrap();
+
+    assert_eq!(
+        content(&response),
+        format!("You need <@&{}> to take 'Gamer'.", MEMBER_ROLE_ID)
+    );
+    assert!(modifications(&discord).is_empty());
+}
+
+#[tokio::test]
+async fn members_with_every_prerequisite_take_the_role() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(
+        GUILD_ID,
+        USER_ID,
+        &[OTHER_ROLE_ID, MEMBER_ROLE_ID],
+    ));
+    let router = router(
+        with_prerequisites().await,
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+
+    let response = router.handle_command(&toggle("1")).await.unwrap();
+
+    assert_eq!(content(&response), "Added 'Gamer'.");
+    assert_eq!(modifications(&discord), [RoleAction::Add]);
+}
+
+#[tokio::test]
+async fn prerequisites_never_stop_a_role_being_dropped() {
+    let discord =
+        Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[ROLE_ID]));
+    let router = router(
+        with_prerequisites().await,
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+
+    let response = router.handle_command(&toggle("1")).await.unwrap();
+
+    assert_eq!(content(&response), "Removed 'Gamer'.");
+    assert_eq!(modifications(&discord), [RoleAction::Remove]);
+}
+
+/// `roles()` for a guild where members take roles a day after arriving,
+/// counted from joining or, with `account`, from signing up.
+async fn after_a_day(account: bool) -> Arc<InMemoryRoleStore> {
+    let role_store = roles();
+    role_store
+        .set_age_requirement(GUILD_ID, Some(AgeRequirement { hours: 24, account }))
+        .await
+        .unwrap();
+    role_store
+}
+
+/// `toggle` by a member who joined `hours_ago`.
+fn toggle_after_joining(interaction_id: &str, hours_ago: i64) -> (RequestContext, i64) {
+    let joined = Utc::now() - Duration::hours(hours_ago);
+    let mut ctx = toggle(interaction_id);
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.joined_at = Some(joined.to_rfc3339_opts(SecondsFormat::Secs, true));
+    }
+    (ctx, joined.timestamp())
+}
+
+#[tokio::test]
+async fn new_members_are_told_when_they_can_take_roles() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let router = router(
+        after_a_day(false).await,
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+    let (ctx, joined) = toggle_after_joining("1", 1);
+
+    let response = router.handle_command(&ctx).await.unwrap();
+
+    assert_eq!(
+        content(&response),
+        format!(
+            "This server lets members take roles a little while after they arrive. You can \
+             take 'Gamer' {}.",
+            timestamp::relative(joined + 24 * 3600)
+        )
+    );
+    assert!(modifications(&discord).is_empty());
+}
+
+#[tokio::test]
+async fn members_past_the_age_requirement_take_roles() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let router = router(
+        after_a_day(false).await,
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+    let (ctx, _) = toggle_after_joining("1", 25);
+
+    let response = router.handle_command(&ctx).await.unwrap();
+
+    assert_eq!(content(&response), "Added 'Gamer'.");
+}
+
+#[tokio::test]
+async fn new_members_can_still_drop_roles() {
+    let discord =
+        Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[ROLE_ID]));
+    let router = router(
+        after_a_day(false).await,
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+    let (ctx, _) = toggle_after_joining("1", 1);
+
+    let response = router.handle_command(&ctx).await.unwrap();
+
+    assert_eq!(content(&response), "Removed 'Gamer'.");
+}
+
+#[tokio::test]
+async fn young_accounts_wait_however_long_ago_they_joined() {
+    // A snowflake minted an hour ago: milliseconds since 2015 in the top bits.
+    let created = Utc::now() - Duration::hours(1);
+    let user_id = (((created.timestamp_millis() - 1_420_070_400_000) as u64) << 22).to_string();
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, &user_id, &[]));
+    let router = router(
+        after_a_day(true).await,
+        discord.clone(),
+        &FakeDynamo::default(),
+    );
+    let (mut ctx, _) = toggle_after_joining("1", 48);
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.user.id = user_id;
+    }
+
+    let response = router.handle_command(&ctx).await.unwrap();
+
+    assert!(content(&response).starts_with("This server lets members take roles"));
+    assert!(modifications(&discord).is_empty());
+}
+
+#[tokio::test]
+async fn old_accounts_take_roles_as_soon_as_they_join() {
+    // `USER_ID` was minted in 2015.
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let router = router(after_a_day(true).await, discord, &FakeDynamo::default());
+    let (ctx, _) = toggle_after_joining("1", 1);
+
+    let response = router.handle_command(&ctx).await.unwrap();
+
+    assert_eq!(content(&response), "Added 'Gamer'.");
+}
+
+/// The channel `common` sends every interaction from.
+const CHANNEL_ID: &str = "400000000000000004";
+const ROLES_CHANNEL_ID: &str = "900000000000000009";
+
+/// `/config channels` limiting 'Gamer' to `channel_id`.
+fn limit_to(interaction_id: &str, channel_id: &str) -> RequestContext {
+    command(
+        interaction_id,
+        "config",
+        "channels",
+        json!([
+            { "name": "role", "type": 8, "value": ROLE_ID },
+            { "name": "channel", "type": 7, "value": channel_id },
+        ]),
+        json!({}),
+    )
+}
+
+#[tokio::test]
+async fn restricted_roles_point_to_their_channels() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let router = router(roles(), discord.clone(), &FakeDynamo::default());
+
+    let response = router
+        .handle_command(&limit_to("1", ROLES_CHANNEL_ID))
+        .await
+        .unwrap();
+    assert_eq!(
+        content(&response),
+        format!(
+            "'Gamer' can now only be toggled from <#{}>.",
+            ROLES_CHANNEL_ID
+        )
+    );
+
+    let response = router.handle_command(&toggle("2")).await.unwrap();
+
+    assert_eq!(
+        content(&response),
+        format!("'Gamer' can only be toggled from <#{}>.", ROLES_CHANNEL_ID)
+    );
+    assert!(modifications(&discord).is_empty());
+}
+
+#[tokio::test]
+async fn restricted_roles_toggle_in_their_channels() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let router = router(roles(), discord.clone(), &FakeDynamo::default());
+
+    router
+        .handle_command(&limit_to("1", ROLES_CHANNEL_ID))
+        .await
+        .unwrap();
+    router
+        .handle_command(&limit_to("2", CHANNEL_ID))
+        .await
+        .unwrap();
+    let response = router.handle_command(&toggle("3")).await.unwrap();
+
+    assert_eq!(content(&response), "Added 'Gamer'.");
+    assert_eq!(modifications(&discord), [RoleAction::Add]);
+}
@@ -0,0 +1,105 @@
+//! `BatchGetSecretValue` takes at most 20 secret IDs, so larger batches are
+//! split, with Secrets Manager answered by a fake endpoint.
+
+use std::sync::{Arc, Mutex};
+
+use aws_sdk_secretsmanager::{
+    config::{BehaviorVersion, Credentials, Region},
+    Client,
+};
+use aws_smithy_runtime_api::{
+    client::{
+        http::{
+            HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings,
+            SharedHttpConnector,
+        },
+        orchestrator::{HttpRequest, HttpResponse},
+        runtime_components::RuntimeComponents,
+    },
+    http::StatusCode,
+    shared::IntoShared,
+};
+use aws_smithy_types::body::SdkBody;
+use s_cybersage_rs::dal::reader::{
+    secrets_manager_provider::SecretsManagerProvider, secrets_provider::SecretsProvider,
+};
+use serde_json::{json, Value};
+
+/// Returns every secret asked for, as `{"id": <name>}`, and records how
+/// many IDs each call asked for.
+#[derive(Debug, Clone, Default)]
+struct FakeSecretsManager {
+    batch_sizes: Arc<Mutex<Vec<usize>>>,
+}
+
+impl FakeSecretsManager {
+    fn client(&self) -> Client {
+        let config = aws_sdk_secretsmanager::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .http_client(self.clone())
+            .build();
+
+        Client::from_conf(config)
+    }
+
+    fn batch_sizes(&self) -> Vec<usize> {
+        self.batch_sizes.lock().unwrap().clone()
+    }
+}
+
+impl HttpConnector for FakeSecretsManager {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let body: Value = request
+            .body()
+            .bytes()
+            .and_then(|bytes| serde_json::from_slice(bytes).ok())
+            .unwrap_or_default();
+        let ids: Vec<&str> = body["SecretIdList"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        self.batch_sizes.lock().unwrap().push(ids.len());
+
+        let values: Vec<Value> = ids
+            .iter()
+            .map(|id| json!({ "Name": id, "SecretString": json!({ "id": id }).to_string() }))
+            .collect();
+
+        let mut response = HttpResponse::new(
+            StatusCode::try_from(200).unwrap(),
+            SdkBody::from(json!({ "SecretValues": values, "Errors": [] }).to_string()),
+        );
+        response
+            .headers_mut()
+            .insert("content-type", "application/x-amz-json-1.1");
+
+        HttpConnectorFuture::ready(Ok(response))
+    }
+}
+
+impl HttpClient for FakeSecretsManager {
+    fn http_connector(
+        &self,
+        _settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        self.clone().into_shared()
+    }
+}
+
+#[tokio::test]
+async fn batches_are_split_at_twenty_secrets() {
+    let fake = FakeSecretsManager::default();
+    let provider = SecretsManagerProvider::new(fake.client());
+    let ids: Vec<String> = (0..45).map(|i| format!("tenant-{}", i)).collect();
+
+    let documents = provider.fetch_secrets(&ids).await.unwrap();
+
+    assert_eq!(fake.batch_sizes(), [20, 20, 5]);
+    assert_eq!(documents.len(), 45);
+    assert_eq!(documents["tenant-44"], json!({ "id": "tenant-44" }));
+}
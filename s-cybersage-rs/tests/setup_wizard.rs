@@ -0,0 +1,86 @@
+//! The `/setup` wizard's import step, which needs what `/role import-all`
+//! does even though the wizard itself only needs Manage Server, and queues
+//! the same import.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{component, content, queued_router, router, FakeDynamo};
+use s_cybersage_rs::{
+    bal::{
+        discord::recording_discord_api::RecordingDiscordApi,
+        route::setup_wizard::{SetupAction, SetupStep},
+    },
+    commands::{MANAGE_GUILD, MANAGE_ROLES},
+    dal::{dao::in_memory_role_store::InMemoryRoleStore, model::deferred_task::DeferredTask},
+};
+
+#[tokio::test]
+async fn import_is_skipped_without_manage_roles() {
+    let mut ctx = component("1", "setup:import", "700000000000000007");
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.permissions = MANAGE_GUILD.to_string();
+    }
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        Arc::new(RecordingDiscordApi::new()),
+        &FakeDynamo::default(),
+    );
+
+    let response = router
+        .handle_setup(&ctx, SetupAction::Apply(SetupStep::ImportRoles))
+        .await
+        .unwrap();
+
+    assert!(content(&response).starts_with("Skipped importing roles"));
+    assert!(content(&response).contains("Setup is complete."));
+}
+
+#[tokio::test]
+async fn import_is_queued_with_manage_roles() {
+    let ctx = component("1", "setup:import", "700000000000000007");
+    let router = router(
+        Arc::new(InMemoryRoleStore::new()),
+        Arc::new(RecordingDiscordApi::new()),
+        &FakeDynamo::default(),
+    );
+
+    let response = router
+        .handle_setup(&ctx, SetupAction::Apply(SetupStep::ImportRoles))
+        .await
+        .unwrap();
+
+    // The router under test has no queue to hand the import to.
+    assert_eq!(content(&response), "Background tasks are not configured.");
+}
+
+#[tokio::test]
+async fn the_queued_import_is_held_to_the_members_roles() {
+    const MEMBER_ROLE_ID: &str = "500000000000000005";
+    let mut ctx = component("1", "setup:import", "700000000000000007");
+    if let Some(member) = ctx.interaction.member.as_mut() {
+        member.permissions = (MANAGE_GUILD | MANAGE_ROLES).to_string();
+        member.roles = vec![MEMBER_ROLE_ID.to_string()];
+    }
+    let dynamo = FakeDynamo::default();
+    let router = queued_router(
+        Arc::new(InMemoryRoleStore::new()),
+        Arc::new(RecordingDiscordApi::new()),
+        &dynamo,
+    );
+
+    router
+        .handle_setup(&ctx, SetupAction::Apply(SetupStep::ImportRoles))
+        .await
+        .unwrap();
+
+    let sent = dynamo.requests("SendMessage");
+    let task: DeferredTask =
+        serde_json::from_str(sent[0]["MessageBody"].as_str().unwrap()).unwrap();
+    let DeferredTask::ImportRoles(origin) = task else {
+        panic!("expected an import");
+    };
+    assert_eq!(origin.member_roles, [MEMBER_ROLE_ID]);
+    assert!(!origin.administrator);
+}
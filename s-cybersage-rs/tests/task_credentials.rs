@@ -0,0 +1,107 @@
+//! Which bot a deferred task runs as, with guild records served by the fake
+//! DynamoDB endpoint and tenant secrets by a static provider.
+
+mod common;
+
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use common::{FakeDynamo, APPLICATION_ID, GUILD_ID};
+use ed25519_dalek::SigningKey;
+use s_cybersage_rs::{
+    bal::deferred::task_credentials::TaskCredentials,
+    dal::{
+        dao::guild_record::GuildRecordDao,
+        model::deferred_task::DeferredTask,
+        reader::{
+            secrets_provider::SecretsProvider, secrets_reader::SecretsReader,
+            tenant_secrets::TenantSecrets,
+        },
+    },
+};
+use serde_json::{json, Value};
+
+const TENANT_APPLICATION_ID: &str = "700000000000000007";
+const TENANT_SECRET_PREFIX: &str = "tenant/";
+const TENANT_TOKEN: &str = "tenant-token";
+
+/// Holds the one tenant's secret.
+struct TenantProvider;
+
+#[async_trait]
+impl SecretsProvider for TenantProvider {
+    async fn fetch_secret(&self, secret_id: &str) -> Result<Value> {
+        if secret_id != format!("{}{}", TENANT_SECRET_PREFIX, TENANT_APPLICATION_ID) {
+            bail!("No secret {}", secret_id);
+        }
+
+        let public_key = SigningKey::from_bytes(&[7; 32]).verifying_key();
+        Ok(json!({ "token": TENANT_TOKEN, "key": hex::encode(public_key.as_bytes()) }))
+    }
+}
+
+fn credentials(dynamo: &FakeDynamo) -> TaskCredentials {
+    let tenants = TenantSecrets::new(
+        SecretsReader::new(Arc::new(TenantProvider)),
+        TENANT_SECRET_PREFIX,
+        [TENANT_APPLICATION_ID.to_string()],
+    );
+
+    TaskCredentials::new(
+        Some(tenants),
+        Some(APPLICATION_ID.to_string()),
+        GuildRecordDao::new(dynamo.client(), "role-mappings"),
+    )
+}
+
+fn guild_record(application_id: &str) -> Value {
+    json!({
+        "guild_id": { "S": GUILD_ID },
+        "mapping_key": { "S": "GUILD" },
+        "application_id": { "S": application_id },
+    })
+}
+
+fn reconcile() -> DeferredTask {
+    DeferredTask::ReconcileRoles {
+        guild_id: GUILD_ID.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn reconciling_a_tenant_guild_uses_the_tenant_token() {
+    let dynamo = FakeDynamo::default().with_item(guild_record(TENANT_APPLICATION_ID));
+
+    let tenant = credentials(&dynamo).tenant_for(&reconcile()).await.unwrap();
+
+    assert_eq!(
+        tenant,
+        Some((TENANT_APPLICATION_ID.to_string(), TENANT_TOKEN.to_string()))
+    );
+}
+
+#[tokio::test]
+async fn guilds_without_an_application_use_the_default_bot() {
+    let dynamo = FakeDynamo::default();
+
+    let tenant = credentials(&dynamo).tenant_for(&reconcile()).await.unwrap();
+
+    assert_eq!(tenant, None);
+}
+
+#[tokio::test]
+async fn the_default_application_uses_the_default_bot() {
+    let dynamo = FakeDynamo::default().with_item(guild_record(APPLICATION_ID));
+
+    let tenant = credentials(&dynamo).tenant_for(&reconcile()).await.unwrap();
+
+    assert_eq!(tenant, None);
+}
+
+#[tokio::test]
+async fn unknown_applications_fail_rather_than_use_the_default_bot() {
+    let dynamo = FakeDynamo::default().with_item(guild_record("800000000000000008"));
+
+    assert!(credentials(&dynamo).tenant_for(&reconcile()).await.is_err());
+}
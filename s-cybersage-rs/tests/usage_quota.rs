@@ -0,0 +1,96 @@
+//! The free tier's monthly toggle quota, metered in `USAGE#<yyyy-mm>` items.
+
+#![cfg(feature = "billing")]
+
+mod common;
+
+use std::sync::Arc;
+
+use common::{content, metered_router, role_command, FakeDynamo, GUILD_ID, USER_ID};
+use s_cybersage_rs::{
+    bal::{
+        billing::usage_meter::UsageMeter, discord::recording_discord_api::RecordingDiscordApi,
+        route::request_context::RequestContext,
+    },
+    dal::dao::in_memory_role_store::InMemoryRoleStore,
+};
+use serde_json::{json, Value};
+
+const ROLE_ID: &str = "500000000000000005";
+
+fn toggle() -> RequestContext {
+    role_command(
+        "1",
+        "toggle",
+        json!([{ "name": "role", "type": 3, "value": "Gamer" }]),
+        json!({}),
+    )
+}
+
+fn roles() -> Arc<InMemoryRoleStore> {
+    Arc::new(InMemoryRoleStore::with_roles([(
+        GUILD_ID, ROLE_ID, "Gamer",
+    )]))
+}
+
+fn usage_key() -> String {
+    format!("USAGE#{}", UsageMeter::current_period())
+}
+
+fn usage(toggles: u64) -> Value {
+    json!({
+        "guild_id": { "S": GUILD_ID },
+        "subscription_key": { "S": usage_key() },
+        "toggle_count": { "N": toggles.to_string() },
+    })
+}
+
+/// The `USAGE#` calls of `operation`.
+fn usage_requests(dynamo: &FakeDynamo, operation: &str) -> Vec<Value> {
+    dynamo
+        .requests(operation)
+        .into_iter()
+        .filter(|body| body["Key"]["subscription_key"]["S"] == usage_key().as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn toggles_under_the_quota_are_applied_and_counted() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let dynamo = FakeDynamo::default().with_item(usage(99));
+    let router = metered_router(roles(), discord.clone(), &dynamo, Some(100));
+
+    let response = router.handle_command(&toggle()).await.unwrap();
+
+    assert_eq!(content(&response), "Added 'Gamer'.");
+    let updates = usage_requests(&dynamo, "UpdateItem");
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0]["UpdateExpression"], "ADD toggle_count :one");
+}
+
+#[tokio::test]
+async fn toggles_past_the_quota_get_the_upsell() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let dynamo = FakeDynamo::default().with_item(usage(100));
+    let router = metered_router(roles(), discord.clone(), &dynamo, Some(100));
+
+    let response = router.handle_command(&toggle()).await.unwrap();
+
+    assert!(content(&response)
+        .starts_with("This server has reached its free-tier limit of 100 role toggles"));
+    assert!(discord.member_roles(GUILD_ID, USER_ID).is_empty());
+    assert!(usage_requests(&dynamo, "UpdateItem").is_empty());
+}
+
+#[tokio::test]
+async fn unmetered_guilds_skip_the_quota_but_are_still_counted() {
+    let discord = Arc::new(RecordingDiscordApi::new().with_member_roles(GUILD_ID, USER_ID, &[]));
+    let dynamo = FakeDynamo::default().with_item(usage(1_000));
+    let router = metered_router(roles(), discord.clone(), &dynamo, None);
+
+    let response = router.handle_command(&toggle()).await.unwrap();
+
+    assert_eq!(content(&response), "Added 'Gamer'.");
+    assert!(usage_requests(&dynamo, "GetItem").is_empty());
+    assert_eq!(usage_requests(&dynamo, "UpdateItem").len(), 1);
+}
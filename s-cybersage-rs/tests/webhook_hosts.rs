@@ -0,0 +1,55 @@
+//! Where guild webhooks may deliver. Admins choose the endpoint, so it must
+//! never reach the function's own network, whether by name or by what the
+//! name resolves to.
+
+use std::net::IpAddr;
+
+use s_cybersage_rs::bal::events::webhook_sender::{is_public_address, parse_endpoint};
+
+#[test]
+fn public_https_endpoints_are_accepted() {
+    assert!(parse_endpoint("https://hooks.example.com/cybersage").is_some());
+}
+
+#[test]
+fn internal_names_and_literals_are_refused() {
+    for url in [
+        "http://hooks.example.com/",
+        "https://localhost/",
+        "https://intranet/",
+        "https://metadata.internal/",
+        "https://169.254.169.254/",
+        "https://[::1]/",
+    ] {
+        assert!(parse_endpoint(url).is_none(), "{}", url);
+    }
+}
+
+#[test]
+fn private_and_special_addresses_are_not_public() {
+    for ip in [
+        "10.0.0.5",
+        "172.16.0.1",
+        "192.168.1.1",
+        "127.0.0.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "240.0.0.1",
+        "::1",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:10.0.0.5",
+    ] {
+        let ip: IpAddr = ip.parse().unwrap();
+        assert!(!is_public_address(ip), "{}", ip);
+    }
+}
+
+#[test]
+fn global_addresses_are_public() {
+    for ip in ["1.1.1.1", "93.184.216.34", "2606:4700:4700::1111"] {
+        let ip: IpAddr = ip.parse().unwrap();
+        assert!(is_public_address(ip), "{}", ip);
+    }
+}